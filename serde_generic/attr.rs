@@ -0,0 +1,21 @@
+//! `#[serde_generic(...)]` attributes of fields and variants, which describe
+//! them to formats other than the ones of `serde`.
+//!
+//! They are kept apart from the [`serde`](crate::serde) metadata, which only
+//! mirrors `#[serde(...)]`.
+
+/// `#[serde_generic(...)]` attributes of a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Field {
+    /// `#[serde_generic(proto = N)]`: number of the field in Protocol
+    /// Buffers messages.
+    pub proto: Option<u32>,
+}
+
+/// `#[serde_generic(...)]` attributes of an enum variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Variant {
+    /// `#[serde_generic(proto = N)]`: number of the variant in Protocol
+    /// Buffers `oneof`s or enums.
+    pub proto: Option<u32>,
+}
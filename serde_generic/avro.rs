@@ -0,0 +1,412 @@
+//! Apache Avro schemas of reflected types.
+//!
+//! ```
+//! # use serde_generic::SerdeGeneric;
+//! use serde_json::json;
+//!
+//! #[derive(SerdeGeneric)]
+//! struct Reading {
+//!     sensor: String,
+//!     #[serde(default = "default_unit")]
+//!     unit: String,
+//! }
+//!
+//! fn default_unit() -> String {
+//!     "celsius".to_owned()
+//! }
+//!
+//! assert_eq!(
+//!     serde_generic::avro::schema_for::<Reading, _>(Some("com.example")).unwrap(),
+//!     json!({
+//!         "type": "record",
+//!         "name": "Reading",
+//!         "namespace": "com.example",
+//!         "fields": [
+//!             {"name": "sensor", "type": "string"},
+//!             {"name": "unit", "type": "string", "default": "celsius"},
+//!         ],
+//!     })
+//! );
+//! ```
+//!
+//! Structs are records, enums with only unit variants are enums and other
+//! enums are unions of records, one for each variant, named after the enum
+//! and the variant. Named types are defined once, at their first use, and
+//! referred to by their serialized names afterwards, so two types with the
+//! same name, or two instantiations of a generic type, are an [`Error`], as
+//! are shapes with type parameters, which have no schema. Tuples are
+//! records with fields `field_0`, `field_1` and so on, as are maps with keys
+//! other than primitives, as arrays of `key` and `value` records.
+//!
+//! `#[serde(default = "...")]` of a field is its default when the value it
+//! returns is a [`Value`]. Newtype structs and transparent types are their
+//! field. Types with `from`, `try_from` or `into` proxies and fields or
+//! variants with `serialize_with` or `deserialize_with` are `bytes`. Avro has
+//! no unsigned integers: `u32` and `u64` are `long`, and `i128` and `u128`
+//! are strings.
+
+use crate::naming::{ident, type_name};
+use crate::reflect::{
+    Body, Container, Field, Primitive, Reflect, Registry, Shape, Type, Value, Variant,
+};
+use core::fmt;
+use serde_json::{Map, Value as Json, json};
+use std::collections::HashMap;
+
+/// Why a shape has no schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The shape refers to type parameters, e.g. as a field of a
+    /// [`Definition`](crate::reflect::Definition).
+    Generic,
+    /// Two different shapes are named types with the same name, such as two
+    /// instantiations of a generic type.
+    SameName(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Generic => f.write_str("type parameters have no schema"),
+            Error::SameName(name) => write!(f, "two different types are named `{name}`"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Schema of `T`.
+pub fn schema_for<T: ?Sized + Reflect<M>, M>(namespace: Option<&str>) -> Result<Json, Error> {
+    let mut registry = Registry::new();
+    let shape = registry.register::<T, _>();
+    schema(&registry, &shape, namespace)
+}
+
+/// Schema of a shape with no type parameters, made with `registry`, with
+/// named types in `namespace`.
+pub fn schema(registry: &Registry, shape: &Shape, namespace: Option<&str>) -> Result<Json, Error> {
+    Generator {
+        registry,
+        namespace,
+        defined: HashMap::new(),
+    }
+    .schema(shape)
+}
+
+struct Generator<'a> {
+    registry: &'a Registry,
+    namespace: Option<&'a str>,
+    /// Names of the types defined so far, with the shapes they are defined
+    /// for.
+    defined: HashMap<String, Shape>,
+}
+
+impl Generator<'_> {
+    fn schema(&mut self, shape: &Shape) -> Result<Json, Error> {
+        Ok(match shape {
+            Shape::Param(_) => return Err(Error::Generic),
+            Shape::Primitive(primitive) => primitive_schema(*primitive).into(),
+            Shape::Option(shape) => {
+                let mut members = vec![Json::from("null")];
+                members.extend(union_members(self.schema(shape)?));
+                members.dedup();
+                Json::Array(members)
+            }
+            Shape::Seq(shape) | Shape::Array(shape, _) => {
+                json!({"type": "array", "items": self.schema(shape)?})
+            }
+            Shape::Map(key, value) => match **key {
+                // Keys are strings, whatever they are in Rust
+                Shape::Primitive(_) => json!({"type": "map", "values": self.schema(value)?}),
+                _ => {
+                    let name = format!("{}Entry", shape.mono_name(self.registry));
+                    let entry = self.record(&name, shape, None, |generator| {
+                        Ok(vec![
+                            json!({"name": "key", "type": generator.schema(key)?}),
+                            json!({"name": "value", "type": generator.schema(value)?}),
+                        ])
+                    })?;
+                    json!({"type": "array", "items": entry})
+                }
+            },
+            Shape::Tuple(shapes) => {
+                self.record(&shape.mono_name(self.registry), shape, None, |generator| {
+                    shapes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, shape)| {
+                        Ok(json!({"name": format!("field_{i}"), "type": generator.schema(shape)?}))
+                    })
+                    .collect()
+                })?
+            }
+            Shape::Type(ty) => self.type_schema(shape, ty)?,
+        })
+    }
+
+    fn type_schema(&mut self, shape: &Shape, ty: &Type) -> Result<Json, Error> {
+        let container = &self.registry.definition_of(ty).container;
+        if container.proxy().is_some() {
+            return Ok("bytes".into());
+        }
+        let body = self.registry.body(ty);
+        if let Some(field) = newtype_field(container, &body) {
+            return self.schema(&field.shape);
+        }
+        let doc = container.doc.as_deref();
+        match &body {
+            Body::Unit => Ok("null".into()),
+            Body::Struct(fields) | Body::Tuple(fields) => {
+                self.record(&container.name.serialize, shape, doc, |generator| {
+                    generator.fields(fields)
+                })
+            }
+            Body::Enum(variants) => {
+                let variants = variants
+                    .iter()
+                    .filter(|variant| !(variant.skip_serializing && variant.skip_deserializing))
+                    .collect::<Vec<_>>();
+                if variants.iter().all(|variant| variant.body == Body::Unit) {
+                    self.enumeration(container, shape, &variants)
+                } else {
+                    let name = &container.name.serialize;
+                    let records = variants
+                        .iter()
+                        .map(|variant| self.variant_record(name, shape, variant))
+                        .collect::<Result<_, _>>()?;
+                    Ok(Json::Array(records))
+                }
+            }
+        }
+    }
+
+    fn enumeration(
+        &mut self,
+        container: &Container,
+        shape: &Shape,
+        variants: &[&Variant],
+    ) -> Result<Json, Error> {
+        let name = ident(&container.name.serialize);
+        if self.defined(&name, shape)? {
+            return Ok(name.into());
+        }
+        let symbols = variants
+            .iter()
+            .map(|variant| ident(&variant.name.serialize))
+            .collect::<Vec<_>>();
+        let mut schema = self.named("enum", &name, container.doc.as_deref());
+        schema.insert("symbols".into(), symbols.into());
+        // Unknown symbols are read as the one catching unknown variants
+        if let Some(other) = variants.iter().find(|variant| variant.other) {
+            schema.insert("default".into(), ident(&other.name.serialize).into());
+        }
+        Ok(Json::Object(schema))
+    }
+
+    /// Record of a variant of the enum of `shape`.
+    fn variant_record(
+        &mut self,
+        enum_name: &str,
+        shape: &Shape,
+        variant: &Variant,
+    ) -> Result<Json, Error> {
+        let name = format!("{enum_name}{}", type_name(&variant.name.serialize));
+        self.record(
+            &name,
+            shape,
+            variant.doc.as_deref(),
+            |generator| match &variant.body {
+                _ if variant.serialize_with.is_some() || variant.deserialize_with.is_some() => {
+                    Ok(vec![json!({"name": "field_0", "type": "bytes"})])
+                }
+                Body::Struct(fields) | Body::Tuple(fields) => generator.fields(fields),
+                Body::Unit => Ok(Vec::new()),
+                Body::Enum(_) => unreachable!("variants have no variants"),
+            },
+        )
+    }
+
+    /// Record named `name` for `shape` with the fields made by `fields`, or a
+    /// reference to it if it is defined already.
+    fn record(
+        &mut self,
+        name: &str,
+        shape: &Shape,
+        doc: Option<&str>,
+        fields: impl FnOnce(&mut Self) -> Result<Vec<Json>, Error>,
+    ) -> Result<Json, Error> {
+        let name = ident(name);
+        if self.defined(&name, shape)? {
+            return Ok(name.into());
+        }
+        let mut schema = self.named("record", &name, doc);
+        schema.insert("fields".into(), fields(self)?.into());
+        Ok(Json::Object(schema))
+    }
+
+    /// Whether `name` is defined already, for `shape` and not another one.
+    fn defined(&mut self, name: &str, shape: &Shape) -> Result<bool, Error> {
+        match self.defined.get(name) {
+            Some(defined) if defined == shape => Ok(true),
+            Some(_) => Err(Error::SameName(name.to_owned())),
+            None => {
+                self.defined.insert(name.to_owned(), shape.clone());
+                Ok(false)
+            }
+        }
+    }
+
+    fn named(&self, kind: &str, name: &str, doc: Option<&str>) -> Map<String, Json> {
+        let mut schema = Map::new();
+        schema.insert("type".into(), kind.into());
+        schema.insert("name".into(), name.into());
+        if let Some(namespace) = self.namespace {
+            schema.insert("namespace".into(), namespace.into());
+        }
+        if let Some(doc) = doc {
+            schema.insert("doc".into(), doc.into());
+        }
+        schema
+    }
+
+    fn fields(&mut self, fields: &[Field]) -> Result<Vec<Json>, Error> {
+        fields
+            .iter()
+            .filter(|field| !(field.skip_serializing && field.skip_deserializing))
+            .map(|field| self.field(field))
+            .collect()
+    }
+
+    fn field(&mut self, field: &Field) -> Result<Json, Error> {
+        // Fields of tuples are named by their positions, and have no aliases
+        let positional = (field.name.serialize).starts_with(|c: char| c.is_ascii_digit());
+        let name = if positional {
+            format!("field_{}", field.name.serialize)
+        } else {
+            ident(&field.name.serialize)
+        };
+        let mut schema = Map::new();
+        schema.insert("name".into(), name.clone().into());
+        let mut field_type = if field.serialize_with.is_some() || field.deserialize_with.is_some() {
+            "bytes".into()
+        } else {
+            self.schema(&field.shape)?
+        };
+        let default = (field.default_value.as_ref()).and_then(|value| default(&field.shape, value));
+        // The default of a union is one of its first member
+        if let (Some(default), Json::Array(members)) = (&default, &mut field_type)
+            && !default.is_null()
+        {
+            members.rotate_left(1);
+        }
+        schema.insert("type".into(), field_type);
+        if let Some(doc) = &field.doc {
+            schema.insert("doc".into(), doc.as_str().into());
+        }
+        if let Some(default) = default {
+            schema.insert("default".into(), default);
+        }
+        let mut aliases = Vec::new();
+        for alias in std::iter::once(&field.name.deserialize).chain(&field.name.deserialize_aliases)
+        {
+            let alias = ident(alias);
+            if !positional && alias != name && !aliases.contains(&alias) {
+                aliases.push(alias);
+            }
+        }
+        if !aliases.is_empty() {
+            schema.insert("aliases".into(), aliases.into());
+        }
+        Ok(Json::Object(schema))
+    }
+}
+
+fn primitive_schema(primitive: Primitive) -> &'static str {
+    match primitive {
+        Primitive::Bool => "boolean",
+        Primitive::I8 | Primitive::I16 | Primitive::I32 | Primitive::U8 | Primitive::U16 => "int",
+        Primitive::I64 | Primitive::U32 | Primitive::U64 => "long",
+        Primitive::F32 => "float",
+        Primitive::F64 => "double",
+        Primitive::I128 | Primitive::U128 | Primitive::Char | Primitive::String => "string",
+        Primitive::Unit => "null",
+    }
+}
+
+/// Members of a union with `schema`, as unions can't be nested.
+fn union_members(schema: Json) -> Vec<Json> {
+    match schema {
+        Json::Array(members) => members,
+        schema => vec![schema],
+    }
+}
+
+/// Field a newtype struct or a transparent type is serialized as.
+fn newtype_field<'a>(container: &Container, body: &'a Body) -> Option<&'a Field> {
+    let (Body::Struct(fields) | Body::Tuple(fields)) = body else {
+        return None;
+    };
+    let mut listed = fields
+        .iter()
+        .filter(|field| !(field.skip_serializing && field.skip_deserializing));
+    if container.transparent {
+        listed.next()
+    } else if let (Body::Tuple(_), [field]) = (body, &fields[..]) {
+        Some(field)
+    } else {
+        None
+    }
+}
+
+/// Default of a field, in the JSON encoding of the schema of `shape`.
+fn default(shape: &Shape, value: &Value) -> Option<Json> {
+    match (shape, value) {
+        (Shape::Primitive(Primitive::I128 | Primitive::U128), Value::Integer(value)) => {
+            Some(value.to_string().into())
+        }
+        (Shape::Primitive(_) | Shape::Option(_), Value::Null) => Some(Json::Null),
+        (Shape::Primitive(_), Value::Bool(value)) => Some((*value).into()),
+        (Shape::Primitive(_), Value::Integer(value)) => Some(i64::try_from(*value).ok()?.into()),
+        (Shape::Primitive(_), Value::Float(value)) => {
+            serde_json::Number::from_f64(*value).map(Json::Number)
+        }
+        (Shape::Primitive(_), Value::String(value)) => Some(value.as_str().into()),
+        (Shape::Option(shape), value) => default(shape, value),
+        (Shape::Seq(shape) | Shape::Array(shape, _), Value::Seq(values)) => values
+            .iter()
+            .map(|value| default(shape, value))
+            .collect::<Option<_>>()
+            .map(Json::Array),
+        (Shape::Tuple(shapes), Value::Seq(values)) => (shapes.iter().zip(values).enumerate())
+            .map(|(i, (shape, value))| Some((format!("field_{i}"), default(shape, value)?)))
+            .collect::<Option<_>>()
+            .map(Json::Object),
+        (Shape::Map(key, value_shape), Value::Map(entries)) => match **key {
+            Shape::Primitive(_) => entries
+                .iter()
+                .map(|(key, value)| Some((map_key(key)?, default(value_shape, value)?)))
+                .collect::<Option<_>>()
+                .map(Json::Object),
+            _ => entries
+                .iter()
+                .map(|(key_value, value)| {
+                    Some(json!({
+                        "key": default(key, key_value)?,
+                        "value": default(value_shape, value)?,
+                    }))
+                })
+                .collect::<Option<_>>()
+                .map(Json::Array),
+        },
+        _ => None,
+    }
+}
+
+fn map_key(key: &Value) -> Option<String> {
+    match key {
+        Value::Bool(key) => Some(key.to_string()),
+        Value::Integer(key) => Some(key.to_string()),
+        Value::String(key) => Some(key.clone()),
+        _ => None,
+    }
+}
@@ -0,0 +1,1135 @@
+//! Generic [`Deserialize`] implementation driven by [`SerdeGeneric::Repr`]
+//! and `serde` metadata.
+//!
+//! [`deserialize`] accepts the same input as `serde_derive` would for the
+//! same type and attributes, so it can be used to implement [`Deserialize`]
+//! by hand:
+//!
+//! ```
+//! # use serde_generic::SerdeGeneric;
+//! #[derive(SerdeGeneric)]
+//! #[serde(rename_all = "camelCase")]
+//! struct Foo {
+//!     foo_bar: u8,
+//!     #[serde(default)]
+//!     baz: Option<String>,
+//! }
+//!
+//! impl<'de> serde::Deserialize<'de> for Foo {
+//!     fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+//!         serde_generic::de::deserialize(deserializer)
+//!     }
+//! }
+//! ```
+//!
+//! Every field type has to implement [`Deserialize`], even skipped ones and
+//! ones with `deserialize_with`.
+//! Field names are not known statically, so structs are requested with
+//! an empty list of fields and only self-describing formats are supported.
+
+mod content;
+
+use self::content::{
+    Content, ContentDeserializer, FlatMapDeserializer, IdentifierSeed, MissingFieldDeserializer,
+};
+use crate::serde::{MultiName, TagType};
+use crate::traverse::{FieldAttrs, FieldDefaults, FieldWith, StructField, VariantField};
+use crate::{
+    Enum, HCons, HLen, HNil, HSum, HSumNil, NamedStruct, SerdeContainerDefault, SerdeContainerFrom,
+    SerdeGeneric, SerdeRemote, SerdeVariantAttr, SerdeVariantWith, Succ, TypeVar, UnitStruct,
+    UnnamedStruct, Zero,
+};
+use ::serde::de::{
+    DeserializeSeed, EnumAccess, Error as _, Expected, IgnoredAny, MapAccess, SeqAccess,
+    Unexpected, VariantAccess, Visitor,
+};
+pub use ::serde::{Deserialize, Deserializer};
+use core::fmt;
+use core::marker::PhantomData;
+
+/// Deserialize a value the way `serde_derive` would.
+///
+/// Types with `#[serde(from = "...")]` or `#[serde(try_from = "...")]` are
+/// rejected, as the conversion is not known here; use [`deserialize_from`]
+/// for them.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: SerdeGeneric,
+    T::Repr: DeserializeRepr<'de, T>,
+    D: Deserializer<'de>,
+{
+    let container = &T::CONTAINER;
+    if let Some(proxy) = container.type_from.or(container.type_try_from) {
+        return Err(D::Error::custom(format_args!(
+            "{} is deserialized from {proxy}, use `deserialize_from`",
+            container.name.deserialize,
+        )));
+    }
+    T::Repr::deserialize_repr(deserializer).map(T::from_repr)
+}
+
+/// Deserialize a value of the `#[serde(remote = "...")]` type mirrored by
+/// `T`, like the `deserialize` function `serde_derive` generates for remote
+/// types.
+pub fn deserialize_remote<'de, T, D>(deserializer: D) -> Result<T::Remote, D::Error>
+where
+    T: SerdeRemote,
+    T::Repr: DeserializeRepr<'de, T>,
+    D: Deserializer<'de>,
+{
+    T::Repr::deserialize_repr(deserializer).map(T::remote_from_repr)
+}
+
+/// Deserialize a value through its `#[serde(from = "...")]` or
+/// `#[serde(try_from = "...")]` proxy.
+pub fn deserialize_from<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: SerdeContainerFrom,
+    T::Proxy: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    let proxy = T::Proxy::deserialize(deserializer)?;
+    T::from_proxy(proxy).map_err(D::Error::custom)
+}
+
+/// Type variables carry no data and are deserialized from unit.
+impl<'de, X: Default> Deserialize<'de> for TypeVar<X> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <()>::deserialize(deserializer).map(|()| TypeVar::default())
+    }
+}
+
+/// Representation of `T` which can be deserialized.
+pub trait DeserializeRepr<'de, T>: Sized {
+    fn deserialize_repr<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+}
+
+impl<'de, T: SerdeGeneric> DeserializeRepr<'de, T> for UnitStruct {
+    fn deserialize_repr<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let visitor = UnitVisitor(Expecting::container::<T>("unit struct"));
+        deserializer.deserialize_unit_struct(T::CONTAINER.name.deserialize, visitor)
+    }
+}
+
+impl<'de, T, X> DeserializeRepr<'de, T> for NamedStruct<X>
+where
+    T: SerdeGeneric<Repr = Self> + SerdeContainerDefault,
+    X: HLen + DeserializeFields<'de, StructField<T>, X::Len>,
+{
+    fn deserialize_repr<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let container = &T::CONTAINER;
+        let fallback = <T as SerdeContainerDefault>::DEFAULT.map(|default| default().to_repr().0);
+        if container.transparent {
+            return TransparentSeed::<StructField<T>, X>::new(fallback)
+                .deserialize(deserializer)
+                .map(NamedStruct);
+        }
+        let visitor = FieldsVisitor::<StructField<T>, X> {
+            expecting: Expecting::container::<T>("struct"),
+            form: Form::Struct,
+            deny_unknown_fields: container.deny_unknown_fields,
+            fallback,
+            attrs: PhantomData,
+        };
+        deserializer
+            .deserialize_struct(container.name.deserialize, container.fields, visitor)
+            .map(NamedStruct)
+    }
+}
+
+impl<'de, T, X> DeserializeRepr<'de, T> for UnnamedStruct<X>
+where
+    T: SerdeGeneric<Repr = Self> + SerdeContainerDefault,
+    X: HLen + DeserializeFields<'de, StructField<T>, X::Len>,
+{
+    fn deserialize_repr<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let container = &T::CONTAINER;
+        let fallback = <T as SerdeContainerDefault>::DEFAULT.map(|default| default().to_repr().0);
+        if container.transparent {
+            return TransparentSeed::<StructField<T>, X>::new(fallback)
+                .deserialize(deserializer)
+                .map(UnnamedStruct);
+        }
+        let name = container.name.deserialize;
+        let newtype = is_newtype::<X>();
+        let visitor = FieldsVisitor::<StructField<T>, X> {
+            expecting: Expecting::container::<T>("tuple struct"),
+            form: if newtype { Form::Newtype } else { Form::Tuple },
+            deny_unknown_fields: container.deny_unknown_fields,
+            fallback,
+            attrs: PhantomData,
+        };
+        if newtype {
+            deserializer.deserialize_newtype_struct(name, visitor)
+        } else {
+            deserializer.deserialize_tuple_struct(name, X::seq_len(), visitor)
+        }
+        .map(UnnamedStruct)
+    }
+}
+
+impl<'de, T, X> DeserializeRepr<'de, T> for Enum<X>
+where
+    T: SerdeGeneric,
+    X: HLen + DeserializeVariants<'de, T, X::Len>,
+{
+    fn deserialize_repr<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let container = &T::CONTAINER;
+        let name = container.name.deserialize;
+        let variants = container.variants;
+        let visitor = EnumVisitor::<T, X>(PhantomData);
+        if container.tag == TagType::External && !X::has_untagged() {
+            return deserializer
+                .deserialize_enum(name, variants, visitor)
+                .map(Enum);
+        }
+        // Other representations can only be recognized after looking ahead
+        let content = Content::deserialize(deserializer)?;
+        let tagged = match container.tag {
+            TagType::External => ContentDeserializer::new(content.clone())
+                .deserialize_enum(name, variants, visitor)
+                .map(Some),
+            TagType::Internal { tag } => deserialize_internal::<T, X, D::Error>(tag, &content),
+            TagType::Adjacent { tag, content: key } => {
+                deserialize_adjacent::<T, X, D::Error>(tag, key, &content)
+            }
+            TagType::None => Ok(None),
+        };
+        let untagged = container.tag == TagType::None;
+        match tagged {
+            Ok(Some(value)) => Ok(Enum(value)),
+            Err(err) if !X::has_untagged() => Err(err),
+            _ => X::deserialize_untagged(&content, untagged)
+                .map(Enum)
+                .ok_or_else(|| {
+                    D::Error::custom(format_args!(
+                        "data did not match any variant of untagged enum {name}"
+                    ))
+                }),
+        }
+    }
+}
+
+fn is_newtype<X: HLen>() -> bool {
+    <X::Len as crate::PeanoNumber>::NUMBER == 1
+}
+
+/// Position of the variant named by `key`, or of the `#[serde(other)]` one.
+fn variant_position<'de, T, X, E>(key: &Content) -> Result<usize, E>
+where
+    T: SerdeGeneric,
+    X: HLen + DeserializeVariants<'de, T, X::Len>,
+    E: ::serde::de::Error,
+{
+    X::position(key, 0).or_else(X::other).ok_or_else(|| {
+        let mut names = Vec::new();
+        X::names(&mut names);
+        unknown("variant", key, &names)
+    })
+}
+
+/// Internally tagged enum: `{"tag": "variant", ...fields}`.
+fn deserialize_internal<'de, T, X, E>(tag: &'static str, content: &Content) -> Result<Option<X>, E>
+where
+    T: SerdeGeneric,
+    X: HLen + DeserializeVariants<'de, T, X::Len>,
+    E: ::serde::de::Error,
+{
+    let Content::Map(entries) = content else {
+        return Err(E::invalid_type(
+            Unexpected::Other("non-map"),
+            &Expecting::container::<T>("internally tagged enum"),
+        ));
+    };
+    let mut variant = None;
+    let mut rest = Vec::with_capacity(entries.len());
+    for (key, value) in entries {
+        if key.as_str() == Some(tag) {
+            if variant.is_some() {
+                return Err(E::duplicate_field(tag));
+            }
+            variant = Some(value);
+        } else {
+            rest.push((key.clone(), value.clone()));
+        }
+    }
+    let variant = variant.ok_or_else(|| E::missing_field(tag))?;
+    let position = variant_position::<T, X, E>(variant)?;
+    X::deserialize_internal(position, Content::Map(rest)).map(Some)
+}
+
+/// Adjacently tagged enum: `{"tag": "variant", "content": ...}`.
+fn deserialize_adjacent<'de, T, X, E>(
+    tag: &'static str,
+    content_key: &'static str,
+    content: &Content,
+) -> Result<Option<X>, E>
+where
+    T: SerdeGeneric,
+    X: HLen + DeserializeVariants<'de, T, X::Len>,
+    E: ::serde::de::Error,
+{
+    let Content::Map(entries) = content else {
+        return Err(E::invalid_type(
+            Unexpected::Other("non-map"),
+            &Expecting::container::<T>("adjacently tagged enum"),
+        ));
+    };
+    let mut variant = None;
+    let mut value = None;
+    for (key, entry) in entries {
+        match key.as_str() {
+            Some(key) if key == tag && variant.replace(entry).is_some() => {
+                return Err(E::duplicate_field(tag));
+            }
+            Some(key) if key == content_key && value.replace(entry).is_some() => {
+                return Err(E::duplicate_field(content_key));
+            }
+            Some(key) if key == tag || key == content_key => {}
+            _ if T::CONTAINER.deny_unknown_fields => {
+                return Err(unknown("field", key, &[tag, content_key]));
+            }
+            _ => {}
+        }
+    }
+    let variant = variant.ok_or_else(|| E::missing_field(tag))?;
+    let position = variant_position::<T, X, E>(variant)?;
+    match value {
+        Some(value) => X::deserialize_content(position, value.clone()).map(Some),
+        None => X::deserialize_missing(position, content_key).map(Some),
+    }
+}
+
+/// Error about an unexpected field or variant, worded like
+/// `Error::unknown_field` and `Error::unknown_variant`.
+fn unknown<E: ::serde::de::Error>(what: &str, key: &Content, expected: &[&str]) -> E {
+    let Some(key) = key.as_str() else {
+        return E::custom(format_args!("invalid {what} identifier"));
+    };
+    match expected {
+        [] => E::custom(format_args!("unknown {what} `{key}`, there are no {what}s")),
+        [one] => E::custom(format_args!("unknown {what} `{key}`, expected `{one}`")),
+        [one, two] => E::custom(format_args!(
+            "unknown {what} `{key}`, expected `{one}` or `{two}`"
+        )),
+        _ => {
+            let expected = expected
+                .iter()
+                .map(|name| format!("`{name}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            E::custom(format_args!(
+                "unknown {what} `{key}`, expected one of {expected}"
+            ))
+        }
+    }
+}
+
+fn matches_name(name: &MultiName, key: &str) -> bool {
+    name.deserialize == key || name.deserialize_aliases.contains(&key)
+}
+
+fn push_names(name: &MultiName, names: &mut Vec<&'static str>) {
+    if name.deserialize_aliases.is_empty() {
+        names.push(name.deserialize);
+    } else {
+        names.extend(name.deserialize_aliases);
+    }
+}
+
+/// What a visitor expects, phrased like `serde_derive` does.
+#[derive(Clone, Copy)]
+struct Expecting {
+    custom: Option<&'static str>,
+    kind: &'static str,
+    name: &'static str,
+    variant: Option<&'static str>,
+}
+
+impl Expecting {
+    fn container<T: SerdeGeneric>(kind: &'static str) -> Self {
+        Expecting {
+            custom: T::CONTAINER.expecting,
+            kind,
+            name: T::CONTAINER.name.deserialize,
+            variant: None,
+        }
+    }
+
+    fn variant<T: SerdeGeneric + SerdeVariantAttr<VI>, VI>(kind: &'static str) -> Self {
+        Expecting {
+            variant: Some(T::VARIANT.name.deserialize),
+            ..Self::container::<T>(kind)
+        }
+    }
+}
+
+impl fmt::Display for Expecting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(custom) = self.custom {
+            return f.write_str(custom);
+        }
+        write!(f, "{} {}", self.kind, self.name)?;
+        if let Some(variant) = self.variant {
+            write!(f, "::{variant}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Expected for Expecting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Expectation of a sequence of `len` fields.
+struct WithLen(Expecting, usize);
+
+impl Expected for WithLen {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let plural = if self.1 == 1 { "" } else { "s" };
+        write!(f, "{} with {} element{plural}", self.0, self.1)
+    }
+}
+
+/// List of field values, with metadata looked up in `A`.
+pub trait DeserializeFields<'de, A, I>: Sized {
+    /// Values of the fields read so far.
+    type Slots;
+
+    fn slots() -> Self::Slots;
+    /// Whether any of the fields is `#[serde(flatten)]`.
+    fn has_flatten() -> bool;
+    /// Number of fields which are read from a sequence.
+    fn seq_len() -> usize;
+    /// Names of the fields which can be read from a map.
+    fn names(names: &mut Vec<&'static str>);
+
+    /// Reads the value of the entry `key`, if it belongs to one of the fields.
+    fn deserialize_entry<M: MapAccess<'de>>(
+        slots: &mut Self::Slots,
+        key: &str,
+        map: &mut M,
+    ) -> Result<bool, M::Error>;
+
+    /// Reads `#[serde(flatten)]` fields from the entries not claimed by
+    /// other fields.
+    fn deserialize_flatten<E: ::serde::de::Error>(
+        slots: &mut Self::Slots,
+        entries: &mut Vec<Option<(Content, Content)>>,
+    ) -> Result<(), E>;
+
+    /// Reads fields in order. Missing trailing elements are an error
+    /// unless `lenient` or the field has a default.
+    fn deserialize_seq<S: SeqAccess<'de>>(
+        slots: &mut Self::Slots,
+        seq: &mut S,
+        read: &mut usize,
+        lenient: bool,
+        expected: &dyn Expected,
+    ) -> Result<(), S::Error>;
+
+    /// Reads the first non-skipped field with the whole deserializer.
+    fn deserialize_transparent<D: Deserializer<'de>>(
+        slots: &mut Self::Slots,
+        deserializer: &mut Option<D>,
+    ) -> Result<(), D::Error>;
+
+    /// Fills in missing fields with their defaults, falling back to the
+    /// value from `#[serde(default)]` on the container.
+    fn finish<E: ::serde::de::Error>(slots: Self::Slots, fallback: Option<Self>)
+    -> Result<Self, E>;
+}
+
+/// Field value, deserialized with its `deserialize_with` function if any.
+struct FieldSeed<A, F, I>(PhantomData<(A, F, I)>);
+
+impl<A, F, I> FieldSeed<A, F, I> {
+    fn new() -> Self {
+        FieldSeed(PhantomData)
+    }
+}
+
+impl<'de, A: FieldWith<F, I>, F: Deserialize<'de>, I> DeserializeSeed<'de> for FieldSeed<A, F, I> {
+    type Value = F;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<F, D::Error> {
+        match A::deserialize_with() {
+            Some(deserialize_with) => deserialize_with(deserializer),
+            None => F::deserialize(deserializer),
+        }
+    }
+}
+
+impl<'de, A, H, T, I> DeserializeFields<'de, A, Succ<I>> for HCons<H, T>
+where
+    A: FieldAttrs<H, Succ<I>> + FieldDefaults<H, Succ<I>> + FieldWith<H, Succ<I>>,
+    H: Deserialize<'de>,
+    T: DeserializeFields<'de, A, I>,
+{
+    type Slots = HCons<Option<H>, T::Slots>;
+
+    fn slots() -> Self::Slots {
+        HCons(None, T::slots())
+    }
+
+    fn has_flatten() -> bool {
+        A::FIELD.flatten || T::has_flatten()
+    }
+
+    fn seq_len() -> usize {
+        usize::from(!A::FIELD.skip_deserializing) + T::seq_len()
+    }
+
+    fn names(names: &mut Vec<&'static str>) {
+        let field = A::FIELD;
+        if !field.skip_deserializing && !field.flatten {
+            push_names(&field.name, names);
+        }
+        T::names(names)
+    }
+
+    fn deserialize_entry<M: MapAccess<'de>>(
+        slots: &mut Self::Slots,
+        key: &str,
+        map: &mut M,
+    ) -> Result<bool, M::Error> {
+        let field = A::FIELD;
+        if field.skip_deserializing || field.flatten || !matches_name(&field.name, key) {
+            return T::deserialize_entry(&mut slots.1, key, map);
+        }
+        if slots.0.is_some() {
+            return Err(M::Error::duplicate_field(field.name.deserialize));
+        }
+        slots.0 = Some(map.next_value_seed(FieldSeed::<A, H, Succ<I>>::new())?);
+        Ok(true)
+    }
+
+    fn deserialize_flatten<E: ::serde::de::Error>(
+        slots: &mut Self::Slots,
+        entries: &mut Vec<Option<(Content, Content)>>,
+    ) -> Result<(), E> {
+        let field = A::FIELD;
+        if field.flatten && !field.skip_deserializing {
+            let seed = FieldSeed::<A, H, Succ<I>>::new();
+            slots.0 = Some(seed.deserialize(FlatMapDeserializer::new(entries))?);
+        }
+        T::deserialize_flatten(&mut slots.1, entries)
+    }
+
+    fn deserialize_seq<S: SeqAccess<'de>>(
+        slots: &mut Self::Slots,
+        seq: &mut S,
+        read: &mut usize,
+        lenient: bool,
+        expected: &dyn Expected,
+    ) -> Result<(), S::Error> {
+        if !A::FIELD.skip_deserializing {
+            slots.0 = seq.next_element_seed(FieldSeed::<A, H, Succ<I>>::new())?;
+            if slots.0.is_none() && !lenient && A::DEFAULT.is_none() {
+                return Err(S::Error::invalid_length(*read, expected));
+            }
+            *read += 1;
+        }
+        T::deserialize_seq(&mut slots.1, seq, read, lenient, expected)
+    }
+
+    fn deserialize_transparent<D: Deserializer<'de>>(
+        slots: &mut Self::Slots,
+        deserializer: &mut Option<D>,
+    ) -> Result<(), D::Error> {
+        if !A::FIELD.skip_deserializing
+            && let Some(deserializer) = deserializer.take()
+        {
+            slots.0 = Some(FieldSeed::<A, H, Succ<I>>::new().deserialize(deserializer)?);
+        }
+        T::deserialize_transparent(&mut slots.1, deserializer)
+    }
+
+    fn finish<E: ::serde::de::Error>(
+        slots: Self::Slots,
+        fallback: Option<Self>,
+    ) -> Result<Self, E> {
+        let (fallback_head, fallback_tail) = match fallback {
+            Some(HCons(head, tail)) => (Some(head), Some(tail)),
+            None => (None, None),
+        };
+        let head = match (slots.0, A::DEFAULT, fallback_head) {
+            (Some(value), _, _) => value,
+            (None, Some(default), _) => default(),
+            (None, None, Some(value)) => value,
+            (None, None, None) if A::FIELD.deserialize_with.is_some() => {
+                return Err(E::missing_field(A::FIELD.name.deserialize));
+            }
+            // `Option` fields may be omitted
+            (None, None, None) => H::deserialize(MissingFieldDeserializer::<E>::new(
+                A::FIELD.name.deserialize,
+            ))?,
+        };
+        Ok(HCons(head, T::finish(slots.1, fallback_tail)?))
+    }
+}
+
+impl<'de, A> DeserializeFields<'de, A, Zero> for HNil {
+    type Slots = HNil;
+
+    fn slots() -> HNil {
+        HNil
+    }
+
+    fn has_flatten() -> bool {
+        false
+    }
+
+    fn seq_len() -> usize {
+        0
+    }
+
+    fn names(_names: &mut Vec<&'static str>) {}
+
+    fn deserialize_entry<M: MapAccess<'de>>(
+        _slots: &mut HNil,
+        _key: &str,
+        _map: &mut M,
+    ) -> Result<bool, M::Error> {
+        Ok(false)
+    }
+
+    fn deserialize_flatten<E: ::serde::de::Error>(
+        _slots: &mut HNil,
+        _entries: &mut Vec<Option<(Content, Content)>>,
+    ) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn deserialize_seq<S: SeqAccess<'de>>(
+        _slots: &mut HNil,
+        _seq: &mut S,
+        _read: &mut usize,
+        _lenient: bool,
+        _expected: &dyn Expected,
+    ) -> Result<(), S::Error> {
+        Ok(())
+    }
+
+    fn deserialize_transparent<D: Deserializer<'de>>(
+        _slots: &mut HNil,
+        _deserializer: &mut Option<D>,
+    ) -> Result<(), D::Error> {
+        Ok(())
+    }
+
+    fn finish<E: ::serde::de::Error>(_slots: HNil, _fallback: Option<Self>) -> Result<Self, E> {
+        Ok(HNil)
+    }
+}
+
+/// Shapes of input accepted by [`FieldsVisitor`].
+#[derive(Clone, Copy, PartialEq)]
+enum Form {
+    /// A map or a sequence.
+    Struct,
+    /// A sequence.
+    Tuple,
+    /// A newtype or a sequence.
+    Newtype,
+}
+
+/// Reads fields of a struct or a variant.
+struct FieldsVisitor<A, X> {
+    expecting: Expecting,
+    form: Form,
+    deny_unknown_fields: bool,
+    fallback: Option<X>,
+    attrs: PhantomData<A>,
+}
+
+impl<A, X> FieldsVisitor<A, X> {
+    fn variant<T, VI>(kind: &'static str, form: Form) -> Self
+    where
+        T: SerdeGeneric + SerdeVariantAttr<VI>,
+    {
+        FieldsVisitor {
+            expecting: Expecting::variant::<T, VI>(kind),
+            form,
+            deny_unknown_fields: T::CONTAINER.deny_unknown_fields,
+            fallback: None,
+            attrs: PhantomData,
+        }
+    }
+}
+
+impl<'de, A, X> Visitor<'de> for FieldsVisitor<A, X>
+where
+    X: HLen + DeserializeFields<'de, A, X::Len>,
+{
+    type Value = X;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.expecting, f)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<X, D::Error> {
+        if self.form != Form::Newtype {
+            return Err(D::Error::invalid_type(Unexpected::NewtypeStruct, &self));
+        }
+        TransparentSeed::<A, X>::new(self.fallback).deserialize(deserializer)
+    }
+
+    fn visit_seq<S: SeqAccess<'de>>(self, mut seq: S) -> Result<X, S::Error> {
+        let mut slots = X::slots();
+        let lenient = self.fallback.is_some();
+        let expected = WithLen(self.expecting, X::seq_len());
+        X::deserialize_seq(&mut slots, &mut seq, &mut 0, lenient, &expected)?;
+        X::finish(slots, self.fallback)
+    }
+
+    fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<X, M::Error> {
+        if self.form != Form::Struct {
+            return Err(M::Error::invalid_type(Unexpected::Map, &self));
+        }
+        let mut slots = X::slots();
+        let mut unclaimed = Vec::new();
+        while let Some(key) = map.next_key_seed(IdentifierSeed)? {
+            let claimed = match key.as_str() {
+                Some(name) => X::deserialize_entry(&mut slots, name, &mut map)?,
+                None => false,
+            };
+            if claimed {
+                continue;
+            }
+            if X::has_flatten() {
+                unclaimed.push(Some((key, map.next_value()?)));
+            } else if self.deny_unknown_fields {
+                let mut names = Vec::new();
+                X::names(&mut names);
+                return Err(unknown("field", &key, &names));
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        X::deserialize_flatten(&mut slots, &mut unclaimed)?;
+        if self.deny_unknown_fields
+            && let Some((key, _)) = unclaimed.iter().flatten().next()
+        {
+            let mut names = Vec::new();
+            X::names(&mut names);
+            return Err(unknown("field", key, &names));
+        }
+        X::finish(slots, self.fallback)
+    }
+}
+
+/// Reads the only deserialized field of a transparent struct or a newtype.
+struct TransparentSeed<A, X> {
+    fallback: Option<X>,
+    attrs: PhantomData<A>,
+}
+
+impl<A, X> TransparentSeed<A, X> {
+    fn new(fallback: Option<X>) -> Self {
+        TransparentSeed {
+            fallback,
+            attrs: PhantomData,
+        }
+    }
+}
+
+impl<'de, A, X> DeserializeSeed<'de> for TransparentSeed<A, X>
+where
+    X: HLen + DeserializeFields<'de, A, X::Len>,
+{
+    type Value = X;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<X, D::Error> {
+        let mut slots = X::slots();
+        X::deserialize_transparent(&mut slots, &mut Some(deserializer))?;
+        X::finish(slots, self.fallback)
+    }
+}
+
+/// Accepts unit and empty maps.
+struct UnitVisitor(Expecting);
+
+impl<'de> Visitor<'de> for UnitVisitor {
+    type Value = UnitStruct;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+
+    fn visit_unit<E>(self) -> Result<UnitStruct, E> {
+        Ok(UnitStruct)
+    }
+
+    fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<UnitStruct, M::Error> {
+        match map.next_key::<IgnoredAny>()? {
+            None => Ok(UnitStruct),
+            Some(_) => Err(M::Error::invalid_type(Unexpected::Map, &self)),
+        }
+    }
+}
+
+/// Accepts what is left of a unit variant of an internally tagged enum,
+/// ignoring any other entries as `serde` does, even with
+/// `deny_unknown_fields`.
+struct InternalUnitVisitor(Expecting);
+
+impl<'de> Visitor<'de> for InternalUnitVisitor {
+    type Value = UnitStruct;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+
+    fn visit_unit<E>(self) -> Result<UnitStruct, E> {
+        Ok(UnitStruct)
+    }
+
+    fn visit_seq<S: SeqAccess<'de>>(self, _: S) -> Result<UnitStruct, S::Error> {
+        Ok(UnitStruct)
+    }
+
+    fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<UnitStruct, M::Error> {
+        while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+        Ok(UnitStruct)
+    }
+}
+
+/// Sum of enum variants of `T`.
+pub trait DeserializeVariants<'de, T, I>: Sized {
+    /// Whether any of the variants is `#[serde(untagged)]`.
+    fn has_untagged() -> bool;
+    /// Names of the tagged variants.
+    fn names(names: &mut Vec<&'static str>);
+    /// Position of the tagged variant identified by name or by `index`
+    /// among the deserializable tagged variants.
+    fn position(key: &Content, index: u64) -> Option<usize>;
+    /// Position of the `#[serde(other)]` variant.
+    fn other() -> Option<usize>;
+
+    fn deserialize_access<A: VariantAccess<'de>>(
+        position: usize,
+        access: A,
+    ) -> Result<Self, A::Error>;
+
+    fn deserialize_content<E: ::serde::de::Error>(
+        position: usize,
+        content: Content,
+    ) -> Result<Self, E>;
+
+    fn deserialize_internal<E: ::serde::de::Error>(
+        position: usize,
+        content: Content,
+    ) -> Result<Self, E>;
+
+    /// Reads the variant of an adjacently tagged enum with no `content` key.
+    fn deserialize_missing<E: ::serde::de::Error>(
+        position: usize,
+        content: &'static str,
+    ) -> Result<Self, E>;
+
+    /// Tries untagged variants in order, or every variant if `all`.
+    fn deserialize_untagged(content: &Content, all: bool) -> Option<Self>;
+}
+
+impl<'de, T, H, R, I> DeserializeVariants<'de, T, Succ<I>> for HSum<H, R>
+where
+    T: SerdeVariantAttr<Succ<I>> + SerdeVariantWith<H, Succ<I>>,
+    H: DeserializeVariant<'de, T, Succ<I>>,
+    R: DeserializeVariants<'de, T, I>,
+{
+    fn has_untagged() -> bool {
+        T::VARIANT.untagged || R::has_untagged()
+    }
+
+    fn names(names: &mut Vec<&'static str>) {
+        let variant = T::VARIANT;
+        if !variant.skip_deserializing && !variant.untagged {
+            push_names(&variant.name, names);
+        }
+        R::names(names)
+    }
+
+    fn position(key: &Content, index: u64) -> Option<usize> {
+        let variant = T::VARIANT;
+        if variant.skip_deserializing || variant.untagged {
+            return R::position(key, index).map(|position| position + 1);
+        }
+        let found = match key {
+            Content::U64(key) => *key == index,
+            key => key
+                .as_str()
+                .is_some_and(|key| matches_name(&variant.name, key)),
+        };
+        if found {
+            Some(0)
+        } else {
+            R::position(key, index + 1).map(|position| position + 1)
+        }
+    }
+
+    fn other() -> Option<usize> {
+        let variant = T::VARIANT;
+        if variant.other && !variant.skip_deserializing {
+            Some(0)
+        } else {
+            R::other().map(|position| position + 1)
+        }
+    }
+
+    fn deserialize_access<A: VariantAccess<'de>>(
+        position: usize,
+        access: A,
+    ) -> Result<Self, A::Error> {
+        match position {
+            0 if T::VARIANT.deserialize_with.is_some() => access
+                .newtype_variant_seed(VariantWithSeed::<T, Succ<I>, H>(PhantomData))
+                .map(HSum::L),
+            0 => H::deserialize_access(access).map(HSum::L),
+            _ => R::deserialize_access(position - 1, access).map(HSum::R),
+        }
+    }
+
+    fn deserialize_content<E: ::serde::de::Error>(
+        position: usize,
+        content: Content,
+    ) -> Result<Self, E> {
+        match position {
+            0 => {
+                variant_content::<T, Succ<I>, H, _>(ContentDeserializer::new(content)).map(HSum::L)
+            }
+            _ => R::deserialize_content(position - 1, content).map(HSum::R),
+        }
+    }
+
+    fn deserialize_internal<E: ::serde::de::Error>(
+        position: usize,
+        content: Content,
+    ) -> Result<Self, E> {
+        match position {
+            0 => match T::deserialize_with() {
+                Some(deserialize_with) => deserialize_with(ContentDeserializer::new(content)),
+                None => H::deserialize_internal(ContentDeserializer::new(content)),
+            }
+            .map(HSum::L),
+            _ => R::deserialize_internal(position - 1, content).map(HSum::R),
+        }
+    }
+
+    fn deserialize_missing<E: ::serde::de::Error>(
+        position: usize,
+        content: &'static str,
+    ) -> Result<Self, E> {
+        match position {
+            0 => H::deserialize_missing(content).map(HSum::L),
+            _ => R::deserialize_missing(position - 1, content).map(HSum::R),
+        }
+    }
+
+    fn deserialize_untagged(content: &Content, all: bool) -> Option<Self> {
+        let variant = T::VARIANT;
+        if !variant.skip_deserializing && (all || variant.untagged) {
+            let deserializer =
+                ContentDeserializer::<::serde::de::value::Error>::new(content.clone());
+            if let Ok(value) = variant_content::<T, Succ<I>, H, _>(deserializer) {
+                return Some(HSum::L(value));
+            }
+        }
+        R::deserialize_untagged(content, all).map(HSum::R)
+    }
+}
+
+impl<'de, T> DeserializeVariants<'de, T, Zero> for HSumNil {
+    fn has_untagged() -> bool {
+        false
+    }
+
+    fn names(_names: &mut Vec<&'static str>) {}
+
+    fn position(_key: &Content, _index: u64) -> Option<usize> {
+        None
+    }
+
+    fn other() -> Option<usize> {
+        None
+    }
+
+    fn deserialize_access<A: VariantAccess<'de>>(_: usize, _: A) -> Result<Self, A::Error> {
+        Err(A::Error::custom("variant index out of range"))
+    }
+
+    fn deserialize_content<E: ::serde::de::Error>(_: usize, _: Content) -> Result<Self, E> {
+        Err(E::custom("variant index out of range"))
+    }
+
+    fn deserialize_internal<E: ::serde::de::Error>(_: usize, _: Content) -> Result<Self, E> {
+        Err(E::custom("variant index out of range"))
+    }
+
+    fn deserialize_missing<E: ::serde::de::Error>(_: usize, _: &'static str) -> Result<Self, E> {
+        Err(E::custom("variant index out of range"))
+    }
+
+    fn deserialize_untagged(_content: &Content, _all: bool) -> Option<Self> {
+        None
+    }
+}
+
+/// Reads the variant `VI` of enum `T` from its content alone, with its
+/// `deserialize_with` function if it has one.
+fn variant_content<'de, T, VI, H, D>(deserializer: D) -> Result<H, D::Error>
+where
+    T: SerdeVariantWith<H, VI>,
+    H: DeserializeVariant<'de, T, VI>,
+    D: Deserializer<'de>,
+{
+    match T::deserialize_with() {
+        Some(deserialize_with) => deserialize_with(deserializer),
+        None => H::deserialize_content(deserializer),
+    }
+}
+
+/// Variant with `#[serde(deserialize_with = "...")]` of an externally tagged
+/// enum, which is read as a newtype variant as in `serde_derive`.
+struct VariantWithSeed<T, VI, H>(PhantomData<(T, VI, H)>);
+
+impl<'de, T, VI, H> DeserializeSeed<'de> for VariantWithSeed<T, VI, H>
+where
+    T: SerdeVariantWith<H, VI>,
+    H: DeserializeVariant<'de, T, VI>,
+{
+    type Value = H;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<H, D::Error> {
+        variant_content::<T, VI, H, D>(deserializer)
+    }
+}
+
+/// Representation of the variant `VI` of enum `T`.
+pub trait DeserializeVariant<'de, T, VI>: Sized {
+    /// Reads the variant from an externally tagged enum.
+    fn deserialize_access<A: VariantAccess<'de>>(access: A) -> Result<Self, A::Error>;
+    /// Reads the variant from its content alone.
+    fn deserialize_content<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+    /// Reads the variant from the entries besides the tag of an internally
+    /// tagged enum.
+    fn deserialize_internal<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::deserialize_content(deserializer)
+    }
+    /// Reads the variant from an adjacently tagged enum with no `content`
+    /// key, which only unit and newtype variants accept, as in `serde_derive`.
+    fn deserialize_missing<E: ::serde::de::Error>(content: &'static str) -> Result<Self, E> {
+        Err(E::missing_field(content))
+    }
+}
+
+impl<'de, T, VI> DeserializeVariant<'de, T, VI> for UnitStruct
+where
+    T: SerdeGeneric + SerdeVariantAttr<VI>,
+{
+    fn deserialize_access<A: VariantAccess<'de>>(access: A) -> Result<Self, A::Error> {
+        access.unit_variant().map(|()| UnitStruct)
+    }
+
+    fn deserialize_content<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(UnitVisitor(Expecting::variant::<T, VI>("unit variant")))
+    }
+
+    fn deserialize_internal<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let visitor = InternalUnitVisitor(Expecting::variant::<T, VI>("unit variant"));
+        deserializer.deserialize_any(visitor)
+    }
+
+    fn deserialize_missing<E: ::serde::de::Error>(_content: &'static str) -> Result<Self, E> {
+        Ok(UnitStruct)
+    }
+}
+
+impl<'de, T, VI, X> DeserializeVariant<'de, T, VI> for UnnamedStruct<X>
+where
+    T: SerdeGeneric + SerdeVariantAttr<VI>,
+    X: HLen + DeserializeFields<'de, VariantField<T, VI>, X::Len>,
+{
+    fn deserialize_access<A: VariantAccess<'de>>(access: A) -> Result<Self, A::Error> {
+        if is_newtype::<X>() {
+            return access
+                .newtype_variant_seed(TransparentSeed::<VariantField<T, VI>, X>::new(None))
+                .map(UnnamedStruct);
+        }
+        let visitor =
+            FieldsVisitor::<VariantField<T, VI>, X>::variant::<T, VI>("tuple variant", Form::Tuple);
+        access
+            .tuple_variant(X::seq_len(), visitor)
+            .map(UnnamedStruct)
+    }
+
+    fn deserialize_content<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if is_newtype::<X>() {
+            return TransparentSeed::<VariantField<T, VI>, X>::new(None)
+                .deserialize(deserializer)
+                .map(UnnamedStruct);
+        }
+        let visitor =
+            FieldsVisitor::<VariantField<T, VI>, X>::variant::<T, VI>("tuple variant", Form::Tuple);
+        deserializer
+            .deserialize_tuple(X::seq_len(), visitor)
+            .map(UnnamedStruct)
+    }
+
+    fn deserialize_missing<E: ::serde::de::Error>(content: &'static str) -> Result<Self, E> {
+        if !is_newtype::<X>() || T::VARIANT.deserialize_with.is_some() {
+            return Err(E::missing_field(content));
+        }
+        // `Option` fields may be omitted
+        TransparentSeed::<VariantField<T, VI>, X>::new(None)
+            .deserialize(MissingFieldDeserializer::<E>::new(content))
+            .map(UnnamedStruct)
+    }
+}
+
+impl<'de, T, VI, X> DeserializeVariant<'de, T, VI> for NamedStruct<X>
+where
+    T: SerdeGeneric + SerdeVariantAttr<VI>,
+    X: HLen + DeserializeFields<'de, VariantField<T, VI>, X::Len>,
+{
+    fn deserialize_access<A: VariantAccess<'de>>(access: A) -> Result<Self, A::Error> {
+        let visitor = FieldsVisitor::<VariantField<T, VI>, X>::variant::<T, VI>(
+            "struct variant",
+            Form::Struct,
+        );
+        access
+            .struct_variant(T::VARIANT.fields, visitor)
+            .map(NamedStruct)
+    }
+
+    fn deserialize_content<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let visitor = FieldsVisitor::<VariantField<T, VI>, X>::variant::<T, VI>(
+            "struct variant",
+            Form::Struct,
+        );
+        deserializer.deserialize_any(visitor).map(NamedStruct)
+    }
+}
+
+/// Reads an externally tagged enum.
+struct EnumVisitor<T, X>(PhantomData<(T, X)>);
+
+impl<'de, T, X> Visitor<'de> for EnumVisitor<T, X>
+where
+    T: SerdeGeneric,
+    X: HLen + DeserializeVariants<'de, T, X::Len>,
+{
+    type Value = X;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&Expecting::container::<T>("enum"), f)
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<X, A::Error> {
+        let (key, access) = data.variant_seed(IdentifierSeed)?;
+        let position = variant_position::<T, X, A::Error>(&key)?;
+        X::deserialize_access(position, access)
+    }
+}
@@ -0,0 +1,634 @@
+//! Buffered self-describing data, for representations which can only be
+//! decided after looking ahead: tagged and untagged enums and flattened
+//! fields.
+
+use ::serde::de::value::{MapDeserializer, SeqDeserializer};
+use ::serde::de::{
+    self, Deserialize, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess,
+    SeqAccess, Unexpected, VariantAccess, Visitor,
+};
+use ::serde::forward_to_deserialize_any;
+use core::fmt;
+use core::marker::PhantomData;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+    Bool(bool),
+    U64(u64),
+    I64(i64),
+    U128(u128),
+    I128(i128),
+    F64(f64),
+    Char(char),
+    String(String),
+    Bytes(Vec<u8>),
+    None,
+    Some(Box<Content>),
+    Unit,
+    Newtype(Box<Content>),
+    Seq(Vec<Content>),
+    Map(Vec<(Content, Content)>),
+}
+
+impl Content {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Content::String(s) => Some(s),
+            Content::Bytes(b) => core::str::from_utf8(b).ok(),
+            _ => None,
+        }
+    }
+
+    fn unexpected(&self) -> Unexpected<'_> {
+        match self {
+            Content::Bool(b) => Unexpected::Bool(*b),
+            Content::U64(n) => Unexpected::Unsigned(*n),
+            Content::I64(n) => Unexpected::Signed(*n),
+            Content::U128(_) | Content::I128(_) => Unexpected::Other("128-bit integer"),
+            Content::F64(f) => Unexpected::Float(*f),
+            Content::Char(c) => Unexpected::Char(*c),
+            Content::String(s) => Unexpected::Str(s),
+            Content::Bytes(b) => Unexpected::Bytes(b),
+            Content::None | Content::Some(_) => Unexpected::Option,
+            Content::Unit => Unexpected::Unit,
+            Content::Newtype(_) => Unexpected::NewtypeStruct,
+            Content::Seq(_) => Unexpected::Seq,
+            Content::Map(_) => Unexpected::Map,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Content {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ContentVisitor)
+    }
+}
+
+/// Deserializes map keys and variant names, which formats may only provide
+/// through `deserialize_identifier`.
+pub struct IdentifierSeed;
+
+impl<'de> DeserializeSeed<'de> for IdentifierSeed {
+    type Value = Content;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Content, D::Error> {
+        deserializer.deserialize_identifier(ContentVisitor)
+    }
+}
+
+struct ContentVisitor;
+
+impl<'de> Visitor<'de> for ContentVisitor {
+    type Value = Content;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Content, E> {
+        Ok(Content::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Content, E> {
+        Ok(Content::I64(v))
+    }
+
+    fn visit_i128<E>(self, v: i128) -> Result<Content, E> {
+        Ok(Content::I128(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Content, E> {
+        Ok(Content::U64(v))
+    }
+
+    fn visit_u128<E>(self, v: u128) -> Result<Content, E> {
+        Ok(Content::U128(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Content, E> {
+        Ok(Content::F64(v))
+    }
+
+    fn visit_char<E>(self, v: char) -> Result<Content, E> {
+        Ok(Content::Char(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Content, E> {
+        Ok(Content::String(v.to_owned()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Content, E> {
+        Ok(Content::String(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Content, E> {
+        Ok(Content::Bytes(v.to_owned()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Content, E> {
+        Ok(Content::Bytes(v))
+    }
+
+    fn visit_none<E>(self) -> Result<Content, E> {
+        Ok(Content::None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Content, D::Error> {
+        Content::deserialize(deserializer).map(|v| Content::Some(Box::new(v)))
+    }
+
+    fn visit_unit<E>(self) -> Result<Content, E> {
+        Ok(Content::Unit)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Content, D::Error> {
+        Content::deserialize(deserializer).map(|v| Content::Newtype(Box::new(v)))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Content, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Content::Seq(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Content, A::Error> {
+        let mut entries = Vec::with_capacity(map.size_hint().unwrap_or(0).min(4096));
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(Content::Map(entries))
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Content, A::Error> {
+        let (variant, access) = data.variant::<Content>()?;
+        let value = access.newtype_variant::<Content>()?;
+        Ok(Content::Map(vec![(variant, value)]))
+    }
+}
+
+/// Deserializer of buffered [`Content`].
+pub struct ContentDeserializer<E> {
+    content: Content,
+    err: PhantomData<E>,
+}
+
+impl<E> ContentDeserializer<E> {
+    pub fn new(content: Content) -> Self {
+        ContentDeserializer {
+            content,
+            err: PhantomData,
+        }
+    }
+}
+
+impl<'de, E: de::Error> IntoDeserializer<'de, E> for Content {
+    type Deserializer = ContentDeserializer<E>;
+
+    fn into_deserializer(self) -> ContentDeserializer<E> {
+        ContentDeserializer::new(self)
+    }
+}
+
+fn visit_seq<'de, V: Visitor<'de>, E: de::Error>(
+    items: Vec<Content>,
+    visitor: V,
+) -> Result<V::Value, E> {
+    let mut seq = SeqDeserializer::new(items.into_iter());
+    let value = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(value)
+}
+
+fn visit_map<'de, V: Visitor<'de>, E: de::Error>(
+    entries: Vec<(Content, Content)>,
+    visitor: V,
+) -> Result<V::Value, E> {
+    let mut map = MapDeserializer::new(entries.into_iter());
+    let value = visitor.visit_map(&mut map)?;
+    map.end()?;
+    Ok(value)
+}
+
+impl<'de, E: de::Error> Deserializer<'de> for ContentDeserializer<E> {
+    type Error = E;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        match self.content {
+            Content::Bool(v) => visitor.visit_bool(v),
+            Content::U64(v) => visitor.visit_u64(v),
+            Content::I64(v) => visitor.visit_i64(v),
+            Content::U128(v) => visitor.visit_u128(v),
+            Content::I128(v) => visitor.visit_i128(v),
+            Content::F64(v) => visitor.visit_f64(v),
+            Content::Char(v) => visitor.visit_char(v),
+            Content::String(v) => visitor.visit_string(v),
+            Content::Bytes(v) => visitor.visit_byte_buf(v),
+            Content::None => visitor.visit_none(),
+            Content::Some(v) => visitor.visit_some(ContentDeserializer::new(*v)),
+            Content::Unit => visitor.visit_unit(),
+            Content::Newtype(v) => visitor.visit_newtype_struct(ContentDeserializer::new(*v)),
+            Content::Seq(v) => visit_seq(v, visitor),
+            Content::Map(v) => visit_map(v, visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        match self.content {
+            Content::None => visitor.visit_none(),
+            Content::Some(v) => visitor.visit_some(ContentDeserializer::new(*v)),
+            Content::Unit => visitor.visit_unit(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        match self.content {
+            Content::Unit => visitor.visit_unit(),
+            // Some formats encode unit as an empty sequence
+            Content::Seq(ref v) if v.is_empty() => visitor.visit_unit(),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        match self.content {
+            Content::Newtype(v) => visitor.visit_newtype_struct(ContentDeserializer::new(*v)),
+            _ => visitor.visit_newtype_struct(self),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        let (variant, value) = match self.content {
+            Content::Map(entries) => {
+                let mut entries = entries.into_iter();
+                let Some((variant, value)) = entries.next() else {
+                    return Err(E::invalid_value(Unexpected::Map, &"map with a single key"));
+                };
+                if entries.next().is_some() {
+                    return Err(E::invalid_value(Unexpected::Map, &"map with a single key"));
+                }
+                (variant, Some(value))
+            }
+            variant @ (Content::String(_) | Content::Bytes(_) | Content::U64(_)) => (variant, None),
+            other => return Err(E::invalid_type(other.unexpected(), &"string or map")),
+        };
+        visitor.visit_enum(EnumDeserializer {
+            variant,
+            value,
+            err: PhantomData,
+        })
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        match self.content {
+            Content::String(v) => visitor.visit_string(v),
+            Content::Bytes(v) => visitor.visit_byte_buf(v),
+            Content::U64(v) => visitor.visit_u64(v),
+            other => Err(E::invalid_type(other.unexpected(), &"identifier")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf seq tuple tuple_struct map struct ignored_any
+    }
+}
+
+struct EnumDeserializer<E> {
+    variant: Content,
+    value: Option<Content>,
+    err: PhantomData<E>,
+}
+
+impl<'de, E: de::Error> EnumAccess<'de> for EnumDeserializer<E> {
+    type Error = E;
+    type Variant = VariantDeserializer<E>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantDeserializer<E>), E> {
+        let variant = seed.deserialize(ContentDeserializer::new(self.variant))?;
+        Ok((
+            variant,
+            VariantDeserializer {
+                value: self.value,
+                err: PhantomData,
+            },
+        ))
+    }
+}
+
+pub struct VariantDeserializer<E> {
+    value: Option<Content>,
+    err: PhantomData<E>,
+}
+
+impl<'de, E: de::Error> VariantAccess<'de> for VariantDeserializer<E> {
+    type Error = E;
+
+    fn unit_variant(self) -> Result<(), E> {
+        match self.value {
+            None | Some(Content::Unit) => Ok(()),
+            Some(other) => Err(E::invalid_type(other.unexpected(), &"unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, E> {
+        match self.value {
+            Some(value) => seed.deserialize(ContentDeserializer::new(value)),
+            None => Err(E::invalid_type(Unexpected::UnitVariant, &"newtype variant")),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, E> {
+        match self.value {
+            Some(Content::Seq(v)) => visit_seq(v, visitor),
+            Some(other) => Err(E::invalid_type(other.unexpected(), &"tuple variant")),
+            None => Err(E::invalid_type(Unexpected::UnitVariant, &"tuple variant")),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        match self.value {
+            Some(Content::Map(v)) => visit_map(v, visitor),
+            Some(Content::Seq(v)) => visit_seq(v, visitor),
+            Some(other) => Err(E::invalid_type(other.unexpected(), &"struct variant")),
+            None => Err(E::invalid_type(Unexpected::UnitVariant, &"struct variant")),
+        }
+    }
+}
+
+/// Deserializes a `#[serde(flatten)]` field from the entries of the
+/// enclosing map which were not claimed by other fields.
+pub struct FlatMapDeserializer<'a, E> {
+    entries: &'a mut Vec<Option<(Content, Content)>>,
+    err: PhantomData<E>,
+}
+
+impl<'a, E> FlatMapDeserializer<'a, E> {
+    pub fn new(entries: &'a mut Vec<Option<(Content, Content)>>) -> Self {
+        FlatMapDeserializer {
+            entries,
+            err: PhantomData,
+        }
+    }
+
+    fn remaining(&self) -> Vec<(Content, Content)> {
+        self.entries.iter().flatten().cloned().collect()
+    }
+}
+
+impl<'de, E: de::Error> Deserializer<'de> for FlatMapDeserializer<'_, E> {
+    type Error = E;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        visitor.visit_map(MapDeserializer::new(self.remaining().into_iter()))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        // The struct gets to see all the remaining entries and claims those
+        // which it does not ignore, which also covers the fields flattened
+        // into it in turn.
+        visitor.visit_map(FlatStructAccess {
+            entries: self.entries,
+            next: 0,
+            current: None,
+            err: PhantomData,
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        // The first entry keyed by a variant is claimed as the enum
+        let entry = self.entries.iter_mut().find(|entry| {
+            entry
+                .as_ref()
+                .and_then(|(key, _)| key.as_str())
+                .is_some_and(|key| variants.contains(&key))
+        });
+        let Some((variant, value)) = entry.and_then(Option::take) else {
+            return Err(E::custom(format_args!(
+                "no variant of enum {name} found in flattened data"
+            )));
+        };
+        visitor.visit_enum(EnumDeserializer {
+            variant,
+            value: Some(value),
+            err: PhantomData,
+        })
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, E> {
+        Err(E::custom("can only flatten structs and maps"))
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf seq tuple tuple_struct identifier
+    }
+}
+
+struct FlatStructAccess<'a, E> {
+    entries: &'a mut Vec<Option<(Content, Content)>>,
+    next: usize,
+    current: Option<usize>,
+    err: PhantomData<E>,
+}
+
+impl<'de, E: de::Error> MapAccess<'de> for FlatStructAccess<'_, E> {
+    type Error = E;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, E> {
+        while let Some(entry) = self.entries.get(self.next) {
+            let index = self.next;
+            self.next += 1;
+            if let Some((key, _)) = entry {
+                self.current = Some(index);
+                return seed
+                    .deserialize(ContentDeserializer::new(key.clone()))
+                    .map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, E> {
+        let index = self
+            .current
+            .take()
+            .ok_or_else(|| E::custom("value is missing"))?;
+        let Some((_, value)) = &self.entries[index] else {
+            return Err(E::custom("value is missing"));
+        };
+        let mut ignored = false;
+        let value = seed.deserialize(ClaimingDeserializer {
+            content: value.clone(),
+            ignored: &mut ignored,
+            err: PhantomData,
+        })?;
+        if !ignored {
+            self.entries[index] = None;
+        }
+        Ok(value)
+    }
+}
+
+/// Forwards to [`ContentDeserializer`], recording whether the value was
+/// only ignored, which is how unknown fields are skipped.
+struct ClaimingDeserializer<'a, E> {
+    content: Content,
+    ignored: &'a mut bool,
+    err: PhantomData<E>,
+}
+
+macro_rules! forward_to_content {
+    ($($method:ident($($arg:ident: $ty:ty),*))*) => {
+        $(fn $method<V: Visitor<'de>>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, E> {
+            ContentDeserializer::new(self.content).$method($($arg,)* visitor)
+        })*
+    };
+}
+
+impl<'de, E: de::Error> Deserializer<'de> for ClaimingDeserializer<'_, E> {
+    type Error = E;
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        *self.ignored = true;
+        visitor.visit_unit()
+    }
+
+    forward_to_content! {
+        deserialize_any()
+        deserialize_bool()
+        deserialize_i8()
+        deserialize_i16()
+        deserialize_i32()
+        deserialize_i64()
+        deserialize_i128()
+        deserialize_u8()
+        deserialize_u16()
+        deserialize_u32()
+        deserialize_u64()
+        deserialize_u128()
+        deserialize_f32()
+        deserialize_f64()
+        deserialize_char()
+        deserialize_str()
+        deserialize_string()
+        deserialize_bytes()
+        deserialize_byte_buf()
+        deserialize_option()
+        deserialize_unit()
+        deserialize_unit_struct(name: &'static str)
+        deserialize_newtype_struct(name: &'static str)
+        deserialize_seq()
+        deserialize_tuple(len: usize)
+        deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_map()
+        deserialize_struct(name: &'static str, fields: &'static [&'static str])
+        deserialize_enum(name: &'static str, variants: &'static [&'static str])
+        deserialize_identifier()
+    }
+}
+
+/// Deserializer of a field missing from the input: only an `Option`
+/// can be deserialized from it, as `None`.
+pub struct MissingFieldDeserializer<E> {
+    name: &'static str,
+    err: PhantomData<E>,
+}
+
+impl<E> MissingFieldDeserializer<E> {
+    pub fn new(name: &'static str) -> Self {
+        MissingFieldDeserializer {
+            name,
+            err: PhantomData,
+        }
+    }
+}
+
+impl<'de, E: de::Error> Deserializer<'de> for MissingFieldDeserializer<E> {
+    type Error = E;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, E> {
+        Err(E::missing_field(self.name))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        visitor.visit_none()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple tuple_struct
+        map struct enum identifier ignored_any
+    }
+}
@@ -0,0 +1,566 @@
+//! GraphQL schemas (SDL) of reflected types, with output and input types.
+//!
+//! ```
+//! # use serde_generic::SerdeGeneric;
+//! #[derive(SerdeGeneric)]
+//! struct User {
+//!     #[serde(skip_deserializing)]
+//!     id: u32,
+//!     #[serde(rename(deserialize = "displayName"))]
+//!     name: String,
+//! }
+//!
+//! assert_eq!(
+//!     serde_generic::graphql::schema_for::<User, _>(),
+//!     "\
+//! scalar Long
+//!
+//! type User {
+//!   id: Long!
+//!   name: String!
+//! }
+//!
+//! input UserInput {
+//!   displayName: String!
+//! }
+//! "
+//! );
+//! ```
+//!
+//! GraphQL has no generics, so every instantiation is a type of its own,
+//! named by [`Registry::name`], and an input type with the `Input` suffix.
+//! Output types have the fields that are serialized, by their serialize
+//! names, and input types the ones that are deserialized, by their
+//! deserialize names. Fields that can be missing are nullable, as are
+//! options. Flattened structs have their fields inlined, other flattened
+//! fields, such as maps, have no fixed fields and are left out with a
+//! comment saying so.
+//!
+//! Enums with only unit variants are enums. Other enums are unions of object
+//! types, one for each variant, named after the enum and the variant, with
+//! `__typename` standing for the tag. Their input types are `@oneOf` input
+//! types with a field for every variant, except for internally tagged enums,
+//! which are deserialized from a single object: their input type has the tag
+//! as a `String!` field, and the fields of all variants, the ones of the
+//! structs in newtype variants included, nullable. A field of several
+//! variants with different types is `JSON`. Elsewhere, newtype and tuple
+//! variants have a `value`.
+//!
+//! Types can't be empty, so object types with no fields, such as the ones of
+//! unit variants, have a single [`PLACEHOLDER`] field, `empty: Boolean`,
+//! which is always `null`: only their `__typename` is meant to be queried.
+//!
+//! Tuples and tuple structs are lists when their elements all have the same
+//! type, and `JSON` otherwise. Tuple structs and newtype structs that contain
+//! themselves are `JSON` as well.
+//!
+//! `Int` is 32-bit, so larger integers are the custom `Long` scalar, except
+//! `i128` and `u128` which are strings. Maps, types with `from`, `try_from`
+//! or `into` proxies and fields or variants with `serialize_with` or
+//! `deserialize_with` are the custom `JSON` scalar, and `()` and unit structs
+//! are `Unit`. Newtype structs and transparent types are their field.
+
+use crate::naming::{ident, type_name, unit_only};
+use crate::reflect::{
+    Body, Container, Field, Name, Primitive, Reflect, Registry, Shape, TagType, Type, Variant,
+};
+use std::collections::BTreeSet;
+
+/// Name of the field of types that have no other, as GraphQL types can't be
+/// empty. It is always `null`.
+pub const PLACEHOLDER: &str = "empty";
+
+/// Schema with `T` and the types it refers to.
+pub fn schema_for<T: ?Sized + Reflect<M>, M>() -> String {
+    let mut registry = Registry::new();
+    registry.register::<T, _>();
+    schema(&registry)
+}
+
+/// Schema with an output and an input type for every instantiation in the
+/// registry, in topological order.
+pub fn schema(registry: &Registry) -> String {
+    let mut generator = Generator {
+        registry,
+        scalars: BTreeSet::new(),
+        inlined: Vec::new(),
+    };
+    let mut definitions = Vec::new();
+    for ty in registry.instances() {
+        let name = registry.name(ty).expect("instance is registered");
+        definitions.extend(generator.definitions(ty, &ident(name)));
+    }
+    let scalars = generator
+        .scalars
+        .iter()
+        .map(|scalar| format!("scalar {scalar}\n"));
+    scalars.chain(definitions).collect::<Vec<_>>().join("\n")
+}
+
+struct Generator<'a> {
+    registry: &'a Registry,
+    /// Custom scalars used so far.
+    scalars: BTreeSet<&'static str>,
+    /// Types being inlined, tuple structs and newtype structs, which are
+    /// `JSON` if they contain themselves.
+    inlined: Vec<Type>,
+}
+
+/// Output type or input type.
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Output,
+    Input,
+}
+
+impl Side {
+    fn suffix(self) -> &'static str {
+        match self {
+            Side::Output => "",
+            Side::Input => "Input",
+        }
+    }
+
+    fn keyword(self) -> &'static str {
+        match self {
+            Side::Output => "type",
+            Side::Input => "input",
+        }
+    }
+
+    /// Whether a field or variant is on this side.
+    fn listed(self, skip_serializing: bool, skip_deserializing: bool) -> bool {
+        match self {
+            Side::Output => !skip_serializing,
+            Side::Input => !skip_deserializing,
+        }
+    }
+
+    fn name(self, name: &Name) -> &str {
+        match self {
+            Side::Output => &name.serialize,
+            Side::Input => &name.deserialize,
+        }
+    }
+}
+
+impl Generator<'_> {
+    /// Definitions of an instantiation, transparent types, newtype structs,
+    /// tuple structs, unit structs and types with proxies have none.
+    fn definitions(&mut self, ty: &Type, name: &str) -> Vec<String> {
+        let container = &self.registry.definition_of(ty).container;
+        let body = self.registry.body(ty);
+        if container.proxy().is_some() || newtype_field(container, &body).is_some() {
+            return Vec::new();
+        }
+        let doc = description(container.doc.as_deref(), "");
+        match &body {
+            Body::Unit | Body::Tuple(_) => Vec::new(),
+            Body::Struct(fields) => [Side::Output, Side::Input]
+                .into_iter()
+                .map(|side| {
+                    let default = side == Side::Input && container.default;
+                    doc.clone() + &self.object(side, name, fields, default)
+                })
+                .collect(),
+            Body::Enum(variants) if unit_only(container, variants.iter()) => {
+                let mut definitions =
+                    vec![doc.clone() + &enumeration(Side::Output, name, variants)];
+                if !same_names(variants) {
+                    definitions.push(doc + &enumeration(Side::Input, name, variants));
+                }
+                definitions
+            }
+            Body::Enum(variants) => {
+                let mut definitions = Vec::new();
+                let mut members = Vec::new();
+                for variant in variants {
+                    if !Side::Output.listed(variant.skip_serializing, variant.skip_deserializing) {
+                        continue;
+                    }
+                    let member = format!("{name}{}", type_name(&variant.name.serialize));
+                    let doc = description(variant.doc.as_deref(), "");
+                    let fields = self.variant_fields(Side::Output, variant);
+                    definitions.push(format!("{doc}type {member} {{\n{fields}}}\n"));
+                    members.push(member);
+                }
+                let union = format!("{doc}union {name} = {}\n", members.join(" | "));
+                definitions.insert(0, union);
+                let input = match &container.tag {
+                    TagType::Internal { tag } => self.tagged_input(name, tag, variants),
+                    _ => self.one_of(name, variants),
+                };
+                definitions.push(doc + &input);
+                definitions
+            }
+        }
+    }
+
+    /// Object or input object type with `fields`.
+    fn object(&mut self, side: Side, name: &str, fields: &[Field], default: bool) -> String {
+        let fields = self.fields(side, fields, default);
+        format!(
+            "{} {name}{} {{\n{fields}}}\n",
+            side.keyword(),
+            side.suffix()
+        )
+    }
+
+    /// Fields of an object or input object type, fields are optional in
+    /// input types if `default`.
+    fn fields(&mut self, side: Side, fields: &[Field], default: bool) -> String {
+        let members = self.members(side, fields, default);
+        lines(&members)
+    }
+
+    /// Members of an object or input object type, with the fields of
+    /// flattened structs inlined.
+    fn members(&mut self, side: Side, fields: &[Field], default: bool) -> Vec<Member> {
+        let mut members = Vec::new();
+        for field in fields {
+            if !side.listed(field.skip_serializing, field.skip_deserializing) {
+                continue;
+            }
+            if field.flatten {
+                members.extend(self.flattened(side, side.name(&field.name), &field.shape));
+                continue;
+            }
+            let missing = match side {
+                Side::Output => field.skip_serializing_if.is_some(),
+                Side::Input => default || field.default,
+            };
+            let field_type = if field.serialize_with.is_some() || field.deserialize_with.is_some() {
+                format!("{}!", self.scalar("JSON"))
+            } else {
+                self.type_ref(side, &field.shape)
+            };
+            members.push(Member::Field {
+                doc: field.doc.clone(),
+                name: ident(side.name(&field.name)),
+                ty: nullable(&field_type, missing).to_owned(),
+            });
+        }
+        members
+    }
+
+    /// Members of a flattened shape named `name`: the fields of a struct,
+    /// nullable if it is an option, or a note that it is left out.
+    fn flattened(&mut self, side: Side, name: &str, shape: &Shape) -> Vec<Member> {
+        let (shape, optional) = match shape {
+            Shape::Option(shape) => (&**shape, true),
+            shape => (shape, false),
+        };
+        if let Shape::Type(ty) = shape
+            && let Body::Struct(fields) = self.registry.body(ty)
+            && self.registry.definition_of(ty).container.proxy().is_none()
+            && !self.registry.definition_of(ty).container.transparent
+        {
+            let mut members = self.members(
+                side,
+                &fields,
+                self.registry.definition_of(ty).container.default,
+            );
+            if optional {
+                members.iter_mut().for_each(Member::make_nullable);
+            }
+            return members;
+        }
+        vec![Member::Note(format!(
+            "The fields of `{name}` are not fixed, so they are left out"
+        ))]
+    }
+
+    /// Fields of the object type of a variant.
+    fn variant_fields(&mut self, side: Side, variant: &Variant) -> String {
+        if variant.serialize_with.is_some() || variant.deserialize_with.is_some() {
+            return format!("  value: {}!\n", self.scalar("JSON"));
+        }
+        match &variant.body {
+            Body::Unit => lines(&[]),
+            Body::Tuple(fields) if fields.len() == 1 => {
+                format!("  value: {}\n", self.type_ref(side, &fields[0].shape))
+            }
+            Body::Tuple(fields) => format!("  value: {}\n", self.tuple(side, fields)),
+            Body::Struct(fields) => self.fields(side, fields, false),
+            Body::Enum(_) => unreachable!("variants have no variants"),
+        }
+    }
+
+    /// `@oneOf` input type of an enum with data, with a field for every
+    /// variant.
+    fn one_of(&mut self, name: &str, variants: &[Variant]) -> String {
+        let mut members = Vec::new();
+        let mut inputs = Vec::new();
+        for variant in variants {
+            if !Side::Input.listed(variant.skip_serializing, variant.skip_deserializing) {
+                continue;
+            }
+            let field_type = match &variant.body {
+                _ if variant.serialize_with.is_some() || variant.deserialize_with.is_some() => {
+                    self.scalar("JSON")
+                }
+                Body::Unit => "Boolean".to_owned(),
+                Body::Tuple(fields) if fields.len() == 1 => {
+                    self.type_ref(Side::Input, &fields[0].shape)
+                }
+                Body::Tuple(fields) => self.tuple(Side::Input, fields),
+                Body::Struct(variant_fields) => {
+                    let input = format!("{name}{}", type_name(&variant.name.serialize));
+                    inputs.push(self.object(Side::Input, &input, variant_fields, false));
+                    format!("{input}Input")
+                }
+                Body::Enum(_) => unreachable!("variants have no variants"),
+            };
+            // All fields of a `@oneOf` input type are nullable
+            members.push(Member::Field {
+                doc: variant.doc.clone(),
+                name: ident(&variant.name.deserialize),
+                ty: field_type.trim_end_matches('!').to_owned(),
+            });
+        }
+        let mut one_of = format!("input {name}Input @oneOf {{\n{}}}\n", lines(&members));
+        for input in inputs {
+            one_of += &format!("\n{input}");
+        }
+        one_of
+    }
+
+    /// Input type of an internally tagged enum: the `tag` field with the
+    /// name of the variant, and the fields of all variants, nullable. Fields
+    /// of several variants with different types are `JSON`.
+    fn tagged_input(&mut self, name: &str, tag: &str, variants: &[Variant]) -> String {
+        let mut members = vec![Member::Field {
+            doc: Some("Name of the variant.".to_owned()),
+            name: ident(tag),
+            ty: "String!".to_owned(),
+        }];
+        for variant in variants {
+            if !Side::Input.listed(variant.skip_serializing, variant.skip_deserializing) {
+                continue;
+            }
+            let variant_name = &variant.name.deserialize;
+            let variant_members = match &variant.body {
+                _ if variant.serialize_with.is_some() || variant.deserialize_with.is_some() => {
+                    vec![Member::Note(format!(
+                        "`{variant_name}` has a custom deserializer, so it is left out"
+                    ))]
+                }
+                Body::Tuple(fields) if fields.len() == 1 => {
+                    self.flattened(Side::Input, variant_name, &fields[0].shape)
+                }
+                Body::Struct(fields) => self.members(Side::Input, fields, false),
+                Body::Unit | Body::Tuple(_) => Vec::new(),
+                Body::Enum(_) => unreachable!("variants have no variants"),
+            };
+            for mut member in variant_members {
+                member.make_nullable();
+                if let Member::Field { name, ty, .. } = &member
+                    && let Some(Member::Field { ty: other, .. }) = members.iter_mut().find(
+                        |other| matches!(other, Member::Field { name: other, .. } if other == name),
+                    )
+                {
+                    if other != ty {
+                        *other = self.scalar("JSON");
+                    }
+                    continue;
+                }
+                members.push(member);
+            }
+        }
+        format!("input {name}Input {{\n{}}}\n", lines(&members))
+    }
+
+    /// Type of a field, with `!` unless it is nullable.
+    fn type_ref(&mut self, side: Side, shape: &Shape) -> String {
+        match shape {
+            Shape::Param(_) => unreachable!("instances have no type parameters"),
+            Shape::Primitive(primitive) => format!("{}!", self.primitive(*primitive)),
+            Shape::Option(shape) => self.type_ref(side, shape).trim_end_matches('!').to_owned(),
+            Shape::Seq(shape) | Shape::Array(shape, _) => {
+                format!("[{}]!", self.type_ref(side, shape))
+            }
+            Shape::Map(_, _) => format!("{}!", self.scalar("JSON")),
+            Shape::Tuple(shapes) => self.list(side, shapes.iter()),
+            Shape::Type(ty) => {
+                let container = &self.registry.definition_of(ty).container;
+                let body = self.registry.body(ty);
+                if container.proxy().is_some() || self.inlined.contains(ty) {
+                    return format!("{}!", self.scalar("JSON"));
+                }
+                if let Some(field) = newtype_field(container, &body) {
+                    return self.inline(ty, |generator| generator.type_ref(side, &field.shape));
+                }
+                let name = ident(self.registry.name(ty).expect("type is registered"));
+                match &body {
+                    Body::Unit => format!("{}!", self.scalar("Unit")),
+                    Body::Tuple(fields) => {
+                        self.inline(ty, |generator| generator.tuple(side, fields))
+                    }
+                    Body::Enum(variants)
+                        if unit_only(
+                            &self.registry.definition_of(ty).container,
+                            variants.iter(),
+                        ) && same_names(variants) =>
+                    {
+                        format!("{name}!")
+                    }
+                    _ => format!("{name}{}!", side.suffix()),
+                }
+            }
+        }
+    }
+
+    /// Type made by `inline` from the fields of `ty`.
+    fn inline(&mut self, ty: &Type, inline: impl FnOnce(&mut Self) -> String) -> String {
+        self.inlined.push(ty.clone());
+        let type_ref = inline(self);
+        self.inlined.pop();
+        type_ref
+    }
+
+    /// Type of tuple fields, fields skipped either way are left out, as they
+    /// have no position in the sequence.
+    fn tuple(&mut self, side: Side, fields: &[Field]) -> String {
+        let fields = fields
+            .iter()
+            .filter(|field| !field.skip_serializing && !field.skip_deserializing);
+        if fields
+            .clone()
+            .any(|field| field.serialize_with.is_some() || field.deserialize_with.is_some())
+        {
+            return format!("{}!", self.scalar("JSON"));
+        }
+        self.list(side, fields.map(|field| &field.shape))
+    }
+
+    /// List of the type of all `shapes` if they have the same one, or `JSON`.
+    fn list<'s>(&mut self, side: Side, shapes: impl Iterator<Item = &'s Shape>) -> String {
+        let types = shapes
+            .map(|shape| self.type_ref(side, shape))
+            .collect::<BTreeSet<_>>();
+        match types.into_iter().collect::<Vec<_>>().as_slice() {
+            [item] => format!("[{item}]!"),
+            _ => format!("{}!", self.scalar("JSON")),
+        }
+    }
+
+    fn primitive(&mut self, primitive: Primitive) -> String {
+        match primitive {
+            Primitive::Bool => "Boolean".to_owned(),
+            Primitive::I8 | Primitive::I16 | Primitive::I32 | Primitive::U8 | Primitive::U16 => {
+                "Int".to_owned()
+            }
+            Primitive::I64 | Primitive::U32 | Primitive::U64 => self.scalar("Long"),
+            Primitive::F32 | Primitive::F64 => "Float".to_owned(),
+            Primitive::I128 | Primitive::U128 | Primitive::Char | Primitive::String => {
+                "String".to_owned()
+            }
+            Primitive::Unit => self.scalar("Unit"),
+        }
+    }
+
+    fn scalar(&mut self, scalar: &'static str) -> String {
+        self.scalars.insert(scalar);
+        scalar.to_owned()
+    }
+}
+
+/// Field of an object or input object type, or a note on a flattened field
+/// left out.
+enum Member {
+    Field {
+        doc: Option<String>,
+        name: String,
+        ty: String,
+    },
+    Note(String),
+}
+
+impl Member {
+    fn make_nullable(&mut self) {
+        if let Member::Field { ty, .. } = self {
+            ty.truncate(ty.trim_end_matches('!').len());
+        }
+    }
+}
+
+/// Lines of the members of a type, notes being comments, with the
+/// [`PLACEHOLDER`] field if there are no fields.
+fn lines(members: &[Member]) -> String {
+    let mut lines = String::new();
+    for member in members {
+        match member {
+            Member::Field { doc, name, ty } => {
+                lines += &description(doc.as_deref(), "  ");
+                lines += &format!("  {name}: {ty}\n");
+            }
+            Member::Note(note) => lines += &format!("  # {note}\n"),
+        }
+    }
+    if !members
+        .iter()
+        .any(|member| matches!(member, Member::Field { .. }))
+    {
+        lines += &format!("  {PLACEHOLDER}: Boolean\n");
+    }
+    lines
+}
+
+/// Enum of the variants on `side`, by their names on that side.
+fn enumeration(side: Side, name: &str, variants: &[Variant]) -> String {
+    let mut values = String::new();
+    for variant in variants {
+        if side.listed(variant.skip_serializing, variant.skip_deserializing) {
+            values += &description(variant.doc.as_deref(), "  ");
+            values += &format!("  {}\n", ident(side.name(&variant.name)));
+        }
+    }
+    format!("enum {name}{} {{\n{values}}}\n", side.suffix())
+}
+
+/// Whether the output enum can be used as an input, with the same values.
+fn same_names(variants: &[Variant]) -> bool {
+    variants.iter().all(|variant| {
+        variant.name.serialize == variant.name.deserialize
+            && variant.skip_serializing == variant.skip_deserializing
+    })
+}
+
+fn nullable(field_type: &str, nullable: bool) -> &str {
+    if nullable {
+        field_type.trim_end_matches('!')
+    } else {
+        field_type
+    }
+}
+
+/// Field a newtype struct or a transparent type is serialized as.
+fn newtype_field<'a>(container: &Container, body: &'a Body) -> Option<&'a Field> {
+    match body {
+        Body::Struct(fields) | Body::Tuple(fields) if container.transparent => fields
+            .iter()
+            .find(|field| !(field.skip_serializing && field.skip_deserializing)),
+        Body::Tuple(fields) if fields.len() == 1 => fields.first(),
+        _ => None,
+    }
+}
+
+/// Block string description ending with a newline, indented by `indent`.
+fn description(doc: Option<&str>, indent: &str) -> String {
+    let Some(doc) = doc else {
+        return String::new();
+    };
+    let doc = doc.replace("\"\"\"", "\\\"\"\"");
+    if !doc.contains('\n') {
+        return format!("{indent}\"\"\"{doc}\"\"\"\n");
+    }
+    let mut description = format!("{indent}\"\"\"\n");
+    for line in doc.lines() {
+        if line.is_empty() {
+            description.push('\n');
+        } else {
+            description += &format!("{indent}{line}\n");
+        }
+    }
+    description + indent + "\"\"\"\n"
+}
@@ -0,0 +1,449 @@
+//! JSON Schema (draft 2020-12) of reflected types.
+//!
+//! ```
+//! # use serde_generic::SerdeGeneric;
+//! use serde_json::json;
+//!
+//! #[derive(SerdeGeneric)]
+//! #[serde(deny_unknown_fields)]
+//! struct Foo<X> {
+//!     #[serde(default)]
+//!     foo: X,
+//! }
+//!
+//! assert_eq!(
+//!     serde_generic::json_schema::schema_for::<Foo<bool>, _>(),
+//!     json!({
+//!         "$schema": "https://json-schema.org/draft/2020-12/schema",
+//!         "$ref": "#/$defs/FooBool",
+//!         "$defs": {
+//!             "FooBool": {
+//!                 "type": "object",
+//!                 "properties": {"foo": {"type": "boolean"}},
+//!                 "additionalProperties": false,
+//!             },
+//!         },
+//!     })
+//! );
+//! ```
+//!
+//! JSON Schema has no generics, so every instantiation of a type gets a
+//! definition of its own, named by [`Registry::name`].
+//!
+//! A schema is meant to accept what is serialized as well as what can be
+//! deserialized: fields that can be missing in either are optional, and
+//! aliases are listed as optional properties. Types with `from`, `try_from`
+//! or `into` proxies and fields or variants with `serialize_with` or
+//! `deserialize_with` accept anything, as their shape is not known.
+
+use crate::reflect::{
+    Body, Container, Field, Len, Name, Primitive, Reflect, Registry, Shape, TagType, Type, Variant,
+};
+use serde_json::{Map, Value, json};
+
+/// `$schema` of the documents.
+pub const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Document with the schema of `T` and definitions of the types it refers
+/// to under `$defs`.
+pub fn schema_for<T: ?Sized + Reflect<M>, M>() -> Value {
+    let mut registry = Registry::new();
+    registry.register::<T, _>();
+    Generator::new(&registry).document(&registry.roots()[0])
+}
+
+/// Schemas of the types in a [`Registry`]. Shapes given to it have to be
+/// registered.
+pub struct Generator<'a> {
+    registry: &'a Registry,
+    ref_prefix: &'a str,
+}
+
+impl<'a> Generator<'a> {
+    /// Refers to definitions in `$defs`.
+    pub fn new(registry: &'a Registry) -> Self {
+        Self::with_ref_prefix(registry, "#/$defs/")
+    }
+
+    /// Refers to definitions by `ref_prefix` followed by their names, e.g.
+    /// `#/components/schemas/` in OpenAPI.
+    pub fn with_ref_prefix(registry: &'a Registry, ref_prefix: &'a str) -> Self {
+        Generator {
+            registry,
+            ref_prefix,
+        }
+    }
+
+    /// Document with the schema of `shape` and all of the definitions.
+    pub fn document(&self, shape: &Shape) -> Value {
+        let mut document = Map::new();
+        document.insert("$schema".into(), DIALECT.into());
+        if let Value::Object(schema) = self.schema(shape) {
+            document.extend(schema);
+        }
+        let definitions = self.definitions();
+        if !definitions.is_empty() {
+            document.insert("$defs".into(), Value::Object(definitions));
+        }
+        Value::Object(document)
+    }
+
+    /// Definitions of all instantiations in the registry by their names.
+    pub fn definitions(&self) -> Map<String, Value> {
+        self.registry
+            .instances()
+            .iter()
+            .map(|ty| (self.name(ty).to_owned(), self.definition(ty)))
+            .collect()
+    }
+
+    /// Schema of `shape`, types are referred to by `$ref`.
+    pub fn schema(&self, shape: &Shape) -> Value {
+        match shape {
+            // Instantiations have no parameters left, but a shape from a
+            // definition may be given
+            Shape::Param(_) => json!({}),
+            Shape::Primitive(primitive) => primitive_schema(*primitive),
+            Shape::Option(shape) => json!({"anyOf": [self.schema(shape), {"type": "null"}]}),
+            Shape::Seq(shape) => json!({"type": "array", "items": self.schema(shape)}),
+            Shape::Array(shape, Len::Fixed(len)) => json!({
+                "type": "array",
+                "items": self.schema(shape),
+                "minItems": len,
+                "maxItems": len,
+            }),
+            Shape::Array(shape, Len::Param(_)) => {
+                json!({"type": "array", "items": self.schema(shape)})
+            }
+            Shape::Tuple(shapes) => json!({
+                "type": "array",
+                "prefixItems": shapes.iter().map(|shape| self.schema(shape)).collect::<Vec<_>>(),
+                "items": false,
+                "minItems": shapes.len(),
+                "maxItems": shapes.len(),
+            }),
+            // Keys are strings in JSON, whatever they are in Rust
+            Shape::Map(_, value) => {
+                json!({"type": "object", "additionalProperties": self.schema(value)})
+            }
+            Shape::Type(ty) => json!({"$ref": format!("{}{}", self.ref_prefix, self.name(ty))}),
+        }
+    }
+
+    fn name(&self, ty: &Type) -> &'a str {
+        self.registry
+            .name(ty)
+            .unwrap_or_else(|| panic!("`{}` is not registered", ty.name))
+    }
+
+    /// Schema of an instantiation.
+    pub fn definition(&self, ty: &Type) -> Value {
+        let container = &self.registry.definition_of(ty).container;
+        let proxy = container.proxy();
+        let mut schema = match (proxy, self.registry.body(ty)) {
+            (Some(proxy), _) => json!({"$comment": format!("serialized as `{proxy}`")}),
+            (None, Body::Struct(fields) | Body::Tuple(fields)) if container.transparent => {
+                let field = fields
+                    .iter()
+                    .find(|field| !is_skipped(field))
+                    .expect("transparent struct has a field");
+                self.field_schema(field)
+            }
+            (None, Body::Struct(fields)) => self.object_schema(
+                &fields,
+                container.default,
+                container.deny_unknown_fields,
+                None,
+            ),
+            (None, Body::Tuple(fields)) => self.tuple_schema(&fields),
+            (None, Body::Unit) => json!({"type": "null"}),
+            (None, Body::Enum(variants)) => self.enum_schema(container, &variants),
+        };
+        describe(&mut schema, &container.doc);
+        schema
+    }
+
+    fn field_schema(&self, field: &Field) -> Value {
+        let mut schema = if field.serialize_with.is_some() || field.deserialize_with.is_some() {
+            json!({})
+        } else {
+            self.schema(&field.shape)
+        };
+        describe(&mut schema, &field.doc);
+        schema
+    }
+
+    /// Schema of named fields, `tag` is the tag property of an internally
+    /// tagged variant along with its schema.
+    fn object_schema(
+        &self,
+        fields: &[Field],
+        default: bool,
+        deny_unknown_fields: bool,
+        tag: Option<(&str, Value)>,
+    ) -> Value {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        let mut flattened = Vec::new();
+        if let Some((tag, schema)) = tag {
+            properties.insert(tag.to_owned(), schema);
+            required.push(tag.to_owned());
+        }
+        for field in fields.iter().filter(|field| !is_skipped(field)) {
+            if field.flatten {
+                // `None` of a flattened option is no fields at all
+                let shape = match &field.shape {
+                    Shape::Option(shape) => shape,
+                    shape => shape,
+                };
+                flattened.push(self.flattened_schema(shape));
+                continue;
+            }
+            let schema = self.field_schema(field);
+            for name in names(&field.name) {
+                properties.insert(name.to_owned(), schema.clone());
+            }
+            let optional = default
+                || field.default
+                || field.skip_serializing
+                || field.skip_deserializing
+                || field.skip_serializing_if.is_some()
+                || matches!(field.shape, Shape::Option(_));
+            if !optional {
+                required.push(field.name.serialize.clone());
+            }
+        }
+        let mut schema = Map::new();
+        schema.insert("type".into(), "object".into());
+        schema.insert("properties".into(), Value::Object(properties));
+        if !required.is_empty() {
+            schema.insert("required".into(), required.into());
+        }
+        let flattened_deny = fields
+            .iter()
+            .any(|field| field.flatten && !is_skipped(field) && self.denies(&field.shape));
+        if deny_unknown_fields || flattened_deny {
+            // Properties of flattened fields are only seen by
+            // `unevaluatedProperties`
+            let keyword = if flattened.is_empty() {
+                "additionalProperties"
+            } else {
+                "unevaluatedProperties"
+            };
+            schema.insert(keyword.into(), false.into());
+        }
+        if !flattened.is_empty() {
+            schema.insert("allOf".into(), flattened.into());
+        }
+        Value::Object(schema)
+    }
+
+    /// Schema of a flattened shape. Structs that deny unknown fields would
+    /// reject the other properties of the object they are flattened in, so
+    /// they are inlined without `additionalProperties`, which is left to that
+    /// object.
+    fn flattened_schema(&self, shape: &Shape) -> Value {
+        let Shape::Type(ty) = shape else {
+            return self.schema(shape);
+        };
+        match self.registry.body(ty) {
+            Body::Struct(fields) if self.denies(shape) => {
+                let container = &self.registry.definition_of(ty).container;
+                let mut schema = self.object_schema(&fields, container.default, false, None);
+                describe(&mut schema, &container.doc);
+                schema
+            }
+            _ => self.schema(shape),
+        }
+    }
+
+    /// Whether a flattened shape is a struct that denies unknown fields,
+    /// itself or through its own flattened fields.
+    fn denies(&self, shape: &Shape) -> bool {
+        let shape = match shape {
+            Shape::Option(shape) => shape,
+            shape => shape,
+        };
+        let Shape::Type(ty) = shape else {
+            return false;
+        };
+        let container = &self.registry.definition_of(ty).container;
+        if container.proxy().is_some() || container.transparent {
+            return false;
+        }
+        match self.registry.body(ty) {
+            Body::Struct(fields) => {
+                container.deny_unknown_fields
+                    || fields.iter().any(|field| {
+                        field.flatten && !is_skipped(field) && self.denies(&field.shape)
+                    })
+            }
+            _ => false,
+        }
+    }
+
+    /// Schema of tuple fields. Fields skipped either way are left out, as
+    /// they have no position in the sequence.
+    fn tuple_schema(&self, fields: &[Field]) -> Value {
+        if let [field] = fields {
+            return self.field_schema(field);
+        }
+        let items = fields
+            .iter()
+            .filter(|field| !field.skip_serializing && !field.skip_deserializing)
+            .map(|field| self.field_schema(field))
+            .collect::<Vec<_>>();
+        json!({
+            "type": "array",
+            "prefixItems": items,
+            "items": false,
+            "minItems": items.len(),
+            "maxItems": items.len(),
+        })
+    }
+
+    fn enum_schema(&self, container: &Container, variants: &[Variant]) -> Value {
+        // Untagged variants may overlap with the others
+        let untagged =
+            container.tag == TagType::None || variants.iter().any(|variant| variant.untagged);
+        let variants = listed_variants(variants)
+            .map(|variant| self.variant_schema(container, variant))
+            .collect::<Vec<_>>();
+        if untagged {
+            json!({"anyOf": variants})
+        } else {
+            json!({"oneOf": variants})
+        }
+    }
+
+    pub(crate) fn variant_schema(&self, container: &Container, variant: &Variant) -> Value {
+        let content = match &variant.body {
+            _ if variant.serialize_with.is_some() || variant.deserialize_with.is_some() => {
+                Some(json!({}))
+            }
+            Body::Unit => None,
+            Body::Tuple(fields) => Some(self.tuple_schema(fields)),
+            Body::Struct(fields) => {
+                Some(self.object_schema(fields, false, container.deny_unknown_fields, None))
+            }
+            Body::Enum(_) => unreachable!("variants have no variants"),
+        };
+        let name = name_schema(&variant.name);
+        let mut schema = match (&container.tag, content) {
+            (_, content) if variant.untagged => content.unwrap_or(json!({"type": "null"})),
+            (TagType::None, content) => content.unwrap_or(json!({"type": "null"})),
+            (TagType::External, None) => name,
+            (TagType::External, Some(content)) => {
+                let properties = names(&variant.name)
+                    .map(|name| (name.to_owned(), content.clone()))
+                    .collect::<Map<_, _>>();
+                json!({
+                    "type": "object",
+                    "properties": properties,
+                    "minProperties": 1,
+                    "maxProperties": 1,
+                    "additionalProperties": false,
+                })
+            }
+            (TagType::Internal { tag }, _) => match &variant.body {
+                Body::Struct(fields) => self.object_schema(
+                    fields,
+                    false,
+                    container.deny_unknown_fields,
+                    Some((tag, name)),
+                ),
+                Body::Tuple(fields) => json!({
+                    "allOf": [
+                        self.object_schema(&[], false, false, Some((tag, name))),
+                        self.tuple_schema(fields),
+                    ]
+                }),
+                _ => {
+                    self.object_schema(&[], false, container.deny_unknown_fields, Some((tag, name)))
+                }
+            },
+            (TagType::Adjacent { tag, content: key }, content) => {
+                let mut properties = Map::new();
+                properties.insert(tag.clone(), name);
+                let mut required = vec![tag.clone()];
+                if let Some(content) = content {
+                    properties.insert(key.clone(), content);
+                    required.push(key.clone());
+                }
+                let mut schema = json!({
+                    "type": "object",
+                    "properties": properties,
+                    "required": required,
+                });
+                if container.deny_unknown_fields {
+                    schema["additionalProperties"] = false.into();
+                }
+                schema
+            }
+        };
+        describe(&mut schema, &variant.doc);
+        schema
+    }
+}
+
+fn primitive_schema(primitive: Primitive) -> Value {
+    let integer = |format: &str| json!({"type": "integer", "format": format});
+    let unsigned = |format: &str| json!({"type": "integer", "format": format, "minimum": 0});
+    match primitive {
+        Primitive::Bool => json!({"type": "boolean"}),
+        Primitive::I8 => integer("int8"),
+        Primitive::I16 => integer("int16"),
+        Primitive::I32 => integer("int32"),
+        Primitive::I64 => integer("int64"),
+        Primitive::I128 => integer("int128"),
+        Primitive::U8 => unsigned("uint8"),
+        Primitive::U16 => unsigned("uint16"),
+        Primitive::U32 => unsigned("uint32"),
+        Primitive::U64 => unsigned("uint64"),
+        Primitive::U128 => unsigned("uint128"),
+        Primitive::F32 => json!({"type": "number", "format": "float"}),
+        Primitive::F64 => json!({"type": "number", "format": "double"}),
+        Primitive::Char => json!({"type": "string", "minLength": 1, "maxLength": 1}),
+        Primitive::String => json!({"type": "string"}),
+        Primitive::Unit => json!({"type": "null"}),
+    }
+}
+
+/// Variants that are a part of the schema of an enum.
+pub(crate) fn listed_variants(variants: &[Variant]) -> impl Iterator<Item = &Variant> {
+    variants
+        .iter()
+        .filter(|variant| !(variant.skip_serializing && variant.skip_deserializing))
+        // Catches unknown tags when deserializing
+        .filter(|variant| !variant.other)
+}
+
+fn is_skipped(field: &Field) -> bool {
+    field.skip_serializing && field.skip_deserializing
+}
+
+/// Serialized name followed by all the deserialized ones.
+pub(crate) fn names(name: &Name) -> impl Iterator<Item = &str> {
+    let mut names = vec![name.serialize.as_str(), name.deserialize.as_str()];
+    names.extend(name.deserialize_aliases.iter().map(String::as_str));
+    let mut seen = Vec::new();
+    names.into_iter().filter(move |name| {
+        let new = !seen.contains(name);
+        seen.push(*name);
+        new
+    })
+}
+
+fn name_schema(name: &Name) -> Value {
+    let names = names(name).collect::<Vec<_>>();
+    match names.as_slice() {
+        [name] => json!({"const": name}),
+        names => json!({"enum": names}),
+    }
+}
+
+fn describe(schema: &mut Value, doc: &Option<String>) {
+    if let (Value::Object(schema), Some(doc)) = (schema, doc) {
+        schema.insert("description".into(), doc.as_str().into());
+    }
+}
@@ -0,0 +1,787 @@
+//! Kotlin files of reflected types for kotlinx.serialization, generics
+//! included.
+//!
+//! ```
+//! # use serde_generic::SerdeGeneric;
+//! #[derive(SerdeGeneric)]
+//! struct Page<T> {
+//!     items: Vec<T>,
+//!     next_page: Option<String>,
+//! }
+//!
+//! assert_eq!(
+//!     serde_generic::kotlin::file_for::<Page<u8>, _>(Some("com.example")),
+//!     "\
+//! package com.example
+//!
+//! import kotlinx.serialization.SerialName
+//! import kotlinx.serialization.Serializable
+//!
+//! @Serializable
+//! data class Page<T>(
+//!     val items: List<T>,
+//!     @SerialName(\"next_page\")
+//!     val nextPage: String? = null,
+//! )
+//! "
+//! );
+//! ```
+//!
+//! Every [`Definition`] is declared once, with the names of its type
+//! parameters, see [`Definition::params`]. Types are named after their
+//! serialized names, followed by a number when two types have the same name,
+//! see [`Registry::definition_name`].
+//!
+//! Structs are `@Serializable data class`es with camel case properties, and
+//! `@SerialName` and `@JsonNames` for the serialize and deserialize names
+//! that differ from them. Fields that can be missing have a default: the
+//! value of `#[serde(default = "...")]` or of `Default::default()` for
+//! `#[serde(default)]` if it is a [`Value`](crate::reflect::Value), written
+//! as a constructor call for data classes, the zero value of primitives and
+//! collections for `#[serde(default)]` on the container, and `null` for
+//! options. Other properties have no default, except the ones of fields that
+//! are never serialized, which are `@Transient` and nullable with a `null`
+//! default when it can't be written. Flattened structs have their fields
+//! inlined, flattened maps are left out.
+//!
+//! Enums with only unit variants are `enum class`es. Internally and
+//! adjacently tagged enums are sealed classes with a
+//! `@JsonClassDiscriminator`, their variants being subclasses named after
+//! their serialize names. kotlinx.serialization only discriminates
+//! polymorphic classes by a property, which externally tagged and untagged
+//! enums with data don't have: they are type aliases of `JsonElement`, left
+//! to be decoded by hand, as are types with `from`, `try_from` or `into`
+//! proxies and fields or variants with `serialize_with` or
+//! `deserialize_with`. Newtype structs and transparent types are type aliases
+//! of their field, other tuples are `JsonArray`s, unit is `JsonNull` and
+//! `i128` and `u128` are `JsonPrimitive`s.
+
+use crate::naming::{self, Comment, comment, escape, ident, unicode, unit_only};
+use crate::reflect::{
+    Body, Container, Definition, Field, Primitive, Reflect, Registry, Shape, TagType, Type, Value,
+    Variant,
+};
+use std::collections::BTreeSet;
+
+/// File with `T` and the types it refers to.
+pub fn file_for<T: ?Sized + Reflect<M>, M>(package: Option<&str>) -> String {
+    let mut registry = Registry::new();
+    registry.register::<T, _>();
+    file(&registry, package)
+}
+
+/// File with all definitions in the registry, in topological order.
+pub fn file(registry: &Registry, package: Option<&str>) -> String {
+    let mut generator = Generator {
+        registry,
+        params: Vec::new(),
+        imports: BTreeSet::new(),
+    };
+    let definitions = registry
+        .definitions()
+        .map(|(id, def)| generator.definition(id, def))
+        .collect::<Vec<_>>();
+
+    let mut file = String::new();
+    if generator.imports.contains(JSON_NAMES)
+        || generator.imports.contains(JSON_CLASS_DISCRIMINATOR)
+    {
+        generator.imports.insert(EXPERIMENTAL);
+        file += "@file:OptIn(ExperimentalSerializationApi::class)\n\n";
+    }
+    if let Some(package) = package {
+        file += &format!("package {package}\n\n");
+    }
+    for import in &generator.imports {
+        file += &format!("import {import}\n");
+    }
+    if !generator.imports.is_empty() {
+        file.push('\n');
+    }
+    file + &definitions.join("\n")
+}
+
+const EXPERIMENTAL: &str = "kotlinx.serialization.ExperimentalSerializationApi";
+const SERIAL_NAME: &str = "kotlinx.serialization.SerialName";
+const SERIALIZABLE: &str = "kotlinx.serialization.Serializable";
+const TRANSIENT: &str = "kotlinx.serialization.Transient";
+const JSON_ARRAY: &str = "kotlinx.serialization.json.JsonArray";
+const JSON_CLASS_DISCRIMINATOR: &str = "kotlinx.serialization.json.JsonClassDiscriminator";
+const JSON_ELEMENT: &str = "kotlinx.serialization.json.JsonElement";
+const JSON_NAMES: &str = "kotlinx.serialization.json.JsonNames";
+const JSON_NULL: &str = "kotlinx.serialization.json.JsonNull";
+const JSON_PRIMITIVE: &str = "kotlinx.serialization.json.JsonPrimitive";
+
+struct Generator<'a> {
+    registry: &'a Registry,
+    /// Names of the type parameters of the definition being declared.
+    params: Vec<String>,
+    /// Fully qualified names of the classes used.
+    imports: BTreeSet<&'static str>,
+}
+
+/// Property of a data class.
+struct Property {
+    annotations: Vec<String>,
+    name: String,
+    ty: String,
+    default: Option<String>,
+    doc: Option<String>,
+}
+
+impl Generator<'_> {
+    /// Simple name of a class, imported.
+    fn import(&mut self, class: &'static str) -> &'static str {
+        self.imports.insert(class);
+        class.rsplit('.').next().unwrap_or(class)
+    }
+
+    /// Name of the class of a registered definition.
+    fn name(&self, id: u64) -> String {
+        ident(
+            self.registry
+                .definition_name(id)
+                .expect("registered definition"),
+        )
+    }
+
+    fn definition(&mut self, id: u64, def: &Definition) -> String {
+        let container = &def.container;
+        let name = self.name(id);
+        self.params = def
+            .params
+            .iter()
+            .map(|param| keyword(ident(param)))
+            .collect();
+        let params = params(&self.params, "");
+        let doc = kdoc(container.doc.as_deref(), "");
+        let alias = |ty: &str| format!("{doc}typealias {name}{params} = {ty}\n");
+        match &def.body {
+            _ if container.proxy().is_some() => alias(self.import(JSON_ELEMENT)),
+            Body::Struct(fields) | Body::Tuple(fields) if container.transparent => {
+                let field = fields
+                    .iter()
+                    .find(|field| !(field.skip_serializing && field.skip_deserializing))
+                    .expect("transparent struct has a field");
+                alias(&self.field_type(field))
+            }
+            Body::Tuple(fields) => alias(&self.tuple(fields)),
+            Body::Unit => alias(self.import(JSON_NULL)),
+            Body::Struct(fields) => {
+                let properties = self.properties(fields, container.default);
+                let serializable = self.import(SERIALIZABLE);
+                let header = format!("{doc}@{serializable}\n");
+                self.class(&header, &format!("{name}{params}"), &properties, "", "")
+            }
+            Body::Enum(variants) => {
+                let variants = variants
+                    .iter()
+                    .filter(|variant| !(variant.skip_serializing && variant.skip_deserializing))
+                    .collect::<Vec<_>>();
+                if unit_only(container, variants.iter().copied()) {
+                    self.enumeration(&doc, &name, &variants)
+                } else if variants
+                    .iter()
+                    .all(|variant| sealed(self.registry, container, variant))
+                {
+                    self.sealed(container, &doc, &name, &variants)
+                } else {
+                    alias(self.import(JSON_ELEMENT))
+                }
+            }
+        }
+    }
+
+    /// `enum class` of an enum with only unit variants.
+    fn enumeration(&mut self, doc: &str, name: &str, variants: &[&Variant]) -> String {
+        let serializable = self.import(SERIALIZABLE);
+        let mut entries = String::new();
+        for variant in variants {
+            let entry = ident(&variant.name.serialize);
+            entries += &kdoc(variant.doc.as_deref(), "    ");
+            for annotation in self.names(&variant.name, &entry) {
+                entries += &format!("    {annotation}\n");
+            }
+            entries += &format!("    {},\n", keyword(entry));
+        }
+        format!("{doc}@{serializable}\nenum class {name} {{\n{entries}}}\n")
+    }
+
+    /// Sealed class of an internally or adjacently tagged enum, with a
+    /// subclass for every variant.
+    fn sealed(
+        &mut self,
+        container: &Container,
+        doc: &str,
+        name: &str,
+        variants: &[&Variant],
+    ) -> String {
+        let (TagType::Internal { tag } | TagType::Adjacent { tag, .. }) = &container.tag else {
+            unreachable!("sealed class of an enum with no tag property");
+        };
+        let serializable = self.import(SERIALIZABLE);
+        let serial_name = self.import(SERIAL_NAME);
+        let discriminator = self.import(JSON_CLASS_DISCRIMINATOR);
+        let args = params(&self.params, "");
+        let nothing = if self.params.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", vec!["Nothing"; self.params.len()].join(", "))
+        };
+        let mut subclasses = Vec::new();
+        for variant in variants {
+            let class_name = class_name(&variant.name.serialize);
+            let header = format!(
+                "{}    @{serializable}\n    @{serial_name}({})\n",
+                kdoc(variant.doc.as_deref(), "    "),
+                string(&variant.name.serialize)
+            );
+            let properties = match (&container.tag, &variant.body) {
+                (TagType::Internal { .. }, Body::Struct(fields)) => self.properties(fields, false),
+                (TagType::Internal { .. }, Body::Tuple(fields)) => {
+                    let Shape::Type(ty) = &fields[0].shape else {
+                        unreachable!("internally tagged newtype variant of a struct");
+                    };
+                    self.properties(
+                        &self.registry.body(ty).fields().cloned().collect::<Vec<_>>(),
+                        self.registry.definition_of(ty).container.default,
+                    )
+                }
+                (TagType::Adjacent { content, .. }, Body::Struct(fields)) => {
+                    let fields_name = format!("{class_name}Fields");
+                    let properties = self.properties(fields, false);
+                    let fields_header = format!("    @{serializable}\n");
+                    let fields_class = format!("{fields_name}{args}");
+                    subclasses.push(self.class(
+                        &fields_header,
+                        &fields_class,
+                        &properties,
+                        "",
+                        "    ",
+                    ));
+                    vec![self.content(content, format!("{fields_name}{args}"))]
+                }
+                (TagType::Adjacent { content, .. }, Body::Tuple(fields)) => {
+                    let ty = self.tuple(fields);
+                    vec![self.content(content, ty)]
+                }
+                _ => Vec::new(),
+            };
+            let class = if properties.is_empty() {
+                format!("{header}    data object {class_name} : {name}{nothing}()\n")
+            } else {
+                let class_name = format!("{class_name}{}", params(&self.params, "out "));
+                let supertype = format!(" : {name}{args}()");
+                self.class(&header, &class_name, &properties, &supertype, "    ")
+            };
+            subclasses.push(class);
+        }
+        format!(
+            "{doc}@{serializable}\n@{discriminator}({})\nsealed class {name}{} {{\n{}}}\n",
+            string(tag),
+            params(&self.params, "out "),
+            subclasses.join("\n")
+        )
+    }
+
+    /// Property of the content of an adjacently tagged variant.
+    fn content(&mut self, content: &str, ty: String) -> Property {
+        let name = property_name(content);
+        let mut annotations = Vec::new();
+        if name != content {
+            let serial_name = self.import(SERIAL_NAME);
+            annotations.push(format!("@{serial_name}({})", string(content)));
+        }
+        Property {
+            annotations,
+            name,
+            ty,
+            default: None,
+            doc: None,
+        }
+    }
+
+    /// Data class, or class if it has no properties, indented by `indent`.
+    fn class(
+        &mut self,
+        header: &str,
+        name: &str,
+        properties: &[Property],
+        supertype: &str,
+        indent: &str,
+    ) -> String {
+        if properties.is_empty() {
+            return format!("{header}{indent}class {name}{supertype}\n");
+        }
+        let mut class = format!("{header}{indent}data class {name}(\n");
+        for property in properties {
+            class += &kdoc(property.doc.as_deref(), &format!("{indent}    "));
+            for annotation in &property.annotations {
+                class += &format!("{indent}    {annotation}\n");
+            }
+            let default = match &property.default {
+                Some(default) => format!(" = {default}"),
+                None => String::new(),
+            };
+            class += &format!(
+                "{indent}    val {}: {}{default},\n",
+                property.name, property.ty
+            );
+        }
+        class + &format!("{indent}){supertype}\n")
+    }
+
+    /// Properties of the fields of a struct or variant.
+    fn properties(&mut self, fields: &[Field], default: bool) -> Vec<Property> {
+        let mut properties = Vec::new();
+        for field in fields {
+            if field.skip_serializing && field.skip_deserializing {
+                continue;
+            }
+            if field.flatten {
+                if let Shape::Type(ty) = &field.shape
+                    && let Body::Struct(fields) = self.registry.body(ty)
+                    && self.registry.definition_of(ty).container.proxy().is_none()
+                    && !self.registry.definition_of(ty).container.transparent
+                {
+                    properties.extend(
+                        self.properties(&fields, self.registry.definition_of(ty).container.default),
+                    );
+                }
+                continue;
+            }
+            let name = property_name(&field.name.serialize);
+            let mut annotations = self.names(&field.name, &name);
+            let mut ty = self.field_type(field);
+            let missing = default
+                || field.default
+                || field.skip_serializing
+                || field.skip_deserializing
+                || field.skip_serializing_if.is_some()
+                || matches!(field.shape, Shape::Option(_));
+            let default = if missing {
+                let value = field.default_value.as_ref();
+                let literal = value.and_then(|value| self.literal(&field.shape, value));
+                let zero = || {
+                    (default || field.default)
+                        .then(|| self.zero(&field.shape))
+                        .flatten()
+                };
+                // `@Transient` properties need a default, even if it has to
+                // be `null`
+                literal.or_else(zero).or_else(|| {
+                    if field.skip_serializing && !ty.ends_with('?') {
+                        ty.push('?');
+                    }
+                    ty.ends_with('?').then(|| "null".to_owned())
+                })
+            } else {
+                None
+            };
+            if field.skip_serializing {
+                let transient = self.import(TRANSIENT);
+                annotations = vec![format!("@{transient}")];
+            }
+            properties.push(Property {
+                annotations,
+                name,
+                ty,
+                default,
+                doc: field.doc.clone(),
+            });
+        }
+        properties
+    }
+
+    /// `@SerialName` and `@JsonNames` of a property or enum entry.
+    fn names(&mut self, name: &crate::reflect::Name, ident: &str) -> Vec<String> {
+        let mut annotations = Vec::new();
+        if name.serialize != ident.trim_matches('`') {
+            let serial_name = self.import(SERIAL_NAME);
+            annotations.push(format!("@{serial_name}({})", string(&name.serialize)));
+        }
+        let mut aliases = Vec::new();
+        for alias in std::iter::once(&name.deserialize).chain(&name.deserialize_aliases) {
+            if *alias != name.serialize && !aliases.contains(&alias) {
+                aliases.push(alias);
+            }
+        }
+        if !aliases.is_empty() {
+            let json_names = self.import(JSON_NAMES);
+            let aliases = aliases
+                .iter()
+                .map(|alias| string(alias))
+                .collect::<Vec<_>>();
+            annotations.push(format!("@{json_names}({})", aliases.join(", ")));
+        }
+        annotations
+    }
+
+    fn tuple(&mut self, fields: &[Field]) -> String {
+        match fields {
+            [field] => self.field_type(field),
+            _ => self.import(JSON_ARRAY).to_owned(),
+        }
+    }
+
+    fn field_type(&mut self, field: &Field) -> String {
+        if field.serialize_with.is_some() || field.deserialize_with.is_some() {
+            self.import(JSON_ELEMENT).to_owned()
+        } else {
+            self.type_expr(&field.shape)
+        }
+    }
+
+    fn type_expr(&mut self, shape: &Shape) -> String {
+        match shape {
+            Shape::Param(i) => self.params[*i].clone(),
+            Shape::Primitive(Primitive::I128 | Primitive::U128) => {
+                self.import(JSON_PRIMITIVE).to_owned()
+            }
+            Shape::Primitive(Primitive::Unit) => self.import(JSON_NULL).to_owned(),
+            Shape::Primitive(primitive) => primitive_type(*primitive).to_owned(),
+            Shape::Option(shape) => {
+                let ty = self.type_expr(shape);
+                // Nested options are both `null` once serialized
+                if ty.ends_with('?') { ty } else { ty + "?" }
+            }
+            Shape::Seq(shape) | Shape::Array(shape, _) => {
+                format!("List<{}>", self.type_expr(shape))
+            }
+            Shape::Tuple(_) => self.import(JSON_ARRAY).to_owned(),
+            Shape::Map(key, value) => {
+                format!("Map<{}, {}>", self.type_expr(key), self.type_expr(value))
+            }
+            Shape::Type(ty) => {
+                let name = self.name(ty.id);
+                if ty.args.is_empty() {
+                    name
+                } else {
+                    let args = ty
+                        .args
+                        .iter()
+                        .map(|arg| self.type_expr(arg))
+                        .collect::<Vec<_>>();
+                    format!("{name}<{}>", args.join(", "))
+                }
+            }
+        }
+    }
+
+    /// Kotlin literal of a default value of a field of shape `shape`.
+    fn literal(&mut self, shape: &Shape, value: &Value) -> Option<String> {
+        match (shape, value) {
+            (Shape::Option(shape), value) => match value {
+                Value::Null => Some("null".to_owned()),
+                value => self.literal(shape, value),
+            },
+            (Shape::Primitive(primitive), value) => primitive_literal(*primitive, value),
+            (Shape::Seq(shape) | Shape::Array(shape, _), Value::Seq(values)) => {
+                if values.is_empty() {
+                    return Some("emptyList()".to_owned());
+                }
+                let items = values.iter().map(|value| self.literal(shape, value));
+                let items = items.collect::<Option<Vec<_>>>()?;
+                Some(format!("listOf({})", items.join(", ")))
+            }
+            (Shape::Map(key, value_shape), Value::Map(entries)) => {
+                if entries.is_empty() {
+                    return Some("emptyMap()".to_owned());
+                }
+                let entries = entries.iter().map(|(key_value, value)| {
+                    let key = self.literal(key, key_value)?;
+                    Some(format!("{key} to {}", self.literal(value_shape, value)?))
+                });
+                let entries = entries.collect::<Option<Vec<_>>>()?;
+                Some(format!("mapOf({})", entries.join(", ")))
+            }
+            (Shape::Type(ty), value) => self.type_literal(ty, value),
+            _ => None,
+        }
+    }
+
+    /// Kotlin literal of a value of a registered type: an entry of an enum
+    /// class, a constructor call of a data class, or the literal of the
+    /// field of a type alias.
+    fn type_literal(&mut self, ty: &Type, value: &Value) -> Option<String> {
+        let container = &self.registry.definition_of(ty).container;
+        if container.proxy().is_some() {
+            return None;
+        }
+        match (self.registry.body(ty), value) {
+            (Body::Struct(fields) | Body::Tuple(fields), value) if container.transparent => {
+                let field = fields
+                    .iter()
+                    .find(|field| !(field.skip_serializing && field.skip_deserializing))?;
+                self.field_literal(field, value)
+            }
+            (Body::Tuple(fields), value) if fields.len() == 1 => {
+                self.field_literal(&fields[0], value)
+            }
+            (Body::Struct(fields), Value::Map(entries)) => {
+                let mut arguments = Vec::new();
+                self.arguments(&fields, entries, &mut arguments)?;
+                Some(format!("{}({})", self.name(ty.id), arguments.join(", ")))
+            }
+            (Body::Enum(variants), Value::String(value)) => {
+                let variant = variants
+                    .iter()
+                    .find(|variant| variant.name.serialize == *value)?;
+                unit_only(container, variants.iter()).then(|| {
+                    format!(
+                        "{}.{}",
+                        self.name(ty.id),
+                        keyword(ident(&variant.name.serialize))
+                    )
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Named arguments of the constructor of a data class, for the entries
+    /// of the value of a struct, as in [`Generator::properties`]. Only fields
+    /// that are never serialized can be left out of the entries.
+    fn arguments(
+        &mut self,
+        fields: &[Field],
+        entries: &[(Value, Value)],
+        arguments: &mut Vec<String>,
+    ) -> Option<()> {
+        for field in fields {
+            if field.skip_serializing && field.skip_deserializing {
+                continue;
+            }
+            if field.flatten {
+                if let Shape::Type(ty) = &field.shape
+                    && let Body::Struct(fields) = self.registry.body(ty)
+                    && self.registry.definition_of(ty).container.proxy().is_none()
+                    && !self.registry.definition_of(ty).container.transparent
+                {
+                    self.arguments(&fields, entries, arguments)?;
+                }
+                continue;
+            }
+            let value = entries.iter().find_map(|(key, value)| {
+                matches!(key, Value::String(key) if *key == field.name.serialize).then_some(value)
+            });
+            match value {
+                Some(value) => {
+                    let name = property_name(&field.name.serialize);
+                    arguments.push(format!("{name} = {}", self.field_literal(field, value)?));
+                }
+                // Skipped by `skip_serializing_if`, its value is unknown
+                None if !field.skip_serializing => return None,
+                None => {}
+            }
+        }
+        Some(())
+    }
+
+    /// Kotlin literal of a value of a field, which has none if it is a
+    /// `JsonElement`.
+    fn field_literal(&mut self, field: &Field, value: &Value) -> Option<String> {
+        if field.serialize_with.is_some() || field.deserialize_with.is_some() {
+            return None;
+        }
+        self.literal(&field.shape, value)
+    }
+
+    /// Literal of `Default::default()` for primitives, options and
+    /// collections.
+    fn zero(&mut self, shape: &Shape) -> Option<String> {
+        match shape {
+            Shape::Primitive(Primitive::Bool) => Some("false".to_owned()),
+            Shape::Primitive(Primitive::Char) => Some("'\\u0000'".to_owned()),
+            Shape::Primitive(Primitive::String) => Some("\"\"".to_owned()),
+            Shape::Primitive(Primitive::F32 | Primitive::F64) => {
+                self.literal(shape, &Value::Float(0.0))
+            }
+            Shape::Primitive(_) => self.literal(shape, &Value::Integer(0)),
+            Shape::Option(_) => Some("null".to_owned()),
+            Shape::Seq(_) => Some("emptyList()".to_owned()),
+            Shape::Map(_, _) => Some("emptyMap()".to_owned()),
+            _ => None,
+        }
+    }
+}
+
+/// Whether a variant can be a subclass of a sealed class.
+fn sealed(registry: &Registry, container: &Container, variant: &Variant) -> bool {
+    if variant.untagged || variant.serialize_with.is_some() || variant.deserialize_with.is_some() {
+        return false;
+    }
+    match (&container.tag, &variant.body) {
+        (TagType::Internal { .. }, Body::Tuple(fields)) => match &fields[..] {
+            [field] if field.serialize_with.is_none() && field.deserialize_with.is_none() => {
+                matches!(&field.shape, Shape::Type(ty) if {
+                    let def = registry.definition_of(ty);
+                    matches!(def.body, Body::Struct(_))
+                        && def.container.proxy().is_none()
+                        && !def.container.transparent
+                })
+            }
+            _ => false,
+        },
+        (TagType::Internal { .. } | TagType::Adjacent { .. }, _) => true,
+        (TagType::External | TagType::None, _) => false,
+    }
+}
+
+fn primitive_type(primitive: Primitive) -> &'static str {
+    match primitive {
+        Primitive::Bool => "Boolean",
+        Primitive::I8 => "Byte",
+        Primitive::I16 => "Short",
+        Primitive::I32 => "Int",
+        Primitive::I64 => "Long",
+        Primitive::U8 => "UByte",
+        Primitive::U16 => "UShort",
+        Primitive::U32 => "UInt",
+        Primitive::U64 => "ULong",
+        Primitive::F32 => "Float",
+        Primitive::F64 => "Double",
+        Primitive::Char => "Char",
+        Primitive::String => "String",
+        Primitive::I128 | Primitive::U128 => "JsonPrimitive",
+        Primitive::Unit => "JsonNull",
+    }
+}
+
+fn primitive_literal(primitive: Primitive, value: &Value) -> Option<String> {
+    match (primitive, value) {
+        (Primitive::Bool, Value::Bool(value)) => Some(value.to_string()),
+        (Primitive::I64, Value::Integer(value)) => Some(format!("{value}L")),
+        (Primitive::U8 | Primitive::U16 | Primitive::U32, Value::Integer(value)) => {
+            Some(format!("{value}u"))
+        }
+        (Primitive::U64, Value::Integer(value)) => Some(format!("{value}uL")),
+        (Primitive::I8 | Primitive::I16 | Primitive::I32, Value::Integer(value)) => {
+            Some(value.to_string())
+        }
+        (Primitive::F32 | Primitive::F64, Value::Float(value)) => {
+            let ty = primitive_type(primitive);
+            if value.is_nan() {
+                Some(format!("{ty}.NaN"))
+            } else if value.is_infinite() {
+                let sign = if *value > 0.0 { "POSITIVE" } else { "NEGATIVE" };
+                Some(format!("{ty}.{sign}_INFINITY"))
+            } else if primitive == Primitive::F32 {
+                Some(format!("{value:?}f"))
+            } else {
+                Some(format!("{value:?}"))
+            }
+        }
+        (Primitive::Char, Value::String(value)) => {
+            let mut chars = value.chars();
+            let (Some(c), None) = (chars.next(), chars.next()) else {
+                return None;
+            };
+            Some(match c {
+                '\'' => "'\\''".to_owned(),
+                c => format!("'{}'", escape(c, unicode)),
+            })
+        }
+        (Primitive::String, Value::String(value)) => Some(string(value)),
+        _ => None,
+    }
+}
+
+/// Type parameters, each preceded by `variance`.
+fn params(names: &[String], variance: &str) -> String {
+    if names.is_empty() {
+        return String::new();
+    }
+    let params = names
+        .iter()
+        .map(|name| format!("{variance}{name}"))
+        .collect::<Vec<_>>();
+    format!("<{}>", params.join(", "))
+}
+
+const KEYWORDS: &[&str] = &[
+    "as",
+    "break",
+    "class",
+    "continue",
+    "do",
+    "else",
+    "false",
+    "for",
+    "fun",
+    "if",
+    "in",
+    "interface",
+    "is",
+    "null",
+    "object",
+    "package",
+    "return",
+    "super",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typealias",
+    "typeof",
+    "val",
+    "var",
+    "when",
+    "while",
+];
+
+/// Identifier in backticks if it is a keyword.
+fn keyword(ident: String) -> String {
+    if KEYWORDS.contains(&ident.as_str()) {
+        format!("`{ident}`")
+    } else {
+        ident
+    }
+}
+
+/// Name in camel case, starting with a lowercase letter.
+fn camel_case(name: &str) -> String {
+    let mut camel = String::new();
+    let mut upper = false;
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            upper = !camel.is_empty();
+        } else if upper {
+            camel.push(c.to_ascii_uppercase());
+            upper = false;
+        } else if camel.is_empty() {
+            camel.push(c.to_ascii_lowercase());
+        } else {
+            camel.push(c);
+        }
+    }
+    ident(&camel)
+}
+
+/// Name of a property, fields of tuples are named by their positions.
+fn property_name(name: &str) -> String {
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        return format!("field{name}");
+    }
+    keyword(camel_case(name))
+}
+
+/// Name of a subclass, in Pascal case.
+fn class_name(name: &str) -> String {
+    let camel = camel_case(name);
+    let mut chars = camel.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => camel,
+    }
+}
+
+/// String literal, `$` starting a template.
+fn string(value: &str) -> String {
+    naming::string(value, unicode).replace('$', "\\$")
+}
+
+/// KDoc ending with a newline, indented by `indent`.
+fn kdoc(doc: Option<&str>, indent: &str) -> String {
+    comment(doc, indent, Comment::Block("*&#47;"))
+}
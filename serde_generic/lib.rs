@@ -1,17 +1,91 @@
 pub use serde_generic_derive::SerdeGeneric;
+pub mod attr;
+pub mod reflect;
+pub mod rust;
 pub mod serde;
+pub mod traverse;
+
+#[cfg(feature = "avro")]
+pub mod avro;
+#[cfg(feature = "serde")]
+pub mod de;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "json-schema")]
+pub mod json_schema;
+#[cfg(feature = "kotlin")]
+pub mod kotlin;
+mod naming;
+#[cfg(feature = "openapi")]
+pub mod openapi;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "serde")]
+pub mod ser;
+#[cfg(feature = "swift")]
+pub mod swift;
+#[cfg(feature = "typescript")]
+pub mod typescript;
+
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub mod __private {
+    pub use ::serde;
+}
 
 pub trait SerdeGeneric {
-    /// Type list of polymorphic (generic) parameters.
+    /// Type list of polymorphic (generic) type parameters.
+    ///
+    /// Lifetime parameters are not included: they do not change the shape of
+    /// the data, and borrowed fields keep their lifetimes in `Repr`.
     type Params;
     /// Generic representation of the type.
     type Repr;
-    /// Self but with mocked type parameters
+    /// Self but with mocked type parameters, lifetime parameters are kept as is
     type Mocked: SerdeGeneric;
 
     fn to_repr(self) -> Self::Repr;
     fn from_repr(_: Self::Repr) -> Self;
     const CONTAINER: serde::Container<Self>;
+    /// Names of the type parameters in `Params`, as declared.
+    const TYPE_PARAMS: &'static [&'static str] = &[];
+    /// Const generic parameters in order of declaration, with their values.
+    ///
+    /// They are not a part of `Params` and are left as is in `Mocked`.
+    const CONST_PARAMS: &'static [ConstParam] = &[];
+}
+
+/// Const generic parameter of a type, e.g. `N` of `Buf<const N: usize>`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConstParam {
+    pub name: &'static str,
+    /// Type of the parameter as written in the source.
+    pub ty: &'static str,
+    /// Value of the parameter for the type it is looked up on.
+    pub value: ConstValue,
+}
+
+/// Value of a const generic parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum ConstValue {
+    Unsigned(u128),
+    Signed(i128),
+    Bool(bool),
+    Char(char),
+}
+
+impl core::fmt::Display for ConstValue {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            ConstValue::Unsigned(value) => write!(f, "{value}"),
+            ConstValue::Signed(value) => write!(f, "{value}"),
+            ConstValue::Bool(value) => write!(f, "{value}"),
+            ConstValue::Char(value) => write!(f, "{value:?}"),
+        }
+    }
 }
 
 // Product types
@@ -30,10 +104,10 @@ pub enum HSum<H, T> {
 pub enum HSumNil {}
 
 // Peano numbers
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
 pub struct Succ<X>(core::marker::PhantomData<X>);
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
 pub struct Zero;
 
 pub trait PeanoNumber {
@@ -48,9 +122,21 @@ impl<P: PeanoNumber> PeanoNumber for Succ<P> {
     const NUMBER: usize = P::NUMBER + 1;
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Placeholder of the type parameter number `X` in [`SerdeGeneric::Mocked`].
+///
+/// It implements common traits, so that types whose parameters are bounded
+/// by them can be mocked. Other parameters can be given a substitute with
+/// `#[serde_generic(mock(X = "Type"))]` on the type, or the type can opt out
+/// of mocking with `#[serde_generic(no_mock)]`, which makes `Mocked` be `Self`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
 pub struct TypeVar<X>(pub X);
 
+impl<X: PeanoNumber> core::fmt::Display for TypeVar<X> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "X{}", X::NUMBER)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct NamedStruct<X>(pub X);
 
@@ -75,6 +161,121 @@ pub trait SerdeVariantAttr<VI> {
     const VARIANT: serde::Variant;
 }
 
+/// `#[serde_generic(...)]` attributes of a struct field, see [`attr`].
+pub trait GenericFieldAttr<F, I> {
+    const FIELD: attr::Field;
+}
+
+/// `#[serde_generic(...)]` attributes of an enum variant field.
+pub trait GenericVariantFieldAttr<F, VI, FI> {
+    const FIELD: attr::Field;
+}
+
+/// `#[serde_generic(...)]` attributes of an enum variant.
+pub trait GenericVariantAttr<VI> {
+    const VARIANT: attr::Variant;
+}
+
+/// Constructor of the `#[serde(default)]` value of the container.
+///
+/// Kept apart from [`serde::Container`] because with `Default::Default` it is
+/// only implemented when the type is `Default`, just like `serde_derive` requires.
+pub trait SerdeContainerDefault {
+    const DEFAULT: Option<fn() -> Self>;
+}
+
+/// `#[serde(from = "Proxy")]` or `#[serde(try_from = "Proxy")]`: the type is
+/// deserialized as `Proxy` and then converted. Only implemented by types
+/// with one of these attributes.
+///
+/// Errors of `TryFrom` are turned into strings, as `serde_derive` passes
+/// them to `serde::de::Error::custom`.
+pub trait SerdeContainerFrom: Sized {
+    type Proxy;
+    fn from_proxy(proxy: Self::Proxy) -> Result<Self, String>;
+}
+
+/// `#[serde(into = "Proxy")]`: the type is converted to `Proxy` and then
+/// serialized. Only implemented by types with this attribute.
+pub trait SerdeContainerInto {
+    type Proxy;
+    fn into_proxy(self) -> Self::Proxy;
+}
+
+/// `#[serde(remote = "Remote")]`: the type is a mirror of `Remote`, which is
+/// described by the metadata of the mirror and shares its `Repr`. Only
+/// implemented by types with this attribute.
+///
+/// Fields with `#[serde(getter = "...")]` are cloned from what the getter
+/// returns. As in `serde_derive`, if there are any, `remote_from_repr` goes
+/// through the mirror and needs `Remote: From<Self>`.
+pub trait SerdeRemote: SerdeGeneric {
+    type Remote;
+    fn remote_to_repr(remote: Self::Remote) -> Self::Repr;
+    fn remote_from_repr(repr: Self::Repr) -> Self::Remote;
+}
+
+/// Typed `#[serde(getter = "...")]` function, returning a copy of the field.
+pub type Getter<R, F> = fn(&R) -> F;
+
+/// `#[serde(getter = "...")]` of the field `I` of a remote struct, with the
+/// path of the function. Implemented for every field of a struct with
+/// `#[serde(remote = "...")]`.
+pub trait SerdeRemoteGetter<F, I>: SerdeRemote {
+    const GETTER: Option<(&'static str, Getter<Self::Remote, F>)>;
+}
+
+/// Constructor of the `#[serde(default)]` value of a struct field, see
+/// [`SerdeContainerDefault`].
+pub trait SerdeFieldDefault<F, I> {
+    const DEFAULT: Option<fn() -> F>;
+}
+
+/// Constructor of the `#[serde(default)]` value of an enum variant field, see
+/// [`SerdeContainerDefault`].
+pub trait SerdeVariantFieldDefault<F, VI, FI> {
+    const DEFAULT: Option<fn() -> F>;
+}
+
+/// Typed `#[serde(serialize_with = "...")]` function.
+#[cfg(feature = "serde")]
+pub type SerializeWith<T, S> =
+    fn(&T, S) -> Result<<S as ::serde::Serializer>::Ok, <S as ::serde::Serializer>::Error>;
+
+/// Typed `#[serde(deserialize_with = "...")]` function.
+#[cfg(feature = "serde")]
+pub type DeserializeWith<'de, T, D> = fn(D) -> Result<T, <D as ::serde::Deserializer<'de>>::Error>;
+
+/// `#[serde(serialize_with = "...")]`, `#[serde(deserialize_with = "...")]`
+/// and `#[serde(with = "...")]` functions of a struct field.
+///
+/// Kept apart from [`serde::Field`], which only has their paths, because
+/// the functions are generic over the serializer and the deserializer.
+/// Both functions are referenced even if only one of them is used, so a
+/// `with` module has to provide both.
+#[cfg(feature = "serde")]
+pub trait SerdeFieldWith<F, I> {
+    fn serialize_with<S: ::serde::Serializer>() -> Option<SerializeWith<F, S>>;
+    fn deserialize_with<'de, D: ::serde::Deserializer<'de>>() -> Option<DeserializeWith<'de, F, D>>;
+}
+
+/// Functions of an enum variant field, see [`SerdeFieldWith`].
+#[cfg(feature = "serde")]
+pub trait SerdeVariantFieldWith<F, VI, FI> {
+    fn serialize_with<S: ::serde::Serializer>() -> Option<SerializeWith<F, S>>;
+    fn deserialize_with<'de, D: ::serde::Deserializer<'de>>() -> Option<DeserializeWith<'de, F, D>>;
+}
+
+/// Functions of an enum variant, see [`SerdeFieldWith`]. They work on the
+/// representation `R` of the variant: `serialize_with` gets references to
+/// all of the fields and `deserialize_with` returns them in a tuple, as in
+/// `serde_derive`.
+#[cfg(feature = "serde")]
+pub trait SerdeVariantWith<R, VI> {
+    fn serialize_with<S: ::serde::Serializer>() -> Option<SerializeWith<R, S>>;
+    fn deserialize_with<'de, D: ::serde::Deserializer<'de>>() -> Option<DeserializeWith<'de, R, D>>;
+}
+
 pub trait HLen {
     type Len: PeanoNumber;
 }
@@ -91,3 +292,23 @@ impl<H, T: HLen> HLen for (H, T) {
 impl HLen for () {
     type Len = Zero;
 }
+
+impl<H, T: HLen> HLen for HSum<H, T> {
+    type Len = Succ<T::Len>;
+}
+impl HLen for HSumNil {
+    type Len = Zero;
+}
+
+impl<X: HLen> HLen for NamedStruct<X> {
+    type Len = X::Len;
+}
+impl<X: HLen> HLen for UnnamedStruct<X> {
+    type Len = X::Len;
+}
+impl<X: HLen> HLen for Enum<X> {
+    type Len = X::Len;
+}
+impl HLen for UnitStruct {
+    type Len = Zero;
+}
@@ -0,0 +1,107 @@
+//! Naming and escaping shared by the generators.
+
+// Each generator only uses some of these, depending on the features
+#![allow(dead_code)]
+
+use crate::reflect::{Body, Container, TagType, Variant};
+
+/// Name with the characters not allowed in an identifier replaced by
+/// underscores, and an underscore in front if it starts with a digit.
+pub(crate) fn ident(name: &str) -> String {
+    let ident = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{ident}")
+    } else {
+        ident
+    }
+}
+
+/// [`ident`] starting with an uppercase letter, as types are named.
+pub(crate) fn type_name(name: &str) -> String {
+    let ident = ident(name);
+    let mut chars = ident.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => ident,
+    }
+}
+
+/// Whether an enum is serialized as the name of its variant alone: it is
+/// externally tagged and none of its variants has fields or is untagged.
+pub(crate) fn unit_only<'a>(
+    container: &Container,
+    mut variants: impl Iterator<Item = &'a Variant>,
+) -> bool {
+    container.tag == TagType::External
+        && variants.all(|variant| variant.body == Body::Unit && !variant.untagged)
+}
+
+/// Double-quoted string literal of `value` in a language with the escapes
+/// of C, other control characters being escaped by `unicode`.
+pub(crate) fn string(value: &str, unicode: fn(char) -> String) -> String {
+    let mut literal = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => literal += "\\\"",
+            c => literal += &escape(c, unicode),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// Character `c` in a string or character literal, see [`string`]. Quotes
+/// are left to the caller.
+pub(crate) fn escape(c: char, unicode: fn(char) -> String) -> String {
+    match c {
+        '\\' => "\\\\".to_owned(),
+        '\n' => "\\n".to_owned(),
+        '\r' => "\\r".to_owned(),
+        '\t' => "\\t".to_owned(),
+        c if c.is_control() => unicode(c),
+        c => c.to_string(),
+    }
+}
+
+/// `\u` escape with four hexadecimal digits, as in Python, TypeScript and
+/// Kotlin.
+pub(crate) fn unicode(c: char) -> String {
+    format!("\\u{:04x}", c as u32)
+}
+
+/// How [`comment`] writes a doc comment.
+pub(crate) enum Comment<'a> {
+    /// Line comments starting with a marker, e.g. `//`.
+    Line(&'a str),
+    /// `/** ... */`, as JSDoc and KDoc, with `*/` in the doc replaced by
+    /// the given escape.
+    Block(&'a str),
+}
+
+/// Doc comment ending with a newline, indented by `indent`.
+pub(crate) fn comment(doc: Option<&str>, indent: &str, style: Comment) -> String {
+    let Some(doc) = doc else {
+        return String::new();
+    };
+    let line = |marker: &str, line: &str| {
+        if line.is_empty() {
+            format!("{indent}{marker}\n")
+        } else {
+            format!("{indent}{marker} {line}\n")
+        }
+    };
+    match style {
+        Comment::Line(marker) => doc.lines().map(|doc| line(marker, doc)).collect(),
+        Comment::Block(escape) => {
+            let doc = doc.replace("*/", escape);
+            if let [doc] = doc.lines().collect::<Vec<_>>()[..] {
+                return format!("{indent}/** {doc} */\n");
+            }
+            let lines = doc.lines().map(|doc| line(" *", doc)).collect::<String>();
+            format!("{indent}/**\n{lines}{indent} */\n")
+        }
+    }
+}
@@ -0,0 +1,301 @@
+//! OpenAPI 3.1 documents with `components/schemas` made of reflected types.
+//!
+//! ```
+//! # use serde_generic::SerdeGeneric;
+//! use serde_generic::openapi::{OpenApi, Operation};
+//!
+//! #[derive(SerdeGeneric)]
+//! struct Pet {
+//!     name: String,
+//! }
+//!
+//! let document = OpenApi::new("Pets", "1.0.0")
+//!     .operation(
+//!         Operation::post("/pets")
+//!             .operation_id("addPet")
+//!             .request::<Pet, _>()
+//!             .response::<Pet, _>(201, "Created"),
+//!     )
+//!     .to_json();
+//! assert_eq!(
+//!     document["paths"]["/pets"]["post"]["requestBody"]["content"]["application/json"]["schema"],
+//!     serde_json::json!({"$ref": "#/components/schemas/Pet"})
+//! );
+//! assert!(document["components"]["schemas"]["Pet"].is_object());
+//! ```
+//!
+//! Schemas are the ones of [`json_schema`](crate::json_schema), as OpenAPI
+//! 3.1 uses JSON Schema 2020-12. Enums with an internal or adjacent tag get a
+//! `discriminator`: their variants become schemas of their own, named after
+//! the enum and the variant, and the `mapping` refers to them by the names
+//! of the variants.
+
+use crate::json_schema::{self, Generator};
+use crate::reflect::{Body, Reflect, Registry, Shape, TagType, Type};
+use serde_json::{Map, Value, json};
+
+const REF_PREFIX: &str = "#/components/schemas/";
+
+/// OpenAPI document, made of operations and types.
+pub struct OpenApi {
+    title: String,
+    version: String,
+    registry: Registry,
+    operations: Vec<Operation>,
+}
+
+impl OpenApi {
+    /// Document with the `title` and `version` of the API.
+    pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+        OpenApi {
+            title: title.into(),
+            version: version.into(),
+            registry: Registry::new(),
+            operations: Vec::new(),
+        }
+    }
+
+    /// Adds `T` and the types it refers to to the components, even if no
+    /// operation uses them.
+    pub fn schema<T: ?Sized + Reflect<M>, M>(mut self) -> Self {
+        self.registry.register::<T, _>();
+        self
+    }
+
+    /// Adds an operation along with its types.
+    pub fn operation(mut self, operation: Operation) -> Self {
+        self.registry.merge(&operation.registry);
+        self.operations.push(operation);
+        self
+    }
+
+    pub fn to_json(&self) -> Value {
+        let generator = Generator::with_ref_prefix(&self.registry, REF_PREFIX);
+        let mut paths = Map::new();
+        for operation in &self.operations {
+            let path = paths
+                .entry(operation.path.clone())
+                .or_insert_with(|| json!({}));
+            path[operation.method.as_str()] = operation.to_json(&generator);
+        }
+        json!({
+            "openapi": "3.1.0",
+            "info": {"title": self.title, "version": self.version},
+            "paths": paths,
+            "components": {"schemas": self.schemas(&generator)},
+        })
+    }
+
+    fn schemas(&self, generator: &Generator) -> Map<String, Value> {
+        let mut schemas = Map::new();
+        for ty in self.registry.instances() {
+            let name = self.registry.name(ty).expect("instance is registered");
+            match discriminated(&self.registry, generator, ty, name) {
+                Some((schema, variants)) => {
+                    schemas.insert(name.to_owned(), schema);
+                    schemas.extend(variants);
+                }
+                None => {
+                    schemas.insert(name.to_owned(), generator.definition(ty));
+                }
+            }
+        }
+        schemas
+    }
+}
+
+/// Schema of an enum with an internal or adjacent tag, and the schemas of its
+/// variants.
+fn discriminated(
+    registry: &Registry,
+    generator: &Generator,
+    ty: &Type,
+    name: &str,
+) -> Option<(Value, Vec<(String, Value)>)> {
+    let container = &registry.definition_of(ty).container;
+    let (TagType::Internal { tag } | TagType::Adjacent { tag, .. }) = &container.tag else {
+        return None;
+    };
+    let Body::Enum(variants) = registry.body(ty) else {
+        return None;
+    };
+    // Untagged variants have no value of the tag
+    if container.proxy().is_some() || variants.iter().any(|variant| variant.untagged) {
+        return None;
+    }
+    let mut refs = Vec::new();
+    let mut mapping = Map::new();
+    let mut schemas = Vec::new();
+    for variant in json_schema::listed_variants(&variants) {
+        let variant_name = format!("{name}.{}", component_name(&variant.name.serialize));
+        let reference = format!("{REF_PREFIX}{variant_name}");
+        for tag_value in json_schema::names(&variant.name) {
+            mapping.insert(tag_value.to_owned(), reference.clone().into());
+        }
+        refs.push(json!({"$ref": reference}));
+        schemas.push((variant_name, generator.variant_schema(container, variant)));
+    }
+    let mut schema = json!({
+        "oneOf": refs,
+        "discriminator": {"propertyName": tag, "mapping": mapping},
+    });
+    if let Some(doc) = &container.doc {
+        schema["description"] = doc.as_str().into();
+    }
+    Some((schema, schemas))
+}
+
+/// Replaces characters not allowed in the names of components.
+fn component_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// HTTP method of an operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Put,
+    Post,
+    Delete,
+    Options,
+    Head,
+    Patch,
+    Trace,
+}
+
+impl Method {
+    /// Name of the method in lowercase, as in a path item.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Method::Get => "get",
+            Method::Put => "put",
+            Method::Post => "post",
+            Method::Delete => "delete",
+            Method::Options => "options",
+            Method::Head => "head",
+            Method::Patch => "patch",
+            Method::Trace => "trace",
+        }
+    }
+}
+
+/// Operation on a path, with JSON request and response bodies.
+#[derive(Debug, Clone)]
+pub struct Operation {
+    method: Method,
+    path: String,
+    operation_id: Option<String>,
+    summary: Option<String>,
+    request: Option<Shape>,
+    /// Status codes with descriptions and bodies.
+    responses: Vec<(u16, String, Option<Shape>)>,
+    /// Types of the bodies.
+    registry: Registry,
+}
+
+impl Operation {
+    pub fn new(method: Method, path: impl Into<String>) -> Self {
+        Operation {
+            method,
+            path: path.into(),
+            operation_id: None,
+            summary: None,
+            request: None,
+            responses: Vec::new(),
+            registry: Registry::new(),
+        }
+    }
+
+    pub fn get(path: impl Into<String>) -> Self {
+        Self::new(Method::Get, path)
+    }
+
+    pub fn put(path: impl Into<String>) -> Self {
+        Self::new(Method::Put, path)
+    }
+
+    pub fn post(path: impl Into<String>) -> Self {
+        Self::new(Method::Post, path)
+    }
+
+    pub fn delete(path: impl Into<String>) -> Self {
+        Self::new(Method::Delete, path)
+    }
+
+    pub fn patch(path: impl Into<String>) -> Self {
+        Self::new(Method::Patch, path)
+    }
+
+    pub fn operation_id(mut self, operation_id: impl Into<String>) -> Self {
+        self.operation_id = Some(operation_id.into());
+        self
+    }
+
+    pub fn summary(mut self, summary: impl Into<String>) -> Self {
+        self.summary = Some(summary.into());
+        self
+    }
+
+    /// Required request body of type `T`.
+    pub fn request<T: ?Sized + Reflect<M>, M>(mut self) -> Self {
+        self.request = Some(self.registry.register::<T, _>());
+        self
+    }
+
+    /// Response with a body of type `T`.
+    pub fn response<T: ?Sized + Reflect<M>, M>(
+        mut self,
+        status: u16,
+        description: impl Into<String>,
+    ) -> Self {
+        let shape = self.registry.register::<T, _>();
+        self.responses
+            .push((status, description.into(), Some(shape)));
+        self
+    }
+
+    /// Response without a body.
+    pub fn empty_response(mut self, status: u16, description: impl Into<String>) -> Self {
+        self.responses.push((status, description.into(), None));
+        self
+    }
+
+    fn to_json(&self, generator: &Generator) -> Value {
+        let content =
+            |shape: &Shape| json!({"application/json": {"schema": generator.schema(shape)}});
+        let mut operation = Map::new();
+        if let Some(operation_id) = &self.operation_id {
+            operation.insert("operationId".into(), operation_id.as_str().into());
+        }
+        if let Some(summary) = &self.summary {
+            operation.insert("summary".into(), summary.as_str().into());
+        }
+        if let Some(request) = &self.request {
+            operation.insert(
+                "requestBody".into(),
+                json!({"required": true, "content": content(request)}),
+            );
+        }
+        let responses = self
+            .responses
+            .iter()
+            .map(|(status, description, shape)| {
+                let mut response = json!({"description": description});
+                if let Some(shape) = shape {
+                    response["content"] = content(shape);
+                }
+                (status.to_string(), response)
+            })
+            .collect::<Map<_, _>>();
+        operation.insert("responses".into(), Value::Object(responses));
+        Value::Object(operation)
+    }
+}
@@ -0,0 +1,444 @@
+//! Protocol Buffers (proto3) files of reflected types.
+//!
+//! ```
+//! # use serde_generic::SerdeGeneric;
+//! #[derive(SerdeGeneric)]
+//! struct Page<T> {
+//!     items: Vec<T>,
+//!     #[serde_generic(proto = 5)]
+//!     next: Option<String>,
+//! }
+//!
+//! assert_eq!(
+//!     serde_generic::protobuf::file_for::<Page<u8>, _>(Some("pages")).unwrap(),
+//!     "\
+//! syntax = \"proto3\";
+//!
+//! package pages;
+//!
+//! message PageU8 {
+//!   repeated uint32 items = 1;
+//!   optional string next = 5;
+//! }
+//! "
+//! );
+//! ```
+//!
+//! Protocol Buffers have no generics, so every instantiation is a message of
+//! its own, named by [`Registry::name`]. Structs are messages, enums with
+//! only unit variants are enums, and other enums are messages with a `oneof`,
+//! in which struct and tuple variants are nested messages and unit variants
+//! are `google.protobuf.Empty`.
+//!
+//! Fields and variants are numbered by their position, starting from 1, and
+//! enum values from 0, so that renaming does not change them. Reordering,
+//! inserting or removing them does: `#[serde_generic(proto = N)]` pins the
+//! number of a field or variant. The ones skipped either way are left out but
+//! keep their numbers. A number given twice is an error when deriving:
+//!
+//! ```compile_fail
+//! # use serde_generic::SerdeGeneric;
+//! #[derive(SerdeGeneric)]
+//! struct Twice {
+//!     #[serde_generic(proto = 1)]
+//!     first: u8,
+//!     #[serde_generic(proto = 1)]
+//!     second: u8,
+//! }
+//! ```
+//!
+//! Field names are in snake case, which the JSON mapping of Protocol Buffers
+//! turns into camel case. Transparent types are their field. Types with
+//! `from`, `try_from` or `into` proxies and fields or variants with
+//! `serialize_with` or `deserialize_with` are `google.protobuf.Value`. Nested
+//! options and collections are wrapped in messages named by
+//! [`Shape::mono_name`], and so are maps with keys other than integers,
+//! strings or booleans, as repeated entries. `i128` and `u128` are strings.
+
+use crate::naming::{Comment, comment, ident, type_name};
+use crate::reflect::{Body, Field, Primitive, Reflect, Registry, Shape, Type, Variant};
+use core::fmt;
+use std::collections::{BTreeSet, HashSet};
+
+/// Numbers given to two fields of a message, or to two values of an enum,
+/// by `#[serde_generic(proto = N)]` on one and by position on the other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// Name of the message or enum.
+    pub name: String,
+    pub number: u32,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "number {} is used twice in {}", self.number, self.name)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// File with `T` and the types it refers to.
+pub fn file_for<T: ?Sized + Reflect<M>, M>(package: Option<&str>) -> Result<String, Error> {
+    let mut registry = Registry::new();
+    registry.register::<T, _>();
+    file(&registry, package)
+}
+
+/// File with a message or enum for every instantiation in the registry, in
+/// topological order, followed by the wrapper messages.
+///
+/// Numbers given twice by `#[serde_generic(proto = N)]` are rejected when
+/// deriving; this fails if one collides with the position of another field
+/// or variant.
+pub fn file(registry: &Registry, package: Option<&str>) -> Result<String, Error> {
+    let mut generator = Generator {
+        registry,
+        imports: BTreeSet::new(),
+        wrappers: Vec::new(),
+        wrapped: HashSet::new(),
+    };
+    let mut declarations = Vec::new();
+    for ty in registry.instances() {
+        let name = registry.name(ty).expect("instance is registered");
+        declarations.extend(generator.declaration(ty, &ident(name))?);
+    }
+    declarations.append(&mut generator.wrappers);
+
+    let mut file = String::from("syntax = \"proto3\";\n");
+    if let Some(package) = package {
+        file += &format!("\npackage {package};\n");
+    }
+    if !generator.imports.is_empty() {
+        file.push('\n');
+        for import in &generator.imports {
+            file += &format!("import \"{import}\";\n");
+        }
+    }
+    for declaration in declarations {
+        file += &format!("\n{declaration}");
+    }
+    Ok(file)
+}
+
+struct Generator<'a> {
+    registry: &'a Registry,
+    imports: BTreeSet<&'static str>,
+    /// Messages wrapping shapes that can't be the type of a field.
+    wrappers: Vec<String>,
+    wrapped: HashSet<String>,
+}
+
+/// Type of a field with its label, e.g. `repeated` or `optional`.
+struct FieldType {
+    label: &'static str,
+    name: String,
+}
+
+impl Generator<'_> {
+    /// Message or enum of an instantiation, transparent types and types with
+    /// proxies have none.
+    fn declaration(&mut self, ty: &Type, name: &str) -> Result<Option<String>, Error> {
+        let container = &self.registry.definition_of(ty).container;
+        if container.transparent || container.proxy().is_some() {
+            return Ok(None);
+        }
+        let doc = comment(container.doc.as_deref(), "", Comment::Line("//"));
+        let declaration = match self.registry.body(ty) {
+            Body::Struct(fields) | Body::Tuple(fields) => self.message(name, &fields, "")?,
+            Body::Unit => format!("message {name} {{}}\n"),
+            Body::Enum(variants) => {
+                let variants = listed_variants(&variants);
+                if variants
+                    .iter()
+                    .all(|(_, variant)| variant.body == Body::Unit)
+                {
+                    enumeration(name, &variants)?
+                } else {
+                    self.oneof(name, &variants)?
+                }
+            }
+        };
+        Ok(Some(doc + &declaration))
+    }
+
+    /// Message with `fields`, indented by `indent`.
+    fn message(&mut self, name: &str, fields: &[Field], indent: &str) -> Result<String, Error> {
+        let mut message = format!("{indent}message {name} {{\n");
+        let mut numbers = HashSet::new();
+        for (i, field) in fields.iter().enumerate() {
+            let number = field.proto_number.unwrap_or(i as u32 + 1);
+            distinct(&mut numbers, name, number)?;
+            if field.skip_serializing && field.skip_deserializing {
+                continue;
+            }
+            let field_type = if field.serialize_with.is_some() || field.deserialize_with.is_some() {
+                self.value()
+            } else {
+                self.field_type(&field.shape)
+            };
+            // Fields of tuples are named by their positions
+            let field_name = if field
+                .name
+                .serialize
+                .starts_with(|c: char| c.is_ascii_digit())
+            {
+                format!("field_{}", field.name.serialize)
+            } else {
+                field_name(&field.name.serialize)
+            };
+            message += &comment(
+                field.doc.as_deref(),
+                &format!("{indent}  "),
+                Comment::Line("//"),
+            );
+            message += &format!(
+                "{indent}  {}{} {field_name} = {number};\n",
+                field_type.label, field_type.name
+            );
+        }
+        Ok(message + indent + "}\n")
+    }
+
+    /// Message with a `oneof` of the variants of an enum with data.
+    fn oneof(&mut self, name: &str, variants: &[(usize, &Variant)]) -> Result<String, Error> {
+        let mut nested = String::new();
+        let mut cases = String::new();
+        let mut numbers = HashSet::new();
+        for &(i, variant) in variants {
+            let number = variant.proto_number.unwrap_or(i as u32 + 1);
+            distinct(&mut numbers, name, number)?;
+            let case_type = match &variant.body {
+                _ if variant.serialize_with.is_some() || variant.deserialize_with.is_some() => {
+                    self.value().name
+                }
+                Body::Unit => self.empty(),
+                Body::Tuple(fields) if fields.len() == 1 => self.singular(&fields[0].shape),
+                Body::Struct(fields) | Body::Tuple(fields) => {
+                    let message = type_name(&variant.name.serialize);
+                    nested += &self.message(&message, fields, "  ")?;
+                    message
+                }
+                Body::Enum(_) => unreachable!("variants have no variants"),
+            };
+            cases += &comment(variant.doc.as_deref(), "    ", Comment::Line("//"));
+            cases += &format!(
+                "    {case_type} {} = {number};\n",
+                field_name(&variant.name.serialize)
+            );
+        }
+        Ok(format!(
+            "message {name} {{\n{nested}  oneof {} {{\n{cases}  }}\n}}\n",
+            field_name(name)
+        ))
+    }
+
+    fn field_type(&mut self, shape: &Shape) -> FieldType {
+        let singular = |name| FieldType { label: "", name };
+        match shape {
+            Shape::Option(shape) => FieldType {
+                label: "optional ",
+                name: self.singular(shape),
+            },
+            Shape::Seq(shape) | Shape::Array(shape, _) => FieldType {
+                label: "repeated ",
+                name: self.singular(shape),
+            },
+            Shape::Map(key, value) => match map_key(key) {
+                Some(key) => singular(format!("map<{key}, {}>", self.singular(value))),
+                None => FieldType {
+                    label: "repeated ",
+                    name: self.wrapper(
+                        &format!("{}Entry", shape.mono_name(self.registry)),
+                        |generator| {
+                            format!(
+                                "  {} key = 1;\n  {} value = 2;\n",
+                                generator.singular(key),
+                                generator.singular(value)
+                            )
+                        },
+                    ),
+                },
+            },
+            Shape::Type(ty) => match transparent_field(self.registry, ty) {
+                Some(shape) => self.field_type(&shape),
+                None => singular(self.singular(shape)),
+            },
+            shape => singular(self.singular(shape)),
+        }
+    }
+
+    /// Type of a field without a label, as in a `oneof` or as the element of
+    /// a repeated field.
+    fn singular(&mut self, shape: &Shape) -> String {
+        match shape {
+            Shape::Param(_) => unreachable!("instances have no type parameters"),
+            Shape::Primitive(Primitive::Unit) => self.empty(),
+            Shape::Primitive(primitive) => scalar(*primitive).to_owned(),
+            Shape::Type(ty) if self.registry.definition_of(ty).container.proxy().is_some() => {
+                self.value().name
+            }
+            Shape::Type(ty) => match transparent_field(self.registry, ty) {
+                Some(shape) => self.singular(&shape),
+                None => {
+                    let name = self.registry.name(ty).expect("type is registered");
+                    ident(name)
+                }
+            },
+            Shape::Tuple(shapes) => self.wrapper(&shape.mono_name(self.registry), |generator| {
+                let mut fields = String::new();
+                for (i, shape) in shapes.iter().enumerate() {
+                    let field_type = generator.field_type(shape);
+                    fields += &format!(
+                        "  {}{} field_{i} = {};\n",
+                        field_type.label,
+                        field_type.name,
+                        i + 1
+                    );
+                }
+                fields
+            }),
+            shape => self.wrapper(&shape.mono_name(self.registry), |generator| {
+                let field_type = generator.field_type(shape);
+                format!("  {}{} value = 1;\n", field_type.label, field_type.name)
+            }),
+        }
+    }
+
+    /// Name of a wrapper message, declared once with the fields made by
+    /// `fields`.
+    fn wrapper(&mut self, name: &str, fields: impl FnOnce(&mut Self) -> String) -> String {
+        let name = ident(name);
+        if self.wrapped.insert(name.clone()) {
+            let fields = fields(self);
+            self.wrappers
+                .push(format!("message {name} {{\n{fields}}}\n"));
+        }
+        name
+    }
+
+    fn empty(&mut self) -> String {
+        self.imports.insert("google/protobuf/empty.proto");
+        "google.protobuf.Empty".to_owned()
+    }
+
+    fn value(&mut self) -> FieldType {
+        self.imports.insert("google/protobuf/struct.proto");
+        FieldType {
+            label: "",
+            name: "google.protobuf.Value".to_owned(),
+        }
+    }
+}
+
+/// Enum of an enum with only unit variants, with values prefixed by the name
+/// of the enum, as they share its scope.
+fn enumeration(name: &str, variants: &[(usize, &Variant)]) -> Result<String, Error> {
+    let prefix = constant_name(name);
+    let mut values = String::new();
+    let mut numbers = HashSet::new();
+    for &(i, variant) in variants {
+        let number = variant.proto_number.unwrap_or(i as u32);
+        distinct(&mut numbers, name, number)?;
+        values += &comment(variant.doc.as_deref(), "  ", Comment::Line("//"));
+        values += &format!(
+            "  {prefix}_{} = {number};\n",
+            constant_name(&variant.name.serialize)
+        );
+    }
+    // The first value of a proto3 enum must be zero
+    let unspecified = if numbers.contains(&0) {
+        String::new()
+    } else {
+        format!("  {prefix}_UNSPECIFIED = 0;\n")
+    };
+    Ok(format!("enum {name} {{\n{unspecified}{values}}}\n"))
+}
+
+/// Adds the number of a field or value of `name` to `numbers`, which must
+/// not have it yet.
+fn distinct(numbers: &mut HashSet<u32>, name: &str, number: u32) -> Result<(), Error> {
+    if numbers.insert(number) {
+        Ok(())
+    } else {
+        Err(Error {
+            name: name.to_owned(),
+            number,
+        })
+    }
+}
+
+/// Variants not skipped either way, with their positions among all variants.
+fn listed_variants(variants: &[Variant]) -> Vec<(usize, &Variant)> {
+    variants
+        .iter()
+        .enumerate()
+        .filter(|(_, variant)| !(variant.skip_serializing && variant.skip_deserializing))
+        .collect()
+}
+
+/// Shape of the field of a transparent type.
+fn transparent_field(registry: &Registry, ty: &Type) -> Option<Shape> {
+    let container = &registry.definition_of(ty).container;
+    if !container.transparent || container.proxy().is_some() {
+        return None;
+    }
+    let body = registry.body(ty);
+    let field = body
+        .fields()
+        .find(|field| !(field.skip_serializing && field.skip_deserializing))
+        .expect("transparent struct has a field");
+    Some(field.shape.clone())
+}
+
+fn scalar(primitive: Primitive) -> &'static str {
+    match primitive {
+        Primitive::Bool => "bool",
+        Primitive::I8 | Primitive::I16 | Primitive::I32 => "int32",
+        Primitive::I64 => "int64",
+        Primitive::U8 | Primitive::U16 | Primitive::U32 => "uint32",
+        Primitive::U64 => "uint64",
+        Primitive::F32 => "float",
+        Primitive::F64 => "double",
+        Primitive::I128 | Primitive::U128 | Primitive::Char | Primitive::String => "string",
+        Primitive::Unit => unreachable!("unit is a message"),
+    }
+}
+
+/// Type of the keys of a `map` field, which can only be an integer, a string
+/// or a boolean.
+fn map_key(shape: &Shape) -> Option<&'static str> {
+    match shape {
+        Shape::Primitive(Primitive::F32 | Primitive::F64 | Primitive::Unit) => None,
+        Shape::Primitive(primitive) => Some(scalar(*primitive)),
+        _ => None,
+    }
+}
+
+/// Words of a name in any case, split at underscores, hyphens and
+/// lowercase to uppercase transitions.
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut lower = false;
+    for c in ident(name).chars() {
+        if (c == '_' || (lower && c.is_ascii_uppercase())) && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        if c != '_' {
+            word.push(c);
+        }
+        lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn field_name(name: &str) -> String {
+    ident(&words(name).join("_").to_ascii_lowercase())
+}
+
+fn constant_name(name: &str) -> String {
+    ident(&words(name).join("_").to_ascii_uppercase())
+}
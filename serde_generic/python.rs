@@ -0,0 +1,894 @@
+//! Python modules of reflected types: pydantic v2 models or `TypedDict`s,
+//! generics included.
+//!
+//! ```
+//! # use serde_generic::SerdeGeneric;
+//! use serde_generic::python::{Style, module_for};
+//!
+//! #[derive(SerdeGeneric)]
+//! #[serde(rename_all = "camelCase")]
+//! struct Page<T> {
+//!     items: Vec<T>,
+//!     next_page: Option<String>,
+//! }
+//!
+//! assert_eq!(
+//!     module_for::<Page<u8>, _>(Style::Pydantic),
+//!     r#"from __future__ import annotations
+//!
+//! from typing import Generic, Optional, TypeVar
+//!
+//! from pydantic import BaseModel, ConfigDict, Field
+//!
+//! X0 = TypeVar("X0")
+//!
+//!
+//! class Page(BaseModel, Generic[X0]):
+//!     model_config = ConfigDict(populate_by_name=True)
+//!
+//!     items: list[X0]
+//!     next_page: Optional[str] = Field(default=None, alias="nextPage")
+//! "#
+//! );
+//! ```
+//!
+//! Every [`Definition`] is declared once, with its type parameters as the
+//! `TypeVar`s `X0`, `X1` and so on, as in [`Shape::Param`]. Types are named
+//! after their serialized names, followed by a number when two types have
+//! the same name, see [`Registry::definition_name`].
+//!
+//! Models have snake case attributes with aliases for the serialize and
+//! deserialize names that differ, so `model_dump(by_alias=True)` gives what
+//! `serde` reads. Fields that can be missing have a default: the value of
+//! `#[serde(default = "...")]` if it is a [`Value`](crate::reflect::Value),
+//! and `None` otherwise. Flattened models are base classes. Newtype and
+//! tuple structs and transparent types are `RootModel`s.
+//!
+//! Enums with only unit variants are `Literal`s. Other enums are unions of a
+//! model for each variant, named after the enum and the variant, in a
+//! `RootModel` so that they can be generic. The unions of internally and
+//! adjacently tagged enums are discriminated by their tags.
+//!
+//! With [`Style::TypedDict`], structs and variants are `TypedDict`s keyed by
+//! their serialize names, with `NotRequired` fields that can be missing, and
+//! the rest are type aliases. Keys that are not identifiers need the
+//! functional syntax, which cannot be generic, so the type parameters of
+//! such definitions are `Any`.
+//!
+//! Types with `from`, `try_from` or `into` proxies and fields or variants
+//! with `serialize_with` or `deserialize_with` are `Any`.
+
+use crate::naming::{Comment, comment, ident, string, type_name, unicode, unit_only};
+use crate::reflect::{
+    Body, Container, Definition, Field, Name, Primitive, Reflect, Registry, Shape, TagType, Value,
+    Variant,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Kind of classes structs and variants become.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// pydantic v2 `BaseModel`s and `RootModel`s.
+    Pydantic,
+    /// `TypedDict`s and type aliases, with no dependencies.
+    TypedDict,
+}
+
+/// Module with `T` and the types it refers to.
+pub fn module_for<T: ?Sized + Reflect<M>, M>(style: Style) -> String {
+    let mut registry = Registry::new();
+    registry.register::<T, _>();
+    module(&registry, style)
+}
+
+/// Module with all definitions in the registry, in topological order.
+pub fn module(registry: &Registry, style: Style) -> String {
+    let mut generator = Generator {
+        registry,
+        style,
+        imports: BTreeMap::new(),
+        type_vars: 0,
+        erased: false,
+    };
+    let definitions = registry
+        .definitions()
+        .map(|(id, def)| generator.definition(id, def))
+        .collect::<Vec<_>>();
+
+    let mut module = String::from("from __future__ import annotations\n");
+    // The standard library comes first
+    for package in ["typing", "pydantic"] {
+        if let Some(names) = generator.imports.get(package) {
+            let names = names.iter().copied().collect::<Vec<_>>();
+            module += &format!("\nfrom {package} import {}\n", names.join(", "));
+        }
+    }
+    if generator.type_vars > 0 {
+        module.push('\n');
+        for i in 0..generator.type_vars {
+            module += &format!("X{i} = TypeVar(\"X{i}\")\n");
+        }
+    }
+    for definition in definitions {
+        module += &format!("\n\n{definition}");
+    }
+    module
+}
+
+struct Generator<'a> {
+    registry: &'a Registry,
+    style: Style,
+    /// Names imported from every module.
+    imports: BTreeMap<&'static str, BTreeSet<&'static str>>,
+    /// Number of `TypeVar`s used.
+    type_vars: usize,
+    /// Whether the type parameters of the definition being generated are
+    /// `Any`.
+    erased: bool,
+}
+
+/// Class of a struct or variant.
+struct Class {
+    name: String,
+    doc: Option<String>,
+    /// Flattened models.
+    bases: Vec<String>,
+    members: Vec<Member>,
+    deny_unknown_fields: bool,
+    /// Whether fields that are not members are kept, for flattened values
+    /// that are not models.
+    extra: bool,
+}
+
+/// Field of a class.
+struct Member {
+    name: Name,
+    ty: String,
+    /// Whether the field can be missing.
+    missing: bool,
+    default: Option<String>,
+    /// Whether the field is not serialized.
+    exclude: bool,
+    doc: Option<String>,
+}
+
+impl Generator<'_> {
+    fn import(&mut self, package: &'static str, name: &'static str) {
+        self.imports.entry(package).or_default().insert(name);
+    }
+
+    /// Name of the class or alias of a registered definition.
+    fn name(&self, id: u64) -> String {
+        keyword(ident(
+            self.registry
+                .definition_name(id)
+                .expect("registered definition"),
+        ))
+    }
+
+    fn definition(&mut self, id: u64, def: &Definition) -> String {
+        let container = &def.container;
+        let name = self.name(id);
+        // Functional `TypedDict`s are not generic
+        self.erased = self.functional(def);
+        let params = if self.erased {
+            Vec::new()
+        } else {
+            self.params(def.params.len())
+        };
+        match &def.body {
+            _ if container.proxy().is_some() => {
+                self.import("typing", "Any");
+                format!("{name} = Any\n")
+            }
+            Body::Struct(fields) | Body::Tuple(fields) if container.transparent => {
+                let field = fields
+                    .iter()
+                    .find(|field| !(field.skip_serializing && field.skip_deserializing))
+                    .expect("transparent struct has a field");
+                let root = self.field_type(field);
+                self.root(&name, &params, container.doc.as_deref(), &root)
+            }
+            Body::Tuple(fields) => {
+                let root = self.tuple(fields);
+                self.root(&name, &params, container.doc.as_deref(), &root)
+            }
+            Body::Unit => self.root(&name, &params, container.doc.as_deref(), "None"),
+            Body::Struct(fields) => {
+                let mut class = Class {
+                    name,
+                    doc: container.doc.clone(),
+                    bases: Vec::new(),
+                    members: Vec::new(),
+                    deny_unknown_fields: container.deny_unknown_fields,
+                    extra: false,
+                };
+                self.members(&mut class, fields, container.default);
+                self.class(&class, &params)
+            }
+            Body::Enum(variants) => {
+                let variants = variants
+                    .iter()
+                    .filter(|variant| !(variant.skip_serializing && variant.skip_deserializing))
+                    .collect::<Vec<_>>();
+                if unit_only(container, variants.iter().copied()) {
+                    let values = variants
+                        .iter()
+                        .flat_map(|variant| names(&variant.name))
+                        .collect::<Vec<_>>();
+                    let doc = comment(container.doc.as_deref(), "", Comment::Line("#"));
+                    format!("{doc}{name} = {}\n", self.literal(&values))
+                } else {
+                    self.union(container, &name, &params, &variants)
+                }
+            }
+        }
+    }
+
+    /// `TypeVar`s of a definition with `count` type parameters.
+    fn params(&mut self, count: usize) -> Vec<String> {
+        if count > 0 {
+            self.import("typing", "TypeVar");
+            self.import("typing", "Generic");
+        }
+        self.type_vars = self.type_vars.max(count);
+        (0..count).map(|i| format!("X{i}")).collect()
+    }
+
+    /// Root model, or type alias, of `root`.
+    fn root(&mut self, name: &str, params: &[String], doc: Option<&str>, root: &str) -> String {
+        match self.style {
+            Style::Pydantic => {
+                self.import("pydantic", "RootModel");
+                let generic = generic(params);
+                format!(
+                    "class {name}(RootModel[{root}]{generic}):\n{}",
+                    docstring(doc).unwrap_or("    pass\n".to_owned())
+                )
+            }
+            Style::TypedDict => {
+                format!("{}{name} = {root}\n", comment(doc, "", Comment::Line("#")))
+            }
+        }
+    }
+
+    /// Members of the fields of a struct or variant.
+    fn members(&mut self, class: &mut Class, fields: &[Field], default: bool) {
+        for field in fields {
+            if field.skip_serializing && field.skip_deserializing {
+                continue;
+            }
+            if field.flatten {
+                match &field.shape {
+                    Shape::Type(_) if !self.is_alias(&field.shape) => {
+                        let base = self.type_expr(&field.shape);
+                        class.bases.push(base);
+                    }
+                    _ => class.extra = true,
+                }
+                continue;
+            }
+            let ty = self.field_type(field);
+            let missing = default
+                || field.default
+                || field.skip_serializing
+                || field.skip_serializing_if.is_some()
+                || matches!(field.shape, Shape::Option(_));
+            let default = if missing {
+                let value = field.default_value.as_ref();
+                let literal = value.and_then(|value| literal(&field.shape, value));
+                Some(literal.unwrap_or("None".to_owned()))
+            } else {
+                None
+            };
+            class.members.push(Member {
+                name: field.name.clone(),
+                ty,
+                missing,
+                default,
+                exclude: field.skip_serializing,
+                doc: field.doc.clone(),
+            });
+        }
+    }
+
+    /// Whether a definition has `TypedDict`s with keys that are not
+    /// identifiers.
+    fn functional(&self, def: &Definition) -> bool {
+        self.style == Style::TypedDict && !keys(def).iter().all(|key| is_key(key))
+    }
+
+    /// Whether a shape is declared as a type alias rather than a class.
+    fn is_alias(&self, shape: &Shape) -> bool {
+        let Shape::Type(ty) = shape else {
+            return true;
+        };
+        match &self.registry.definition_of(ty).body {
+            _ if self.registry.definition_of(ty).container.proxy().is_some() => true,
+            Body::Struct(_) if !self.registry.definition_of(ty).container.transparent => false,
+            // Root models of unions cannot be bases either
+            Body::Enum(_) => true,
+            _ => self.style == Style::TypedDict,
+        }
+    }
+
+    fn class(&mut self, class: &Class, params: &[String]) -> String {
+        match self.style {
+            Style::Pydantic => self.model(class, params),
+            Style::TypedDict => self.typed_dict(class, params),
+        }
+    }
+
+    fn model(&mut self, class: &Class, params: &[String]) -> String {
+        let mut bases = class.bases.clone();
+        if bases.is_empty() {
+            self.import("pydantic", "BaseModel");
+            bases.push("BaseModel".to_owned());
+        }
+        let mut body = Vec::new();
+        body.extend(docstring(class.doc.as_deref()));
+
+        let mut config = Vec::new();
+        let mut fields = String::new();
+        for member in &class.members {
+            let attr = attribute(&member.name.serialize);
+            let mut args = Vec::new();
+            let mut validation = vec![member.name.deserialize.as_str()];
+            for alias in &member.name.deserialize_aliases {
+                if !validation.contains(&alias.as_str()) {
+                    validation.push(alias);
+                }
+            }
+            if member.name.serialize == member.name.deserialize && validation.len() == 1 {
+                if member.name.serialize != attr {
+                    args.push(format!("alias={}", string(&member.name.serialize, unicode)));
+                }
+            } else {
+                if member.name.serialize != attr {
+                    let alias = string(&member.name.serialize, unicode);
+                    args.push(format!("serialization_alias={alias}"));
+                }
+                if let [alias] = &validation[..] {
+                    args.push(format!("validation_alias={}", string(alias, unicode)));
+                } else {
+                    self.import("pydantic", "AliasChoices");
+                    let aliases = validation.iter().map(|alias| string(alias, unicode));
+                    let aliases = aliases.collect::<Vec<_>>().join(", ");
+                    args.push(format!("validation_alias=AliasChoices({aliases})"));
+                }
+            }
+            if !args.is_empty() {
+                config.push("populate_by_name=True");
+            }
+            if member.exclude {
+                args.push("exclude=True".to_owned());
+            }
+            let value = match (&member.default, &args[..]) {
+                (None, []) => String::new(),
+                (Some(default), []) => format!(" = {default}"),
+                (default, _) => {
+                    self.import("pydantic", "Field");
+                    let default = default.iter().map(|default| format!("default={default}"));
+                    let args = default.chain(args).collect::<Vec<_>>();
+                    format!(" = Field({})", args.join(", "))
+                }
+            };
+            fields += &field_comment(member.doc.as_deref());
+            fields += &format!("    {attr}: {}{value}\n", member.ty);
+        }
+        config.dedup();
+        if class.deny_unknown_fields {
+            config.push("extra=\"forbid\"");
+        } else if class.extra {
+            config.push("extra=\"allow\"");
+        }
+        if !config.is_empty() {
+            self.import("pydantic", "ConfigDict");
+            body.push(format!(
+                "    model_config = ConfigDict({})\n",
+                config.join(", ")
+            ));
+        }
+        if !fields.is_empty() {
+            body.push(fields);
+        }
+        if body.is_empty() {
+            body.push("    pass\n".to_owned());
+        }
+        format!(
+            "class {}({}{}):\n{}",
+            class.name,
+            bases.join(", "),
+            generic(params),
+            body.join("\n")
+        )
+    }
+
+    fn typed_dict(&mut self, class: &Class, params: &[String]) -> String {
+        let mut bases = class.bases.clone();
+        if bases.is_empty() {
+            self.import("typing", "TypedDict");
+            bases.push("TypedDict".to_owned());
+        }
+        let mut items = Vec::new();
+        for member in &class.members {
+            let ty = if member.missing {
+                self.import("typing", "NotRequired");
+                format!("NotRequired[{}]", member.ty)
+            } else {
+                member.ty.clone()
+            };
+            items.push((member, ty));
+        }
+        // Keys that are not identifiers need the functional syntax, which
+        // has no bases
+        if !items
+            .iter()
+            .all(|(member, _)| is_key(&member.name.serialize))
+        {
+            self.import("typing", "TypedDict");
+            let items = items
+                .iter()
+                .map(|(member, ty)| {
+                    let key = string(&member.name.serialize, unicode);
+                    field_comment(member.doc.as_deref()) + &format!("    {key}: {ty},\n")
+                })
+                .collect::<String>();
+            let doc = comment(class.doc.as_deref(), "", Comment::Line("#"));
+            let name = &class.name;
+            if class.bases.is_empty() {
+                return format!("{doc}{name} = TypedDict(\"{name}\", {{\n{items}}})\n");
+            }
+            return format!(
+                "_{name} = TypedDict(\"_{name}\", {{\n{items}}})\n\n\n{doc}class {name}(_{name}, {}):\n    pass\n",
+                class.bases.join(", ")
+            );
+        }
+        let mut body = Vec::new();
+        body.extend(docstring(class.doc.as_deref()));
+        let fields = items
+            .iter()
+            .map(|(member, ty)| {
+                field_comment(member.doc.as_deref())
+                    + &format!("    {}: {ty}\n", member.name.serialize)
+            })
+            .collect::<String>();
+        if !fields.is_empty() {
+            body.push(fields);
+        }
+        if body.is_empty() {
+            body.push("    pass\n".to_owned());
+        }
+        format!(
+            "class {}({}{}):\n{}",
+            class.name,
+            bases.join(", "),
+            generic(params),
+            body.join("\n")
+        )
+    }
+
+    /// Classes of the variants of an enum with data, followed by their union.
+    fn union(
+        &mut self,
+        container: &Container,
+        name: &str,
+        params: &[String],
+        variants: &[&Variant],
+    ) -> String {
+        let mut definitions = Vec::new();
+        let mut members = Vec::new();
+        let args = if params.is_empty() {
+            String::new()
+        } else {
+            format!("[{}]", params.join(", "))
+        };
+        let tag = match &container.tag {
+            TagType::Internal { tag } | TagType::Adjacent { tag, .. } => Some(tag),
+            TagType::External | TagType::None => None,
+        };
+        let discriminated = tag.is_some() && !variants.iter().any(|variant| variant.untagged);
+        for variant in variants {
+            let class_name = format!("{name}{}", type_name(&variant.name.serialize));
+            let mut class = Class {
+                name: class_name.clone(),
+                doc: variant.doc.clone(),
+                bases: Vec::new(),
+                members: Vec::new(),
+                deny_unknown_fields: container.deny_unknown_fields,
+                extra: false,
+            };
+            let tag_member = |generator: &mut Self, tag: &String| Member {
+                name: Name {
+                    serialize: tag.clone(),
+                    deserialize: tag.clone(),
+                    deserialize_aliases: vec![tag.clone()],
+                },
+                ty: generator.literal(&names(&variant.name)),
+                missing: false,
+                default: None,
+                exclude: false,
+                doc: None,
+            };
+            let member = |name: &Name, ty: String| Member {
+                name: name.clone(),
+                ty,
+                missing: false,
+                default: None,
+                exclude: false,
+                doc: None,
+            };
+            let variant_tag = if variant.untagged {
+                &TagType::None
+            } else {
+                &container.tag
+            };
+            match (variant_tag, &variant.body) {
+                (TagType::None, Body::Struct(fields)) => self.members(&mut class, fields, false),
+                (TagType::None, _) => {
+                    members.push(self.variant_content(variant));
+                    continue;
+                }
+                (TagType::External, Body::Unit) => {
+                    members.push(self.literal(&names(&variant.name)));
+                    continue;
+                }
+                (TagType::External, _) => {
+                    let content = self.external_content(
+                        &class_name,
+                        &args,
+                        params,
+                        variant,
+                        &mut definitions,
+                    );
+                    class.members.push(member(&variant.name, content));
+                }
+                (TagType::Internal { tag }, body) => {
+                    let tag_member = tag_member(self, tag);
+                    class.members.push(tag_member);
+                    match body {
+                        Body::Struct(fields) => self.members(&mut class, fields, false),
+                        Body::Tuple(fields) if !self.is_alias(&fields[0].shape) => {
+                            let base = self.type_expr(&fields[0].shape);
+                            class.bases.push(base);
+                        }
+                        Body::Tuple(_) => class.extra = true,
+                        _ => {}
+                    }
+                }
+                (TagType::Adjacent { tag, content }, body) => {
+                    let tag_member = tag_member(self, tag);
+                    class.members.push(tag_member);
+                    if *body != Body::Unit {
+                        let content_name = Name {
+                            serialize: content.clone(),
+                            deserialize: content.clone(),
+                            deserialize_aliases: vec![content.clone()],
+                        };
+                        let ty = self.external_content(
+                            &class_name,
+                            &args,
+                            params,
+                            variant,
+                            &mut definitions,
+                        );
+                        class.members.push(member(&content_name, ty));
+                    }
+                }
+            }
+            definitions.push(self.class(&class, params));
+            members.push(format!("{class_name}{args}"));
+        }
+        self.import("typing", "Union");
+        let mut union = format!("Union[{}]", members.join(", "));
+        let doc = container.doc.as_deref();
+        match self.style {
+            Style::Pydantic => {
+                if let (true, Some(tag)) = (discriminated, tag) {
+                    self.import("typing", "Annotated");
+                    self.import("pydantic", "Field");
+                    let tag = string(&attribute(tag), unicode);
+                    union = format!("Annotated[{union}, Field(discriminator={tag})]");
+                }
+                definitions.push(self.root(name, params, doc, &union));
+            }
+            Style::TypedDict => definitions.push(format!(
+                "{}{name} = {union}\n",
+                comment(doc, "", Comment::Line("#"))
+            )),
+        }
+        definitions.join("\n\n")
+    }
+
+    /// Type of the content of an externally or adjacently tagged variant,
+    /// struct variants get a class of their own.
+    fn external_content(
+        &mut self,
+        class_name: &str,
+        args: &str,
+        params: &[String],
+        variant: &Variant,
+        definitions: &mut Vec<String>,
+    ) -> String {
+        let Body::Struct(fields) = &variant.body else {
+            return self.variant_content(variant);
+        };
+        let mut class = Class {
+            name: format!("{class_name}Fields"),
+            doc: None,
+            bases: Vec::new(),
+            members: Vec::new(),
+            deny_unknown_fields: false,
+            extra: false,
+        };
+        self.members(&mut class, fields, false);
+        definitions.push(self.class(&class, params));
+        format!("{}{args}", class.name)
+    }
+
+    /// Type of a variant that is not a struct variant.
+    fn variant_content(&mut self, variant: &Variant) -> String {
+        if variant.serialize_with.is_some() || variant.deserialize_with.is_some() {
+            self.import("typing", "Any");
+            return "Any".to_owned();
+        }
+        match &variant.body {
+            Body::Unit => "None".to_owned(),
+            Body::Tuple(fields) => self.tuple(fields),
+            Body::Struct(_) | Body::Enum(_) => unreachable!("variant has no struct content"),
+        }
+    }
+
+    fn tuple(&mut self, fields: &[Field]) -> String {
+        if let [field] = fields {
+            return self.field_type(field);
+        }
+        let items = fields
+            .iter()
+            .filter(|field| !field.skip_serializing && !field.skip_deserializing)
+            .map(|field| self.field_type(field))
+            .collect::<Vec<_>>();
+        format!("tuple[{}]", items.join(", "))
+    }
+
+    fn field_type(&mut self, field: &Field) -> String {
+        if field.serialize_with.is_some() || field.deserialize_with.is_some() {
+            self.import("typing", "Any");
+            "Any".to_owned()
+        } else {
+            self.type_expr(&field.shape)
+        }
+    }
+
+    fn type_expr(&mut self, shape: &Shape) -> String {
+        match shape {
+            Shape::Param(_) if self.erased => {
+                self.import("typing", "Any");
+                "Any".to_owned()
+            }
+            Shape::Param(i) => format!("X{i}"),
+            Shape::Primitive(primitive) => primitive_type(*primitive).to_owned(),
+            Shape::Option(shape) => {
+                self.import("typing", "Optional");
+                format!("Optional[{}]", self.type_expr(shape))
+            }
+            Shape::Seq(shape) | Shape::Array(shape, _) => {
+                format!("list[{}]", self.type_expr(shape))
+            }
+            Shape::Tuple(shapes) => {
+                let items = shapes
+                    .iter()
+                    .map(|shape| self.type_expr(shape))
+                    .collect::<Vec<_>>();
+                format!("tuple[{}]", items.join(", "))
+            }
+            Shape::Map(key, value) => {
+                format!("dict[{}, {}]", self.type_expr(key), self.type_expr(value))
+            }
+            Shape::Type(ty) => {
+                let name = self.name(ty.id);
+                if ty.args.is_empty() || self.functional(self.registry.definition_of(ty)) {
+                    name
+                } else {
+                    let args = ty
+                        .args
+                        .iter()
+                        .map(|arg| self.type_expr(arg))
+                        .collect::<Vec<_>>();
+                    format!("{name}[{}]", args.join(", "))
+                }
+            }
+        }
+    }
+
+    fn literal(&mut self, values: &[&str]) -> String {
+        self.import("typing", "Literal");
+        let values = values
+            .iter()
+            .map(|value| string(value, unicode))
+            .collect::<Vec<_>>();
+        format!("Literal[{}]", values.join(", "))
+    }
+}
+
+/// Keys of the `TypedDict`s of a definition.
+fn keys(def: &Definition) -> Vec<&str> {
+    fn fields(fields: &[Field]) -> Vec<&str> {
+        fields
+            .iter()
+            .filter(|field| !(field.flatten || field.skip_serializing && field.skip_deserializing))
+            .map(|field| field.name.serialize.as_str())
+            .collect()
+    }
+    match &def.body {
+        _ if def.container.proxy().is_some() || def.container.transparent => Vec::new(),
+        Body::Struct(body) => fields(body),
+        Body::Enum(variants) => {
+            let mut keys = Vec::new();
+            for variant in variants {
+                if variant.skip_serializing && variant.skip_deserializing {
+                    continue;
+                }
+                if let Body::Struct(body) = &variant.body {
+                    keys.extend(fields(body));
+                }
+                match &def.container.tag {
+                    _ if variant.untagged => {}
+                    TagType::External if variant.body != Body::Unit => {
+                        keys.push(&variant.name.serialize);
+                    }
+                    TagType::Internal { tag } => keys.push(tag),
+                    TagType::Adjacent { tag, content } => {
+                        keys.extend([tag, content].map(String::as_str))
+                    }
+                    TagType::External | TagType::None => {}
+                }
+            }
+            keys
+        }
+        Body::Tuple(_) | Body::Unit => Vec::new(),
+    }
+}
+
+/// Whether a `TypedDict` key can be declared with the class syntax.
+fn is_key(key: &str) -> bool {
+    keyword(ident(key)) == key
+}
+
+fn primitive_type(primitive: Primitive) -> &'static str {
+    match primitive {
+        Primitive::Bool => "bool",
+        Primitive::I8
+        | Primitive::I16
+        | Primitive::I32
+        | Primitive::I64
+        | Primitive::I128
+        | Primitive::U8
+        | Primitive::U16
+        | Primitive::U32
+        | Primitive::U64
+        | Primitive::U128 => "int",
+        Primitive::F32 | Primitive::F64 => "float",
+        Primitive::Char | Primitive::String => "str",
+        Primitive::Unit => "None",
+    }
+}
+
+/// Serialize name followed by the deserialize names that differ from it.
+fn names(name: &Name) -> Vec<&str> {
+    let mut names = vec![name.serialize.as_str()];
+    for alias in std::iter::once(&name.deserialize).chain(&name.deserialize_aliases) {
+        if !names.contains(&alias.as_str()) {
+            names.push(alias);
+        }
+    }
+    names
+}
+
+/// Python literal of a default value of a field of shape `shape`.
+fn literal(shape: &Shape, value: &Value) -> Option<String> {
+    match (shape, value) {
+        (_, Value::Null) => Some("None".to_owned()),
+        (_, Value::Bool(value)) => Some(if *value { "True" } else { "False" }.to_owned()),
+        (_, Value::Integer(value)) => Some(value.to_string()),
+        (_, Value::Float(value)) if value.is_finite() => Some(format!("{value:?}")),
+        (_, Value::Float(value)) => Some(format!("float(\"{value}\")")),
+        (_, Value::String(value)) => Some(string(value, unicode)),
+        (Shape::Option(shape), value) => literal(shape, value),
+        (Shape::Seq(shape) | Shape::Array(shape, _), Value::Seq(values)) => {
+            let items = values.iter().map(|value| literal(shape, value));
+            let items = items.collect::<Option<Vec<_>>>()?;
+            Some(format!("[{}]", items.join(", ")))
+        }
+        (Shape::Tuple(shapes), Value::Seq(values)) => {
+            let items = shapes
+                .iter()
+                .zip(values)
+                .map(|(shape, value)| literal(shape, value));
+            let items = items.collect::<Option<Vec<_>>>()?;
+            match &items[..] {
+                [item] => Some(format!("({item},)")),
+                items => Some(format!("({})", items.join(", "))),
+            }
+        }
+        (Shape::Map(key, value_shape), Value::Map(entries)) => {
+            let entries = entries.iter().map(|(key_value, value)| {
+                Some(format!(
+                    "{}: {}",
+                    literal(key, key_value)?,
+                    literal(value_shape, value)?
+                ))
+            });
+            let entries = entries.collect::<Option<Vec<_>>>()?;
+            Some(format!("{{{}}}", entries.join(", ")))
+        }
+        _ => None,
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// Identifier followed by an underscore if it is a keyword.
+fn keyword(ident: String) -> String {
+    if KEYWORDS.contains(&ident.as_str()) {
+        ident + "_"
+    } else {
+        ident
+    }
+}
+
+/// Snake case attribute of a field, fields of tuples are named by their
+/// positions.
+fn attribute(name: &str) -> String {
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        return format!("field_{name}");
+    }
+    let mut attribute = String::new();
+    let mut lower = false;
+    for c in name.chars() {
+        if lower && c.is_ascii_uppercase() {
+            attribute.push('_');
+        }
+        attribute.push(c.to_ascii_lowercase());
+        lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+    }
+    keyword(ident(&attribute))
+}
+
+fn generic(params: &[String]) -> String {
+    if params.is_empty() {
+        String::new()
+    } else {
+        format!(", Generic[{}]", params.join(", "))
+    }
+}
+
+/// Docstring of a class, indented by four spaces.
+fn docstring(doc: Option<&str>) -> Option<String> {
+    let doc = doc?.replace('\\', "\\\\").replace("\"\"\"", "\\\"\"\"");
+    if !doc.contains('\n') {
+        return Some(format!("    \"\"\"{doc}\"\"\"\n"));
+    }
+    let mut docstring = String::from("    \"\"\"\n");
+    for line in doc.lines() {
+        if line.is_empty() {
+            docstring.push('\n');
+        } else {
+            docstring += &format!("    {line}\n");
+        }
+    }
+    Some(docstring + "    \"\"\"\n")
+}
+
+/// Comments of a field, indented by four spaces.
+fn field_comment(doc: Option<&str>) -> String {
+    comment(doc, "", Comment::Line("#"))
+        .lines()
+        .map(|line| format!("    {line}\n"))
+        .collect()
+}
@@ -0,0 +1,1161 @@
+//! Runtime reflection: an owned [`Shape`] tree of a type, built from
+//! [`SerdeGeneric::Repr`], [`SerdeGeneric::Mocked`] and `serde` metadata.
+//!
+//! [`Reflect::shape`] turns the type-level description into plain data, so
+//! that schema and code generators can be ordinary functions walking the
+//! tree. `SerdeGeneric` types are [`Shape::Type`], which refers to a
+//! [`Definition`] added to a [`Registry`] once:
+//!
+//! ```
+//! # use serde_generic::SerdeGeneric;
+//! use serde_generic::reflect::{Body, Primitive, Registry, Shape};
+//!
+//! #[derive(SerdeGeneric)]
+//! struct Foo<X> {
+//!     foo: Vec<X>,
+//! }
+//!
+//! let mut registry = Registry::new();
+//! let Shape::Type(ty) = registry.register::<Foo<u8>, _>() else {
+//!     unreachable!()
+//! };
+//! assert_eq!(ty.args, [Shape::Primitive(Primitive::U8)]);
+//! let Body::Struct(fields) = &registry.definition_of(&ty).body else {
+//!     unreachable!()
+//! };
+//! assert_eq!(fields[0].shape, Shape::Seq(Box::new(Shape::Param(0))));
+//! ```
+//!
+//! Every `SerdeGeneric` type comes with a [`Definition`], made from its
+//! `Mocked` version, in which type parameters are [`Shape::Param`], and with
+//! the arguments it is instantiated with. The derive macro implements
+//! [`Reflect`] with a [`ViaGeneric`] proof. Types that are not
+//! `SerdeGeneric`, such as primitives and standard collections, implement it
+//! by hand with a [`Manual`] proof, which other types can do as well.
+//!
+//! Types can be recursive, as a definition is only made once: a type
+//! reached again while its definition is being made is a [`Shape::Type`] as
+//! well. Definitions are identified by the [`TypeId`](core::any::TypeId) of
+//! their `Mocked` version, so that one has to be `'static`.
+
+use crate::traverse::{
+    FieldAttrs, FieldDefaults, FieldGenericAttrs, StructField, Traverse, Traverser, VariantField,
+};
+use crate::{
+    ConstValue, Enum, GenericVariantAttr, HCons, HLen, HNil, HSum, HSumNil, NamedStruct,
+    PeanoNumber, SerdeGeneric, SerdeVariantAttr, Succ, TypeVar, UnitStruct, UnnamedStruct, Zero,
+    attr, serde,
+};
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
+use std::rc::Rc;
+use std::sync::Arc;
+
+mod registry;
+
+pub use self::registry::Registry;
+
+/// Shape of a type.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Shape {
+    /// `TypeVar<X>`: type parameter number `X` of the enclosing definition.
+    Param(usize),
+    Primitive(Primitive),
+    /// `Option<T>`
+    Option(Box<Shape>),
+    /// `Vec<T>`, slices, sets and other sequences.
+    Seq(Box<Shape>),
+    /// `[T; N]`
+    Array(Box<Shape>, Len),
+    Tuple(Vec<Shape>),
+    /// `HashMap<K, V>` or `BTreeMap<K, V>`
+    Map(Box<Shape>, Box<Shape>),
+    /// A `SerdeGeneric` type.
+    Type(Box<Type>),
+}
+
+/// Length of an array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Len {
+    Fixed(usize),
+    /// Const generic parameter number `N` of the enclosing definition, see
+    /// [`Definition::const_params`].
+    Param(usize),
+}
+
+/// Types `serde` serializes as one value. `usize` and `isize` are 64-bit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Primitive {
+    Bool,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    F32,
+    F64,
+    Char,
+    String,
+    /// `()`, as well as `PhantomData`.
+    Unit,
+}
+
+/// Value of a reflected type in the data model of `serde`: options are
+/// `Null` or what they hold, and tuples and sets are sequences.
+///
+/// Floats are compared by their bits, so that `Value` is `Eq` and `Hash`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Integer(i128),
+    Float(f64),
+    String(String),
+    Seq(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+}
+
+/// Instantiation of a `SerdeGeneric` type, whose definition is in the
+/// [`Registry`] the shape is made with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Type {
+    /// Identity of the definition, the same for all instantiations of a
+    /// generic type: hash of the `TypeId` of `Mocked`, which only holds
+    /// within one build.
+    pub id: u64,
+    /// Type name of `Mocked`, only meant to be displayed.
+    pub name: String,
+    /// Type parameters, see [`Shape::Param`].
+    pub args: Vec<Shape>,
+    /// Values of the const generic parameters, see [`Definition::const_params`].
+    pub const_args: Vec<ConstValue>,
+}
+
+/// Definition of a `SerdeGeneric` type, with its type parameters left as
+/// [`Shape::Param`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Definition {
+    pub container: Container,
+    /// Names of the type parameters, which [`Shape::Param`] refers to by
+    /// position.
+    pub params: Vec<String>,
+    /// Names of the const generic parameters, which [`Len::Param`] refers to
+    /// by position. They are left as is in `Mocked`, so the definition is
+    /// made with their values, except for the lengths of arrays.
+    pub const_params: Vec<String>,
+    pub body: Body,
+}
+
+/// Fields of a struct or a variant, or variants of an enum.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Body {
+    Struct(Vec<Field>),
+    Tuple(Vec<Field>),
+    Unit,
+    /// Never the body of a variant.
+    Enum(Vec<Variant>),
+}
+
+/// Owned [`serde::Container`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Container {
+    pub name: Name,
+    pub transparent: bool,
+    pub deny_unknown_fields: bool,
+    /// `#[serde(default)]` or `#[serde(default = "...")]`.
+    pub default: bool,
+    pub tag: TagType,
+    pub type_from: Option<String>,
+    pub type_try_from: Option<String>,
+    pub type_into: Option<String>,
+    pub remote: Option<String>,
+    pub expecting: Option<String>,
+    pub non_exhaustive: bool,
+    pub doc: Option<String>,
+}
+
+/// Owned [`serde::TagType`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum TagType {
+    External,
+    Internal { tag: String },
+    Adjacent { tag: String, content: String },
+    None,
+}
+
+/// Owned [`serde::MultiName`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Name {
+    pub serialize: String,
+    pub deserialize: String,
+    pub deserialize_aliases: Vec<String>,
+}
+
+/// Owned [`serde::Field`] and [`attr::Field`], with the shape of its type.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Field {
+    pub name: Name,
+    pub shape: Shape,
+    pub skip_serializing: bool,
+    pub skip_deserializing: bool,
+    /// Path of the `skip_serializing_if` function.
+    pub skip_serializing_if: Option<String>,
+    /// `#[serde(default)]` or `#[serde(default = "...")]` of the field itself.
+    pub default: bool,
+    pub serialize_with: Option<String>,
+    pub deserialize_with: Option<String>,
+    pub flatten: bool,
+    pub doc: Option<String>,
+    /// `#[serde_generic(proto = N)]`.
+    pub proto_number: Option<u32>,
+    /// Value of `#[serde(default = "...")]` or of `Default::default()` for
+    /// `#[serde(default)]`, if the type of the field has one, see
+    /// [`Reflect::value`].
+    pub default_value: Option<Value>,
+}
+
+/// Owned [`serde::Variant`] and [`attr::Variant`], with the fields of the
+/// variant.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Variant {
+    pub name: Name,
+    pub skip_serializing: bool,
+    pub skip_deserializing: bool,
+    pub other: bool,
+    pub serialize_with: Option<String>,
+    pub deserialize_with: Option<String>,
+    pub untagged: bool,
+    pub doc: Option<String>,
+    /// `#[serde_generic(proto = N)]`.
+    pub proto_number: Option<u32>,
+    pub body: Body,
+}
+
+impl Shape {
+    /// Replaces type parameters with `args` and const parameters with
+    /// `const_args`, e.g. to turn a shape from a [`Definition`] into the one
+    /// of an instantiation.
+    pub fn instantiate(&self, args: &[Shape], const_args: &[ConstValue]) -> Shape {
+        let instantiate = |shape: &Shape| Box::new(shape.instantiate(args, const_args));
+        match self {
+            Shape::Param(i) => args[*i].clone(),
+            Shape::Primitive(primitive) => Shape::Primitive(*primitive),
+            Shape::Option(shape) => Shape::Option(instantiate(shape)),
+            Shape::Seq(shape) => Shape::Seq(instantiate(shape)),
+            Shape::Array(shape, len) => {
+                Shape::Array(instantiate(shape), len.instantiate(const_args))
+            }
+            Shape::Tuple(shapes) => Shape::Tuple(
+                shapes
+                    .iter()
+                    .map(|shape| shape.instantiate(args, const_args))
+                    .collect(),
+            ),
+            Shape::Map(key, value) => Shape::Map(instantiate(key), instantiate(value)),
+            // Definitions refer to their own parameters only
+            Shape::Type(ty) => Shape::Type(Box::new(Type {
+                args: ty
+                    .args
+                    .iter()
+                    .map(|arg| arg.instantiate(args, const_args))
+                    .collect(),
+                ..(**ty).clone()
+            })),
+        }
+    }
+
+    /// Name made of the names of everything the shape consists of, e.g.
+    /// `FooU8OptionString` for `Foo<u8, Option<String>>`, for formats
+    /// without generics. Types are named after their definitions in
+    /// `registry`.
+    pub fn mono_name(&self, registry: &Registry) -> String {
+        let mono_name = |shape: &Shape| shape.mono_name(registry);
+        match self {
+            Shape::Param(i) => format!("X{i}"),
+            Shape::Primitive(primitive) => format!("{primitive:?}"),
+            Shape::Option(shape) => format!("Option{}", mono_name(shape)),
+            Shape::Seq(shape) => format!("Vec{}", mono_name(shape)),
+            Shape::Array(shape, Len::Fixed(len)) => format!("Array{len}{}", mono_name(shape)),
+            Shape::Array(shape, Len::Param(i)) => format!("ArrayN{i}{}", mono_name(shape)),
+            Shape::Tuple(shapes) => shapes
+                .iter()
+                .fold("Tuple".to_owned(), |name, shape| name + &mono_name(shape)),
+            Shape::Map(key, value) => format!("Map{}{}", mono_name(key), mono_name(value)),
+            Shape::Type(ty) => {
+                let name = registry.definition_of(ty).container.name.serialize.clone();
+                let name = ty
+                    .args
+                    .iter()
+                    .fold(name, |name, arg| name + &mono_name(arg));
+                ty.const_args
+                    .iter()
+                    .fold(name, |name, value| name + &value.to_string())
+            }
+        }
+    }
+
+    /// Whether the shape refers to type or const parameters of a definition.
+    pub fn is_generic(&self) -> bool {
+        match self {
+            Shape::Param(_) | Shape::Array(_, Len::Param(_)) => true,
+            Shape::Primitive(_) => false,
+            Shape::Option(shape) | Shape::Seq(shape) | Shape::Array(shape, _) => shape.is_generic(),
+            Shape::Tuple(shapes) => shapes.iter().any(Shape::is_generic),
+            Shape::Map(key, value) => key.is_generic() || value.is_generic(),
+            Shape::Type(ty) => ty.args.iter().any(Shape::is_generic),
+        }
+    }
+}
+
+impl Len {
+    /// Length given by `const_args`, or `self` if the value of the parameter
+    /// is missing or is not a `usize`.
+    fn instantiate(self, const_args: &[ConstValue]) -> Len {
+        let Len::Param(i) = self else {
+            return self;
+        };
+        let len = match const_args.get(i) {
+            Some(ConstValue::Unsigned(len)) => usize::try_from(*len).ok(),
+            Some(ConstValue::Signed(len)) => usize::try_from(*len).ok(),
+            Some(ConstValue::Bool(_) | ConstValue::Char(_)) | None => None,
+        };
+        len.map_or(self, Len::Fixed)
+    }
+}
+
+/// Turns the lengths of the arrays in `shape` into [`Len::Param`] as given
+/// by [`rust::Field::array_lens`](crate::rust::Field::array_lens), unless the
+/// arrays of the shape are not the ones written, as with type aliases.
+fn const_lens(shape: &mut Shape, lens: &[Option<usize>]) {
+    fn arrays<'s>(shape: &'s mut Shape, found: &mut Vec<&'s mut Len>) {
+        match shape {
+            Shape::Param(_) | Shape::Primitive(_) => {}
+            Shape::Option(shape) | Shape::Seq(shape) => arrays(shape, found),
+            Shape::Array(shape, len) => {
+                found.push(len);
+                arrays(shape, found);
+            }
+            Shape::Tuple(shapes) => shapes.iter_mut().for_each(|shape| arrays(shape, found)),
+            Shape::Map(key, value) => {
+                arrays(key, found);
+                arrays(value, found);
+            }
+            Shape::Type(ty) => ty.args.iter_mut().for_each(|arg| arrays(arg, found)),
+        }
+    }
+    if lens.iter().all(Option::is_none) {
+        return;
+    }
+    let mut found = Vec::new();
+    arrays(shape, &mut found);
+    if found.len() == lens.len() {
+        for (len, param) in found.into_iter().zip(lens) {
+            if let Some(param) = param {
+                *len = Len::Param(*param);
+            }
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Integer(a), Value::Integer(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Seq(a), Value::Seq(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Value::Null => {}
+            Value::Bool(value) => value.hash(state),
+            Value::Integer(value) => value.hash(state),
+            Value::Float(value) => value.to_bits().hash(state),
+            Value::String(value) => value.hash(state),
+            Value::Seq(values) => values.hash(state),
+            Value::Map(entries) => entries.hash(state),
+        }
+    }
+}
+
+impl Body {
+    /// Instantiates the shapes of all fields, see [`Shape::instantiate`].
+    pub fn instantiate(&self, args: &[Shape], const_args: &[ConstValue]) -> Body {
+        let instantiate_fields = |fields: &[Field]| {
+            fields
+                .iter()
+                .map(|field| Field {
+                    shape: field.shape.instantiate(args, const_args),
+                    ..field.clone()
+                })
+                .collect()
+        };
+        match self {
+            Body::Struct(fields) => Body::Struct(instantiate_fields(fields)),
+            Body::Tuple(fields) => Body::Tuple(instantiate_fields(fields)),
+            Body::Unit => Body::Unit,
+            Body::Enum(variants) => Body::Enum(
+                variants
+                    .iter()
+                    .map(|variant| Variant {
+                        body: variant.body.instantiate(args, const_args),
+                        ..variant.clone()
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Fields of a struct or a variant, or fields of all variants of an enum.
+    pub fn fields(&self) -> Box<dyn Iterator<Item = &Field> + '_> {
+        match self {
+            Body::Struct(fields) | Body::Tuple(fields) => Box::new(fields.iter()),
+            Body::Unit => Box::new(core::iter::empty()),
+            Body::Enum(variants) => {
+                Box::new(variants.iter().flat_map(|variant| variant.body.fields()))
+            }
+        }
+    }
+}
+
+impl From<&serde::MultiName> for Name {
+    fn from(name: &serde::MultiName) -> Self {
+        Name {
+            serialize: name.serialize.to_owned(),
+            deserialize: name.deserialize.to_owned(),
+            deserialize_aliases: name
+                .deserialize_aliases
+                .iter()
+                .map(|alias| (*alias).to_owned())
+                .collect(),
+        }
+    }
+}
+
+impl From<&serde::TagType> for TagType {
+    fn from(tag: &serde::TagType) -> Self {
+        match tag {
+            serde::TagType::External => TagType::External,
+            serde::TagType::Internal { tag } => TagType::Internal {
+                tag: (*tag).to_owned(),
+            },
+            serde::TagType::Adjacent { tag, content } => TagType::Adjacent {
+                tag: (*tag).to_owned(),
+                content: (*content).to_owned(),
+            },
+            serde::TagType::None => TagType::None,
+        }
+    }
+}
+
+impl Container {
+    /// Type the container is serialized or deserialized through, with
+    /// `#[serde(into = "...")]`, `#[serde(from = "...")]` or
+    /// `#[serde(try_from = "...")]`, in that order.
+    pub fn proxy(&self) -> Option<&str> {
+        (self.type_into.as_deref())
+            .or(self.type_from.as_deref())
+            .or(self.type_try_from.as_deref())
+    }
+}
+
+impl<T: ?Sized> From<&serde::Container<T>> for Container {
+    fn from(container: &serde::Container<T>) -> Self {
+        Container {
+            name: (&container.name).into(),
+            transparent: container.transparent,
+            deny_unknown_fields: container.deny_unknown_fields,
+            default: !matches!(container.default, serde::Default::None),
+            tag: (&container.tag).into(),
+            type_from: container.type_from.map(str::to_owned),
+            type_try_from: container.type_try_from.map(str::to_owned),
+            type_into: container.type_into.map(str::to_owned),
+            remote: container.remote.map(str::to_owned),
+            expecting: container.expecting.map(str::to_owned),
+            non_exhaustive: container.non_exhaustive,
+            doc: container.doc.map(str::to_owned),
+        }
+    }
+}
+
+impl Field {
+    fn new<S: ?Sized, F: ?Sized>(
+        field: &serde::Field<S, F>,
+        generic: &attr::Field,
+        shape: Shape,
+    ) -> Self {
+        Field {
+            name: (&field.name).into(),
+            shape,
+            skip_serializing: field.skip_serializing,
+            skip_deserializing: field.skip_deserializing,
+            skip_serializing_if: field.skip_serializing_if.map(|(path, _)| path.to_owned()),
+            default: !matches!(field.default, serde::Default::None),
+            serialize_with: field.serialize_with.map(str::to_owned),
+            deserialize_with: field.deserialize_with.map(str::to_owned),
+            flatten: field.flatten,
+            doc: field.doc.map(str::to_owned),
+            proto_number: generic.proto,
+            default_value: None,
+        }
+    }
+}
+
+impl Variant {
+    fn new(variant: &serde::Variant, generic: &attr::Variant, body: Body) -> Self {
+        Variant {
+            name: (&variant.name).into(),
+            skip_serializing: variant.skip_serializing,
+            skip_deserializing: variant.skip_deserializing,
+            other: variant.other,
+            serialize_with: variant.serialize_with.map(str::to_owned),
+            deserialize_with: variant.deserialize_with.map(str::to_owned),
+            untagged: variant.untagged,
+            doc: variant.doc.map(str::to_owned),
+            proto_number: generic.proto,
+            body,
+        }
+    }
+}
+
+/// Type with a [`Shape`]. `M` is a proof of how it is reflected:
+/// [`ViaGeneric`] for `SerdeGeneric` types and [`Manual`] for the rest.
+///
+/// Functions taking a `T: Reflect<M>` leave the proof to type inference, as
+/// in `registry.register::<T, _>()`.
+pub trait Reflect<M> {
+    /// Shape of the type, with the definitions of the `SerdeGeneric` types it
+    /// refers to added to `registry`.
+    fn shape(registry: &mut Registry) -> Shape;
+
+    /// [`Value`] of `value`, `None` if it has none, which is the case of
+    /// pointers, of types with proxies and of enums other than the ones
+    /// serialized as the names of their variants. Structs are maps of the
+    /// fields they serialize, by their serialized names.
+    fn value(value: Self) -> Option<Value>
+    where
+        Self: Sized,
+    {
+        let _ = value;
+        None
+    }
+}
+
+/// Proof of [`Reflect`] implemented by hand, with `M` holding the proofs of
+/// the types it is made of.
+pub struct Manual<M>(PhantomData<M>);
+
+/// Proof of [`Reflect`] derived for a `SerdeGeneric` type, with `M` holding
+/// the proofs of its type parameters.
+pub struct ViaGeneric<M>(PhantomData<M>);
+
+/// [`Reflect::shape`] of a `SerdeGeneric` type `T`, whose type parameters
+/// have shapes `args`. Its definition is added to `registry` unless it is
+/// already there or being made, so that recursive types refer to themselves.
+///
+/// This is what the derived `Reflect` impls call, leaving `Q` to type
+/// inference.
+pub fn generic_shape<T, Q>(registry: &mut Registry, args: Vec<Shape>) -> Shape
+where
+    T: ?Sized + SerdeGeneric,
+    T::Mocked: 'static,
+    <T::Mocked as SerdeGeneric>::Repr: ReflectBody<T::Mocked, Q>,
+{
+    let mut id = std::hash::DefaultHasher::new();
+    core::any::TypeId::of::<T::Mocked>().hash(&mut id);
+    let id = id.finish();
+    registry.define(id, |registry| Definition {
+        container: (&<T::Mocked as SerdeGeneric>::CONTAINER).into(),
+        params: T::TYPE_PARAMS.iter().map(|&name| name.to_owned()).collect(),
+        const_params: T::CONST_PARAMS
+            .iter()
+            .map(|param| param.name.to_owned())
+            .collect(),
+        body: <<T::Mocked as SerdeGeneric>::Repr as ReflectBody<T::Mocked, Q>>::body(registry),
+    });
+    Shape::Type(Box::new(Type {
+        id,
+        name: core::any::type_name::<T::Mocked>().to_owned(),
+        args,
+        const_args: T::CONST_PARAMS.iter().map(|param| param.value).collect(),
+    }))
+}
+
+/// [`Reflect::value`] of a `SerdeGeneric` type, which the derived `Reflect`
+/// impls call, see [`generic_shape`].
+pub fn generic_value<T, Q>(value: T) -> Option<Value>
+where
+    T: SerdeGeneric,
+    T::Repr: ReflectValue<T, Q>,
+{
+    <T::Repr as ReflectValue<T, Q>>::value(value.to_repr())
+}
+
+/// Representation of a value, reflected as the position of its variant if
+/// it is an enum.
+pub trait ReflectVariant {
+    /// Whether this is the representation of a unit struct or variant.
+    const UNIT: bool = false;
+
+    fn variant(&self) -> Option<usize>;
+}
+
+impl<X> ReflectVariant for NamedStruct<X> {
+    fn variant(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl<X> ReflectVariant for UnnamedStruct<X> {
+    fn variant(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl ReflectVariant for UnitStruct {
+    const UNIT: bool = true;
+
+    fn variant(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl<X: ReflectVariant> ReflectVariant for Enum<X> {
+    fn variant(&self) -> Option<usize> {
+        self.0.variant()
+    }
+}
+
+impl<H, T: ReflectVariant> ReflectVariant for HSum<H, T> {
+    fn variant(&self) -> Option<usize> {
+        match self {
+            HSum::L(_) => Some(0),
+            HSum::R(rest) => Some(rest.variant()? + 1),
+        }
+    }
+}
+
+impl ReflectVariant for HSumNil {
+    fn variant(&self) -> Option<usize> {
+        match *self {}
+    }
+}
+
+/// Representation of container `C`, reflected as its [`Value`]: a map of
+/// the fields of a struct, the field of a newtype struct, a sequence of the
+/// fields of a tuple struct, or the serialized name of its variant if `C` is
+/// an enum serialized as the names of its variants.
+pub trait ReflectValue<C, Q> {
+    fn value(repr: Self) -> Option<Value>;
+}
+
+/// Whether values of container `C` are serialized as something else.
+fn proxied<C: SerdeGeneric>() -> bool {
+    let container = &C::CONTAINER;
+    container.type_into.is_some() || container.remote.is_some()
+}
+
+impl<C, X, Q> ReflectValue<C, Q> for NamedStruct<X>
+where
+    C: SerdeGeneric,
+    X: HLen + ReflectEntries<StructField<C>, <X as HLen>::Len, Q>,
+{
+    fn value(repr: Self) -> Option<Value> {
+        if proxied::<C>() {
+            return None;
+        }
+        let mut entries = Vec::new();
+        repr.0.entries(&mut entries)?;
+        if C::CONTAINER.transparent {
+            return entries.pop().map(|(_, value)| value);
+        }
+        Some(Value::Map(entries))
+    }
+}
+
+impl<C, X, Q> ReflectValue<C, Q> for UnnamedStruct<X>
+where
+    C: SerdeGeneric,
+    X: HLen + ReflectEntries<StructField<C>, <X as HLen>::Len, Q>,
+    <X as HLen>::Len: PeanoNumber,
+{
+    fn value(repr: Self) -> Option<Value> {
+        if proxied::<C>() {
+            return None;
+        }
+        let mut entries = Vec::new();
+        repr.0.entries(&mut entries)?;
+        let mut values = entries.into_iter().map(|(_, value)| value);
+        if C::CONTAINER.transparent || <X as HLen>::Len::NUMBER == 1 {
+            return values.next();
+        }
+        Some(Value::Seq(values.collect()))
+    }
+}
+
+impl<C: SerdeGeneric> ReflectValue<C, ()> for UnitStruct {
+    fn value(_: Self) -> Option<Value> {
+        (!proxied::<C>()).then_some(Value::Null)
+    }
+}
+
+impl<C, X, Q> ReflectValue<C, Q> for Enum<X>
+where
+    C: SerdeGeneric,
+    X: ReflectVariant + HLen + Traverse<VariantNames<C>, VariantNames<C>, <X as HLen>::Len, Q>,
+{
+    fn value(repr: Self) -> Option<Value> {
+        if proxied::<C>() || !matches!(C::CONTAINER.tag, serde::TagType::External) {
+            return None;
+        }
+        let mut names = VariantNames {
+            names: Vec::new(),
+            unit_only: true,
+            container: PhantomData,
+        };
+        <X as Traverse<VariantNames<C>, _, _, _>>::traverse(&mut names);
+        if !names.unit_only {
+            return None;
+        }
+        names.names[repr.variant()?].map(|name| Value::String(name.to_owned()))
+    }
+}
+
+/// Values of fields described by `A`, reflected as the entries of the map of
+/// a struct, see [`ReflectValue`]. `I` is the index of the first field, as
+/// in [`Traverse`].
+pub trait ReflectEntries<A, I, Q> {
+    /// Adds the entries of the fields that are serialized, unless one of
+    /// them has no value.
+    fn entries(self, entries: &mut Vec<(Value, Value)>) -> Option<()>;
+}
+
+impl<A> ReflectEntries<A, Zero, ()> for HNil {
+    fn entries(self, _: &mut Vec<(Value, Value)>) -> Option<()> {
+        Some(())
+    }
+}
+
+impl<A, F, T, I, M, QT> ReflectEntries<A, Succ<I>, (M, QT)> for HCons<F, T>
+where
+    A: FieldAttrs<F, Succ<I>>,
+    F: Reflect<M>,
+    T: ReflectEntries<A, I, QT>,
+{
+    fn entries(self, entries: &mut Vec<(Value, Value)>) -> Option<()> {
+        let HCons(value, rest) = self;
+        let field = &A::FIELD;
+        let skipped = field.skip_serializing
+            || field
+                .skip_serializing_if
+                .is_some_and(|(_, skip)| skip(&value));
+        if !skipped {
+            if field.serialize_with.is_some() {
+                return None;
+            }
+            match F::value(value)? {
+                Value::Map(flattened) if field.flatten => entries.extend(flattened),
+                _ if field.flatten => return None,
+                value => entries.push((Value::String(field.name.serialize.to_owned()), value)),
+            }
+        }
+        rest.entries(entries)
+    }
+}
+
+/// Collects the serialized names of the variants of enum `S`, without
+/// their shapes.
+pub struct VariantNames<S> {
+    /// `None` for variants skipped when serializing.
+    names: Vec<Option<&'static str>>,
+    /// Whether all variants so far are unit variants and not untagged.
+    unit_only: bool,
+    container: PhantomData<S>,
+}
+
+impl<S, H, I> Traverser<H, I, ()> for VariantNames<S>
+where
+    S: SerdeVariantAttr<I>,
+    H: ReflectVariant,
+{
+    type Collector = Self;
+    fn apply(names: &mut Self) {
+        let variant = &<S as SerdeVariantAttr<I>>::VARIANT;
+        names.unit_only &= H::UNIT && !variant.untagged;
+        let name = variant.name.serialize;
+        names
+            .names
+            .push((!variant.skip_serializing).then_some(name));
+    }
+}
+
+/// Representation of container `C`, reflected as its [`Body`].
+pub trait ReflectBody<C, Q> {
+    fn body(registry: &mut Registry) -> Body;
+}
+
+/// Representation of a struct or a variant, with fields described by `A`,
+/// see [`FieldAttrs`](crate::traverse::FieldAttrs).
+pub trait ReflectFields<A, Q> {
+    fn body(registry: &mut Registry) -> Body;
+}
+
+impl<A, X, Q> ReflectFields<A, Q> for NamedStruct<X>
+where
+    X: HLen + Traverse<FieldShapes<A>, Collected<Field>, <X as HLen>::Len, Q>,
+{
+    fn body(registry: &mut Registry) -> Body {
+        Body::Struct(Collected::with(registry, |fields| {
+            <X as Traverse<FieldShapes<A>, _, _, _>>::traverse(fields);
+        }))
+    }
+}
+
+impl<A, X, Q> ReflectFields<A, Q> for UnnamedStruct<X>
+where
+    X: HLen + Traverse<FieldShapes<A>, Collected<Field>, <X as HLen>::Len, Q>,
+{
+    fn body(registry: &mut Registry) -> Body {
+        Body::Tuple(Collected::with(registry, |fields| {
+            <X as Traverse<FieldShapes<A>, _, _, _>>::traverse(fields);
+        }))
+    }
+}
+
+impl<A> ReflectFields<A, ()> for UnitStruct {
+    fn body(_: &mut Registry) -> Body {
+        Body::Unit
+    }
+}
+
+impl<C, X, Q> ReflectBody<C, Q> for NamedStruct<X>
+where
+    Self: ReflectFields<StructField<C>, Q>,
+{
+    fn body(registry: &mut Registry) -> Body {
+        <Self as ReflectFields<StructField<C>, Q>>::body(registry)
+    }
+}
+
+impl<C, X, Q> ReflectBody<C, Q> for UnnamedStruct<X>
+where
+    Self: ReflectFields<StructField<C>, Q>,
+{
+    fn body(registry: &mut Registry) -> Body {
+        <Self as ReflectFields<StructField<C>, Q>>::body(registry)
+    }
+}
+
+impl<C> ReflectBody<C, ()> for UnitStruct {
+    fn body(_: &mut Registry) -> Body {
+        Body::Unit
+    }
+}
+
+impl<C, X, Q> ReflectBody<C, Q> for Enum<X>
+where
+    X: HLen + Traverse<VariantShapes<C>, Collected<Variant>, <X as HLen>::Len, Q>,
+{
+    fn body(registry: &mut Registry) -> Body {
+        Body::Enum(Collected::with(registry, |variants| {
+            <X as Traverse<VariantShapes<C>, _, _, _>>::traverse(variants);
+        }))
+    }
+}
+
+/// What [`FieldShapes`] and [`VariantShapes`] collect, along with the
+/// registry the definitions of the types of the fields are added to.
+pub struct Collected<T> {
+    registry: Registry,
+    items: Vec<T>,
+}
+
+impl<T> Collected<T> {
+    /// Items collected by `collect`, which adds definitions to `registry`.
+    fn with(registry: &mut Registry, collect: impl FnOnce(&mut Self)) -> Vec<T> {
+        let mut collected = Collected {
+            registry: core::mem::take(registry),
+            items: Vec::new(),
+        };
+        collect(&mut collected);
+        *registry = collected.registry;
+        collected.items
+    }
+}
+
+/// Collects [`Field`]s described by `A`, with both their `serde` and
+/// `serde_generic` attributes.
+pub struct FieldShapes<A>(PhantomData<A>);
+
+impl<A, F: Reflect<M>, I, M> Traverser<F, I, M> for FieldShapes<A>
+where
+    A: FieldAttrs<F, I> + FieldDefaults<F, I> + FieldGenericAttrs<F, I>,
+{
+    type Collector = Collected<Field>;
+    fn apply(fields: &mut Self::Collector) {
+        let field = &A::FIELD;
+        let default_value = match field.default {
+            serde::Default::Path(_, default) => F::value(default()),
+            serde::Default::Default => A::DEFAULT.and_then(|default| F::value(default())),
+            serde::Default::None => None,
+        };
+        let mut shape = F::shape(&mut fields.registry);
+        const_lens(&mut shape, field.rust.array_lens);
+        fields.items.push(Field {
+            default_value,
+            ..Field::new(field, &A::GENERIC, shape)
+        });
+    }
+}
+
+/// Collects [`Variant`]s of enum `S`, see [`FieldShapes`].
+pub struct VariantShapes<S>(PhantomData<S>);
+
+impl<S, H, I, M> Traverser<H, I, M> for VariantShapes<S>
+where
+    S: SerdeVariantAttr<I> + GenericVariantAttr<I>,
+    H: ReflectFields<VariantField<S, I>, M>,
+{
+    type Collector = Collected<Variant>;
+    fn apply(variants: &mut Self::Collector) {
+        let variant = &<S as SerdeVariantAttr<I>>::VARIANT;
+        let generic = &<S as GenericVariantAttr<I>>::VARIANT;
+        let body = H::body(&mut variants.registry);
+        variants.items.push(Variant::new(variant, generic, body));
+    }
+}
+
+impl<X: PeanoNumber> Reflect<Manual<()>> for TypeVar<X> {
+    fn shape(_: &mut Registry) -> Shape {
+        Shape::Param(X::NUMBER)
+    }
+}
+
+macro_rules! reflect_primitive {
+    ($($ty:ty => $primitive:ident $(($value:expr))?,)*) => {$(
+        impl Reflect<Manual<()>> for $ty {
+            fn shape(_: &mut Registry) -> Shape {
+                Shape::Primitive(Primitive::$primitive)
+            }
+            $(
+                fn value(value: Self) -> Option<Value> {
+                    ($value)(value)
+                }
+            )?
+        }
+    )*};
+}
+
+/// `None` for `u128` values out of the range of `i128`.
+fn integer(value: impl TryInto<i128>) -> Option<Value> {
+    value.try_into().ok().map(Value::Integer)
+}
+
+reflect_primitive! {
+    bool => Bool(|value| Some(Value::Bool(value))),
+    i8 => I8(integer),
+    i16 => I16(integer),
+    i32 => I32(integer),
+    i64 => I64(integer),
+    i128 => I128(integer),
+    isize => I64(integer),
+    u8 => U8(integer),
+    u16 => U16(integer),
+    u32 => U32(integer),
+    u64 => U64(integer),
+    u128 => U128(integer),
+    usize => U64(integer),
+    f32 => F32(|value: f32| Some(Value::Float(value.into()))),
+    f64 => F64(|value| Some(Value::Float(value))),
+    char => Char(|value: char| Some(Value::String(value.to_string()))),
+    str => String,
+    String => String(|value| Some(Value::String(value))),
+    () => Unit(|()| Some(Value::Null)),
+}
+
+impl<T: ?Sized> Reflect<Manual<()>> for PhantomData<T> {
+    fn shape(_: &mut Registry) -> Shape {
+        Shape::Primitive(Primitive::Unit)
+    }
+
+    fn value(_: Self) -> Option<Value> {
+        Some(Value::Null)
+    }
+}
+
+// Pointers are serialized as what they point to
+
+macro_rules! reflect_pointer {
+    ($($ty:ty,)*) => {$(
+        impl<T: ?Sized + Reflect<M>, M> Reflect<Manual<M>> for $ty {
+            fn shape(registry: &mut Registry) -> Shape {
+                T::shape(registry)
+            }
+        }
+    )*};
+}
+
+reflect_pointer! {
+    &T,
+    &mut T,
+    Box<T>,
+    Rc<T>,
+    Arc<T>,
+}
+
+impl<T: ?Sized + ToOwned + Reflect<M>, M> Reflect<Manual<M>> for Cow<'_, T> {
+    fn shape(registry: &mut Registry) -> Shape {
+        T::shape(registry)
+    }
+}
+
+impl<T: Reflect<M>, M> Reflect<Manual<M>> for Option<T> {
+    fn shape(registry: &mut Registry) -> Shape {
+        Shape::Option(Box::new(T::shape(registry)))
+    }
+
+    fn value(value: Self) -> Option<Value> {
+        value.map_or(Some(Value::Null), T::value)
+    }
+}
+
+/// Sequence of the values of `values`, if they all have one.
+fn seq<T: Reflect<M>, M>(values: impl IntoIterator<Item = T>) -> Option<Value> {
+    values
+        .into_iter()
+        .map(T::value)
+        .collect::<Option<_>>()
+        .map(Value::Seq)
+}
+
+/// Map of the values of `entries`, if they all have one.
+fn map<K: Reflect<MK>, V: Reflect<MV>, MK, MV>(
+    entries: impl IntoIterator<Item = (K, V)>,
+) -> Option<Value> {
+    entries
+        .into_iter()
+        .map(|(key, value)| Some((K::value(key)?, V::value(value)?)))
+        .collect::<Option<_>>()
+        .map(Value::Map)
+}
+
+impl<T: Reflect<M>, M> Reflect<Manual<M>> for [T] {
+    fn shape(registry: &mut Registry) -> Shape {
+        Shape::Seq(Box::new(T::shape(registry)))
+    }
+}
+
+macro_rules! reflect_seq {
+    ($($ty:ty,)*) => {$(
+        impl<T: Reflect<M>, M> Reflect<Manual<M>> for $ty {
+            fn shape(registry: &mut Registry) -> Shape {
+                Shape::Seq(Box::new(T::shape(registry)))
+            }
+
+            fn value(value: Self) -> Option<Value> {
+                seq(value)
+            }
+        }
+    )*};
+}
+
+reflect_seq! {
+    Vec<T>,
+    VecDeque<T>,
+    LinkedList<T>,
+    BinaryHeap<T>,
+    BTreeSet<T>,
+}
+
+impl<T: Reflect<M>, M, H> Reflect<Manual<M>> for HashSet<T, H> {
+    fn shape(registry: &mut Registry) -> Shape {
+        Shape::Seq(Box::new(T::shape(registry)))
+    }
+
+    fn value(value: Self) -> Option<Value> {
+        seq(value)
+    }
+}
+
+impl<T: Reflect<M>, M, const N: usize> Reflect<Manual<M>> for [T; N] {
+    fn shape(registry: &mut Registry) -> Shape {
+        Shape::Array(Box::new(T::shape(registry)), Len::Fixed(N))
+    }
+
+    fn value(value: Self) -> Option<Value> {
+        seq(value)
+    }
+}
+
+impl<K: Reflect<MK>, V: Reflect<MV>, MK, MV> Reflect<Manual<(MK, MV)>> for BTreeMap<K, V> {
+    fn shape(registry: &mut Registry) -> Shape {
+        Shape::Map(Box::new(K::shape(registry)), Box::new(V::shape(registry)))
+    }
+
+    fn value(value: Self) -> Option<Value> {
+        map(value)
+    }
+}
+
+impl<K: Reflect<MK>, V: Reflect<MV>, MK, MV, H> Reflect<Manual<(MK, MV)>> for HashMap<K, V, H> {
+    fn shape(registry: &mut Registry) -> Shape {
+        Shape::Map(Box::new(K::shape(registry)), Box::new(V::shape(registry)))
+    }
+
+    fn value(value: Self) -> Option<Value> {
+        map(value)
+    }
+}
+
+macro_rules! reflect_tuple {
+    ($($ty:ident $m:ident $value:ident),*) => {
+        impl<$($ty: Reflect<$m>, $m),*> Reflect<Manual<($($m,)*)>> for ($($ty,)*) {
+            fn shape(registry: &mut Registry) -> Shape {
+                Shape::Tuple(vec![$($ty::shape(registry)),*])
+            }
+
+            fn value(($($value,)*): Self) -> Option<Value> {
+                Some(Value::Seq(vec![$($ty::value($value)?),*]))
+            }
+        }
+    };
+}
+
+reflect_tuple!(A MA a);
+reflect_tuple!(A MA a, B MB b);
+reflect_tuple!(A MA a, B MB b, C MC c);
+reflect_tuple!(A MA a, B MB b, C MC c, D MD d);
+reflect_tuple!(A MA a, B MB b, C MC c, D MD d, E ME e);
+reflect_tuple!(A MA a, B MB b, C MC c, D MD d, E ME e, F MF f);
+reflect_tuple!(A MA a, B MB b, C MC c, D MD d, E ME e, F MF f, G MG g);
+reflect_tuple!(A MA a, B MB b, C MC c, D MD d, E ME e, F MF f, G MG g, H MH h);
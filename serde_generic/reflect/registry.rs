@@ -0,0 +1,214 @@
+//! Definitions and instantiations of the types reachable from a set of roots.
+
+use super::{Body, Definition, Reflect, Shape, Type};
+use crate::ConstValue;
+use crate::naming::type_name;
+use std::collections::{HashMap, HashSet};
+
+/// Collects the `SerdeGeneric` types reachable from any number of root types,
+/// each of them once.
+///
+/// Generic definitions are keyed by [`Type::id`], which is the same for all
+/// instantiations, and are added by [`Reflect::shape`]. Instantiations are
+/// keyed by their arguments, and are added with the roots. Equal definitions,
+/// such as the ones of a type with different const arguments, whose array
+/// lengths are [`Len::Param`](super::Len::Param), are only kept once. Only instantiations
+/// with no type parameters are kept, including the ones that only appear in
+/// a definition once its parameters are substituted: for `Foo<u8>` with a
+/// field of type `Bar<X>`, `Bar<u8>` is registered.
+///
+/// Both definitions and instantiations are in topological order: everything
+/// one of them refers to comes before it, unless they refer to each other,
+/// as recursive types do. Definitions are given unique names
+/// from their serialized names, for formats that declare generic types, and
+/// instantiations from [`Shape::mono_name`], for formats without generics.
+///
+/// ```
+/// # use serde_generic::SerdeGeneric;
+/// use serde_generic::reflect::Registry;
+///
+/// #[derive(SerdeGeneric)]
+/// struct Foo<X> {
+///     bar: Bar<X>,
+/// }
+///
+/// #[derive(SerdeGeneric)]
+/// struct Bar<X> {
+///     x: X,
+/// }
+///
+/// let mut registry = Registry::new();
+/// registry.register::<Foo<u8>, _>();
+/// registry.register::<Bar<u8>, _>();
+/// let names = |ty| registry.definition_of(ty).container.name.serialize.clone();
+/// assert_eq!(registry.instances().iter().map(names).collect::<Vec<_>>(), ["Bar", "Foo"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    roots: Vec<Shape>,
+    definitions: Vec<(u64, Definition)>,
+    /// Unique names of `definitions`.
+    definition_names: Vec<String>,
+    /// Positions in `definitions` by id.
+    defined: HashMap<u64, usize>,
+    /// Ids of the definitions being made.
+    defining: HashSet<u64>,
+    /// Positions in `definitions` by definition.
+    distinct: HashMap<Definition, usize>,
+    instances: Vec<Type>,
+    /// Names of `instances` by id and arguments.
+    instantiated: HashMap<(u64, Vec<Shape>, Vec<ConstValue>), String>,
+    /// Identifiers of `definition_names`.
+    names: HashSet<String>,
+    /// Identifiers of the names of `instances`.
+    instance_names: HashSet<String>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `T` and everything it refers to, returning its shape.
+    pub fn register<T: ?Sized + Reflect<M>, M>(&mut self) -> Shape {
+        let shape = T::shape(self);
+        self.add(&shape);
+        self.roots.push(shape.clone());
+        shape
+    }
+
+    /// Registers the roots of `other` along with the definitions they refer
+    /// to.
+    pub fn merge(&mut self, other: &Registry) {
+        for (id, def) in other.definitions() {
+            self.define(id, |_| def.clone());
+        }
+        // Then the ids of the definitions that are equal to others
+        for (&id, &i) in &other.defined {
+            self.define(id, |_| other.definitions[i].1.clone());
+        }
+        for shape in &other.roots {
+            self.add(shape);
+            self.roots.push(shape.clone());
+        }
+    }
+
+    /// Adds the definition with id `id` made by `make`, unless it is already
+    /// there or being made, which is the case of a type reached again from
+    /// its own fields.
+    pub fn define(&mut self, id: u64, make: impl FnOnce(&mut Registry) -> Definition) {
+        if self.defined.contains_key(&id) || !self.defining.insert(id) {
+            return;
+        }
+        let def = make(self);
+        self.defining.remove(&id);
+        let position = match self.distinct.get(&def) {
+            Some(&position) => position,
+            None => {
+                let name = unique(&mut self.names, &def.container.name.serialize);
+                self.distinct.insert(def.clone(), self.definitions.len());
+                self.definitions.push((id, def));
+                self.definition_names.push(name);
+                self.definitions.len() - 1
+            }
+        };
+        self.defined.insert(id, position);
+    }
+
+    /// Registered shapes in order of registration.
+    pub fn roots(&self) -> &[Shape] {
+        &self.roots
+    }
+
+    /// Generic definitions with their ids, in topological order.
+    pub fn definitions(&self) -> impl Iterator<Item = (u64, &Definition)> {
+        self.definitions.iter().map(|(id, def)| (*id, def))
+    }
+
+    pub fn definition(&self, id: u64) -> Option<&Definition> {
+        self.defined.get(&id).map(|&i| &self.definitions[i].1)
+    }
+
+    /// Definition of a type whose shape was made with this registry.
+    ///
+    /// # Panics
+    ///
+    /// If the definition of `ty` is not registered.
+    pub fn definition_of(&self, ty: &Type) -> &Definition {
+        self.definition(ty.id)
+            .unwrap_or_else(|| panic!("`{}` is not registered", ty.name))
+    }
+
+    /// Body of the definition of `ty`, instantiated with its arguments.
+    ///
+    /// # Panics
+    ///
+    /// If the definition of `ty` is not registered.
+    pub fn body(&self, ty: &Type) -> Body {
+        self.definition_of(ty)
+            .body
+            .instantiate(&ty.args, &ty.const_args)
+    }
+
+    /// Unique name of a registered definition, which is its serialized name
+    /// unless another definition has the same one, followed by a number as in
+    /// [`Registry::name`]. Names stay distinct once made identifiers, so
+    /// `my-type` and `my_type` are not both kept.
+    pub fn definition_name(&self, id: u64) -> Option<&str> {
+        self.defined
+            .get(&id)
+            .map(|&i| self.definition_names[i].as_str())
+    }
+
+    /// Instantiations with no type parameters, in topological order. Their
+    /// fields are given by [`Registry::body`].
+    pub fn instances(&self) -> &[Type] {
+        &self.instances
+    }
+
+    /// Unique name of a registered instantiation, which is its
+    /// [`Shape::mono_name`] unless two types have the same one.
+    pub fn name(&self, ty: &Type) -> Option<&str> {
+        let key = (ty.id, ty.args.clone(), ty.const_args.clone());
+        self.instantiated.get(&key).map(String::as_str)
+    }
+
+    fn add(&mut self, shape: &Shape) {
+        match shape {
+            Shape::Param(_) | Shape::Primitive(_) => {}
+            Shape::Option(shape) | Shape::Seq(shape) | Shape::Array(shape, _) => self.add(shape),
+            Shape::Tuple(shapes) => shapes.iter().for_each(|shape| self.add(shape)),
+            Shape::Map(key, value) => {
+                self.add(key);
+                self.add(value);
+            }
+            Shape::Type(ty) => {
+                ty.args.iter().for_each(|arg| self.add(arg));
+                let key = (ty.id, ty.args.clone(), ty.const_args.clone());
+                if !shape.is_generic() && !self.instantiated.contains_key(&key) {
+                    // Named before its fields are added, which may refer to it
+                    let name = shape.mono_name(self);
+                    let name = unique(&mut self.instance_names, &name);
+                    self.instantiated.insert(key, name);
+                    for field in self.body(ty).fields() {
+                        self.add(&field.shape);
+                    }
+                    self.instances.push((**ty).clone());
+                }
+            }
+        }
+    }
+}
+
+/// `name`, or the first of `name2`, `name3`... whose identifier is not in
+/// `taken`, which it is added to.
+fn unique(taken: &mut HashSet<String>, name: &str) -> String {
+    let mut unique = name.to_owned();
+    for i in 2.. {
+        if taken.insert(type_name(&unique)) {
+            break;
+        }
+        unique = format!("{name}{i}");
+    }
+    unique
+}
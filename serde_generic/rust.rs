@@ -0,0 +1,40 @@
+//! Rust side of fields and variants: identifiers, types and visibility as
+//! written in the source, next to the `serde` names in [`crate::serde`].
+
+/// Represents a field as declared in Rust.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Field {
+    /// Identifier of a named field, `None` for tuple fields.
+    pub ident: Option<&'static str>,
+    /// Position among the fields of the struct or variant, starting from 0.
+    pub index: usize,
+    /// Declared type of the field, as written.
+    pub ty: &'static str,
+    /// For every array in `ty`, in the order they are written, the position
+    /// in [`CONST_PARAMS`](crate::SerdeGeneric::CONST_PARAMS) of the const
+    /// parameter that is its length, if it is one.
+    pub array_lens: &'static [Option<usize>],
+    pub vis: Visibility,
+}
+
+/// Represents a variant as declared in Rust.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Variant {
+    pub ident: &'static str,
+    /// Position among the variants of the enum, starting from 0.
+    pub index: usize,
+}
+
+/// Visibility of a field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Visibility {
+    /// `pub`
+    Public,
+    /// `pub(crate)`
+    Crate,
+    /// `pub(self)`, `pub(super)` or `pub(in path)`, with the path as written.
+    Restricted(&'static str),
+    /// No visibility modifier, which is also the case for all fields of
+    /// enum variants.
+    Inherited,
+}
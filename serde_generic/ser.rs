@@ -0,0 +1,1165 @@
+//! Generic [`Serialize`] implementation driven by [`SerdeGeneric::Repr`] and
+//! `serde` metadata.
+//!
+//! [`serialize`] produces the same output as `serde_derive` would for the
+//! same type and attributes, so it can be used to implement [`Serialize`]
+//! by hand:
+//!
+//! ```
+//! # use serde_generic::SerdeGeneric;
+//! #[derive(SerdeGeneric, Clone)]
+//! #[serde(rename_all = "camelCase")]
+//! struct Foo {
+//!     foo_bar: u8,
+//! }
+//!
+//! impl serde::Serialize for Foo {
+//!     fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+//!         serde_generic::ser::serialize(self.clone(), serializer)
+//!     }
+//! }
+//! ```
+//!
+//! Every field type has to implement [`Serialize`], even skipped ones and
+//! ones with `serialize_with`.
+
+use crate::serde::{Container, Field, TagType};
+use crate::traverse::{FieldAttrs, FieldWith, StructField, VariantField};
+use crate::{
+    Enum, HCons, HLen, HNil, HSum, HSumNil, NamedStruct, SerdeContainerInto, SerdeGeneric,
+    SerdeRemote, SerdeVariantAttr, SerdeVariantWith, Succ, TypeVar, UnitStruct, UnnamedStruct,
+    Zero,
+};
+use ::serde::ser::{
+    Error as _, Impossible, SerializeMap, SerializeStruct, SerializeStructVariant, SerializeTuple,
+    SerializeTupleStruct, SerializeTupleVariant,
+};
+pub use ::serde::{Serialize, Serializer};
+use core::marker::PhantomData;
+
+mod content;
+
+use self::content::{Content, ContentSerializer};
+
+/// Serialize `value` the way `serde_derive` would.
+///
+/// Types with `#[serde(into = "...")]` are rejected, as the conversion is
+/// not known here; use [`serialize_into`] for them.
+pub fn serialize<T, S>(value: T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: SerdeGeneric,
+    T::Repr: SerializeRepr<T>,
+    S: Serializer,
+{
+    if let Some(proxy) = T::CONTAINER.type_into {
+        return Err(S::Error::custom(format_args!(
+            "{} is serialized as {proxy}, use `serialize_into`",
+            T::CONTAINER.name.serialize,
+        )));
+    }
+    value.to_repr().serialize_repr(serializer)
+}
+
+/// Serialize a value of the `#[serde(remote = "...")]` type mirrored by `T`,
+/// like the `serialize` function `serde_derive` generates for remote types.
+pub fn serialize_remote<T, S>(remote: T::Remote, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: SerdeRemote,
+    T::Repr: SerializeRepr<T>,
+    S: Serializer,
+{
+    T::remote_to_repr(remote).serialize_repr(serializer)
+}
+
+/// Serialize `value` through its `#[serde(into = "...")]` proxy.
+pub fn serialize_into<T, S>(value: T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: SerdeContainerInto,
+    T::Proxy: Serialize,
+    S: Serializer,
+{
+    value.into_proxy().serialize(serializer)
+}
+
+/// Type variables carry no data and are serialized as unit.
+impl<X> Serialize for TypeVar<X> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_unit()
+    }
+}
+
+/// Representation of `T` which can be serialized.
+pub trait SerializeRepr<T> {
+    fn serialize_repr<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+}
+
+impl<T: SerdeGeneric> SerializeRepr<T> for UnitStruct {
+    fn serialize_repr<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let container = &T::CONTAINER;
+        match &container.tag {
+            TagType::Internal { tag } => {
+                let mut state = serializer.serialize_struct(container.name.serialize, 1)?;
+                state.serialize_field(tag, container.name.serialize)?;
+                state.end()
+            }
+            _ => serializer.serialize_unit_struct(container.name.serialize),
+        }
+    }
+}
+
+impl<T, X> SerializeRepr<T> for NamedStruct<X>
+where
+    T: SerdeGeneric,
+    X: HLen + SerializeFields<StructField<T>, X::Len>,
+{
+    fn serialize_repr<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let container = &T::CONTAINER;
+        if container.transparent {
+            return serialize_transparent(container, &self.0, serializer);
+        }
+        let tag = match &container.tag {
+            TagType::Internal { tag } => Some((*tag, container.name.serialize)),
+            _ => None,
+        };
+        AsStruct::<StructField<T>, X> {
+            name: container.name.serialize,
+            tag,
+            fields: &self.0,
+            attrs: core::marker::PhantomData,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<T, X> SerializeRepr<T> for UnnamedStruct<X>
+where
+    T: SerdeGeneric,
+    X: HLen + SerializeFields<StructField<T>, X::Len>,
+{
+    fn serialize_repr<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let container = &T::CONTAINER;
+        if container.transparent {
+            return serialize_transparent(container, &self.0, serializer);
+        }
+        if is_newtype::<X>() {
+            let value = AsNewtype::<StructField<T>, X>::new(&self.0);
+            return serializer.serialize_newtype_struct(container.name.serialize, &value);
+        }
+        let mut state =
+            serializer.serialize_tuple_struct(container.name.serialize, self.0.count())?;
+        self.0.serialize_fields(&mut TupleStructSink(&mut state))?;
+        state.end()
+    }
+}
+
+impl<T, X> SerializeRepr<T> for Enum<X>
+where
+    T: SerdeGeneric,
+    X: HLen + SerializeVariants<T, X::Len>,
+{
+    fn serialize_repr<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize_variants(0, serializer)
+    }
+}
+
+fn is_newtype<X: HLen>() -> bool {
+    <X::Len as crate::PeanoNumber>::NUMBER == 1
+}
+
+fn serialize_transparent<T, A, X, S>(
+    container: &Container<T>,
+    fields: &X,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    X: SerializeFields<A, X::Len> + HLen,
+    S: Serializer,
+{
+    let mut sink = ForwardSink::new(serializer);
+    fields.serialize_fields(&mut sink)?;
+    sink.finish(|| {
+        format!(
+            "transparent struct {} has no field to serialize",
+            container.name.serialize
+        )
+    })
+}
+
+/// Receives fields of a struct or a variant one by one.
+pub trait FieldSink {
+    type Error: ::serde::ser::Error;
+
+    fn field<V: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &V,
+    ) -> Result<(), Self::Error>;
+
+    /// Called instead of `field` for fields that are not serialized.
+    fn skip(&mut self, _key: &'static str) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Called instead of `field` for `#[serde(flatten)]` fields.
+    fn flatten<V: Serialize + ?Sized>(&mut self, _value: &V) -> Result<(), Self::Error> {
+        Err(Self::Error::custom(
+            "#[serde(flatten)] is only supported in structs and struct variants",
+        ))
+    }
+}
+
+/// List of field values, with metadata looked up in `A`.
+pub trait SerializeFields<A, I> {
+    /// Whether any of the fields is `#[serde(flatten)]`.
+    fn has_flatten() -> bool;
+    /// Number of fields which will be serialized.
+    fn count(&self) -> usize;
+    fn serialize_fields<K: FieldSink>(&self, sink: &mut K) -> Result<(), K::Error>;
+}
+
+fn is_skipped<S, F>(field: &Field<S, F>, value: &F) -> bool {
+    field.skip_serializing
+        || field
+            .skip_serializing_if
+            .is_some_and(|(_, predicate)| predicate(value))
+}
+
+/// Field value, serialized with its `serialize_with` function if any.
+struct FieldValue<'a, A, F, I>(&'a F, core::marker::PhantomData<(A, I)>);
+
+impl<A: FieldWith<F, I>, F: Serialize, I> Serialize for FieldValue<'_, A, F, I> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match A::serialize_with() {
+            Some(serialize_with) => serialize_with(self.0, serializer),
+            None => self.0.serialize(serializer),
+        }
+    }
+}
+
+impl<A, H, T, I> SerializeFields<A, Succ<I>> for HCons<H, T>
+where
+    A: FieldAttrs<H, Succ<I>> + FieldWith<H, Succ<I>>,
+    H: Serialize,
+    T: SerializeFields<A, I>,
+{
+    fn has_flatten() -> bool {
+        A::FIELD.flatten || T::has_flatten()
+    }
+
+    fn count(&self) -> usize {
+        let field = A::FIELD;
+        usize::from(!field.flatten && !is_skipped(&field, &self.0)) + self.1.count()
+    }
+
+    fn serialize_fields<K: FieldSink>(&self, sink: &mut K) -> Result<(), K::Error> {
+        let field = A::FIELD;
+        let value = FieldValue::<A, H, Succ<I>>(&self.0, core::marker::PhantomData);
+        if is_skipped(&field, &self.0) {
+            sink.skip(field.name.serialize)?;
+        } else if field.flatten {
+            sink.flatten(&value)?;
+        } else {
+            sink.field(field.name.serialize, &value)?;
+        }
+        self.1.serialize_fields(sink)
+    }
+}
+
+impl<A> SerializeFields<A, Zero> for HNil {
+    fn has_flatten() -> bool {
+        false
+    }
+
+    fn count(&self) -> usize {
+        0
+    }
+
+    fn serialize_fields<K: FieldSink>(&self, _sink: &mut K) -> Result<(), K::Error> {
+        Ok(())
+    }
+}
+
+/// Sum of enum variants of `T`.
+pub trait SerializeVariants<T, I> {
+    fn serialize_variants<S: Serializer>(
+        &self,
+        variant_index: u32,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>;
+}
+
+impl<T, H, R, I> SerializeVariants<T, Succ<I>> for HSum<H, R>
+where
+    T: SerdeGeneric + SerdeVariantAttr<Succ<I>> + SerdeVariantWith<H, Succ<I>>,
+    H: SerializeVariant<T, Succ<I>>,
+    R: SerializeVariants<T, I>,
+{
+    fn serialize_variants<S: Serializer>(
+        &self,
+        variant_index: u32,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match self {
+            HSum::L(variant) if T::VARIANT.serialize_with.is_some() => {
+                serialize_variant_with::<T, Succ<I>, H, S>(variant, variant_index, serializer)
+            }
+            HSum::L(variant) => variant.serialize_variant(variant_index, serializer),
+            HSum::R(rest) => rest.serialize_variants(variant_index + 1, serializer),
+        }
+    }
+}
+
+impl<T> SerializeVariants<T, Zero> for HSumNil {
+    fn serialize_variants<S: Serializer>(&self, _: u32, _: S) -> Result<S::Ok, S::Error> {
+        match *self {}
+    }
+}
+
+/// Representation of the variant `VI` of enum `T`.
+pub trait SerializeVariant<T, VI> {
+    fn serialize_variant<S: Serializer>(
+        &self,
+        variant_index: u32,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>;
+}
+
+/// Tagging of a variant, taking `#[serde(untagged)]` on variants into account.
+fn variant_tag<T: SerdeGeneric + SerdeVariantAttr<VI>, VI, E: ::serde::ser::Error>()
+-> Result<TagType, E> {
+    let container = &T::CONTAINER;
+    let variant = &T::VARIANT;
+    if variant.skip_serializing {
+        return Err(E::custom(format_args!(
+            "the enum variant {}::{} cannot be serialized",
+            container.name.serialize, variant.name.serialize,
+        )));
+    }
+    Ok(if variant.untagged {
+        TagType::None
+    } else {
+        container.tag.clone()
+    })
+}
+
+/// Variant with `#[serde(serialize_with = "...")]`, whose content is what
+/// the function serializes, as if it were a newtype variant.
+fn serialize_variant_with<T, VI, H, S>(
+    repr: &H,
+    variant_index: u32,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    T: SerdeGeneric + SerdeVariantAttr<VI> + SerdeVariantWith<H, VI>,
+    S: Serializer,
+{
+    let name = T::CONTAINER.name.serialize;
+    let variant = T::VARIANT.name.serialize;
+    let value = VariantWith::<T, VI, H>(repr, PhantomData);
+    match variant_tag::<T, VI, S::Error>()? {
+        TagType::External => {
+            serializer.serialize_newtype_variant(name, variant_index, variant, &value)
+        }
+        TagType::Internal { tag } => value.serialize(TaggedSerializer {
+            container: name,
+            tag,
+            variant,
+            delegate: serializer,
+        }),
+        TagType::Adjacent { tag, content } => {
+            let mut state = serializer.serialize_struct(name, 2)?;
+            state.serialize_field(tag, variant)?;
+            state.serialize_field(content, &value)?;
+            state.end()
+        }
+        TagType::None => value.serialize(serializer),
+    }
+}
+
+/// Representation `H` of variant `VI` serialized by its `serialize_with`.
+struct VariantWith<'a, T, VI, H>(&'a H, PhantomData<(T, VI)>);
+
+impl<T: SerdeVariantWith<H, VI>, VI, H> Serialize for VariantWith<'_, T, VI, H> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let serialize_with = T::serialize_with::<S>().expect("variant has serialize_with");
+        serialize_with(self.0, serializer)
+    }
+}
+
+impl<T, VI> SerializeVariant<T, VI> for UnitStruct
+where
+    T: SerdeGeneric + SerdeVariantAttr<VI>,
+{
+    fn serialize_variant<S: Serializer>(
+        &self,
+        variant_index: u32,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let name = T::CONTAINER.name.serialize;
+        let variant = T::VARIANT.name.serialize;
+        match variant_tag::<T, VI, S::Error>()? {
+            TagType::External => serializer.serialize_unit_variant(name, variant_index, variant),
+            TagType::Internal { tag } | TagType::Adjacent { tag, .. } => {
+                let mut state = serializer.serialize_struct(name, 1)?;
+                state.serialize_field(tag, variant)?;
+                state.end()
+            }
+            TagType::None => serializer.serialize_unit(),
+        }
+    }
+}
+
+impl<T, VI, X> SerializeVariant<T, VI> for UnnamedStruct<X>
+where
+    T: SerdeGeneric + SerdeVariantAttr<VI>,
+    X: HLen + SerializeFields<VariantField<T, VI>, X::Len>,
+{
+    fn serialize_variant<S: Serializer>(
+        &self,
+        variant_index: u32,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let name = T::CONTAINER.name.serialize;
+        let variant = T::VARIANT.name.serialize;
+        let tag = variant_tag::<T, VI, S::Error>()?;
+        if is_newtype::<X>() {
+            let value = AsNewtype::<VariantField<T, VI>, X>::new(&self.0);
+            return match tag {
+                TagType::External => {
+                    serializer.serialize_newtype_variant(name, variant_index, variant, &value)
+                }
+                TagType::Internal { tag } => value.serialize(TaggedSerializer {
+                    container: name,
+                    tag,
+                    variant,
+                    delegate: serializer,
+                }),
+                TagType::Adjacent { tag, content } => {
+                    let mut state = serializer.serialize_struct(name, 2)?;
+                    state.serialize_field(tag, variant)?;
+                    state.serialize_field(content, &value)?;
+                    state.end()
+                }
+                TagType::None => value.serialize(serializer),
+            };
+        }
+        let value = AsTuple::<VariantField<T, VI>, X>::new(&self.0);
+        match tag {
+            TagType::External => {
+                let mut state = serializer.serialize_tuple_variant(
+                    name,
+                    variant_index,
+                    variant,
+                    self.0.count(),
+                )?;
+                self.0.serialize_fields(&mut TupleVariantSink(&mut state))?;
+                state.end()
+            }
+            TagType::Internal { .. } => Err(S::Error::custom(format_args!(
+                "cannot serialize tuple variant {name}::{variant} of an internally tagged enum",
+            ))),
+            TagType::Adjacent { tag, content } => {
+                let mut state = serializer.serialize_struct(name, 2)?;
+                state.serialize_field(tag, variant)?;
+                state.serialize_field(content, &value)?;
+                state.end()
+            }
+            TagType::None => value.serialize(serializer),
+        }
+    }
+}
+
+impl<T, VI, X> SerializeVariant<T, VI> for NamedStruct<X>
+where
+    T: SerdeGeneric + SerdeVariantAttr<VI>,
+    X: HLen + SerializeFields<VariantField<T, VI>, X::Len>,
+{
+    fn serialize_variant<S: Serializer>(
+        &self,
+        variant_index: u32,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let name = T::CONTAINER.name.serialize;
+        let variant = T::VARIANT.name.serialize;
+        let as_struct = |tag| AsStruct::<VariantField<T, VI>, X> {
+            name: variant,
+            tag,
+            fields: &self.0,
+            attrs: core::marker::PhantomData,
+        };
+        match variant_tag::<T, VI, S::Error>()? {
+            TagType::External if X::has_flatten() => {
+                let mut state = serializer.serialize_map(Some(1))?;
+                state.serialize_entry(variant, &as_struct(None))?;
+                state.end()
+            }
+            TagType::External => {
+                let mut state = serializer.serialize_struct_variant(
+                    name,
+                    variant_index,
+                    variant,
+                    self.0.count(),
+                )?;
+                self.0
+                    .serialize_fields(&mut StructVariantSink(&mut state))?;
+                state.end()
+            }
+            TagType::Internal { tag } => AsStruct {
+                name,
+                ..as_struct(Some((tag, variant)))
+            }
+            .serialize(serializer),
+            TagType::Adjacent { tag, content } => {
+                let mut state = serializer.serialize_struct(name, 2)?;
+                state.serialize_field(tag, variant)?;
+                state.serialize_field(content, &as_struct(None))?;
+                state.end()
+            }
+            TagType::None => as_struct(None).serialize(serializer),
+        }
+    }
+}
+
+/// Fields serialized as a struct, or as a map if some of them are flattened.
+struct AsStruct<'a, A, X> {
+    name: &'static str,
+    /// Extra leading `tag: value` field of internally tagged enums.
+    tag: Option<(&'static str, &'static str)>,
+    fields: &'a X,
+    attrs: core::marker::PhantomData<A>,
+}
+
+impl<A, X> Serialize for AsStruct<'_, A, X>
+where
+    X: HLen + SerializeFields<A, X::Len>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if X::has_flatten() {
+            let mut state = serializer.serialize_map(None)?;
+            if let Some((tag, value)) = self.tag {
+                state.serialize_entry(tag, value)?;
+            }
+            self.fields.serialize_fields(&mut MapSink(&mut state))?;
+            state.end()
+        } else {
+            let len = self.fields.count() + usize::from(self.tag.is_some());
+            let mut state = serializer.serialize_struct(self.name, len)?;
+            if let Some((tag, value)) = self.tag {
+                state.serialize_field(tag, value)?;
+            }
+            self.fields.serialize_fields(&mut StructSink(&mut state))?;
+            state.end()
+        }
+    }
+}
+
+/// Fields serialized as a tuple.
+struct AsTuple<'a, A, X>(&'a X, core::marker::PhantomData<A>);
+
+impl<'a, A, X> AsTuple<'a, A, X> {
+    fn new(fields: &'a X) -> Self {
+        AsTuple(fields, core::marker::PhantomData)
+    }
+}
+
+impl<A, X> Serialize for AsTuple<'_, A, X>
+where
+    X: HLen + SerializeFields<A, X::Len>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_tuple(self.0.count())?;
+        self.0.serialize_fields(&mut TupleSink(&mut state))?;
+        state.end()
+    }
+}
+
+/// The only field of a newtype, serialized as itself.
+struct AsNewtype<'a, A, X>(&'a X, core::marker::PhantomData<A>);
+
+impl<'a, A, X> AsNewtype<'a, A, X> {
+    fn new(fields: &'a X) -> Self {
+        AsNewtype(fields, core::marker::PhantomData)
+    }
+}
+
+impl<A, X> Serialize for AsNewtype<'_, A, X>
+where
+    X: HLen + SerializeFields<A, X::Len>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut sink = ForwardSink::new(serializer);
+        self.0.serialize_fields(&mut sink)?;
+        sink.finish(|| "newtype field is skipped".to_owned())
+    }
+}
+
+struct StructSink<'a, M>(&'a mut M);
+
+impl<M: SerializeStruct> FieldSink for StructSink<'_, M> {
+    type Error = M::Error;
+
+    fn field<V: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &V,
+    ) -> Result<(), M::Error> {
+        self.0.serialize_field(key, value)
+    }
+
+    fn skip(&mut self, key: &'static str) -> Result<(), M::Error> {
+        self.0.skip_field(key)
+    }
+}
+
+struct StructVariantSink<'a, M>(&'a mut M);
+
+impl<M: SerializeStructVariant> FieldSink for StructVariantSink<'_, M> {
+    type Error = M::Error;
+
+    fn field<V: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &V,
+    ) -> Result<(), M::Error> {
+        self.0.serialize_field(key, value)
+    }
+
+    fn skip(&mut self, key: &'static str) -> Result<(), M::Error> {
+        self.0.skip_field(key)
+    }
+}
+
+struct MapSink<'a, M>(&'a mut M);
+
+impl<M: SerializeMap> FieldSink for MapSink<'_, M> {
+    type Error = M::Error;
+
+    fn field<V: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &V,
+    ) -> Result<(), M::Error> {
+        self.0.serialize_entry(key, value)
+    }
+
+    fn flatten<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), M::Error> {
+        value.serialize(FlatMapSerializer(self.0))
+    }
+}
+
+struct TupleSink<'a, M>(&'a mut M);
+
+impl<M: SerializeTuple> FieldSink for TupleSink<'_, M> {
+    type Error = M::Error;
+
+    fn field<V: Serialize + ?Sized>(&mut self, _: &'static str, value: &V) -> Result<(), M::Error> {
+        self.0.serialize_element(value)
+    }
+}
+
+struct TupleStructSink<'a, M>(&'a mut M);
+
+impl<M: SerializeTupleStruct> FieldSink for TupleStructSink<'_, M> {
+    type Error = M::Error;
+
+    fn field<V: Serialize + ?Sized>(&mut self, _: &'static str, value: &V) -> Result<(), M::Error> {
+        self.0.serialize_field(value)
+    }
+}
+
+struct TupleVariantSink<'a, M>(&'a mut M);
+
+impl<M: SerializeTupleVariant> FieldSink for TupleVariantSink<'_, M> {
+    type Error = M::Error;
+
+    fn field<V: Serialize + ?Sized>(&mut self, _: &'static str, value: &V) -> Result<(), M::Error> {
+        self.0.serialize_field(value)
+    }
+}
+
+/// Serializes the first non-skipped field directly with the serializer.
+struct ForwardSink<S: Serializer> {
+    serializer: Option<S>,
+    result: Option<S::Ok>,
+}
+
+impl<S: Serializer> ForwardSink<S> {
+    fn new(serializer: S) -> Self {
+        ForwardSink {
+            serializer: Some(serializer),
+            result: None,
+        }
+    }
+
+    fn finish(self, msg: impl FnOnce() -> String) -> Result<S::Ok, S::Error> {
+        self.result.ok_or_else(|| S::Error::custom(msg()))
+    }
+}
+
+impl<S: Serializer> FieldSink for ForwardSink<S> {
+    type Error = S::Error;
+
+    fn field<V: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &V,
+    ) -> Result<(), S::Error> {
+        let serializer = self
+            .serializer
+            .take()
+            .ok_or_else(|| S::Error::custom(format_args!("unexpected second field `{key}`")))?;
+        self.result = Some(value.serialize(serializer)?);
+        Ok(())
+    }
+}
+
+macro_rules! unsupported {
+    ($($method:ident($($ty:ty),*) => $what:literal,)*) => {
+        $(fn $method(self, $(_: $ty),*) -> Result<Self::Ok, Self::Error> {
+            Err(self.unsupported($what))
+        })*
+    };
+}
+
+/// Serializes a newtype variant of an internally tagged enum, injecting
+/// the tag into the map or struct produced by the inner value.
+struct TaggedSerializer<S> {
+    container: &'static str,
+    tag: &'static str,
+    variant: &'static str,
+    delegate: S,
+}
+
+impl<S: Serializer> TaggedSerializer<S> {
+    fn unsupported(&self, what: &str) -> S::Error {
+        S::Error::custom(format_args!(
+            "cannot serialize tagged newtype variant {}::{} containing {}",
+            self.container, self.variant, what,
+        ))
+    }
+}
+
+impl<S: Serializer> Serializer for TaggedSerializer<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Impossible<S::Ok, S::Error>;
+    type SerializeTuple = Impossible<S::Ok, S::Error>;
+    type SerializeTupleStruct = Impossible<S::Ok, S::Error>;
+    type SerializeTupleVariant = SerializeTupleVariantAsMapValue<S::SerializeMap>;
+    type SerializeMap = S::SerializeMap;
+    type SerializeStruct = S::SerializeStruct;
+    type SerializeStructVariant = SerializeStructVariantAsMapValue<S::SerializeMap>;
+
+    unsupported! {
+        serialize_bool(bool) => "a boolean",
+        serialize_i8(i8) => "an integer",
+        serialize_i16(i16) => "an integer",
+        serialize_i32(i32) => "an integer",
+        serialize_i64(i64) => "an integer",
+        serialize_i128(i128) => "an integer",
+        serialize_u8(u8) => "an integer",
+        serialize_u16(u16) => "an integer",
+        serialize_u32(u32) => "an integer",
+        serialize_u64(u64) => "an integer",
+        serialize_u128(u128) => "an integer",
+        serialize_f32(f32) => "a float",
+        serialize_f64(f64) => "a float",
+        serialize_char(char) => "a char",
+        serialize_str(&str) => "a string",
+        serialize_bytes(&[u8]) => "a byte array",
+        serialize_none() => "an optional",
+    }
+
+    fn serialize_some<V: Serialize + ?Sized>(self, _: &V) -> Result<S::Ok, S::Error> {
+        Err(self.unsupported("an optional"))
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        let mut state = self.delegate.serialize_map(Some(1))?;
+        state.serialize_entry(self.tag, self.variant)?;
+        state.end()
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<S::Ok, S::Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        inner_variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        let mut state = self.delegate.serialize_map(Some(2))?;
+        state.serialize_entry(self.tag, self.variant)?;
+        state.serialize_entry(inner_variant, &())?;
+        state.end()
+    }
+
+    fn serialize_newtype_struct<V: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        value: &V,
+    ) -> Result<S::Ok, S::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<V: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        inner_variant: &'static str,
+        inner_value: &V,
+    ) -> Result<S::Ok, S::Error> {
+        let mut state = self.delegate.serialize_map(Some(2))?;
+        state.serialize_entry(self.tag, self.variant)?;
+        state.serialize_entry(inner_variant, inner_value)?;
+        state.end()
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        Err(self.unsupported("a sequence"))
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, S::Error> {
+        Err(self.unsupported("a tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        Err(self.unsupported("a tuple struct"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        inner_variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        let mut state = self.delegate.serialize_map(Some(2))?;
+        state.serialize_entry(self.tag, self.variant)?;
+        state.serialize_key(inner_variant)?;
+        Ok(SerializeTupleVariantAsMapValue {
+            map: state,
+            name: inner_variant,
+            fields: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        let mut state = self.delegate.serialize_map(len.map(|len| len + 1))?;
+        state.serialize_entry(self.tag, self.variant)?;
+        Ok(state)
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        let mut state = self.delegate.serialize_struct(name, len + 1)?;
+        state.serialize_field(self.tag, self.variant)?;
+        Ok(state)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        inner_variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        let mut state = self.delegate.serialize_map(Some(2))?;
+        state.serialize_entry(self.tag, self.variant)?;
+        state.serialize_key(inner_variant)?;
+        Ok(SerializeStructVariantAsMapValue {
+            map: state,
+            name: inner_variant,
+            fields: Vec::with_capacity(len),
+        })
+    }
+}
+
+/// Serializes a `#[serde(flatten)]` field as entries of the enclosing map.
+struct FlatMapSerializer<'a, M>(&'a mut M);
+
+impl<M: SerializeMap> FlatMapSerializer<'_, M> {
+    fn unsupported(&self, what: &str) -> M::Error {
+        M::Error::custom(format_args!(
+            "can only flatten structs, maps and enums (got {what})"
+        ))
+    }
+}
+
+impl<'a, M: SerializeMap> Serializer for FlatMapSerializer<'a, M> {
+    type Ok = ();
+    type Error = M::Error;
+    type SerializeSeq = Impossible<(), M::Error>;
+    type SerializeTuple = Impossible<(), M::Error>;
+    type SerializeTupleStruct = Impossible<(), M::Error>;
+    type SerializeTupleVariant = FlatMapSerializeTupleVariantAsMapValue<'a, M>;
+    type SerializeMap = FlatMapSerializeMap<'a, M>;
+    type SerializeStruct = FlatMapSerializeMap<'a, M>;
+    type SerializeStructVariant = FlatMapSerializeStructVariantAsMapValue<'a, M>;
+
+    unsupported! {
+        serialize_bool(bool) => "a boolean",
+        serialize_i8(i8) => "an integer",
+        serialize_i16(i16) => "an integer",
+        serialize_i32(i32) => "an integer",
+        serialize_i64(i64) => "an integer",
+        serialize_i128(i128) => "an integer",
+        serialize_u8(u8) => "an integer",
+        serialize_u16(u16) => "an integer",
+        serialize_u32(u32) => "an integer",
+        serialize_u64(u64) => "an integer",
+        serialize_u128(u128) => "an integer",
+        serialize_f32(f32) => "a float",
+        serialize_f64(f64) => "a float",
+        serialize_char(char) => "a char",
+        serialize_str(&str) => "a string",
+        serialize_bytes(&[u8]) => "a byte array",
+    }
+
+    fn serialize_none(self) -> Result<(), M::Error> {
+        Ok(())
+    }
+
+    fn serialize_some<V: Serialize + ?Sized>(self, value: &V) -> Result<(), M::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), M::Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<(), M::Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<(), M::Error> {
+        self.0.serialize_entry(variant, &())
+    }
+
+    fn serialize_newtype_struct<V: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        value: &V,
+    ) -> Result<(), M::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<V: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &V,
+    ) -> Result<(), M::Error> {
+        self.0.serialize_entry(variant, value)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, M::Error> {
+        Err(self.unsupported("a sequence"))
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, M::Error> {
+        Err(self.unsupported("a tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, M::Error> {
+        Err(self.unsupported("a tuple struct"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, M::Error> {
+        self.0.serialize_key(variant)?;
+        Ok(FlatMapSerializeTupleVariantAsMapValue {
+            map: self.0,
+            fields: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, M::Error> {
+        Ok(FlatMapSerializeMap(self.0))
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, M::Error> {
+        Ok(FlatMapSerializeMap(self.0))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, M::Error> {
+        self.0.serialize_key(variant)?;
+        Ok(FlatMapSerializeStructVariantAsMapValue {
+            map: self.0,
+            name: variant,
+            fields: Vec::with_capacity(len),
+        })
+    }
+}
+
+struct FlatMapSerializeMap<'a, M>(&'a mut M);
+
+impl<M: SerializeMap> SerializeMap for FlatMapSerializeMap<'_, M> {
+    type Ok = ();
+    type Error = M::Error;
+
+    fn serialize_key<K: Serialize + ?Sized>(&mut self, key: &K) -> Result<(), M::Error> {
+        self.0.serialize_key(key)
+    }
+
+    fn serialize_value<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), M::Error> {
+        self.0.serialize_value(value)
+    }
+
+    fn end(self) -> Result<(), M::Error> {
+        Ok(())
+    }
+}
+
+impl<M: SerializeMap> SerializeStruct for FlatMapSerializeMap<'_, M> {
+    type Ok = ();
+    type Error = M::Error;
+
+    fn serialize_field<V: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &V,
+    ) -> Result<(), M::Error> {
+        self.0.serialize_entry(key, value)
+    }
+
+    fn end(self) -> Result<(), M::Error> {
+        Ok(())
+    }
+}
+
+/// Serializes a tuple variant of a flattened enum as the value of the entry
+/// keyed by the variant.
+struct FlatMapSerializeTupleVariantAsMapValue<'a, M: SerializeMap> {
+    map: &'a mut M,
+    fields: Vec<Content>,
+}
+
+impl<M: SerializeMap> SerializeTupleVariant for FlatMapSerializeTupleVariantAsMapValue<'_, M> {
+    type Ok = ();
+    type Error = M::Error;
+
+    fn serialize_field<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), M::Error> {
+        self.fields.push(value.serialize(ContentSerializer::new())?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), M::Error> {
+        self.map.serialize_value(&Content::Seq(self.fields))
+    }
+}
+
+/// Serializes a struct variant of a flattened enum as the value of the entry
+/// keyed by the variant.
+struct FlatMapSerializeStructVariantAsMapValue<'a, M: SerializeMap> {
+    map: &'a mut M,
+    name: &'static str,
+    fields: Vec<(&'static str, Content)>,
+}
+
+impl<M: SerializeMap> SerializeStructVariant for FlatMapSerializeStructVariantAsMapValue<'_, M> {
+    type Ok = ();
+    type Error = M::Error;
+
+    fn serialize_field<V: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &V,
+    ) -> Result<(), M::Error> {
+        let value = value.serialize(ContentSerializer::new())?;
+        self.fields.push((key, value));
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), M::Error> {
+        self.map
+            .serialize_value(&Content::Struct(self.name, self.fields))
+    }
+}
+
+/// Serializes a tuple variant of an enum in a tagged newtype variant as the
+/// value of the entry keyed by the variant, after the tag.
+struct SerializeTupleVariantAsMapValue<M> {
+    map: M,
+    name: &'static str,
+    fields: Vec<Content>,
+}
+
+impl<M: SerializeMap> SerializeTupleVariant for SerializeTupleVariantAsMapValue<M> {
+    type Ok = M::Ok;
+    type Error = M::Error;
+
+    fn serialize_field<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), M::Error> {
+        self.fields.push(value.serialize(ContentSerializer::new())?);
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<M::Ok, M::Error> {
+        self.map
+            .serialize_value(&Content::TupleStruct(self.name, self.fields))?;
+        self.map.end()
+    }
+}
+
+/// Serializes a struct variant of an enum in a tagged newtype variant as the
+/// value of the entry keyed by the variant, after the tag.
+struct SerializeStructVariantAsMapValue<M> {
+    map: M,
+    name: &'static str,
+    fields: Vec<(&'static str, Content)>,
+}
+
+impl<M: SerializeMap> SerializeStructVariant for SerializeStructVariantAsMapValue<M> {
+    type Ok = M::Ok;
+    type Error = M::Error;
+
+    fn serialize_field<V: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &V,
+    ) -> Result<(), M::Error> {
+        let value = value.serialize(ContentSerializer::new())?;
+        self.fields.push((key, value));
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<M::Ok, M::Error> {
+        self.map
+            .serialize_value(&Content::Struct(self.name, self.fields))?;
+        self.map.end()
+    }
+}
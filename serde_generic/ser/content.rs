@@ -0,0 +1,491 @@
+//! Buffered values, for variants whose fields have to be serialized as the
+//! value of one map entry: variants of flattened enums and of enums in a
+//! newtype variant of an internally tagged enum.
+
+use ::serde::ser::{
+    self, Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+    SerializeTuple, SerializeTupleStruct, SerializeTupleVariant, Serializer,
+};
+use core::marker::PhantomData;
+
+/// Everything a [`Serializer`] can be given, serialized again as is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+    Bool(bool),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    F32(f32),
+    F64(f64),
+    Char(char),
+    String(String),
+    Bytes(Vec<u8>),
+    None,
+    Some(Box<Content>),
+    Unit,
+    UnitStruct(&'static str),
+    UnitVariant(&'static str, u32, &'static str),
+    NewtypeStruct(&'static str, Box<Content>),
+    NewtypeVariant(&'static str, u32, &'static str, Box<Content>),
+    Seq(Vec<Content>),
+    Tuple(Vec<Content>),
+    TupleStruct(&'static str, Vec<Content>),
+    TupleVariant(&'static str, u32, &'static str, Vec<Content>),
+    Map(Vec<(Content, Content)>),
+    Struct(&'static str, Vec<(&'static str, Content)>),
+    StructVariant(
+        &'static str,
+        u32,
+        &'static str,
+        Vec<(&'static str, Content)>,
+    ),
+}
+
+impl Serialize for Content {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Content::Bool(v) => serializer.serialize_bool(*v),
+            Content::U8(v) => serializer.serialize_u8(*v),
+            Content::U16(v) => serializer.serialize_u16(*v),
+            Content::U32(v) => serializer.serialize_u32(*v),
+            Content::U64(v) => serializer.serialize_u64(*v),
+            Content::U128(v) => serializer.serialize_u128(*v),
+            Content::I8(v) => serializer.serialize_i8(*v),
+            Content::I16(v) => serializer.serialize_i16(*v),
+            Content::I32(v) => serializer.serialize_i32(*v),
+            Content::I64(v) => serializer.serialize_i64(*v),
+            Content::I128(v) => serializer.serialize_i128(*v),
+            Content::F32(v) => serializer.serialize_f32(*v),
+            Content::F64(v) => serializer.serialize_f64(*v),
+            Content::Char(v) => serializer.serialize_char(*v),
+            Content::String(v) => serializer.serialize_str(v),
+            Content::Bytes(v) => serializer.serialize_bytes(v),
+            Content::None => serializer.serialize_none(),
+            Content::Some(v) => serializer.serialize_some(&**v),
+            Content::Unit => serializer.serialize_unit(),
+            Content::UnitStruct(name) => serializer.serialize_unit_struct(name),
+            Content::UnitVariant(name, index, variant) => {
+                serializer.serialize_unit_variant(name, *index, variant)
+            }
+            Content::NewtypeStruct(name, v) => serializer.serialize_newtype_struct(name, &**v),
+            Content::NewtypeVariant(name, index, variant, v) => {
+                serializer.serialize_newtype_variant(name, *index, variant, &**v)
+            }
+            Content::Seq(elements) => {
+                let mut seq = serializer.serialize_seq(Some(elements.len()))?;
+                for element in elements {
+                    seq.serialize_element(element)?;
+                }
+                seq.end()
+            }
+            Content::Tuple(elements) => {
+                let mut tuple = serializer.serialize_tuple(elements.len())?;
+                for element in elements {
+                    tuple.serialize_element(element)?;
+                }
+                tuple.end()
+            }
+            Content::TupleStruct(name, fields) => {
+                let mut tuple = serializer.serialize_tuple_struct(name, fields.len())?;
+                for field in fields {
+                    tuple.serialize_field(field)?;
+                }
+                tuple.end()
+            }
+            Content::TupleVariant(name, index, variant, fields) => {
+                let mut tuple =
+                    serializer.serialize_tuple_variant(name, *index, variant, fields.len())?;
+                for field in fields {
+                    tuple.serialize_field(field)?;
+                }
+                tuple.end()
+            }
+            Content::Map(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+            Content::Struct(name, fields) => {
+                let mut state = serializer.serialize_struct(name, fields.len())?;
+                for (key, value) in fields {
+                    state.serialize_field(key, value)?;
+                }
+                state.end()
+            }
+            Content::StructVariant(name, index, variant, fields) => {
+                let mut state =
+                    serializer.serialize_struct_variant(name, *index, variant, fields.len())?;
+                for (key, value) in fields {
+                    state.serialize_field(key, value)?;
+                }
+                state.end()
+            }
+        }
+    }
+}
+
+/// Serializes into [`Content`], with the errors of another serializer.
+pub struct ContentSerializer<E>(PhantomData<E>);
+
+impl<E> ContentSerializer<E> {
+    pub fn new() -> Self {
+        ContentSerializer(PhantomData)
+    }
+}
+
+impl<E: ser::Error> Serializer for ContentSerializer<E> {
+    type Ok = Content;
+    type Error = E;
+    type SerializeSeq = SerializeVec<E>;
+    type SerializeTuple = SerializeVec<E>;
+    type SerializeTupleStruct = SerializeVec<E>;
+    type SerializeTupleVariant = SerializeVec<E>;
+    type SerializeMap = SerializeEntries<E>;
+    type SerializeStruct = SerializeFields<E>;
+    type SerializeStructVariant = SerializeFields<E>;
+
+    fn serialize_bool(self, v: bool) -> Result<Content, E> {
+        Ok(Content::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Content, E> {
+        Ok(Content::I8(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Content, E> {
+        Ok(Content::I16(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Content, E> {
+        Ok(Content::I32(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Content, E> {
+        Ok(Content::I64(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Content, E> {
+        Ok(Content::I128(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Content, E> {
+        Ok(Content::U8(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Content, E> {
+        Ok(Content::U16(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Content, E> {
+        Ok(Content::U32(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Content, E> {
+        Ok(Content::U64(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Content, E> {
+        Ok(Content::U128(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Content, E> {
+        Ok(Content::F32(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Content, E> {
+        Ok(Content::F64(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Content, E> {
+        Ok(Content::Char(v))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Content, E> {
+        Ok(Content::String(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Content, E> {
+        Ok(Content::Bytes(v.to_owned()))
+    }
+
+    fn serialize_none(self) -> Result<Content, E> {
+        Ok(Content::None)
+    }
+
+    fn serialize_some<V: Serialize + ?Sized>(self, value: &V) -> Result<Content, E> {
+        Ok(Content::Some(Box::new(value.serialize(self)?)))
+    }
+
+    fn serialize_unit(self) -> Result<Content, E> {
+        Ok(Content::Unit)
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Content, E> {
+        Ok(Content::UnitStruct(name))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+    ) -> Result<Content, E> {
+        Ok(Content::UnitVariant(name, index, variant))
+    }
+
+    fn serialize_newtype_struct<V: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &V,
+    ) -> Result<Content, E> {
+        Ok(Content::NewtypeStruct(
+            name,
+            Box::new(value.serialize(self)?),
+        ))
+    }
+
+    fn serialize_newtype_variant<V: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &V,
+    ) -> Result<Content, E> {
+        let value = Box::new(value.serialize(self)?);
+        Ok(Content::NewtypeVariant(name, index, variant, value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeVec<E>, E> {
+        Ok(SerializeVec::new(Content::Seq, len.unwrap_or(0)))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeVec<E>, E> {
+        Ok(SerializeVec::new(Content::Tuple, len))
+    }
+
+    fn serialize_tuple_struct(self, name: &'static str, len: usize) -> Result<SerializeVec<E>, E> {
+        Ok(SerializeVec::new(
+            move |fields| Content::TupleStruct(name, fields),
+            len,
+        ))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVec<E>, E> {
+        Ok(SerializeVec::new(
+            move |fields| Content::TupleVariant(name, index, variant, fields),
+            len,
+        ))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeEntries<E>, E> {
+        Ok(SerializeEntries {
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+            error: PhantomData,
+        })
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<SerializeFields<E>, E> {
+        Ok(SerializeFields::new(
+            move |fields| Content::Struct(name, fields),
+            len,
+        ))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeFields<E>, E> {
+        Ok(SerializeFields::new(
+            move |fields| Content::StructVariant(name, index, variant, fields),
+            len,
+        ))
+    }
+}
+
+/// Elements of a sequence, tuple, tuple struct or tuple variant.
+pub struct SerializeVec<E> {
+    make: Box<dyn FnOnce(Vec<Content>) -> Content>,
+    elements: Vec<Content>,
+    error: PhantomData<E>,
+}
+
+impl<E: ser::Error> SerializeVec<E> {
+    fn new(make: impl FnOnce(Vec<Content>) -> Content + 'static, len: usize) -> Self {
+        SerializeVec {
+            make: Box::new(make),
+            elements: Vec::with_capacity(len),
+            error: PhantomData,
+        }
+    }
+
+    fn push<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), E> {
+        self.elements
+            .push(value.serialize(ContentSerializer::new())?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Content, E> {
+        Ok((self.make)(self.elements))
+    }
+}
+
+impl<E: ser::Error> SerializeSeq for SerializeVec<E> {
+    type Ok = Content;
+    type Error = E;
+
+    fn serialize_element<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), E> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Content, E> {
+        self.finish()
+    }
+}
+
+impl<E: ser::Error> SerializeTuple for SerializeVec<E> {
+    type Ok = Content;
+    type Error = E;
+
+    fn serialize_element<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), E> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Content, E> {
+        self.finish()
+    }
+}
+
+impl<E: ser::Error> SerializeTupleStruct for SerializeVec<E> {
+    type Ok = Content;
+    type Error = E;
+
+    fn serialize_field<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), E> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Content, E> {
+        self.finish()
+    }
+}
+
+impl<E: ser::Error> SerializeTupleVariant for SerializeVec<E> {
+    type Ok = Content;
+    type Error = E;
+
+    fn serialize_field<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), E> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Content, E> {
+        self.finish()
+    }
+}
+
+/// Entries of a map.
+pub struct SerializeEntries<E> {
+    entries: Vec<(Content, Content)>,
+    key: Option<Content>,
+    error: PhantomData<E>,
+}
+
+impl<E: ser::Error> SerializeMap for SerializeEntries<E> {
+    type Ok = Content;
+    type Error = E;
+
+    fn serialize_key<K: Serialize + ?Sized>(&mut self, key: &K) -> Result<(), E> {
+        self.key = Some(key.serialize(ContentSerializer::new())?);
+        Ok(())
+    }
+
+    fn serialize_value<V: Serialize + ?Sized>(&mut self, value: &V) -> Result<(), E> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| E::custom("serialize_value called before serialize_key"))?;
+        let value = value.serialize(ContentSerializer::new())?;
+        self.entries.push((key, value));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Content, E> {
+        Ok(Content::Map(self.entries))
+    }
+}
+
+type Fields = Vec<(&'static str, Content)>;
+
+/// Fields of a struct or struct variant.
+pub struct SerializeFields<E> {
+    make: Box<dyn FnOnce(Fields) -> Content>,
+    fields: Fields,
+    error: PhantomData<E>,
+}
+
+impl<E: ser::Error> SerializeFields<E> {
+    fn new(make: impl FnOnce(Fields) -> Content + 'static, len: usize) -> Self {
+        SerializeFields {
+            make: Box::new(make),
+            fields: Vec::with_capacity(len),
+            error: PhantomData,
+        }
+    }
+
+    fn push<V: Serialize + ?Sized>(&mut self, key: &'static str, value: &V) -> Result<(), E> {
+        let value = value.serialize(ContentSerializer::new())?;
+        self.fields.push((key, value));
+        Ok(())
+    }
+}
+
+impl<E: ser::Error> SerializeStruct for SerializeFields<E> {
+    type Ok = Content;
+    type Error = E;
+
+    fn serialize_field<V: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &V,
+    ) -> Result<(), E> {
+        self.push(key, value)
+    }
+
+    fn end(self) -> Result<Content, E> {
+        Ok((self.make)(self.fields))
+    }
+}
+
+impl<E: ser::Error> SerializeStructVariant for SerializeFields<E> {
+    type Ok = Content;
+    type Error = E;
+
+    fn serialize_field<V: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &V,
+    ) -> Result<(), E> {
+        self.push(key, value)
+    }
+
+    fn end(self) -> Result<Content, E> {
+        Ok((self.make)(self.fields))
+    }
+}
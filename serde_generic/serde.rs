@@ -1,22 +1,44 @@
+use crate::rust;
+
 /// Represents `serde` struct or enum attribute information.
 pub struct Container<T: ?Sized> {
     pub name: MultiName,
     pub transparent: bool,
     pub deny_unknown_fields: bool,
     pub default: Default<T>,
-    // pub rename_all_rules: RenameAllRules,
-    // pub rename_all_fields_rules: RenameAllRules,
+    /// `#[serde(rename_all = "...")]`, applied to the fields of a struct or
+    /// to the variants of an enum.
+    pub rename_all_rules: RenameAllRules,
+    /// `#[serde(rename_all_fields = "...")]`, applied to the fields of the
+    /// enum variants that have no `rename_all` of their own.
+    pub rename_all_fields_rules: RenameAllRules,
+    pub bound: Bounds,
     pub tag: TagType,
-    // pub type_from: Option<syn::Type>,
-    // pub type_try_from: Option<syn::Type>,
-    // pub type_into: Option<syn::Type>,
-    // pub remote: Option<syn::Path>,
+    /// Names and aliases of the fields of a struct which are deserialized,
+    /// as `serde_derive` passes them to `Deserializer::deserialize_struct`.
+    pub fields: &'static [&'static str],
+    /// Names and aliases of the variants of an enum which are deserialized,
+    /// as `serde_derive` passes them to `Deserializer::deserialize_enum`.
+    pub variants: &'static [&'static str],
+    /// `#[serde(from = "...")]`, with the conversion given by
+    /// [`SerdeContainerFrom`](crate::SerdeContainerFrom).
+    pub type_from: Option<&'static str>,
+    /// `#[serde(try_from = "...")]`, see `type_from`.
+    pub type_try_from: Option<&'static str>,
+    /// `#[serde(into = "...")]`, with the conversion given by
+    /// [`SerdeContainerInto`](crate::SerdeContainerInto).
+    pub type_into: Option<&'static str>,
+    /// `#[serde(remote = "...")]`, with the conversions given by
+    /// [`SerdeRemote`](crate::SerdeRemote).
+    pub remote: Option<&'static str>,
     pub identifier: Identifier,
     // pub serde_path: Option<syn::Path>,
     pub is_packed: bool,
     /// Error message generated when type can't be deserialized
     pub expecting: Option<&'static str>,
     pub non_exhaustive: bool,
+    /// Doc comment with the leading space of each line stripped, if any.
+    pub doc: Option<&'static str>,
 }
 
 /// Styles of representing an enum.
@@ -54,6 +76,48 @@ pub enum TagType {
     None,
 }
 
+/// The different possible ways to change case of fields in a struct, or variants in an enum.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RenameRule {
+    /// Don't apply a default rename rule.
+    None,
+    /// Rename direct children to "lowercase" style.
+    LowerCase,
+    /// Rename direct children to "UPPERCASE" style.
+    UpperCase,
+    /// Rename direct children to "PascalCase" style, as typically used for
+    /// enum variants.
+    PascalCase,
+    /// Rename direct children to "camelCase" style.
+    CamelCase,
+    /// Rename direct children to "snake_case" style, as commonly used for
+    /// fields.
+    SnakeCase,
+    /// Rename direct children to "SCREAMING_SNAKE_CASE" style, as commonly
+    /// used for constants.
+    ScreamingSnakeCase,
+    /// Rename direct children to "kebab-case" style.
+    KebabCase,
+    /// Rename direct children to "SCREAMING-KEBAB-CASE" style.
+    ScreamingKebabCase,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RenameAllRules {
+    pub serialize: RenameRule,
+    pub deserialize: RenameRule,
+}
+
+/// `#[serde(bound = "...")]` or
+/// `#[serde(bound(serialize = "...", deserialize = "..."))]`: where-predicates
+/// as written, replacing the ones `serde` would infer. `None` if not given,
+/// so that `bound = ""` is told apart as an empty list.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Bounds {
+    pub serialize: Option<&'static [&'static str]>,
+    pub deserialize: Option<&'static [&'static str]>,
+}
+
 /// Whether this enum represents the fields of a struct or the variants of an
 /// enum.
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -73,17 +137,29 @@ pub enum Identifier {
 
 /// Represents `serde` field attribute information
 #[derive(Debug)]
+#[allow(clippy::type_complexity)]
 pub struct Field<S: ?Sized, T: ?Sized> {
     pub name: MultiName,
     pub skip_serializing: bool,
     pub skip_deserializing: bool,
     pub skip_serializing_if: Option<(&'static str, fn(&T) -> bool)>,
     pub default: Default<T>,
-    pub serialize_with: Option<&'static str>, // TODO: add fn ptrs?
+    /// Path of the function, which itself is given by
+    /// [`SerdeFieldWith`](crate::SerdeFieldWith).
+    pub serialize_with: Option<&'static str>,
     pub deserialize_with: Option<&'static str>,
-    pub getter: Option<(&'static str, fn(&S) -> &T)>, // TODO: case for fn() -> T
+    pub bound: Bounds,
+    /// Lifetimes the field borrows from the deserializer: the ones given by
+    /// `#[serde(borrow)]` on the field or its newtype variant, and those of
+    /// `&str` and `&[u8]`, e.g. `["'a"]`.
+    pub borrowed_lifetimes: &'static [&'static str],
     pub flatten: bool,
     pub transparent: bool,
+    /// Doc comment of the field.
+    pub doc: Option<&'static str>,
+    pub rust: rust::Field,
+    /// Struct or enum `S` the field belongs to.
+    pub container: core::marker::PhantomData<fn(&S)>,
 }
 
 #[derive(Debug)]
@@ -111,11 +187,23 @@ pub enum Default<T: ?Sized> {
 #[derive(Debug)]
 pub struct Variant {
     pub name: MultiName,
-    // pub rename_all_rules: RenameAllRules,
+    /// Rules applied to the fields of the variant: its own
+    /// `#[serde(rename_all = "...")]`, or else the container's
+    /// `rename_all_fields`.
+    pub rename_all_rules: RenameAllRules,
+    pub bound: Bounds,
     pub skip_deserializing: bool,
     pub skip_serializing: bool,
     pub other: bool,
-    pub serialize_with: Option<&'static str>, // TODO: add fn ptrs?
+    /// Path of the function, which itself is given by
+    /// [`SerdeVariantWith`](crate::SerdeVariantWith).
+    pub serialize_with: Option<&'static str>,
     pub deserialize_with: Option<&'static str>,
     pub untagged: bool,
+    /// Names and aliases of the fields of a struct variant which are
+    /// deserialized, see [`Container::fields`].
+    pub fields: &'static [&'static str],
+    /// Doc comment of the variant.
+    pub doc: Option<&'static str>,
+    pub rust: rust::Variant,
 }
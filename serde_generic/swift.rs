@@ -0,0 +1,1301 @@
+//! Swift `Codable` models of reflected types, generics included.
+//!
+//! ```
+//! # use serde_generic::SerdeGeneric;
+//! #[derive(SerdeGeneric)]
+//! struct Page<T> {
+//!     items: Vec<T>,
+//!     #[serde(skip_serializing_if = "Option::is_none")]
+//!     next_page: Option<String>,
+//! }
+//!
+//! assert_eq!(
+//!     serde_generic::swift::source_for::<Page<u8>, _>(),
+//!     r#"struct Page<X0: Codable> {
+//!     var items: [X0]
+//!     var nextPage: String?
+//! }
+//!
+//! extension Page: Codable {
+//!     enum CodingKeys: String, CodingKey {
+//!         case items
+//!         case nextPage = "next_page"
+//!     }
+//!
+//!     init(from decoder: Decoder) throws {
+//!         let container = try decoder.container(keyedBy: CodingKeys.self)
+//!         self.items = try container.decode([X0].self, forKey: .items)
+//!         self.nextPage = try container.decodeIfPresent(String.self, forKey: .nextPage)
+//!     }
+//!
+//!     func encode(to encoder: Encoder) throws {
+//!         var container = encoder.container(keyedBy: CodingKeys.self)
+//!         try container.encode(self.items, forKey: .items)
+//!         try container.encodeIfPresent(self.nextPage, forKey: .nextPage)
+//!     }
+//! }
+//! "#
+//! );
+//! ```
+//!
+//! Every [`Definition`] is declared once, with its type parameters as the
+//! `Codable` parameters `X0`, `X1` and so on, as in [`Shape::Param`]. Types
+//! are named after their serialized names, followed by a number when two
+//! types have the same name, see [`Registry::definition_name`].
+//!
+//! Structs have camel case properties and their `Codable` conformance in an
+//! extension, which keeps the memberwise initializer. Their `CodingKeys` have
+//! a case for every serialize and deserialize name and alias, the first
+//! deserialize name present being decoded. Fields that can be missing are
+//! decoded with `decodeIfPresent` and the value of
+//! `#[serde(default = "...")]` if it is a [`Value`](crate::reflect::Value),
+//! the zero value of primitives and collections for `#[serde(default)]`, or
+//! `nil` for options. Other properties are required, except the ones of
+//! fields that are never deserialized, which are optional and `nil` when
+//! their default can't be written. Optional fields
+//! skipped if `is_none` are encoded with `encodeIfPresent` and collections
+//! skipped if `is_empty` only when they are not. Flattened fields are
+//! decoded from and encoded to the decoder and encoder of their struct.
+//!
+//! Externally tagged enums with only unit variants are `String` enums. Other
+//! enums have a case for every variant and their own `init(from:)` and
+//! `encode(to:)` for their tags, trying untagged variants in order, as
+//! `serde` does. Variants with `#[serde(other)]` catch unknown tags. Enums
+//! that contain themselves are `indirect`, which structs can't be: a struct
+//! that contains itself other than in an array or a dictionary, as in
+//! `Option<Box<Self>>`, is declared but doesn't compile.
+//!
+//! Maps are dictionaries keyed by `Int` for integers that fit in one and by
+//! `String` otherwise, the only keys `Codable` encodes as objects. Newtype
+//! structs and transparent types are type aliases of their field, tuples
+//! other than tuple structs are `[JSONValue]`, and unit, `i128`, `u128`,
+//! types with `from`, `try_from` or `into` proxies and fields or variants
+//! with `serialize_with` or `deserialize_with` are `JSONValue`, an enum of
+//! all JSON values declared when needed.
+
+use crate::naming::{Comment, comment, ident, string, unit_only};
+use crate::reflect::{
+    Body, Container, Definition, Field, Name, Primitive, Reflect, Registry, Shape, TagType, Value,
+    Variant,
+};
+
+/// Source with `T` and the types it refers to.
+pub fn source_for<T: ?Sized + Reflect<M>, M>() -> String {
+    let mut registry = Registry::new();
+    registry.register::<T, _>();
+    source(&registry)
+}
+
+/// Source with all definitions in the registry, in topological order and
+/// separated by blank lines.
+pub fn source(registry: &Registry) -> String {
+    let mut generator = Generator {
+        registry,
+        json_value: false,
+    };
+    let mut definitions = registry
+        .definitions()
+        .map(|(id, def)| generator.definition(id, def))
+        .collect::<Vec<_>>();
+    if generator.json_value {
+        definitions.push(JSON_VALUE.to_owned());
+    }
+    definitions.join("\n")
+}
+
+const JSON_VALUE: &str = r#"/// Any JSON value.
+enum JSONValue: Hashable {
+    case null
+    case bool(Bool)
+    case number(Double)
+    case string(String)
+    case array([JSONValue])
+    case object([String: JSONValue])
+}
+
+extension JSONValue: Codable {
+    init(from decoder: Decoder) throws {
+        let container = try decoder.singleValueContainer()
+        if container.decodeNil() {
+            self = .null
+        } else if let value = try? container.decode(Bool.self) {
+            self = .bool(value)
+        } else if let value = try? container.decode(Double.self) {
+            self = .number(value)
+        } else if let value = try? container.decode(String.self) {
+            self = .string(value)
+        } else if let value = try? container.decode([JSONValue].self) {
+            self = .array(value)
+        } else {
+            self = .object(try container.decode([String: JSONValue].self))
+        }
+    }
+
+    func encode(to encoder: Encoder) throws {
+        var container = encoder.singleValueContainer()
+        switch self {
+        case .null:
+            try container.encodeNil()
+        case let .bool(value):
+            try container.encode(value)
+        case let .number(value):
+            try container.encode(value)
+        case let .string(value):
+            try container.encode(value)
+        case let .array(value):
+            try container.encode(value)
+        case let .object(value):
+            try container.encode(value)
+        }
+    }
+}
+"#;
+
+struct Generator<'a> {
+    registry: &'a Registry,
+    /// Whether `JSONValue` is used.
+    json_value: bool,
+}
+
+/// Cases of `CodingKeys`, with their raw values.
+#[derive(Default)]
+struct Keys(Vec<(String, String)>);
+
+impl Keys {
+    /// `CodingKeys` case of a key, added if missing.
+    fn key(&mut self, raw: &str) -> String {
+        if let Some((case, _)) = self.0.iter().find(|(_, key)| key == raw) {
+            return format!(".{case}");
+        }
+        let base = case_name(raw);
+        let mut case = base.clone();
+        let mut i = 2;
+        while self.0.iter().any(|(other, _)| *other == case) {
+            case = format!("{base}{i}");
+            i += 1;
+        }
+        self.0.push((case.clone(), raw.to_owned()));
+        format!(".{case}")
+    }
+
+    /// Cases of the deserialize names.
+    fn deserialize_keys(&mut self, name: &Name) -> Vec<String> {
+        deserialize_names(name)
+            .into_iter()
+            .map(|name| self.key(name))
+            .collect()
+    }
+
+    /// Expression of the first deserialize name present in `container`.
+    fn deserialize(&mut self, name: &Name, container: &str) -> String {
+        match &self.deserialize_keys(name)[..] {
+            [key] => key.clone(),
+            keys => format!("{} ?? {}", first_key(keys, container), keys[0]),
+        }
+    }
+
+    fn declaration(&self) -> String {
+        let mut declaration = String::from("    enum CodingKeys: String, CodingKey {\n");
+        for (case, raw) in &self.0 {
+            if case.trim_matches('`') == raw {
+                declaration += &format!("        case {case}\n");
+            } else {
+                declaration += &format!("        case {case} = {}\n", string(raw, unicode));
+            }
+        }
+        declaration + "    }\n"
+    }
+}
+
+/// Names of the containers, decoder and encoder fields are decoded from
+/// and encoded to.
+struct Coding<'a> {
+    container: &'a str,
+    decoder: &'a str,
+    encoder: &'a str,
+    /// Prefix of the properties, `self.` in structs.
+    prefix: &'a str,
+}
+
+const STRUCT_CODING: Coding<'static> = Coding {
+    container: "container",
+    decoder: "decoder",
+    encoder: "encoder",
+    prefix: "self.",
+};
+
+/// Property of a struct or struct variant.
+struct Property {
+    name: String,
+    ty: String,
+    doc: Option<String>,
+    /// Expression of the decoded value.
+    decode: String,
+    /// Statements encoding the value.
+    encode: Vec<String>,
+}
+
+/// Where the content of a variant is.
+enum Content<'a> {
+    /// Under `key` in the keyed container `container`.
+    Keyed { container: &'a str, key: String },
+    /// In the decoder or encoder itself, along with the keyed container
+    /// `container` if there is one already.
+    Inline { container: Option<&'a str> },
+}
+
+impl Generator<'_> {
+    /// Name of the type of a registered definition.
+    fn name(&self, id: u64) -> String {
+        ident(
+            self.registry
+                .definition_name(id)
+                .expect("registered definition"),
+        )
+    }
+
+    fn definition(&mut self, id: u64, def: &Definition) -> String {
+        let container = &def.container;
+        let name = self.name(id);
+        let params = params(def.params.len());
+        let doc = comment(container.doc.as_deref(), "", Comment::Line("///"));
+        let alias = |ty: &str| format!("{doc}typealias {name}{params} = {ty}\n");
+        match &def.body {
+            _ if container.proxy().is_some() => alias(&self.json_value()),
+            Body::Struct(fields) | Body::Tuple(fields) if container.transparent => {
+                let field = fields
+                    .iter()
+                    .find(|field| !(field.skip_serializing && field.skip_deserializing))
+                    .expect("transparent struct has a field");
+                alias(&self.field_type(field))
+            }
+            Body::Tuple(fields) if fields.len() == 1 => alias(&self.field_type(&fields[0])),
+            Body::Tuple(fields) => self.tuple_struct(&doc, &name, &params, fields),
+            Body::Unit => alias(&self.json_value()),
+            Body::Struct(fields) => self.structure(&doc, &name, &params, fields, container.default),
+            Body::Enum(variants) => {
+                let variants = variants
+                    .iter()
+                    .filter(|variant| !(variant.skip_serializing && variant.skip_deserializing))
+                    .collect::<Vec<_>>();
+                if unit_only(container, variants.iter().copied()) {
+                    self.string_enum(&doc, &name, &variants)
+                } else {
+                    self.enumeration(id, container, &doc, &name, &params, &variants)
+                }
+            }
+        }
+    }
+
+    fn structure(
+        &mut self,
+        doc: &str,
+        name: &str,
+        params: &str,
+        fields: &[Field],
+        default: bool,
+    ) -> String {
+        let mut keys = Keys::default();
+        let properties = self.properties(fields, default, &mut keys, &STRUCT_CODING);
+        if properties.is_empty() {
+            return format!("{doc}struct {name}{params}: Codable {{}}\n");
+        }
+        let mut structure = format!("{doc}struct {name}{params} {{\n");
+        for property in &properties {
+            structure += &comment(property.doc.as_deref(), "    ", Comment::Line("///"));
+            structure += &format!("    var {}: {}\n", property.name, property.ty);
+        }
+        structure += "}\n";
+
+        let mut decode = Vec::new();
+        let mut encode = Vec::new();
+        for property in &properties {
+            decode.push(format!("self.{} = {}", property.name, property.decode));
+            encode.extend(property.encode.iter().cloned());
+        }
+        if decode.iter().any(|line| line.contains("container")) {
+            let container = "let container = try decoder.container(keyedBy: CodingKeys.self)";
+            decode.insert(0, container.to_owned());
+        }
+        if encode.iter().any(|line| line.contains("container")) {
+            let container = "var container = encoder.container(keyedBy: CodingKeys.self)";
+            encode.insert(0, container.to_owned());
+        }
+        structure + "\n" + &conformance(name, Some(&keys), &decode, &encode)
+    }
+
+    /// Struct of a tuple struct with more than one field, coded as an array.
+    fn tuple_struct(&mut self, doc: &str, name: &str, params: &str, fields: &[Field]) -> String {
+        let fields = fields
+            .iter()
+            .filter(|field| !(field.skip_serializing && field.skip_deserializing))
+            .collect::<Vec<_>>();
+        let mut structure = format!("{doc}struct {name}{params} {{\n");
+        let mut decode = vec!["var values = try decoder.unkeyedContainer()".to_owned()];
+        let mut encode = vec!["var values = encoder.unkeyedContainer()".to_owned()];
+        for (i, field) in fields.iter().enumerate() {
+            let ty = self.field_type(field);
+            structure += &comment(field.doc.as_deref(), "    ", Comment::Line("///"));
+            structure += &format!("    var field{i}: {ty}\n");
+            decode.push(format!("self.field{i} = try values.decode({ty}.self)"));
+            encode.push(format!("try values.encode(self.field{i})"));
+        }
+        structure += "}\n";
+        structure + "\n" + &conformance(name, None, &decode, &encode)
+    }
+
+    /// `String` enum of an externally tagged enum with only unit variants.
+    fn string_enum(&mut self, doc: &str, name: &str, variants: &[&Variant]) -> String {
+        let mut enumeration = format!("{doc}enum {name}: String, Codable {{\n");
+        for variant in variants {
+            let case = case_name(&variant.name.serialize);
+            enumeration += &comment(variant.doc.as_deref(), "    ", Comment::Line("///"));
+            if case.trim_matches('`') == variant.name.serialize {
+                enumeration += &format!("    case {case}\n");
+            } else {
+                enumeration += &format!(
+                    "    case {case} = {}\n",
+                    string(&variant.name.serialize, unicode)
+                );
+            }
+        }
+        // The raw values are the serialize names, the only ones decoded
+        let decoded = variants.iter().all(|variant| {
+            deserialize_names(&variant.name) == [variant.name.serialize.as_str()] && !variant.other
+        });
+        if !decoded {
+            enumeration += "\n    init(from decoder: Decoder) throws {\n";
+            enumeration += "        let container = try decoder.singleValueContainer()\n";
+            enumeration += "        switch try container.decode(String.self) {\n";
+            for variant in variants
+                .iter()
+                .filter(|variant| !variant.skip_deserializing)
+            {
+                let names = deserialize_names(&variant.name);
+                let names = names
+                    .iter()
+                    .map(|name| string(name, unicode))
+                    .collect::<Vec<_>>();
+                let case = case_name(&variant.name.serialize);
+                enumeration += &format!("        case {}:\n", names.join(", "));
+                enumeration += &format!("            self = .{case}\n");
+            }
+            match variants.iter().find(|variant| variant.other) {
+                Some(other) => {
+                    enumeration += "        default:\n";
+                    enumeration +=
+                        &format!("            self = .{}\n", case_name(&other.name.serialize));
+                }
+                None => {
+                    enumeration += "        case let name:\n";
+                    enumeration += "            throw DecodingError.dataCorruptedError(in: container, debugDescription: \"unknown variant \\(name)\")\n";
+                }
+            }
+            enumeration += "        }\n    }\n";
+        }
+        enumeration + "}\n"
+    }
+
+    /// Enum with a case for every variant, and its coding.
+    fn enumeration(
+        &mut self,
+        id: u64,
+        container: &Container,
+        doc: &str,
+        name: &str,
+        params: &str,
+        variants: &[&Variant],
+    ) -> String {
+        let mut keys = Keys::default();
+        let recursive = variants
+            .iter()
+            .flat_map(|variant| variant.body.fields())
+            .any(|field| refers(&field.shape, id));
+        let indirect = if recursive { "indirect " } else { "" };
+        let mut enumeration = format!("{doc}{indirect}enum {name}{params} {{\n");
+
+        let mut tagged = Vec::new();
+        let mut untagged = Vec::new();
+        let mut encode = vec!["switch self {".to_owned()];
+        for variant in variants {
+            let case = case_name(&variant.name.serialize);
+            let coding = VariantCoding::new(self, variant, &mut keys);
+            enumeration += &comment(variant.doc.as_deref(), "    ", Comment::Line("///"));
+            enumeration += &format!("    case {case}{}\n", coding.declaration(self));
+
+            // Encoding
+            encode.push(format!("case {}:", coding.pattern(&case)));
+            let mut body = Vec::new();
+            let tag_name = string(&variant.name.serialize, unicode);
+            let variant_tag = if variant.untagged {
+                &TagType::None
+            } else {
+                &container.tag
+            };
+            match variant_tag {
+                _ if variant.skip_serializing => {
+                    body.push("throw EncodingError.invalidValue(self, .init(codingPath: encoder.codingPath, debugDescription: \"the variant cannot be serialized\"))".to_owned());
+                }
+                TagType::None => {
+                    body.extend(coding.encode(
+                        self,
+                        &mut keys,
+                        Content::Inline { container: None },
+                    ));
+                }
+                TagType::External if coding.is_unit() => {
+                    body.push("var container = encoder.singleValueContainer()".to_owned());
+                    body.push(format!("try container.encode({tag_name})"));
+                }
+                TagType::External => {
+                    body.push(
+                        "var container = encoder.container(keyedBy: CodingKeys.self)".to_owned(),
+                    );
+                    let key = keys.key(&variant.name.serialize);
+                    let content = Content::Keyed {
+                        container: "container",
+                        key,
+                    };
+                    body.extend(coding.encode(self, &mut keys, content));
+                }
+                TagType::Internal { tag } => {
+                    body.push(
+                        "var container = encoder.container(keyedBy: CodingKeys.self)".to_owned(),
+                    );
+                    let tag = keys.key(tag);
+                    body.push(format!("try container.encode({tag_name}, forKey: {tag})"));
+                    let content = Content::Inline {
+                        container: Some("container"),
+                    };
+                    body.extend(coding.encode(self, &mut keys, content));
+                }
+                TagType::Adjacent { tag, content } => {
+                    body.push(
+                        "var container = encoder.container(keyedBy: CodingKeys.self)".to_owned(),
+                    );
+                    let tag = keys.key(tag);
+                    body.push(format!("try container.encode({tag_name}, forKey: {tag})"));
+                    if !coding.is_unit() {
+                        let key = keys.key(content);
+                        let content = Content::Keyed {
+                            container: "container",
+                            key,
+                        };
+                        body.extend(coding.encode(self, &mut keys, content));
+                    }
+                }
+            }
+            encode.extend(indent(body));
+
+            if variant.skip_deserializing {
+                continue;
+            }
+            if *variant_tag == TagType::None {
+                untagged.push((variant, case, coding));
+            } else {
+                tagged.push((variant, case, coding));
+            }
+        }
+        encode.push("}".to_owned());
+        enumeration += "}\n";
+
+        // Decoding
+        let other = tagged
+            .iter()
+            .find(|(variant, _, _)| variant.other)
+            .map(|(_, case, _)| format!("self = .{case}"));
+        let unknown = |tag: &str| {
+            other.clone().unwrap_or(format!(
+                "throw DecodingError.dataCorrupted(.init(codingPath: decoder.codingPath, debugDescription: \"unknown variant \\({tag})\"))"
+            ))
+        };
+        let mut decode = Vec::new();
+        match &container.tag {
+            _ if tagged.is_empty() => {}
+            TagType::External => {
+                let (units, others) = tagged
+                    .iter_mut()
+                    .partition::<Vec<_>, _>(|(_, _, coding)| coding.is_unit());
+                if !units.is_empty() || other.is_some() {
+                    decode.push(
+                        "if let tag = try? decoder.singleValueContainer().decode(String.self) {"
+                            .to_owned(),
+                    );
+                    decode.push("    switch tag {".to_owned());
+                    for (variant, case, _) in &units {
+                        let names = deserialize_names(&variant.name);
+                        let names = names
+                            .iter()
+                            .map(|name| string(name, unicode))
+                            .collect::<Vec<_>>();
+                        decode.push(format!("    case {}:", names.join(", ")));
+                        decode.push(format!("        self = .{case}"));
+                    }
+                    decode.push("    default:".to_owned());
+                    decode.push(format!("        {}", unknown("tag")));
+                    decode.push("    }".to_owned());
+                    decode.push("    return".to_owned());
+                    decode.push("}".to_owned());
+                }
+                if others.is_empty() {
+                    decode.push("throw DecodingError.dataCorrupted(.init(codingPath: decoder.codingPath, debugDescription: \"expected a variant name\"))".to_owned());
+                } else {
+                    decode.push(
+                        "let container = try decoder.container(keyedBy: CodingKeys.self)"
+                            .to_owned(),
+                    );
+                    for (i, (variant, case, coding)) in others.into_iter().enumerate() {
+                        let r#else = if i == 0 { "" } else { "} else " };
+                        let key = match &keys.deserialize_keys(&variant.name)[..] {
+                            [key] => {
+                                decode.push(format!("{else}if container.contains({key}) {{"));
+                                key.clone()
+                            }
+                            names => {
+                                let first = first_key(names, "container");
+                                decode.push(format!("{else}if let key = {first} {{"));
+                                "key".to_owned()
+                            }
+                        };
+                        let content = Content::Keyed {
+                            container: "container",
+                            key,
+                        };
+                        decode.extend(indent(coding.decode(self, &mut keys, case, content)));
+                    }
+                    decode.push("} else {".to_owned());
+                    decode.push(format!("    {}", unknown("container.allKeys")));
+                    decode.push("}".to_owned());
+                }
+            }
+            TagType::Internal { tag } | TagType::Adjacent { tag, .. } => {
+                decode.push(
+                    "let container = try decoder.container(keyedBy: CodingKeys.self)".to_owned(),
+                );
+                let tag_key = keys.key(tag);
+                decode.push(format!(
+                    "switch try container.decode(String.self, forKey: {tag_key}) {{"
+                ));
+                for (variant, case, coding) in &mut tagged {
+                    let names = deserialize_names(&variant.name);
+                    let names = names
+                        .iter()
+                        .map(|name| string(name, unicode))
+                        .collect::<Vec<_>>();
+                    decode.push(format!("case {}:", names.join(", ")));
+                    let content = match &container.tag {
+                        TagType::Adjacent { content, .. } if !coding.is_unit() => Content::Keyed {
+                            container: "container",
+                            key: keys.key(content),
+                        },
+                        _ => Content::Inline {
+                            container: Some("container"),
+                        },
+                    };
+                    decode.extend(indent(coding.decode(self, &mut keys, case, content)));
+                }
+                decode.push("case let tag:".to_owned());
+                decode.push(format!("    {}", unknown("tag")));
+                decode.push("}".to_owned());
+            }
+            TagType::None => unreachable!("untagged enums have no tagged variants"),
+        }
+        if !untagged.is_empty() {
+            if !decode.is_empty() {
+                let mut tagged = vec!["do {".to_owned()];
+                tagged.extend(indent(decode));
+                tagged.push("    return".to_owned());
+                tagged.push("} catch {}".to_owned());
+                decode = tagged;
+            }
+            for (_, case, coding) in &mut untagged {
+                decode.push("do {".to_owned());
+                if coding.is_unit() {
+                    decode
+                        .push("    if try decoder.singleValueContainer().decodeNil() {".to_owned());
+                    decode.push(format!("        self = .{case}"));
+                    decode.push("        return".to_owned());
+                    decode.push("    }".to_owned());
+                } else {
+                    let content = Content::Inline { container: None };
+                    decode.extend(indent(coding.decode(self, &mut keys, case, content)));
+                    decode.push("    return".to_owned());
+                }
+                decode.push("} catch {}".to_owned());
+            }
+            decode.push(format!(
+                "throw DecodingError.dataCorrupted(.init(codingPath: decoder.codingPath, debugDescription: \"data did not match any variant of untagged enum {}\"))",
+                container.name.deserialize
+            ));
+        }
+        let keys = (!keys.0.is_empty()).then_some(&keys);
+        enumeration + "\n" + &conformance(name, keys, &decode, &encode)
+    }
+
+    /// Properties of the fields of a struct or struct variant.
+    fn properties(
+        &mut self,
+        fields: &[Field],
+        default: bool,
+        keys: &mut Keys,
+        coding: &Coding,
+    ) -> Vec<Property> {
+        let Coding {
+            container,
+            decoder,
+            encoder,
+            prefix,
+        } = coding;
+        let mut properties = Vec::new();
+        for field in fields {
+            if field.skip_serializing && field.skip_deserializing {
+                continue;
+            }
+            let name = property_name(&field.name.serialize);
+            let value = format!("{prefix}{name}");
+            let mut ty = self.field_type(field);
+            let missing = default
+                || field.default
+                || field.skip_serializing
+                || field.skip_deserializing
+                || field.skip_serializing_if.is_some()
+                || matches!(field.shape, Shape::Option(_));
+            let literal = if missing && !field.flatten {
+                let value = field.default_value.as_ref();
+                let literal = value.and_then(|value| literal(self.registry, &field.shape, value));
+                literal.or_else(|| {
+                    (default || field.default)
+                        .then(|| zero(&field.shape))
+                        .flatten()
+                })
+            } else {
+                None
+            };
+            // Fields that are never deserialized need a value, `nil` if
+            // their default can't be written
+            if field.skip_deserializing && literal.is_none() && !ty.ends_with('?') {
+                ty.push('?');
+            }
+            let base = ty.strip_suffix('?').unwrap_or(&ty).to_owned();
+
+            let decode = if field.flatten {
+                format!("try {ty}(from: {decoder})")
+            } else if field.skip_deserializing {
+                literal.clone().unwrap_or("nil".to_owned())
+            } else {
+                let key = keys.deserialize(&field.name, container);
+                match &literal {
+                    Some(literal) if missing && literal != "nil" => format!(
+                        "try {container}.decodeIfPresent({base}.self, forKey: {key}) ?? {literal}"
+                    ),
+                    _ if missing && ty.ends_with('?') => {
+                        format!("try {container}.decodeIfPresent({base}.self, forKey: {key})")
+                    }
+                    _ => format!("try {container}.decode({ty}.self, forKey: {key})"),
+                }
+            };
+
+            let skip_if = field.skip_serializing_if.as_deref().unwrap_or("");
+            let encode = if field.skip_serializing {
+                Vec::new()
+            } else if field.flatten {
+                vec![format!("try {value}.encode(to: {encoder})")]
+            } else {
+                let key = keys.key(&field.name.serialize);
+                let collection = matches!(
+                    field.shape,
+                    Shape::Seq(_) | Shape::Map(_, _) | Shape::Primitive(Primitive::String)
+                );
+                if ty.ends_with('?')
+                    && (skip_if.ends_with("is_none") || !matches!(field.shape, Shape::Option(_)))
+                {
+                    vec![format!(
+                        "try {container}.encodeIfPresent({value}, forKey: {key})"
+                    )]
+                } else if skip_if.ends_with("is_empty") && collection {
+                    vec![
+                        format!("if !{value}.isEmpty {{"),
+                        format!("    try {container}.encode({value}, forKey: {key})"),
+                        "}".to_owned(),
+                    ]
+                } else {
+                    vec![format!("try {container}.encode({value}, forKey: {key})")]
+                }
+            };
+            properties.push(Property {
+                name,
+                ty,
+                doc: field.doc.clone(),
+                decode,
+                encode,
+            });
+        }
+        properties
+    }
+
+    fn field_type(&mut self, field: &Field) -> String {
+        if field.serialize_with.is_some() || field.deserialize_with.is_some() {
+            self.json_value()
+        } else {
+            self.type_expr(&field.shape)
+        }
+    }
+
+    fn json_value(&mut self) -> String {
+        self.json_value = true;
+        "JSONValue".to_owned()
+    }
+
+    fn type_expr(&mut self, shape: &Shape) -> String {
+        match shape {
+            Shape::Param(i) => format!("X{i}"),
+            Shape::Primitive(Primitive::I128 | Primitive::U128 | Primitive::Unit) => {
+                self.json_value()
+            }
+            Shape::Primitive(primitive) => primitive_type(*primitive).to_owned(),
+            Shape::Option(shape) => {
+                let ty = self.type_expr(shape);
+                // Nested options are both `null` once serialized
+                if ty.ends_with('?') { ty } else { ty + "?" }
+            }
+            Shape::Seq(shape) | Shape::Array(shape, _) => format!("[{}]", self.type_expr(shape)),
+            Shape::Tuple(_) => format!("[{}]", self.json_value()),
+            Shape::Map(key, value) => format!("[{}: {}]", key_type(key), self.type_expr(value)),
+            Shape::Type(ty) => {
+                let name = self.name(ty.id);
+                if ty.args.is_empty() {
+                    name
+                } else {
+                    let args = ty
+                        .args
+                        .iter()
+                        .map(|arg| self.type_expr(arg))
+                        .collect::<Vec<_>>();
+                    format!("{name}<{}>", args.join(", "))
+                }
+            }
+        }
+    }
+}
+
+/// Associated values of a case and their coding.
+enum VariantCoding {
+    Unit,
+    Newtype(String),
+    Tuple(Vec<String>),
+    Struct(Vec<Field>),
+}
+
+impl VariantCoding {
+    fn new(generator: &mut Generator<'_>, variant: &Variant, keys: &mut Keys) -> Self {
+        if variant.serialize_with.is_some() || variant.deserialize_with.is_some() {
+            return VariantCoding::Newtype(generator.json_value());
+        }
+        match &variant.body {
+            Body::Unit => VariantCoding::Unit,
+            Body::Tuple(fields) if fields.len() == 1 => {
+                VariantCoding::Newtype(generator.field_type(&fields[0]))
+            }
+            Body::Tuple(fields) => VariantCoding::Tuple(
+                fields
+                    .iter()
+                    .map(|field| generator.field_type(field))
+                    .collect(),
+            ),
+            Body::Struct(fields) => {
+                // Declares the keys in the order of the fields
+                for field in fields {
+                    if !(field.flatten || field.skip_serializing && field.skip_deserializing) {
+                        keys.key(&field.name.serialize);
+                    }
+                }
+                VariantCoding::Struct(fields.clone())
+            }
+            Body::Enum(_) => unreachable!("variants have no variants"),
+        }
+    }
+
+    fn is_unit(&self) -> bool {
+        matches!(self, VariantCoding::Unit)
+    }
+
+    /// Properties of a struct variant, with the names of their containers.
+    fn properties(
+        &self,
+        generator: &mut Generator<'_>,
+        keys: &mut Keys,
+        coding: &Coding,
+    ) -> Vec<Property> {
+        match self {
+            VariantCoding::Struct(fields) => generator.properties(fields, false, keys, coding),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Associated values in the declaration of the case.
+    fn declaration(&self, generator: &mut Generator<'_>) -> String {
+        match self {
+            VariantCoding::Unit => String::new(),
+            VariantCoding::Newtype(ty) => format!("({ty})"),
+            VariantCoding::Tuple(types) => format!("({})", types.join(", ")),
+            VariantCoding::Struct(fields) => {
+                let properties =
+                    generator.properties(fields, false, &mut Keys::default(), &STRUCT_CODING);
+                if properties.is_empty() {
+                    return String::new();
+                }
+                let values = properties
+                    .iter()
+                    .map(|property| format!("{}: {}", property.name, property.ty))
+                    .collect::<Vec<_>>();
+                format!("({})", values.join(", "))
+            }
+        }
+    }
+
+    /// Pattern binding the associated values.
+    fn pattern(&self, case: &str) -> String {
+        let names = self.names();
+        if names.is_empty() {
+            format!(".{case}")
+        } else {
+            format!("let .{case}({})", names.join(", "))
+        }
+    }
+
+    fn names(&self) -> Vec<String> {
+        match self {
+            VariantCoding::Unit => Vec::new(),
+            VariantCoding::Newtype(_) => vec!["value".to_owned()],
+            VariantCoding::Tuple(types) => (0..types.len()).map(|i| format!("field{i}")).collect(),
+            VariantCoding::Struct(fields) => fields
+                .iter()
+                .filter(|field| !(field.skip_serializing && field.skip_deserializing))
+                .map(|field| property_name(&field.name.serialize))
+                .collect(),
+        }
+    }
+
+    /// Statements encoding the associated values bound by the pattern.
+    fn encode(
+        &self,
+        generator: &mut Generator<'_>,
+        keys: &mut Keys,
+        content: Content,
+    ) -> Vec<String> {
+        let mut lines = Vec::new();
+        match (self, content) {
+            (VariantCoding::Unit, Content::Inline { container: None }) => {
+                lines.push("var container = encoder.singleValueContainer()".to_owned());
+                lines.push("try container.encodeNil()".to_owned());
+            }
+            (VariantCoding::Unit, _) => {}
+            (VariantCoding::Newtype(_), Content::Keyed { container, key }) => {
+                lines.push(format!("try {container}.encode(value, forKey: {key})"));
+            }
+            (VariantCoding::Newtype(_), Content::Inline { .. }) => {
+                lines.push("try value.encode(to: encoder)".to_owned());
+            }
+            (VariantCoding::Tuple(types), content) => {
+                lines.push(match content {
+                    Content::Keyed { container, key } => {
+                        format!("var values = {container}.nestedUnkeyedContainer(forKey: {key})")
+                    }
+                    Content::Inline { .. } => "var values = encoder.unkeyedContainer()".to_owned(),
+                });
+                for i in 0..types.len() {
+                    lines.push(format!("try values.encode(field{i})"));
+                }
+            }
+            (VariantCoding::Struct(_), content) => {
+                let (container, encoder) = match content {
+                    Content::Keyed { container, key } => {
+                        lines.push(format!(
+                            "let contentEncoder = {container}.superEncoder(forKey: {key})"
+                        ));
+                        ("fields", "contentEncoder")
+                    }
+                    Content::Inline {
+                        container: Some(container),
+                    } => (container, "encoder"),
+                    Content::Inline { container: None } => ("fields", "encoder"),
+                };
+                let coding = Coding {
+                    container,
+                    decoder: "decoder",
+                    encoder,
+                    prefix: "",
+                };
+                let properties = self.properties(generator, keys, &coding);
+                let encode = properties
+                    .iter()
+                    .flat_map(|property| property.encode.iter().cloned());
+                let encode = encode.collect::<Vec<_>>();
+                if container == "fields" && encode.iter().any(|line| line.contains("fields")) {
+                    lines.push(format!(
+                        "var fields = {encoder}.container(keyedBy: CodingKeys.self)"
+                    ));
+                }
+                lines.extend(encode);
+            }
+        }
+        lines
+    }
+
+    /// Statements decoding the associated values and assigning the case to
+    /// `self`.
+    fn decode(
+        &self,
+        generator: &mut Generator<'_>,
+        keys: &mut Keys,
+        case: &str,
+        content: Content,
+    ) -> Vec<String> {
+        let mut lines = Vec::new();
+        let values = match (self, content) {
+            (VariantCoding::Unit, _) => Vec::new(),
+            (VariantCoding::Newtype(ty), Content::Keyed { container, key }) => {
+                vec![format!("try {container}.decode({ty}.self, forKey: {key})")]
+            }
+            (VariantCoding::Newtype(ty), Content::Inline { .. }) => {
+                vec![format!("try {ty}(from: decoder)")]
+            }
+            (VariantCoding::Tuple(types), content) => {
+                lines.push(match content {
+                    Content::Keyed { container, key } => format!(
+                        "var values = try {container}.nestedUnkeyedContainer(forKey: {key})"
+                    ),
+                    Content::Inline { .. } => {
+                        "var values = try decoder.unkeyedContainer()".to_owned()
+                    }
+                });
+                types
+                    .iter()
+                    .map(|ty| format!("try values.decode({ty}.self)"))
+                    .collect()
+            }
+            (VariantCoding::Struct(_), content) => {
+                let (container, decoder) = match content {
+                    Content::Keyed { container, key } => {
+                        lines.push(format!(
+                            "let contentDecoder = try {container}.superDecoder(forKey: {key})"
+                        ));
+                        ("fields", "contentDecoder")
+                    }
+                    Content::Inline {
+                        container: Some(container),
+                    } => (container, "decoder"),
+                    Content::Inline { container: None } => ("fields", "decoder"),
+                };
+                let coding = Coding {
+                    container,
+                    decoder,
+                    encoder: "encoder",
+                    prefix: "",
+                };
+                let properties = self.properties(generator, keys, &coding);
+                if container == "fields"
+                    && properties
+                        .iter()
+                        .any(|property| property.decode.contains("fields"))
+                {
+                    lines.push(format!(
+                        "let fields = try {decoder}.container(keyedBy: CodingKeys.self)"
+                    ));
+                }
+                properties
+                    .iter()
+                    .map(|property| format!("{}: {}", property.name, property.decode))
+                    .collect()
+            }
+        };
+        if values.is_empty() {
+            lines.push(format!("self = .{case}"));
+        } else {
+            lines.push(format!("self = .{case}({})", values.join(", ")));
+        }
+        lines
+    }
+}
+
+/// Expression of the first of `keys` present in `container`, if any.
+fn first_key(keys: &[String], container: &str) -> String {
+    let (first, rest) = keys.split_first().expect("a name has a deserialize name");
+    let keys = std::iter::once(format!("CodingKeys{first}")).chain(rest.iter().cloned());
+    format!(
+        "[{}].first(where: {container}.contains)",
+        keys.collect::<Vec<_>>().join(", ")
+    )
+}
+
+/// Extension conforming a type to `Codable`.
+fn conformance(name: &str, keys: Option<&Keys>, decode: &[String], encode: &[String]) -> String {
+    let mut extension = format!("extension {name}: Codable {{\n");
+    if let Some(keys) = keys {
+        extension += &keys.declaration();
+        extension.push('\n');
+    }
+    extension += "    init(from decoder: Decoder) throws {\n";
+    for line in decode {
+        extension += &format!("        {line}\n");
+    }
+    extension += "    }\n\n    func encode(to encoder: Encoder) throws {\n";
+    for line in encode {
+        extension += &format!("        {line}\n");
+    }
+    extension + "    }\n}\n"
+}
+
+fn indent(lines: Vec<String>) -> Vec<String> {
+    lines
+        .into_iter()
+        .map(|line| format!("    {line}"))
+        .collect()
+}
+
+/// Whether a shape contains the type with id `id` other than in a
+/// collection.
+fn refers(shape: &Shape, id: u64) -> bool {
+    match shape {
+        Shape::Type(ty) => ty.id == id,
+        Shape::Option(shape) => refers(shape, id),
+        _ => false,
+    }
+}
+
+fn primitive_type(primitive: Primitive) -> &'static str {
+    match primitive {
+        Primitive::Bool => "Bool",
+        Primitive::I8 => "Int8",
+        Primitive::I16 => "Int16",
+        Primitive::I32 => "Int32",
+        Primitive::I64 => "Int64",
+        Primitive::U8 => "UInt8",
+        Primitive::U16 => "UInt16",
+        Primitive::U32 => "UInt32",
+        Primitive::U64 => "UInt64",
+        Primitive::F32 => "Float",
+        Primitive::F64 => "Double",
+        Primitive::Char | Primitive::String => "String",
+        Primitive::I128 | Primitive::U128 | Primitive::Unit => "JSONValue",
+    }
+}
+
+/// Swift literal of a default value of a field of shape `shape`.
+fn literal(registry: &Registry, shape: &Shape, value: &Value) -> Option<String> {
+    match (shape, value) {
+        (Shape::Option(_), Value::Null) => Some("nil".to_owned()),
+        (Shape::Option(shape), value) => literal(registry, shape, value),
+        (Shape::Primitive(Primitive::Bool), Value::Bool(value)) => Some(value.to_string()),
+        (Shape::Primitive(Primitive::I128 | Primitive::U128), _) => None,
+        (Shape::Primitive(_), Value::Integer(value)) => Some(value.to_string()),
+        (Shape::Primitive(_), Value::Float(value)) if value.is_nan() => Some(".nan".to_owned()),
+        (Shape::Primitive(_), Value::Float(value)) if value.is_infinite() => Some(
+            if *value > 0.0 {
+                ".infinity"
+            } else {
+                "-.infinity"
+            }
+            .to_owned(),
+        ),
+        (Shape::Primitive(_), Value::Float(value)) => Some(format!("{value:?}")),
+        (Shape::Primitive(Primitive::Char | Primitive::String), Value::String(value)) => {
+            Some(string(value, unicode))
+        }
+        (Shape::Seq(shape) | Shape::Array(shape, _), Value::Seq(values)) => {
+            let items = values.iter().map(|value| literal(registry, shape, value));
+            let items = items.collect::<Option<Vec<_>>>()?;
+            Some(format!("[{}]", items.join(", ")))
+        }
+        (Shape::Map(_, _), Value::Map(entries)) if entries.is_empty() => Some("[:]".to_owned()),
+        (Shape::Map(key, value_shape), Value::Map(entries)) => {
+            let entries = entries.iter().map(|(key_value, value)| {
+                Some(format!(
+                    "{}: {}",
+                    key_literal(key, key_value)?,
+                    literal(registry, value_shape, value)?
+                ))
+            });
+            let entries = entries.collect::<Option<Vec<_>>>()?;
+            Some(format!("[{}]", entries.join(", ")))
+        }
+        // Cases of `String` enums
+        (Shape::Type(ty), Value::String(value)) => {
+            let def = registry.definition_of(ty);
+            let Body::Enum(variants) = &def.body else {
+                return None;
+            };
+            let variant = variants
+                .iter()
+                .find(|variant| variant.name.serialize == *value)?;
+            unit_only(&def.container, variants.iter())
+                .then(|| format!(".{}", case_name(&variant.name.serialize)))
+        }
+        _ => None,
+    }
+}
+
+/// Type of the keys of a dictionary: `Codable` only encodes dictionaries
+/// keyed by `String` or `Int` as objects, as `serde` does with maps.
+fn key_type(shape: &Shape) -> &'static str {
+    match shape {
+        Shape::Primitive(
+            Primitive::I8
+            | Primitive::I16
+            | Primitive::I32
+            | Primitive::I64
+            | Primitive::U8
+            | Primitive::U16
+            | Primitive::U32,
+        ) => "Int",
+        _ => "String",
+    }
+}
+
+/// Swift literal of a key of a dictionary keyed by [`key_type`].
+fn key_literal(shape: &Shape, value: &Value) -> Option<String> {
+    match (key_type(shape), value) {
+        ("Int", Value::Integer(value)) => Some(value.to_string()),
+        ("String", Value::String(value)) => Some(string(value, unicode)),
+        ("String", Value::Integer(value)) => Some(format!("\"{value}\"")),
+        ("String", Value::Bool(value)) => Some(format!("\"{value}\"")),
+        _ => None,
+    }
+}
+
+/// Literal of `Default::default()` for primitives and collections.
+fn zero(shape: &Shape) -> Option<String> {
+    match shape {
+        Shape::Primitive(Primitive::Bool) => Some("false".to_owned()),
+        Shape::Primitive(Primitive::Char) => Some("\"\\0\"".to_owned()),
+        Shape::Primitive(Primitive::String) => Some("\"\"".to_owned()),
+        Shape::Primitive(Primitive::I128 | Primitive::U128 | Primitive::Unit) => None,
+        Shape::Primitive(_) => Some("0".to_owned()),
+        Shape::Seq(_) => Some("[]".to_owned()),
+        Shape::Map(_, _) => Some("[:]".to_owned()),
+        _ => None,
+    }
+}
+
+/// Deserialize name followed by the aliases that differ from it.
+fn deserialize_names(name: &Name) -> Vec<&str> {
+    let mut names = vec![name.deserialize.as_str()];
+    for alias in &name.deserialize_aliases {
+        if !names.contains(&alias.as_str()) {
+            names.push(alias);
+        }
+    }
+    names
+}
+
+fn params(len: usize) -> String {
+    if len == 0 {
+        return String::new();
+    }
+    let params = (0..len)
+        .map(|i| format!("X{i}: Codable"))
+        .collect::<Vec<_>>();
+    format!("<{}>", params.join(", "))
+}
+
+const KEYWORDS: &[&str] = &[
+    "Any",
+    "Self",
+    "Type",
+    "as",
+    "associatedtype",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "continue",
+    "default",
+    "defer",
+    "deinit",
+    "do",
+    "else",
+    "enum",
+    "extension",
+    "fallthrough",
+    "false",
+    "fileprivate",
+    "for",
+    "func",
+    "guard",
+    "if",
+    "import",
+    "in",
+    "init",
+    "inout",
+    "internal",
+    "is",
+    "let",
+    "nil",
+    "operator",
+    "private",
+    "protocol",
+    "public",
+    "repeat",
+    "rethrows",
+    "return",
+    "self",
+    "static",
+    "struct",
+    "subscript",
+    "super",
+    "switch",
+    "throw",
+    "throws",
+    "true",
+    "try",
+    "typealias",
+    "var",
+    "where",
+    "while",
+];
+
+/// Identifier in backticks if it is a keyword.
+fn keyword(ident: String) -> String {
+    if KEYWORDS.contains(&ident.as_str()) {
+        format!("`{ident}`")
+    } else {
+        ident
+    }
+}
+
+/// Name in camel case, starting with a lowercase letter.
+fn camel_case(name: &str) -> String {
+    let mut camel = String::new();
+    let mut upper = false;
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            upper = !camel.is_empty();
+        } else if upper {
+            camel.push(c.to_ascii_uppercase());
+            upper = false;
+        } else if camel.is_empty() {
+            camel.push(c.to_ascii_lowercase());
+        } else {
+            camel.push(c);
+        }
+    }
+    ident(&camel)
+}
+
+/// Name of a property, fields of tuples are named by their positions.
+fn property_name(name: &str) -> String {
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        return format!("field{name}");
+    }
+    keyword(camel_case(name))
+}
+
+/// `\u` escape of a control character in a string literal.
+fn unicode(c: char) -> String {
+    format!("\\u{{{:x}}}", c as u32)
+}
+
+/// Name of an enum or `CodingKeys` case.
+fn case_name(name: &str) -> String {
+    keyword(camel_case(name))
+}
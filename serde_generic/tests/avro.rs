@@ -0,0 +1,198 @@
+use serde_generic::SerdeGeneric;
+use serde_generic::avro::{Error, schema, schema_for};
+use serde_generic::reflect::{Registry, Shape};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+/// A transfer between two accounts.
+#[derive(SerdeGeneric)]
+struct Transfer {
+    #[serde(rename = "transfer-id")]
+    id: u64,
+    amount: i128,
+    #[serde(default = "default_fee")]
+    fee: u128,
+    /// Shown to the payee.
+    #[serde(default = "default_memo", alias = "reference")]
+    memo: Option<String>,
+    #[serde(default)]
+    note: Option<Option<String>>,
+    currency: Currency,
+    #[serde(serialize_with = "as_hex")]
+    digest: [u8; 4],
+    fees: BTreeMap<Currency, u32>,
+    rates: BTreeMap<char, f64>,
+}
+
+fn default_fee() -> u128 {
+    250
+}
+
+fn default_memo() -> Option<String> {
+    Some("n/a".to_owned())
+}
+
+fn as_hex<S: serde::Serializer>(digest: &[u8; 4], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u32(u32::from_be_bytes(*digest))
+}
+
+#[derive(SerdeGeneric, PartialEq, Eq, PartialOrd, Ord)]
+enum Currency {
+    #[serde(rename = "EUR")]
+    Euro,
+    #[serde(rename = "USD")]
+    Dollar,
+    #[serde(other, rename = "?")]
+    Other,
+}
+
+#[test]
+fn records() {
+    assert_eq!(
+        schema_for::<Transfer, _>(Some("com.bank")).unwrap(),
+        json!({
+            "type": "record",
+            "name": "Transfer",
+            "namespace": "com.bank",
+            "doc": "A transfer between two accounts.",
+            "fields": [
+                {"name": "transfer_id", "type": "long"},
+                {"name": "amount", "type": "string"},
+                {"name": "fee", "type": "string", "default": "250"},
+                {
+                    "name": "memo",
+                    "type": ["string", "null"],
+                    "doc": "Shown to the payee.",
+                    "default": "n/a",
+                    "aliases": ["reference"],
+                },
+                {"name": "note", "type": ["null", "string"], "default": null},
+                {
+                    "name": "currency",
+                    "type": {
+                        "type": "enum",
+                        "name": "Currency",
+                        "namespace": "com.bank",
+                        "symbols": ["EUR", "USD", "_"],
+                        "default": "_",
+                    },
+                },
+                {"name": "digest", "type": "bytes"},
+                {
+                    "name": "fees",
+                    "type": {"type": "array", "items": {
+                        "type": "record",
+                        "name": "MapCurrencyU32Entry",
+                        "namespace": "com.bank",
+                        "fields": [
+                            {"name": "key", "type": "Currency"},
+                            {"name": "value", "type": "long"},
+                        ],
+                    }},
+                },
+                {"name": "rates", "type": {"type": "map", "values": "double"}},
+            ],
+        })
+    );
+}
+
+#[derive(SerdeGeneric)]
+struct Reason {
+    code: u16,
+}
+
+#[derive(SerdeGeneric)]
+#[serde(tag = "status")]
+enum State {
+    Pending,
+    /// Settled on the ledger.
+    Settled {
+        at: u64,
+    },
+    Failed(Reason),
+}
+
+#[derive(SerdeGeneric)]
+struct Account(String);
+
+#[derive(SerdeGeneric)]
+struct Ledger {
+    account: Account,
+    state: Option<State>,
+    history: Vec<State>,
+    balances: (i32, f32),
+    closed: (),
+}
+
+#[test]
+fn unions() {
+    assert_eq!(
+        schema_for::<Ledger, _>(None).unwrap(),
+        json!({
+            "type": "record",
+            "name": "Ledger",
+            "fields": [
+                {"name": "account", "type": "string"},
+                {"name": "state", "type": [
+                    "null",
+                    {"type": "record", "name": "StatePending", "fields": []},
+                    {
+                        "type": "record",
+                        "name": "StateSettled",
+                        "doc": "Settled on the ledger.",
+                        "fields": [{"name": "at", "type": "long"}],
+                    },
+                    {"type": "record", "name": "StateFailed", "fields": [{
+                        "name": "field_0",
+                        "type": {
+                            "type": "record",
+                            "name": "Reason",
+                            "fields": [{"name": "code", "type": "int"}],
+                        },
+                    }]},
+                ]},
+                {
+                    "name": "history",
+                    "type": {
+                        "type": "array",
+                        "items": ["StatePending", "StateSettled", "StateFailed"],
+                    },
+                },
+                {"name": "balances", "type": {
+                    "type": "record",
+                    "name": "TupleI32F32",
+                    "fields": [
+                        {"name": "field_0", "type": "int"},
+                        {"name": "field_1", "type": "float"},
+                    ],
+                }},
+                {"name": "closed", "type": "null"},
+            ],
+        })
+    );
+}
+
+#[derive(SerdeGeneric)]
+#[allow(dead_code)]
+struct Boxed<X> {
+    value: X,
+}
+
+#[derive(SerdeGeneric)]
+#[allow(dead_code)]
+struct Both {
+    number: Boxed<u8>,
+    text: Boxed<String>,
+}
+
+#[test]
+fn errors() {
+    assert_eq!(
+        schema_for::<Both, _>(None),
+        Err(Error::SameName("Boxed".to_owned()))
+    );
+    assert_eq!(
+        schema(&Registry::new(), &Shape::Param(0), None),
+        Err(Error::Generic)
+    );
+}
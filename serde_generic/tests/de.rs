@@ -0,0 +1,613 @@
+// checks that `de::deserialize` agrees with `serde_derive`
+
+use serde::{Deserialize, Serialize};
+use serde_generic::SerdeGeneric;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+fn check_json<T>(input: serde_json::Value)
+where
+    T: SerdeGeneric + for<'de> Deserialize<'de> + PartialEq + Debug,
+    T::Repr: for<'de> serde_generic::de::DeserializeRepr<'de, T>,
+{
+    let expected = T::deserialize(&input).map_err(|err| err.to_string());
+    let actual = serde_generic::de::deserialize::<T, _>(&input).map_err(|err| err.to_string());
+    assert_eq!(actual, expected, "input: {input}");
+}
+
+fn check<T>(value: T)
+where
+    T: SerdeGeneric + Serialize + for<'de> Deserialize<'de> + PartialEq + Debug,
+    T::Repr: for<'de> serde_generic::de::DeserializeRepr<'de, T>,
+{
+    let input = serde_json::to_value(&value).unwrap();
+    check_json::<T>(input);
+}
+
+fn seven() -> u8 {
+    7
+}
+
+#[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Named<X> {
+    first_field: u8,
+    #[serde(rename = "second", alias = "deuxieme")]
+    second_field: X,
+    #[serde(skip)]
+    skipped: bool,
+    #[serde(default = "seven")]
+    with_default: u8,
+    maybe: Option<String>,
+}
+
+#[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct Defaults {
+    a: u8,
+    b: String,
+    #[serde(default = "seven")]
+    c: u8,
+}
+
+#[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug)]
+struct Flat {
+    id: u32,
+    #[serde(flatten)]
+    inner: Named<bool>,
+    #[serde(flatten)]
+    extra: BTreeMap<String, u8>,
+}
+
+#[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug)]
+#[serde(transparent)]
+struct Transparent {
+    inner: Vec<u8>,
+    #[serde(skip)]
+    skipped: u8,
+}
+
+#[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug)]
+struct Newtype(String);
+
+#[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug)]
+struct Tuple(u8, #[serde(skip)] bool, String);
+
+#[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug)]
+struct Unit;
+
+mod as_string {
+    use serde::Deserialize;
+
+    pub fn serialize<S: serde::Serializer>(value: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+fn halved<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    u8::deserialize(deserializer).map(|value| value / 2)
+}
+
+fn some_halved<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<u8>, D::Error> {
+    halved(deserializer).map(Some)
+}
+
+#[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug)]
+struct With {
+    #[serde(with = "as_string")]
+    text: u32,
+    #[serde(deserialize_with = "halved")]
+    number: u8,
+    #[serde(deserialize_with = "some_halved")]
+    maybe: Option<u8>,
+}
+
+#[test]
+fn structs() {
+    check(Named {
+        first_field: 1,
+        second_field: "two".to_owned(),
+        skipped: false,
+        with_default: 3,
+        maybe: Some("x".to_owned()),
+    });
+    check_json::<Named<u8>>(json!({"firstField": 1, "deuxieme": 2}));
+    check_json::<Named<u8>>(json!({"firstField": 1, "second": 2, "unknown": 3}));
+    check_json::<Named<u8>>(json!({"firstField": 1}));
+    check_json::<Named<u8>>(json!({"firstField": 1, "second": 2, "second": 2}));
+    check_json::<Named<u8>>(json!({"firstField": "1", "second": 2}));
+    check_json::<Named<u8>>(json!([1, 2, 3, null]));
+    check_json::<Named<u8>>(json!([1, 2]));
+    check_json::<Named<u8>>(json!([1]));
+
+    check_json::<Defaults>(json!({}));
+    check_json::<Defaults>(json!({"b": "x"}));
+    check_json::<Defaults>(json!({"a": 1, "d": 2}));
+    check_json::<Defaults>(json!([1]));
+
+    check(Flat {
+        id: 7,
+        inner: Named {
+            first_field: 1,
+            second_field: false,
+            skipped: false,
+            with_default: 3,
+            maybe: None,
+        },
+        extra: [("k".to_owned(), 3)].into_iter().collect(),
+    });
+    check(Transparent {
+        inner: vec![1, 2],
+        skipped: 0,
+    });
+    check(Newtype("new".to_owned()));
+    check(Tuple(1, false, "three".to_owned()));
+    check_json::<Tuple>(json!([1]));
+    check(Unit);
+    check_json::<Unit>(json!({}));
+    check_json::<With>(json!({"text": "42", "number": 6, "maybe": 4}));
+    check_json::<With>(json!(["42", 6, 4]));
+    check_json::<With>(json!({"text": 42, "number": 6, "maybe": 4}));
+    check_json::<With>(json!({"text": "42", "number": 6}));
+}
+
+macro_rules! enum_with_tagging {
+    ($name:ident, #[$($attr:tt)*]) => {
+        #[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug)]
+        #[$($attr)*]
+        #[serde(rename_all = "snake_case")]
+        enum $name<X> {
+            Unit,
+            Newtype(Named<X>),
+            Struct {
+                a: u8,
+                #[serde(default)]
+                b: Vec<X>,
+            },
+            #[serde(rename = "renamed", alias = "alias")]
+            Map(BTreeMap<String, X>),
+            #[serde(untagged)]
+            Fallback(u64),
+        }
+
+        impl $name<u8> {
+            fn all() -> Vec<Self> {
+                vec![
+                    Self::Unit,
+                    Self::Newtype(Named {
+                        first_field: 1,
+                        second_field: 2,
+                        skipped: false,
+                        with_default: 3,
+                        maybe: None,
+                    }),
+                    Self::Struct { a: 1, b: vec![] },
+                    Self::Struct { a: 1, b: vec![2] },
+                    Self::Map([("k".to_owned(), 1)].into_iter().collect()),
+                    Self::Fallback(42),
+                ]
+            }
+        }
+    };
+}
+
+enum_with_tagging!(External, #[serde()]);
+enum_with_tagging!(Internal, #[serde(tag = "t")]);
+enum_with_tagging!(Adjacent, #[serde(tag = "t", content = "c")]);
+enum_with_tagging!(Untagged, #[serde(untagged)]);
+
+#[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug)]
+enum WithTuples {
+    Tuple(u8, String),
+    Struct {
+        #[serde(flatten)]
+        flat: Named<u8>,
+        other: u8,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug)]
+#[serde(tag = "t", content = "c")]
+enum AdjacentTuples {
+    Tuple(u8, String),
+    Unit,
+}
+
+#[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "lowercase", rename_all_fields = "camelCase")]
+enum RenamedFields {
+    FirstVariant {
+        first_field: u8,
+    },
+    #[serde(rename_all = "SCREAMING-KEBAB-CASE")]
+    SecondVariant {
+        second_field: u8,
+        #[serde(rename = "as_is")]
+        third_field: u8,
+    },
+}
+
+#[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug)]
+#[serde(tag = "t", deny_unknown_fields)]
+enum StrictInternal {
+    Unit,
+    Struct { x: u8 },
+}
+
+#[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug)]
+enum Inner {
+    A,
+    B { x: u8 },
+    C(u8, String),
+    D(u8),
+}
+
+impl Inner {
+    fn all() -> Vec<Self> {
+        vec![
+            Inner::A,
+            Inner::B { x: 1 },
+            Inner::C(2, "c".to_owned()),
+            Inner::D(3),
+        ]
+    }
+}
+
+#[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug)]
+struct FlatEnum {
+    name: String,
+    #[serde(flatten)]
+    inner: Inner,
+}
+
+#[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug)]
+#[serde(tag = "t")]
+enum TaggedEnum {
+    Wrapped(Inner),
+}
+
+/// Deserialized with `de::deserialize` where it is flattened.
+#[derive(SerdeGeneric, Serialize, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+enum Generic {
+    #[serde(alias = "first")]
+    A(u8),
+    B {
+        x: u8,
+    },
+}
+
+impl<'de> Deserialize<'de> for Generic {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        serde_generic::de::deserialize(deserializer)
+    }
+}
+
+#[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug)]
+struct FlatGeneric {
+    name: String,
+    #[serde(flatten)]
+    inner: Generic,
+}
+
+#[test]
+fn enums() {
+    External::all().into_iter().for_each(check);
+    Internal::all().into_iter().for_each(check);
+    Adjacent::all().into_iter().for_each(check);
+    Untagged::all().into_iter().for_each(check);
+
+    check_json::<External<u8>>(json!({"alias": {"k": 1}}));
+    check_json::<External<u8>>(json!({"struct": {"a": 1}}));
+    check_json::<External<u8>>(json!("unknown"));
+    check_json::<Internal<u8>>(json!({"t": "alias", "k": 1}));
+    check_json::<Internal<u8>>(json!({"a": 1}));
+    check_json::<Internal<u8>>(json!({"t": "unit", "x": 1}));
+    check_json::<StrictInternal>(json!({"t": "Unit", "x": 1}));
+    check_json::<StrictInternal>(json!({"t": "Struct", "x": 1, "y": 2}));
+    check_json::<Adjacent<u8>>(json!({"t": "unit"}));
+    check_json::<Adjacent<u8>>(json!({"t": "newtype"}));
+    check_json::<Adjacent<u8>>(json!({"t": "struct"}));
+    check_json::<Adjacent<u8>>(json!({"c": 1}));
+    check_json::<Untagged<u8>>(json!("unknown"));
+
+    let flat = Named {
+        first_field: 1,
+        second_field: 2,
+        skipped: false,
+        with_default: 3,
+        maybe: None,
+    };
+    check(WithTuples::Tuple(1, "a".to_owned()));
+    check(WithTuples::Struct { flat, other: 3 });
+    check_json::<WithTuples>(json!("Unknown"));
+    check(AdjacentTuples::Tuple(1, "a".to_owned()));
+    check(AdjacentTuples::Unit);
+    check(RenamedFields::FirstVariant { first_field: 1 });
+    check(RenamedFields::SecondVariant {
+        second_field: 2,
+        third_field: 3,
+    });
+
+    for inner in Inner::all() {
+        check(FlatEnum {
+            name: "n".to_owned(),
+            inner,
+        });
+    }
+    Inner::all()
+        .into_iter()
+        .map(TaggedEnum::Wrapped)
+        .for_each(check);
+    check_json::<FlatEnum>(json!({"name": "n", "x": 1}));
+    check_json::<FlatGeneric>(json!({"name": "n", "first": 1}));
+    check_json::<FlatGeneric>(json!({"name": "n", "b": {"x": 2}}));
+    check_json::<FlatGeneric>(json!({"name": "n", "c": 3}));
+    assert_eq!(
+        serde_json::from_value::<FlatGeneric>(json!({"name": "n", "first": 1})).unwrap(),
+        FlatGeneric {
+            name: "n".to_owned(),
+            inner: Generic::A(1),
+        }
+    );
+}
+
+fn check_bincode<T>(value: T)
+where
+    T: SerdeGeneric + Serialize + PartialEq + Debug,
+    T::Repr: for<'de> serde_generic::de::DeserializeRepr<'de, T>,
+{
+    use bincode::Options;
+    let options = bincode::DefaultOptions::new();
+    let bytes = options.serialize(&value).unwrap();
+    let mut deserializer = bincode::Deserializer::from_slice(&bytes, options);
+    let actual = serde_generic::de::deserialize::<T, _>(&mut deserializer).unwrap();
+    assert_eq!(actual, value);
+}
+
+#[derive(SerdeGeneric, Serialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Compact<X> {
+    first_field: u8,
+    #[serde(alias = "other")]
+    second_field: Vec<X>,
+    inner: Inner,
+}
+
+#[test]
+fn non_self_describing() {
+    // Lengths and positions come from the field and variant lists
+    check_bincode(Compact {
+        first_field: 1,
+        second_field: vec!["a".to_owned()],
+        inner: Inner::B { x: 2 },
+    });
+    Inner::all().into_iter().for_each(check_bincode);
+    check_bincode(Generic::B { x: 3 });
+    check_bincode(RenamedFields::SecondVariant {
+        second_field: 2,
+        third_field: 3,
+    });
+}
+
+fn pair_to_map<S: serde::Serializer>(
+    first: &u8,
+    second: &u8,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    BTreeMap::from([("pair", format!("{first}-{second}"))]).serialize(serializer)
+}
+
+fn pair_from_map<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<(u8, u8), D::Error> {
+    let map = BTreeMap::<String, String>::deserialize(deserializer)?;
+    map.get("pair")
+        .and_then(|pair| pair.split_once('-'))
+        .and_then(|(first, second)| Some((first.parse().ok()?, second.parse().ok()?)))
+        .ok_or_else(|| serde::de::Error::custom("expected a pair"))
+}
+
+macro_rules! variant_with_tagging {
+    ($name:ident, #[$($attr:tt)*]) => {
+        #[derive(SerdeGeneric, Serialize, Deserialize, PartialEq, Debug)]
+        #[$($attr)*]
+        enum $name {
+            #[serde(serialize_with = "pair_to_map", deserialize_with = "pair_from_map")]
+            Pair { first: u8, second: u8 },
+            Plain { a: u8 },
+        }
+    };
+}
+
+variant_with_tagging!(ExternalWith, #[serde()]);
+variant_with_tagging!(InternalWith, #[serde(tag = "t")]);
+variant_with_tagging!(AdjacentWith, #[serde(tag = "t", content = "c")]);
+variant_with_tagging!(UntaggedWith, #[serde(untagged)]);
+
+#[test]
+fn variants_with() {
+    check(ExternalWith::Pair {
+        first: 1,
+        second: 2,
+    });
+    check(ExternalWith::Plain { a: 3 });
+    check_json::<ExternalWith>(json!({"Pair": [1, 2]}));
+    check_json::<ExternalWith>(json!("Pair"));
+    check(InternalWith::Pair {
+        first: 1,
+        second: 2,
+    });
+    check(InternalWith::Plain { a: 3 });
+    check_json::<InternalWith>(json!({"t": "Pair", "pair": "x"}));
+    check(AdjacentWith::Pair {
+        first: 1,
+        second: 2,
+    });
+    check(AdjacentWith::Plain { a: 3 });
+    check_json::<AdjacentWith>(json!({"t": "Pair"}));
+    check_json::<AdjacentWith>(json!({"t": "Plain"}));
+    check_json::<AdjacentWith>(json!({"t": "Pair"}));
+    check_json::<AdjacentWith>(json!({"t": "Plain"}));
+    check(UntaggedWith::Pair {
+        first: 1,
+        second: 2,
+    });
+    check(UntaggedWith::Plain { a: 3 });
+}
+
+#[derive(SerdeGeneric, Deserialize, PartialEq, Debug)]
+struct Borrowed<'a, X> {
+    s: &'a str,
+    x: X,
+}
+
+#[test]
+fn borrowed() {
+    let input = r#"{"s": "borrowed", "x": 1}"#;
+    let mut deserializer = serde_json::Deserializer::from_str(input);
+    let actual: Borrowed<u8> = serde_generic::de::deserialize(&mut deserializer).unwrap();
+    assert_eq!(actual, serde_json::from_str(input).unwrap());
+    assert_eq!(actual.s, "borrowed");
+}
+
+#[derive(SerdeGeneric, Deserialize, PartialEq, Debug)]
+#[serde(from = "u8")]
+struct FromProxy {
+    a: u8,
+}
+
+impl From<u8> for FromProxy {
+    fn from(a: u8) -> Self {
+        FromProxy { a }
+    }
+}
+
+#[derive(SerdeGeneric, Deserialize, PartialEq, Debug)]
+#[serde(try_from = "String")]
+struct TryFromProxy(u8);
+
+impl TryFrom<String> for TryFromProxy {
+    type Error = std::num::ParseIntError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse().map(TryFromProxy)
+    }
+}
+
+#[test]
+fn proxies() {
+    fn check_from<T>(input: serde_json::Value)
+    where
+        T: serde_generic::SerdeContainerFrom + for<'de> Deserialize<'de> + PartialEq + Debug,
+        T::Proxy: for<'de> Deserialize<'de>,
+    {
+        let expected = T::deserialize(&input).map_err(|err| err.to_string());
+        let actual =
+            serde_generic::de::deserialize_from::<T, _>(&input).map_err(|err| err.to_string());
+        assert_eq!(actual, expected, "input: {input}");
+    }
+
+    check_from::<FromProxy>(json!(1));
+    check_from::<FromProxy>(json!({"a": 1}));
+    check_from::<TryFromProxy>(json!("1"));
+    check_from::<TryFromProxy>(json!("x"));
+    assert_eq!(FromProxy::CONTAINER.type_from, Some("u8"));
+    assert_eq!(TryFromProxy::CONTAINER.type_try_from, Some("String"));
+
+    let err = serde_generic::de::deserialize::<FromProxy, _>(json!(1)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "FromProxy is deserialized from u8, use `deserialize_from`"
+    );
+}
+
+mod remote {
+    #[derive(PartialEq, Debug)]
+    pub struct Span {
+        pub start: u32,
+        length: u32,
+    }
+
+    impl Span {
+        pub fn length(&self) -> u32 {
+            self.length
+        }
+    }
+
+    impl From<super::SpanDef> for Span {
+        fn from(def: super::SpanDef) -> Self {
+            Span {
+                start: def.start,
+                length: def.length,
+            }
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    pub struct Point {
+        pub x: u32,
+        pub y: u32,
+    }
+
+    #[derive(PartialEq, Debug)]
+    pub enum Shape {
+        Point,
+        Line(u32, u32),
+        Rect { w: u32, h: u32 },
+    }
+}
+
+#[derive(SerdeGeneric, Deserialize)]
+#[serde(remote = "remote::Span")]
+struct SpanDef {
+    start: u32,
+    #[serde(getter = "remote::Span::length")]
+    length: u32,
+}
+
+#[derive(SerdeGeneric, Deserialize)]
+#[serde(remote = "remote::Point")]
+struct PointDef {
+    x: u32,
+    y: u32,
+}
+
+#[derive(SerdeGeneric, Deserialize)]
+#[serde(remote = "remote::Shape", rename_all = "lowercase")]
+enum ShapeDef {
+    Point,
+    Line(u32, u32),
+    Rect { w: u32, h: u32 },
+}
+
+#[test]
+fn remotes() {
+    use serde_generic::de::deserialize_remote;
+
+    fn check_remote<T>(
+        input: serde_json::Value,
+        derived: fn(serde_json::Value) -> Result<T::Remote, serde_json::Error>,
+    ) where
+        T: serde_generic::SerdeRemote,
+        T::Remote: PartialEq + Debug,
+        T::Repr: for<'de> serde_generic::de::DeserializeRepr<'de, T>,
+    {
+        let expected = derived(input.clone()).map_err(|err| err.to_string());
+        let actual = deserialize_remote::<T, _>(input.clone()).map_err(|err| err.to_string());
+        assert_eq!(actual, expected, "input: {input}");
+    }
+
+    check_remote::<SpanDef>(json!({"start": 1, "length": 2}), SpanDef::deserialize);
+    check_remote::<PointDef>(json!({"x": 1, "y": 2}), PointDef::deserialize);
+    check_remote::<PointDef>(json!({"x": 1}), PointDef::deserialize);
+    check_remote::<ShapeDef>(json!("point"), ShapeDef::deserialize);
+    check_remote::<ShapeDef>(json!({"line": [1, 2]}), ShapeDef::deserialize);
+    check_remote::<ShapeDef>(json!({"rect": {"w": 1, "h": 2}}), ShapeDef::deserialize);
+}
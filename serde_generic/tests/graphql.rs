@@ -0,0 +1,246 @@
+use serde_generic::SerdeGeneric;
+use serde_generic::graphql::{schema, schema_for};
+use serde_generic::reflect::Registry;
+use std::collections::HashMap;
+
+/// A feed of posts.
+#[derive(SerdeGeneric)]
+#[serde(rename_all(serialize = "camelCase"))]
+struct Feed<T> {
+    posts: Vec<Option<T>>,
+    #[serde(default)]
+    total_count: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+    #[serde(skip_deserializing)]
+    /// Position of the feed,
+    /// from 0.
+    position: (u8, i32),
+}
+
+#[derive(SerdeGeneric)]
+#[serde(transparent)]
+struct Id(String);
+
+#[derive(SerdeGeneric)]
+struct Labels {
+    labels: HashMap<String, u8>,
+    bounds: (u8, String),
+}
+
+#[test]
+fn objects() {
+    let mut registry = Registry::new();
+    registry.register::<Feed<Id>, _>();
+    registry.register::<Feed<Labels>, _>();
+    assert_eq!(
+        schema(&registry),
+        r#"scalar JSON
+
+scalar Long
+
+"""A feed of posts."""
+type FeedId {
+  posts: [String]!
+  totalCount: Long!
+  nextCursor: String
+  """
+  Position of the feed,
+  from 0.
+  """
+  position: [Int!]!
+}
+
+"""A feed of posts."""
+input FeedIdInput {
+  posts: [String]!
+  total_count: Long
+  next_cursor: String
+}
+
+type Labels {
+  labels: JSON!
+  bounds: JSON!
+}
+
+input LabelsInput {
+  labels: JSON!
+  bounds: JSON!
+}
+
+"""A feed of posts."""
+type FeedLabels {
+  posts: [Labels]!
+  totalCount: Long!
+  nextCursor: String
+  """
+  Position of the feed,
+  from 0.
+  """
+  position: [Int!]!
+}
+
+"""A feed of posts."""
+input FeedLabelsInput {
+  posts: [LabelsInput]!
+  total_count: Long
+  next_cursor: String
+}
+"#
+    );
+}
+
+/// Order of a feed.
+#[derive(SerdeGeneric)]
+enum Order {
+    Newest,
+    #[serde(rename(deserialize = "old"))]
+    Oldest,
+}
+
+#[derive(SerdeGeneric)]
+enum Theme {
+    Dark,
+    #[serde(skip)]
+    #[allow(dead_code)]
+    Light,
+}
+
+#[derive(SerdeGeneric)]
+struct Link {
+    url: String,
+}
+
+#[derive(SerdeGeneric)]
+#[serde(tag = "type")]
+enum Block {
+    Spacer,
+    /// A photo.
+    Photo {
+        width: f64,
+        theme: Theme,
+    },
+    Sorted(Order),
+    Video {
+        width: u32,
+    },
+    Linked(Link),
+    #[serde(skip_deserializing)]
+    #[allow(dead_code)]
+    Deleted,
+}
+
+#[test]
+fn enums() {
+    assert_eq!(
+        schema_for::<Block, _>(),
+        r#"scalar JSON
+
+scalar Long
+
+enum Theme {
+  Dark
+}
+
+"""Order of a feed."""
+enum Order {
+  Newest
+  Oldest
+}
+
+"""Order of a feed."""
+enum OrderInput {
+  Newest
+  old
+}
+
+type Link {
+  url: String!
+}
+
+input LinkInput {
+  url: String!
+}
+
+union Block = BlockSpacer | BlockPhoto | BlockSorted | BlockVideo | BlockLinked | BlockDeleted
+
+type BlockSpacer {
+  empty: Boolean
+}
+
+"""A photo."""
+type BlockPhoto {
+  width: Float!
+  theme: Theme!
+}
+
+type BlockSorted {
+  value: Order!
+}
+
+type BlockVideo {
+  width: Long!
+}
+
+type BlockLinked {
+  value: Link!
+}
+
+type BlockDeleted {
+  empty: Boolean
+}
+
+input BlockInput {
+  """Name of the variant."""
+  type: String!
+  width: JSON
+  theme: Theme
+  # The fields of `Sorted` are not fixed, so they are left out
+  url: String
+}
+"#
+    );
+}
+
+#[derive(SerdeGeneric)]
+struct Post {
+    title: String,
+    #[serde(flatten)]
+    author: Author,
+    #[serde(flatten)]
+    extra: HashMap<String, String>,
+}
+
+#[derive(SerdeGeneric, Default)]
+#[serde(default)]
+struct Author {
+    #[serde(rename(deserialize = "authorName"))]
+    name: String,
+}
+
+#[test]
+fn flatten() {
+    assert_eq!(
+        schema_for::<Post, _>(),
+        r#"type Author {
+  name: String!
+}
+
+input AuthorInput {
+  authorName: String
+}
+
+type Post {
+  title: String!
+  name: String!
+  # The fields of `extra` are not fixed, so they are left out
+}
+
+input PostInput {
+  title: String!
+  authorName: String
+  # The fields of `extra` are not fixed, so they are left out
+}
+"#
+    );
+}
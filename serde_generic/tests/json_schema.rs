@@ -0,0 +1,214 @@
+use serde::Serialize;
+use serde_generic::SerdeGeneric;
+use serde_generic::json_schema::schema_for;
+use serde_json::json;
+
+/// A point.
+#[derive(SerdeGeneric, Serialize)]
+#[serde(deny_unknown_fields)]
+struct Point {
+    x: i32,
+    #[serde(default, alias = "ordinate")]
+    y: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    #[serde(skip)]
+    cache: Vec<u8>,
+}
+
+#[derive(SerdeGeneric)]
+#[serde(transparent)]
+struct Meters(f64);
+
+#[derive(SerdeGeneric, Serialize)]
+struct Flat<X> {
+    #[serde(flatten)]
+    extra: X,
+    id: u64,
+}
+
+#[test]
+fn structs() {
+    assert_eq!(
+        schema_for::<Flat<Point>, _>(),
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "$ref": "#/$defs/FlatPoint",
+            "$defs": {
+                "Point": {
+                    "type": "object",
+                    "description": "A point.",
+                    "properties": {
+                        "x": {"type": "integer", "format": "int32"},
+                        "y": {"type": "integer", "format": "int32"},
+                        "ordinate": {"type": "integer", "format": "int32"},
+                        "label": {"anyOf": [{"type": "string"}, {"type": "null"}]},
+                    },
+                    "required": ["x"],
+                    "additionalProperties": false,
+                },
+                "FlatPoint": {
+                    "type": "object",
+                    "properties": {"id": {"type": "integer", "format": "uint64", "minimum": 0}},
+                    "required": ["id"],
+                    "unevaluatedProperties": false,
+                    "allOf": [{
+                        "type": "object",
+                        "description": "A point.",
+                        "properties": {
+                            "x": {"type": "integer", "format": "int32"},
+                            "y": {"type": "integer", "format": "int32"},
+                            "ordinate": {"type": "integer", "format": "int32"},
+                            "label": {"anyOf": [{"type": "string"}, {"type": "null"}]},
+                        },
+                        "required": ["x"],
+                    }],
+                },
+            },
+        })
+    );
+    assert_eq!(
+        schema_for::<(Meters, [bool; 2]), _>(),
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "array",
+            "prefixItems": [
+                {"$ref": "#/$defs/Meters"},
+                {"type": "array", "items": {"type": "boolean"}, "minItems": 2, "maxItems": 2},
+            ],
+            "items": false,
+            "minItems": 2,
+            "maxItems": 2,
+            "$defs": {
+                "Meters": {"type": "number", "format": "double"},
+            },
+        })
+    );
+}
+
+#[test]
+fn flattened_instance() {
+    let validator = jsonschema::validator_for(&schema_for::<Flat<Point>, _>()).unwrap();
+    let flat = Flat {
+        extra: Point {
+            x: 1,
+            y: 2,
+            label: None,
+            cache: Vec::new(),
+        },
+        id: 3,
+    };
+    let instance = serde_json::to_value(&flat).unwrap();
+    assert_eq!(instance, json!({"x": 1, "y": 2, "id": 3}));
+    assert!(validator.is_valid(&instance));
+    assert!(validator.is_valid(&json!({"x": 1, "ordinate": 2, "id": 3})));
+    assert!(!validator.is_valid(&json!({"x": 1, "id": 3, "z": 4})));
+}
+
+#[derive(SerdeGeneric)]
+enum External {
+    #[serde(alias = "nothing")]
+    Unit,
+    Newtype(u8),
+    Struct {
+        a: bool,
+    },
+}
+
+#[derive(SerdeGeneric)]
+#[serde(tag = "type")]
+enum Internal {
+    Unit,
+    Struct { a: bool },
+}
+
+#[derive(SerdeGeneric)]
+#[serde(tag = "t", content = "c", deny_unknown_fields)]
+enum Adjacent {
+    Unit,
+    Tuple(u8, char),
+}
+
+#[derive(SerdeGeneric)]
+#[serde(untagged)]
+enum Untagged {
+    Unit,
+    Newtype(String),
+}
+
+#[test]
+fn enums() {
+    let defs = |schema: serde_json::Value| schema["$defs"].clone();
+    assert_eq!(
+        defs(schema_for::<External, _>()),
+        json!({"External": {"oneOf": [
+            {"enum": ["Unit", "nothing"]},
+            {
+                "type": "object",
+                "properties": {"Newtype": {"type": "integer", "format": "uint8", "minimum": 0}},
+                "minProperties": 1,
+                "maxProperties": 1,
+                "additionalProperties": false,
+            },
+            {
+                "type": "object",
+                "properties": {"Struct": {
+                    "type": "object",
+                    "properties": {"a": {"type": "boolean"}},
+                    "required": ["a"],
+                }},
+                "minProperties": 1,
+                "maxProperties": 1,
+                "additionalProperties": false,
+            },
+        ]}})
+    );
+    assert_eq!(
+        defs(schema_for::<Internal, _>()),
+        json!({"Internal": {"oneOf": [
+            {
+                "type": "object",
+                "properties": {"type": {"const": "Unit"}},
+                "required": ["type"],
+            },
+            {
+                "type": "object",
+                "properties": {"type": {"const": "Struct"}, "a": {"type": "boolean"}},
+                "required": ["type", "a"],
+            },
+        ]}})
+    );
+    assert_eq!(
+        defs(schema_for::<Adjacent, _>()),
+        json!({"Adjacent": {"oneOf": [
+            {
+                "type": "object",
+                "properties": {"t": {"const": "Unit"}},
+                "required": ["t"],
+                "additionalProperties": false,
+            },
+            {
+                "type": "object",
+                "properties": {
+                    "t": {"const": "Tuple"},
+                    "c": {
+                        "type": "array",
+                        "prefixItems": [
+                            {"type": "integer", "format": "uint8", "minimum": 0},
+                            {"type": "string", "minLength": 1, "maxLength": 1},
+                        ],
+                        "items": false,
+                        "minItems": 2,
+                        "maxItems": 2,
+                    },
+                },
+                "required": ["t", "c"],
+                "additionalProperties": false,
+            },
+        ]}})
+    );
+    assert_eq!(
+        defs(schema_for::<Untagged, _>()),
+        json!({"Untagged": {"anyOf": [{"type": "null"}, {"type": "string"}]}})
+    );
+}
@@ -0,0 +1,338 @@
+use serde_generic::SerdeGeneric;
+use serde_generic::kotlin::{file, file_for};
+use serde_generic::reflect::Registry;
+use std::collections::BTreeMap;
+
+/// A room of a hotel, priced in $ (see `*/rates`).
+///
+/// Rooms are booked by night.
+#[derive(SerdeGeneric)]
+#[serde(rename_all = "camelCase")]
+struct Room<G> {
+    #[serde(rename = "object", alias = "room_number")]
+    number: u16,
+    #[serde(default = "default_rate")]
+    nightly_rate: u64,
+    #[serde(default = "default_deposit")]
+    deposit: i64,
+    #[serde(default = "default_discount")]
+    discount: f32,
+    #[serde(default = "default_surcharge")]
+    surcharge: Option<f64>,
+    #[serde(default = "default_wing")]
+    wing: char,
+    #[serde(default = "default_motto")]
+    motto: String,
+    /// Amenities, as in `$amenities`.
+    #[serde(default = "default_amenities")]
+    amenities: BTreeMap<String, u8>,
+    #[serde(default)]
+    view: View,
+    // Has no default, `skip_serializing_if` leaves the value of `note`
+    // unknown
+    #[serde(default)]
+    badge: Badge,
+    #[serde(skip_serializing)]
+    cleaned: bool,
+    guests: Vec<G>,
+    serial: u128,
+    nights: (u8, u8),
+    when: (),
+}
+
+fn default_rate() -> u64 {
+    120
+}
+
+fn default_deposit() -> i64 {
+    -50
+}
+
+fn default_discount() -> f32 {
+    0.25
+}
+
+fn default_surcharge() -> Option<f64> {
+    Some(f64::INFINITY)
+}
+
+fn default_wing() -> char {
+    '\''
+}
+
+fn default_motto() -> String {
+    "Rest for $0".to_owned()
+}
+
+fn default_amenities() -> BTreeMap<String, u8> {
+    BTreeMap::from([("towels".to_owned(), 2)])
+}
+
+#[derive(SerdeGeneric)]
+struct View {
+    floor: i8,
+    #[serde(flatten)]
+    facing: Facing,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        View {
+            floor: 3,
+            facing: Facing::default(),
+        }
+    }
+}
+
+#[derive(SerdeGeneric, Default)]
+struct Facing {
+    side: Side,
+    landmark: Option<String>,
+}
+
+#[derive(SerdeGeneric, Default)]
+enum Side {
+    #[default]
+    #[serde(rename = "in")]
+    Inside,
+    Outside,
+}
+
+#[derive(SerdeGeneric, Default)]
+struct Badge {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+}
+
+#[derive(SerdeGeneric)]
+struct Guest {
+    name: String,
+}
+
+#[test]
+fn data_classes() {
+    let mut registry = Registry::new();
+    registry.register::<Room<Guest>, _>();
+    assert_eq!(
+        file(&registry, Some("com.hotel")),
+        r#"@file:OptIn(ExperimentalSerializationApi::class)
+
+package com.hotel
+
+import kotlinx.serialization.ExperimentalSerializationApi
+import kotlinx.serialization.Serializable
+import kotlinx.serialization.Transient
+import kotlinx.serialization.json.JsonArray
+import kotlinx.serialization.json.JsonNames
+import kotlinx.serialization.json.JsonNull
+import kotlinx.serialization.json.JsonPrimitive
+
+@Serializable
+data class Guest(
+    val name: String,
+)
+
+@Serializable
+enum class Side {
+    `in`,
+    Outside,
+}
+
+@Serializable
+data class Facing(
+    val side: Side,
+    val landmark: String? = null,
+)
+
+@Serializable
+data class View(
+    val floor: Byte,
+    val side: Side,
+    val landmark: String? = null,
+)
+
+@Serializable
+data class Badge(
+    val note: String? = null,
+)
+
+/**
+ * A room of a hotel, priced in $ (see `*&#47;rates`).
+ *
+ * Rooms are booked by night.
+ */
+@Serializable
+data class Room<G>(
+    @JsonNames("room_number")
+    val `object`: UShort,
+    val nightlyRate: ULong = 120uL,
+    val deposit: Long = -50L,
+    val discount: Float = 0.25f,
+    val surcharge: Double? = Double.POSITIVE_INFINITY,
+    val wing: Char = '\'',
+    val motto: String = "Rest for \$0",
+    /** Amenities, as in `$amenities`. */
+    val amenities: Map<String, UByte> = mapOf("towels" to 2u),
+    val view: View = View(floor = 3, side = Side.`in`, landmark = null),
+    val badge: Badge,
+    @Transient
+    val cleaned: Boolean? = null,
+    val guests: List<G>,
+    val serial: JsonPrimitive,
+    val nights: JsonArray,
+    val `when`: JsonNull,
+)
+"#
+    );
+}
+
+#[derive(SerdeGeneric)]
+struct Point {
+    x: i64,
+    y: i64,
+}
+
+/// A parcel.
+#[derive(SerdeGeneric)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Parcel<T> {
+    Lost,
+    /// A box.
+    Box {
+        weight: T,
+    },
+    Redirected(Point),
+}
+
+#[derive(SerdeGeneric)]
+#[serde(tag = "t", content = "c")]
+enum Update {
+    Shipped,
+    Note(String),
+    Amend { id: u64, text: String },
+}
+
+#[derive(SerdeGeneric)]
+enum Action {
+    Cancel,
+    Track(u8, u8),
+}
+
+#[derive(SerdeGeneric)]
+struct Shipment {
+    parcels: Vec<Parcel<f64>>,
+    update: Update,
+    action: Action,
+}
+
+#[test]
+fn sealed_classes() {
+    assert_eq!(
+        file_for::<Shipment, _>(None),
+        r#"@file:OptIn(ExperimentalSerializationApi::class)
+
+import kotlinx.serialization.ExperimentalSerializationApi
+import kotlinx.serialization.SerialName
+import kotlinx.serialization.Serializable
+import kotlinx.serialization.json.JsonClassDiscriminator
+import kotlinx.serialization.json.JsonElement
+
+@Serializable
+data class Point(
+    val x: Long,
+    val y: Long,
+)
+
+/** A parcel. */
+@Serializable
+@JsonClassDiscriminator("kind")
+sealed class Parcel<out T> {
+    @Serializable
+    @SerialName("lost")
+    data object Lost : Parcel<Nothing>()
+
+    /** A box. */
+    @Serializable
+    @SerialName("box")
+    data class Box<out T>(
+        val weight: T,
+    ) : Parcel<T>()
+
+    @Serializable
+    @SerialName("redirected")
+    data class Redirected<out T>(
+        val x: Long,
+        val y: Long,
+    ) : Parcel<T>()
+}
+
+@Serializable
+@JsonClassDiscriminator("t")
+sealed class Update {
+    @Serializable
+    @SerialName("Shipped")
+    data object Shipped : Update()
+
+    @Serializable
+    @SerialName("Note")
+    data class Note(
+        val c: String,
+    ) : Update()
+
+    @Serializable
+    data class AmendFields(
+        val id: ULong,
+        val text: String,
+    )
+
+    @Serializable
+    @SerialName("Amend")
+    data class Amend(
+        val c: AmendFields,
+    ) : Update()
+}
+
+typealias Action = JsonElement
+
+@Serializable
+data class Shipment(
+    val parcels: List<Parcel<Double>>,
+    val update: Update,
+    val action: Action,
+)
+"#
+    );
+}
+
+#[derive(SerdeGeneric)]
+#[serde(tag = "state")]
+enum Payment {
+    Pending,
+    Settled,
+}
+
+#[test]
+fn tagged_unit_variants() {
+    assert_eq!(
+        file_for::<Payment, _>(None),
+        r#"@file:OptIn(ExperimentalSerializationApi::class)
+
+import kotlinx.serialization.ExperimentalSerializationApi
+import kotlinx.serialization.SerialName
+import kotlinx.serialization.Serializable
+import kotlinx.serialization.json.JsonClassDiscriminator
+
+@Serializable
+@JsonClassDiscriminator("state")
+sealed class Payment {
+    @Serializable
+    @SerialName("Pending")
+    data object Pending : Payment()
+
+    @Serializable
+    @SerialName("Settled")
+    data object Settled : Payment()
+}
+"#
+    );
+}
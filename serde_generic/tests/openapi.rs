@@ -0,0 +1,154 @@
+use serde_generic::SerdeGeneric;
+use serde_generic::openapi::{Method, OpenApi, Operation};
+use serde_json::json;
+
+#[derive(SerdeGeneric)]
+struct Pet {
+    name: String,
+    kind: Kind,
+}
+
+/// Kind of a pet.
+#[derive(SerdeGeneric)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum Kind {
+    #[serde(alias = "kitty")]
+    Cat {
+        lives: u8,
+    },
+    Dog,
+}
+
+#[derive(SerdeGeneric)]
+#[serde(tag = "t", content = "c")]
+enum Event<X> {
+    Added(X),
+    Removed,
+}
+
+#[derive(SerdeGeneric)]
+struct Error {
+    message: String,
+}
+
+#[test]
+fn document() {
+    let document = OpenApi::new("Pets", "1.0.0")
+        .operation(
+            Operation::get("/pets")
+                .operation_id("listPets")
+                .summary("List the pets")
+                .response::<Vec<Pet>, _>(200, "The pets")
+                .response::<Error, _>(500, "Failure"),
+        )
+        .operation(
+            Operation::new(Method::Put, "/pets")
+                .request::<Pet, _>()
+                .empty_response(204, "Replaced"),
+        )
+        .schema::<Event<Pet>, _>()
+        .to_json();
+
+    assert_eq!(document["openapi"], "3.1.0");
+    assert_eq!(
+        document["info"],
+        json!({"title": "Pets", "version": "1.0.0"})
+    );
+    assert_eq!(
+        document["paths"],
+        json!({"/pets": {
+            "get": {
+                "operationId": "listPets",
+                "summary": "List the pets",
+                "responses": {
+                    "200": {
+                        "description": "The pets",
+                        "content": {"application/json": {"schema": {
+                            "type": "array",
+                            "items": {"$ref": "#/components/schemas/Pet"},
+                        }}},
+                    },
+                    "500": {
+                        "description": "Failure",
+                        "content": {"application/json": {"schema": {
+                            "$ref": "#/components/schemas/Error",
+                        }}},
+                    },
+                },
+            },
+            "put": {
+                "requestBody": {
+                    "required": true,
+                    "content": {"application/json": {"schema": {
+                        "$ref": "#/components/schemas/Pet",
+                    }}},
+                },
+                "responses": {"204": {"description": "Replaced"}},
+            },
+        }})
+    );
+
+    let schemas = &document["components"]["schemas"];
+    assert_eq!(
+        schemas["Pet"],
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "kind": {"$ref": "#/components/schemas/Kind"},
+            },
+            "required": ["name", "kind"],
+        })
+    );
+    assert_eq!(
+        schemas["Kind"],
+        json!({
+            "description": "Kind of a pet.",
+            "oneOf": [
+                {"$ref": "#/components/schemas/Kind.cat"},
+                {"$ref": "#/components/schemas/Kind.dog"},
+            ],
+            "discriminator": {
+                "propertyName": "kind",
+                "mapping": {
+                    "cat": "#/components/schemas/Kind.cat",
+                    "kitty": "#/components/schemas/Kind.cat",
+                    "dog": "#/components/schemas/Kind.dog",
+                },
+            },
+        })
+    );
+    assert_eq!(
+        schemas["Kind.cat"],
+        json!({
+            "type": "object",
+            "properties": {
+                "kind": {"enum": ["cat", "kitty"]},
+                "lives": {"type": "integer", "format": "uint8", "minimum": 0},
+            },
+            "required": ["kind", "lives"],
+        })
+    );
+    assert_eq!(
+        schemas["EventPet.Added"],
+        json!({
+            "type": "object",
+            "properties": {
+                "t": {"const": "Added"},
+                "c": {"$ref": "#/components/schemas/Pet"},
+            },
+            "required": ["t", "c"],
+        })
+    );
+    assert_eq!(
+        schemas["EventPet"]["discriminator"],
+        json!({
+            "propertyName": "t",
+            "mapping": {
+                "Added": "#/components/schemas/EventPet.Added",
+                "Removed": "#/components/schemas/EventPet.Removed",
+            },
+        })
+    );
+    assert!(schemas["Error"].is_object());
+}
@@ -0,0 +1,208 @@
+use serde_generic::SerdeGeneric;
+use serde_generic::protobuf::{file, file_for};
+use serde_generic::reflect::Registry;
+use std::collections::{BTreeMap, HashMap};
+
+/// A batch of records.
+#[derive(SerdeGeneric)]
+#[serde(rename_all = "camelCase")]
+struct Batch<T> {
+    records: Vec<Option<T>>,
+    /// Number of records
+    /// in all batches.
+    total_count: u64,
+    #[serde(skip)]
+    cache: Vec<u8>,
+    #[serde_generic(proto = 10)]
+    next_batch: Option<Id>,
+}
+
+#[derive(SerdeGeneric)]
+#[serde(transparent)]
+struct Id(u32);
+
+#[derive(SerdeGeneric)]
+struct Sample(i32, f64);
+
+#[derive(SerdeGeneric)]
+struct Tables {
+    by_name: HashMap<String, Vec<i8>>,
+    by_sample: BTreeMap<(u8, u8), bool>,
+    unit: (),
+}
+
+#[test]
+fn messages() {
+    let mut registry = Registry::new();
+    registry.register::<Batch<Sample>, _>();
+    registry.register::<Batch<String>, _>();
+    registry.register::<Tables, _>();
+    assert_eq!(
+        file(&registry, Some("test.v1")).unwrap(),
+        "\
+syntax = \"proto3\";
+
+package test.v1;
+
+import \"google/protobuf/empty.proto\";
+
+message Sample {
+  int32 field_0 = 1;
+  double field_1 = 2;
+}
+
+// A batch of records.
+message BatchSample {
+  repeated OptionSample records = 1;
+  // Number of records
+  // in all batches.
+  uint64 total_count = 2;
+  optional uint32 next_batch = 10;
+}
+
+// A batch of records.
+message BatchString {
+  repeated OptionString records = 1;
+  // Number of records
+  // in all batches.
+  uint64 total_count = 2;
+  optional uint32 next_batch = 10;
+}
+
+message Tables {
+  map<string, VecI8> by_name = 1;
+  repeated MapTupleU8U8BoolEntry by_sample = 2;
+  google.protobuf.Empty unit = 3;
+}
+
+message OptionSample {
+  optional Sample value = 1;
+}
+
+message OptionString {
+  optional string value = 1;
+}
+
+message VecI8 {
+  repeated int32 value = 1;
+}
+
+message TupleU8U8 {
+  uint32 field_0 = 1;
+  uint32 field_1 = 2;
+}
+
+message MapTupleU8U8BoolEntry {
+  TupleU8U8 key = 1;
+  bool value = 2;
+}
+"
+    );
+}
+
+/// Heading of a layer.
+#[derive(SerdeGeneric)]
+#[serde(rename_all = "lowercase")]
+enum Heading {
+    #[serde_generic(proto = 1)]
+    North,
+    /// Backwards.
+    #[serde_generic(proto = 3)]
+    South,
+}
+
+#[derive(SerdeGeneric)]
+enum Color {
+    Red,
+    #[serde(skip)]
+    #[allow(dead_code)]
+    Green,
+    Blue,
+}
+
+#[derive(SerdeGeneric)]
+enum Layer<X> {
+    Empty,
+    /// A disk.
+    Disk {
+        radius: X,
+    },
+    Outline(Vec<Sample>),
+    #[serde_generic(proto = 7)]
+    Rotated(Heading, Color),
+}
+
+#[test]
+fn enums() {
+    assert_eq!(
+        file_for::<Layer<f32>, _>(None).unwrap(),
+        "\
+syntax = \"proto3\";
+
+import \"google/protobuf/empty.proto\";
+
+message Sample {
+  int32 field_0 = 1;
+  double field_1 = 2;
+}
+
+// Heading of a layer.
+enum Heading {
+  HEADING_UNSPECIFIED = 0;
+  HEADING_NORTH = 1;
+  // Backwards.
+  HEADING_SOUTH = 3;
+}
+
+enum Color {
+  COLOR_RED = 0;
+  COLOR_BLUE = 2;
+}
+
+message LayerF32 {
+  message Disk {
+    float radius = 1;
+  }
+  message Rotated {
+    Heading field_0 = 1;
+    Color field_1 = 2;
+  }
+  oneof layer_f32 {
+    google.protobuf.Empty empty = 1;
+    // A disk.
+    Disk disk = 2;
+    VecSample outline = 3;
+    Rotated rotated = 7;
+  }
+}
+
+message VecSample {
+  repeated Sample value = 1;
+}
+"
+    );
+}
+
+#[derive(SerdeGeneric)]
+#[allow(dead_code)]
+struct Clash {
+    first: u8,
+    #[serde_generic(proto = 1)]
+    second: u8,
+}
+
+#[derive(SerdeGeneric)]
+#[allow(dead_code)]
+enum Clashing {
+    #[serde_generic(proto = 1)]
+    Zero,
+    One,
+}
+
+#[test]
+fn collisions() {
+    let err = file_for::<Clash, _>(None).unwrap_err();
+    assert_eq!(err.to_string(), "number 1 is used twice in Clash");
+    let err = file_for::<Clashing, _>(None).unwrap_err();
+    assert_eq!(err.to_string(), "number 1 is used twice in Clashing");
+}
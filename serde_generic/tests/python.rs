@@ -0,0 +1,303 @@
+use serde_generic::SerdeGeneric;
+use serde_generic::python::{Style, module, module_for};
+use serde_generic::reflect::Registry;
+use std::collections::BTreeMap;
+
+/// A flight, see "Schedules" for the times.
+///
+/// Prices are in `"""` quotes, with \ escapes.
+#[derive(SerdeGeneric)]
+#[serde(rename_all = "camelCase")]
+struct Flight<T> {
+    #[serde(rename = "from")]
+    origin: String,
+    #[serde(alias = "to")]
+    destination: String,
+    /// Kept in cents.
+    #[serde(default = "default_fares")]
+    fares: BTreeMap<u8, i128>,
+    #[serde(default = "default_direct")]
+    direct: bool,
+    #[serde(default)]
+    delay: Option<Option<f32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gate: Option<char>,
+    #[serde(serialize_with = "as_minutes")]
+    duration: u64,
+    crew: T,
+    #[serde(flatten)]
+    seat: Seat,
+}
+
+fn default_fares() -> BTreeMap<u8, i128> {
+    BTreeMap::from([(1, -4200)])
+}
+
+fn default_direct() -> bool {
+    true
+}
+
+fn as_minutes<S: serde::Serializer>(duration: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration / 60)
+}
+
+#[derive(SerdeGeneric)]
+#[serde(deny_unknown_fields)]
+struct Seat {
+    row: u16,
+    lambda: String,
+}
+
+#[derive(SerdeGeneric)]
+struct Crew(Vec<String>, u8);
+
+#[test]
+fn models() {
+    assert_eq!(
+        module_for::<Flight<Crew>, _>(Style::Pydantic),
+        r#"from __future__ import annotations
+
+from typing import Any, Generic, Optional, TypeVar
+
+from pydantic import AliasChoices, BaseModel, ConfigDict, Field, RootModel
+
+X0 = TypeVar("X0")
+
+
+class Crew(RootModel[tuple[list[str], int]]):
+    pass
+
+
+class Seat(BaseModel):
+    model_config = ConfigDict(populate_by_name=True, extra="forbid")
+
+    row: int
+    lambda_: str = Field(alias="lambda")
+
+
+class Flight(Seat, Generic[X0]):
+    """
+    A flight, see "Schedules" for the times.
+
+    Prices are in `\"""` quotes, with \\ escapes.
+    """
+
+    model_config = ConfigDict(populate_by_name=True)
+
+    from_: str = Field(alias="from")
+    destination: str = Field(validation_alias=AliasChoices("destination", "to"))
+    # Kept in cents.
+    fares: dict[int, int] = {1: -4200}
+    direct: bool = True
+    delay: Optional[Optional[float]] = None
+    gate: Optional[str] = None
+    duration: Any
+    crew: X0
+"#
+    );
+}
+
+/// A loan.
+#[derive(SerdeGeneric)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Loan<T> {
+    Returned,
+    /// A loan to a member.
+    Lent {
+        days: T,
+    },
+    Seated(Seat),
+}
+
+#[derive(SerdeGeneric)]
+enum Request {
+    Cancel,
+    Renew(u8, u8),
+    Reserve { copies: i16 },
+}
+
+#[derive(SerdeGeneric)]
+#[serde(tag = "t", content = "c")]
+enum Notice {
+    Reminder,
+    Note(String),
+}
+
+#[derive(SerdeGeneric)]
+#[serde(untagged)]
+enum Fee {
+    Amount(f64),
+    Waived { name: String },
+}
+
+#[derive(SerdeGeneric)]
+struct Account {
+    loans: Vec<Loan<f32>>,
+    request: Request,
+    notice: Notice,
+    fee: Fee,
+}
+
+#[test]
+fn unions() {
+    assert_eq!(
+        module_for::<Account, _>(Style::Pydantic),
+        r#"from __future__ import annotations
+
+from typing import Annotated, Generic, Literal, TypeVar, Union
+
+from pydantic import BaseModel, ConfigDict, Field, RootModel
+
+X0 = TypeVar("X0")
+
+
+class Seat(BaseModel):
+    model_config = ConfigDict(populate_by_name=True, extra="forbid")
+
+    row: int
+    lambda_: str = Field(alias="lambda")
+
+
+class LoanReturned(BaseModel, Generic[X0]):
+    type: Literal["returned"]
+
+
+class LoanLent(BaseModel, Generic[X0]):
+    """A loan to a member."""
+
+    type: Literal["lent"]
+    days: X0
+
+
+class LoanSeated(Seat, Generic[X0]):
+    type: Literal["seated"]
+
+
+class Loan(RootModel[Annotated[Union[LoanReturned[X0], LoanLent[X0], LoanSeated[X0]], Field(discriminator="type")]], Generic[X0]):
+    """A loan."""
+
+
+class RequestRenew(BaseModel):
+    model_config = ConfigDict(populate_by_name=True)
+
+    renew: tuple[int, int] = Field(alias="Renew")
+
+
+class RequestReserveFields(BaseModel):
+    copies: int
+
+
+class RequestReserve(BaseModel):
+    model_config = ConfigDict(populate_by_name=True)
+
+    reserve: RequestReserveFields = Field(alias="Reserve")
+
+
+class Request(RootModel[Union[Literal["Cancel"], RequestRenew, RequestReserve]]):
+    pass
+
+
+class NoticeReminder(BaseModel):
+    t: Literal["Reminder"]
+
+
+class NoticeNote(BaseModel):
+    t: Literal["Note"]
+    c: str
+
+
+class Notice(RootModel[Annotated[Union[NoticeReminder, NoticeNote], Field(discriminator="t")]]):
+    pass
+
+
+class FeeWaived(BaseModel):
+    name: str
+
+
+class Fee(RootModel[Union[float, FeeWaived]]):
+    pass
+
+
+class Account(BaseModel):
+    loans: list[Loan[float]]
+    request: Request
+    notice: Notice
+    fee: Fee
+"#
+    );
+}
+
+#[test]
+fn typed_dicts() {
+    let mut registry = Registry::new();
+    registry.register::<Flight<Crew>, _>();
+    registry.register::<Flight<u8>, _>();
+    assert_eq!(
+        module(&registry, Style::TypedDict),
+        r#"from __future__ import annotations
+
+from typing import Any, NotRequired, Optional, TypedDict
+
+
+Crew = tuple[list[str], int]
+
+
+Seat = TypedDict("Seat", {
+    "row": int,
+    "lambda": str,
+})
+
+
+_Flight = TypedDict("_Flight", {
+    "from": str,
+    "destination": str,
+    # Kept in cents.
+    "fares": NotRequired[dict[int, int]],
+    "direct": NotRequired[bool],
+    "delay": NotRequired[Optional[Optional[float]]],
+    "gate": NotRequired[Optional[str]],
+    "duration": Any,
+    "crew": Any,
+})
+
+
+# A flight, see "Schedules" for the times.
+#
+# Prices are in `"""` quotes, with \ escapes.
+class Flight(_Flight, Seat):
+    pass
+"#
+    );
+}
+
+#[derive(SerdeGeneric)]
+#[serde(tag = "state")]
+enum Membership {
+    Valid,
+    Lapsed,
+}
+
+#[test]
+fn tagged_unit_variants() {
+    assert_eq!(
+        module_for::<Membership, _>(Style::Pydantic),
+        r#"from __future__ import annotations
+
+from typing import Annotated, Literal, Union
+
+from pydantic import BaseModel, Field, RootModel
+
+
+class MembershipValid(BaseModel):
+    state: Literal["Valid"]
+
+
+class MembershipLapsed(BaseModel):
+    state: Literal["Lapsed"]
+
+
+class Membership(RootModel[Annotated[Union[MembershipValid, MembershipLapsed], Field(discriminator="state")]]):
+    pass
+"#
+    );
+}
@@ -0,0 +1,491 @@
+// the spec generator of `spec.rs`, written as a function over `Shape`
+
+#![allow(clippy::disallowed_names)]
+
+use serde_generic::ConstValue;
+use serde_generic::SerdeGeneric;
+use serde_generic::reflect::{Body, Len, Primitive, Reflect, Registry, Shape, TagType, Value};
+use std::collections::{HashMap, HashSet};
+
+#[derive(SerdeGeneric)]
+struct Foo<X, Y> {
+    #[serde(skip_serializing_if = "String::is_empty", default = "String::new")]
+    foo: String,
+    boo: u8,
+    xoo: X,
+    coo: Coo<Y>,
+}
+
+#[derive(SerdeGeneric)]
+struct Joo<X> {
+    joo: X,
+}
+
+#[derive(SerdeGeneric)]
+struct Coo<X> {
+    coo: X,
+}
+
+#[derive(SerdeGeneric)]
+struct Buf<X, const N: usize> {
+    data: [X; N],
+}
+
+fn schema(registry: &Registry, shape: &Shape, defs: &mut HashSet<String>) -> String {
+    match shape {
+        Shape::Param(i) => format!("X{i}"),
+        Shape::Primitive(Primitive::U8) => "byte".into(),
+        Shape::Primitive(Primitive::String) => "str".into(),
+        Shape::Option(shape) => format!("{}?", schema(registry, shape, defs)),
+        Shape::Seq(items) => format!("[{}]", schema(registry, items, defs)),
+        Shape::Array(items, Len::Fixed(len)) => {
+            format!("[{};{len}]", schema(registry, items, defs))
+        }
+        Shape::Array(items, Len::Param(i)) => format!("[{};N{i}]", schema(registry, items, defs)),
+        Shape::Type(ty) => {
+            let def = registry.definition_of(ty);
+            let name = &def.container.name.serialize;
+            let params = (0..def.params.len())
+                .map(|i| format!("X{i}"))
+                .chain((0..def.const_params.len()).map(|i| format!("N{i}")))
+                .collect::<Vec<_>>();
+            let head = format!("{name}<{}>", params.join(","));
+            // Recursive types are reached again while their body is written
+            if defs.iter().all(|def| !def.starts_with(&format!("{head}="))) {
+                defs.insert(format!("{head}=..."));
+                let body = match &def.body {
+                    Body::Struct(fields) => fields
+                        .iter()
+                        .map(|field| {
+                            let shape = schema(registry, &field.shape, defs);
+                            format!("{}:{shape}", field.name.serialize)
+                        })
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    _ => unimplemented!(),
+                };
+                defs.remove(&format!("{head}=..."));
+                defs.insert(format!("{head}={{{body}}}"));
+            }
+            let args = ty
+                .args
+                .iter()
+                .map(|arg| schema(registry, arg, defs))
+                .chain(ty.const_args.iter().map(|value| value.to_string()))
+                .collect::<Vec<_>>();
+            format!("{name}<{}>", args.join(","))
+        }
+        _ => unimplemented!(),
+    }
+}
+
+#[test]
+fn schema_gen() {
+    let mut registry = Registry::new();
+    let mut defs = HashSet::new();
+    let shape = registry.register::<Foo<u8, Joo<String>>, _>();
+    assert_eq!(schema(&registry, &shape, &mut defs), "Foo<byte,Joo<str>>");
+    assert_eq!(
+        defs,
+        [
+            "Foo<X0,X1>={foo:str,boo:byte,xoo:X0,coo:Coo<X1>}",
+            "Joo<X0>={joo:X0}",
+            "Coo<X0>={coo:X0}",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect::<HashSet<_>>()
+    );
+
+    let mut defs = HashSet::new();
+    let shape = registry.register::<Buf<Joo<u8>, 4>, _>();
+    assert_eq!(schema(&registry, &shape, &mut defs), "Buf<Joo<byte>,4>");
+    assert_eq!(
+        defs,
+        ["Buf<X0,N0>={data:[X0;N0]}", "Joo<X0>={joo:X0}"]
+            .into_iter()
+            .map(String::from)
+            .collect::<HashSet<_>>()
+    );
+}
+
+/// Events.
+#[derive(SerdeGeneric)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+enum Event<'a, X> {
+    Started {
+        #[serde(rename = "at", alias = "time")]
+        time: u64,
+    },
+    Renamed(&'a str),
+    Batch(Vec<X>, Option<HashMap<String, (bool, X)>>),
+    #[serde(skip)]
+    Stopped,
+}
+
+#[test]
+fn enum_shape() {
+    let mut registry = Registry::new();
+    let Shape::Type(ty) = registry.register::<Event<Box<char>>, _>() else {
+        panic!("not a type");
+    };
+    assert_eq!(
+        ty.name,
+        std::any::type_name::<<Event<char> as SerdeGeneric>::Mocked>()
+    );
+    assert_eq!(ty.args, [Shape::Primitive(Primitive::Char)]);
+    assert!(ty.const_args.is_empty());
+
+    let def = registry.definition_of(&ty);
+    assert_eq!(def.params, ["X"]);
+    assert_eq!(def.container.name.serialize, "Event");
+    assert_eq!(def.container.doc.as_deref(), Some("Events."));
+    assert_eq!(
+        def.container.tag,
+        TagType::Adjacent {
+            tag: "type".into(),
+            content: "data".into()
+        }
+    );
+    let Body::Enum(variants) = &def.body else {
+        panic!("not an enum");
+    };
+    let names = variants
+        .iter()
+        .map(|v| v.name.serialize.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["started", "renamed", "batch", "stopped"]);
+
+    let Body::Struct(fields) = &variants[0].body else {
+        panic!("not a struct variant");
+    };
+    assert_eq!(fields[0].name.serialize, "at");
+    assert_eq!(fields[0].name.deserialize_aliases, ["at", "time"]);
+    assert_eq!(fields[0].shape, Shape::Primitive(Primitive::U64));
+
+    let Body::Tuple(fields) = &variants[1].body else {
+        panic!("not a tuple variant");
+    };
+    assert_eq!(fields[0].shape, Shape::Primitive(Primitive::String));
+
+    let Body::Tuple(fields) = &variants[2].body else {
+        panic!("not a tuple variant");
+    };
+    assert_eq!(fields[0].shape, Shape::Seq(Box::new(Shape::Param(0))));
+    assert_eq!(
+        fields[1].shape,
+        Shape::Option(Box::new(Shape::Map(
+            Box::new(Shape::Primitive(Primitive::String)),
+            Box::new(Shape::Tuple(vec![
+                Shape::Primitive(Primitive::Bool),
+                Shape::Param(0)
+            ]))
+        )))
+    );
+
+    assert_eq!(variants[3].body, Body::Unit);
+    assert!(variants[3].skip_serializing && variants[3].skip_deserializing);
+}
+
+#[test]
+fn serialize_shape() {
+    let mut registry = Registry::new();
+    let shape = registry.register::<Option<[u8; 2]>, _>();
+    let json = serde_json::to_value(&shape).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({"Option": {"Array": [{"Primitive": "U8"}, {"Fixed": 2}]}})
+    );
+    assert_eq!(serde_json::from_value::<Shape>(json).unwrap(), shape);
+
+    let shape = registry.register::<Joo<u8>, _>();
+    let json = serde_json::to_string(&shape).unwrap();
+    assert_eq!(serde_json::from_str::<Shape>(&json).unwrap(), shape);
+}
+
+fn display(registry: &Registry, shape: &Shape) -> String {
+    match shape {
+        Shape::Primitive(Primitive::U8) => "byte".into(),
+        Shape::Primitive(Primitive::String) => "str".into(),
+        Shape::Type(ty) => {
+            let args = ty
+                .args
+                .iter()
+                .map(|arg| display(registry, arg))
+                .chain(ty.const_args.iter().map(|value| value.to_string()))
+                .collect::<Vec<_>>();
+            let name = &registry.definition_of(ty).container.name.serialize;
+            format!("{name}<{}>", args.join(","))
+        }
+        _ => unimplemented!(),
+    }
+}
+
+#[test]
+fn registry() {
+    let mut registry = Registry::new();
+    registry.register::<Foo<u8, Joo<String>>, _>();
+    registry.register::<Buf<Joo<u8>, 4>, _>();
+    registry.register::<Joo<u8>, _>();
+    registry.register::<u8, _>();
+    registry.register::<Buf<u8, 2>, _>();
+
+    assert_eq!(registry.roots().len(), 5);
+    let definitions = registry
+        .definitions()
+        .map(|(_, def)| def.container.name.serialize.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(definitions, ["Joo", "Coo", "Foo", "Buf"]);
+    let instances = registry
+        .instances()
+        .iter()
+        .map(|ty| display(&registry, &Shape::Type(Box::new(ty.clone()))))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        instances,
+        [
+            "Joo<str>",
+            "Coo<Joo<str>>",
+            "Foo<byte,Joo<str>>",
+            "Joo<byte>",
+            "Buf<Joo<byte>,4>",
+            "Buf<byte,2>",
+        ]
+    );
+    let Body::Struct(fields) = registry.body(&registry.instances()[5]) else {
+        panic!("not a struct");
+    };
+    assert_eq!(
+        fields[0].shape,
+        Shape::Array(Box::new(Shape::Primitive(Primitive::U8)), Len::Fixed(2))
+    );
+
+    let Shape::Type(coo) = registry.register::<Coo<u8>, _>() else {
+        panic!("not a type");
+    };
+    assert_eq!(registry.definitions().count(), 4);
+    assert_eq!(registry.definition_name(coo.id), Some("Coo"));
+    let Body::Struct(fields) = registry.body(&registry.instances()[1]) else {
+        panic!("not a struct");
+    };
+    assert_eq!(display(&registry, &fields[0].shape), "Joo<str>");
+}
+
+#[test]
+fn same_type_names() {
+    let mut registry = Registry::new();
+    let first = {
+        #[derive(SerdeGeneric)]
+        #[allow(dead_code)]
+        struct Same {
+            a: u8,
+        }
+        registry.register::<Same, _>()
+    };
+    let second = {
+        #[derive(SerdeGeneric)]
+        #[allow(dead_code)]
+        struct Same {
+            b: String,
+        }
+        registry.register::<Same, _>()
+    };
+    let (Shape::Type(first_ty), Shape::Type(second_ty)) = (&first, &second) else {
+        unreachable!()
+    };
+    assert_eq!(first_ty.name, second_ty.name);
+    assert_ne!(first_ty.id, second_ty.id);
+    assert_eq!(registry.definitions().count(), 2);
+    assert_eq!(registry.instances().len(), 2);
+}
+
+#[test]
+fn default_values() {
+    let mut registry = Registry::new();
+    let Shape::Type(ty) = registry.register::<Foo<u8, u8>, _>() else {
+        unreachable!()
+    };
+    let defaults = registry
+        .body(&ty)
+        .fields()
+        .map(|field| field.default_value.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        defaults,
+        [Some(Value::String(String::new())), None, None, None]
+    );
+
+    assert_eq!(
+        <(Option<u8>, [char; 1], HashMap<bool, ()>)>::value((
+            None,
+            ['a'],
+            HashMap::from([(true, ())])
+        )),
+        Some(Value::Seq(vec![
+            Value::Null,
+            Value::Seq(vec![Value::String("a".to_owned())]),
+            Value::Map(vec![(Value::Bool(true), Value::Null)]),
+        ]))
+    );
+    assert_eq!(<u128>::value(u128::MAX), None);
+    assert_eq!(
+        <Level>::value(Level::Warning),
+        Some(Value::String("warn".to_owned()))
+    );
+    assert_eq!(<Level>::value(Level::Hidden), None);
+    assert_eq!(<Event<u8>>::value(Event::Stopped), None);
+}
+
+#[test]
+fn struct_values() {
+    assert_eq!(
+        <Joo<u8>>::value(Joo { joo: 0 }),
+        Some(Value::Map(vec![(
+            Value::String("joo".to_owned()),
+            Value::Integer(0)
+        )]))
+    );
+    assert_eq!(
+        <Margins>::value(Margins(1, 2)),
+        Some(Value::Seq(vec![Value::Integer(1), Value::Integer(2)]))
+    );
+    assert_eq!(<Meters>::value(Meters(1.5)), Some(Value::Float(1.5)));
+    assert_eq!(<Blank>::value(Blank), Some(Value::Null));
+    assert_eq!(<Joo<u128>>::value(Joo { joo: u128::MAX }), None);
+
+    let mut registry = Registry::new();
+    let Shape::Type(ty) = registry.register::<Book, _>() else {
+        unreachable!()
+    };
+    let defaults = registry
+        .body(&ty)
+        .fields()
+        .map(|field| field.default_value.clone())
+        .collect::<Vec<_>>();
+    let page = Value::Map(vec![
+        (
+            Value::String("color".to_owned()),
+            Value::String("black".to_owned()),
+        ),
+        (
+            Value::String("margins".to_owned()),
+            Value::Seq(vec![Value::Integer(0), Value::Integer(0)]),
+        ),
+    ]);
+    assert_eq!(defaults, [Some(page), Some(Value::Float(0.0)), None]);
+}
+
+#[derive(SerdeGeneric, Default)]
+struct Margins(u8, u8);
+
+#[derive(SerdeGeneric, Default)]
+struct Meters(f64);
+
+#[derive(SerdeGeneric)]
+struct Blank;
+
+#[derive(SerdeGeneric)]
+struct Ink {
+    color: String,
+}
+
+impl Default for Ink {
+    fn default() -> Self {
+        Ink {
+            color: "black".to_owned(),
+        }
+    }
+}
+
+#[derive(SerdeGeneric, Default)]
+struct Page {
+    #[serde(rename = "n", skip_serializing_if = "Option::is_none")]
+    number: Option<u32>,
+    #[serde(flatten)]
+    ink: Ink,
+    margins: Margins,
+}
+
+#[derive(SerdeGeneric)]
+struct Book {
+    #[serde(default)]
+    page: Page,
+    #[serde(default)]
+    width: Meters,
+    #[serde(default)]
+    title: Box<str>,
+}
+
+#[derive(SerdeGeneric)]
+#[serde(rename_all = "lowercase")]
+enum Level {
+    Info,
+    #[serde(rename = "warn")]
+    Warning,
+    #[serde(skip)]
+    #[allow(dead_code)]
+    Hidden,
+}
+
+#[derive(SerdeGeneric)]
+struct Tree<X> {
+    value: X,
+    children: Vec<Tree<X>>,
+}
+
+#[derive(SerdeGeneric)]
+struct Forest {
+    trees: Vec<Tree<String>>,
+    first: Option<Box<Forest>>,
+}
+
+#[test]
+fn recursive_types() {
+    let mut registry = Registry::new();
+    let mut defs = HashSet::new();
+    let shape = registry.register::<Forest, _>();
+    assert_eq!(schema(&registry, &shape, &mut defs), "Forest<>");
+    assert_eq!(
+        defs,
+        [
+            "Tree<X0>={value:X0,children:[Tree<X0>]}",
+            "Forest<>={trees:[Tree<str>],first:Forest<>?}",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect::<HashSet<_>>()
+    );
+
+    let definitions = registry
+        .definitions()
+        .map(|(_, def)| def.container.name.serialize.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(definitions, ["Tree", "Forest"]);
+    let instances = registry
+        .instances()
+        .iter()
+        .map(|ty| display(&registry, &Shape::Type(Box::new(ty.clone()))))
+        .collect::<Vec<_>>();
+    assert_eq!(instances, ["Tree<str>", "Forest<>"]);
+    let Body::Struct(fields) = registry.body(&registry.instances()[1]) else {
+        panic!("not a struct");
+    };
+    assert_eq!(
+        fields[1].shape,
+        Shape::Option(Box::new(Shape::Type(Box::new(
+            registry.instances()[1].clone()
+        ))))
+    );
+}
+
+#[test]
+fn const_lengths() {
+    let array = Shape::Array(Box::new(Shape::Primitive(Primitive::U8)), Len::Param(0));
+    assert_eq!(
+        array.instantiate(&[], &[ConstValue::Signed(3)]),
+        Shape::Array(Box::new(Shape::Primitive(Primitive::U8)), Len::Fixed(3))
+    );
+    assert_eq!(array.instantiate(&[], &[ConstValue::Bool(true)]), array);
+    assert_eq!(array.instantiate(&[], &[ConstValue::Signed(-1)]), array);
+    assert_eq!(array.instantiate(&[], &[]), array);
+}
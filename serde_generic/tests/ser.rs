@@ -0,0 +1,398 @@
+// checks that `ser::serialize` agrees with `serde_derive`
+
+use serde::Serialize;
+use serde_generic::{SerdeGeneric, SerdeRemoteGetter, Succ, Zero};
+use std::collections::BTreeMap;
+
+fn check<T: SerdeGeneric + Serialize + Clone>(value: T)
+where
+    T::Repr: serde_generic::ser::SerializeRepr<T>,
+{
+    let expected = serde_json::to_value(&value).unwrap();
+    let actual = serde_generic::ser::serialize(value, serde_json::value::Serializer).unwrap();
+    assert_eq!(actual, expected);
+}
+
+#[derive(SerdeGeneric, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Named<X> {
+    first_field: u8,
+    #[serde(rename = "second")]
+    second_field: X,
+    #[serde(skip_serializing)]
+    skipped: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maybe: Option<String>,
+}
+
+#[derive(SerdeGeneric, Serialize, Clone)]
+struct Flat {
+    id: u32,
+    #[serde(flatten)]
+    inner: Named<bool>,
+    #[serde(flatten)]
+    extra: BTreeMap<String, u8>,
+}
+
+#[derive(SerdeGeneric, Serialize, Clone)]
+#[serde(transparent)]
+struct Transparent {
+    inner: Vec<u8>,
+}
+
+#[derive(SerdeGeneric, Serialize, Clone)]
+struct Newtype(String);
+
+#[derive(SerdeGeneric, Serialize, Clone)]
+struct Tuple(u8, #[serde(skip_serializing)] bool, String);
+
+#[derive(SerdeGeneric, Serialize, Clone)]
+struct Unit;
+
+#[derive(SerdeGeneric, Serialize, Clone)]
+#[serde(tag = "type")]
+struct TaggedStruct {
+    a: u8,
+}
+
+mod as_string {
+    pub fn serialize<T: ToString, S: serde::Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+}
+
+fn doubled<S: serde::Serializer>(value: &u8, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u16(u16::from(*value) * 2)
+}
+
+#[derive(SerdeGeneric, Serialize, Clone)]
+struct With {
+    #[serde(serialize_with = "as_string::serialize")]
+    text: u32,
+    #[serde(serialize_with = "doubled")]
+    number: u8,
+}
+
+#[test]
+fn structs() {
+    let named = Named {
+        first_field: 1,
+        second_field: "two".to_owned(),
+        skipped: true,
+        maybe: None,
+    };
+    check(named.clone());
+    check(Named {
+        maybe: Some("x".to_owned()),
+        ..named
+    });
+    check(Flat {
+        id: 7,
+        inner: Named {
+            first_field: 1,
+            second_field: false,
+            skipped: false,
+            maybe: Some("y".to_owned()),
+        },
+        extra: [("k".to_owned(), 3)].into_iter().collect(),
+    });
+    check(Transparent { inner: vec![1, 2] });
+    check(Newtype("new".to_owned()));
+    check(Tuple(1, true, "three".to_owned()));
+    check(Unit);
+    check(With {
+        text: 42,
+        number: 3,
+    });
+    check(TaggedStruct { a: 1 });
+}
+
+macro_rules! enum_with_tagging {
+    ($name:ident, #[$($attr:tt)*]) => {
+        #[derive(SerdeGeneric, Serialize, Clone)]
+        #[$($attr)*]
+        #[serde(rename_all = "snake_case")]
+        enum $name<X> {
+            Unit,
+            Newtype(Named<X>),
+            Struct {
+                a: u8,
+                #[serde(skip_serializing_if = "Vec::is_empty")]
+                b: Vec<X>,
+            },
+            #[serde(rename = "renamed")]
+            Map(BTreeMap<String, X>),
+            #[serde(untagged)]
+            Fallback(u64),
+        }
+
+        impl $name<u8> {
+            fn all() -> Vec<Self> {
+                vec![
+                    Self::Unit,
+                    Self::Newtype(Named {
+                        first_field: 1,
+                        second_field: 2,
+                        skipped: false,
+                        maybe: None,
+                    }),
+                    Self::Struct { a: 1, b: vec![] },
+                    Self::Struct { a: 1, b: vec![2] },
+                    Self::Map([("k".to_owned(), 1)].into_iter().collect()),
+                    Self::Fallback(42),
+                ]
+            }
+        }
+    };
+}
+
+enum_with_tagging!(External, #[serde()]);
+enum_with_tagging!(Internal, #[serde(tag = "t")]);
+enum_with_tagging!(Adjacent, #[serde(tag = "t", content = "c")]);
+enum_with_tagging!(Untagged, #[serde(untagged)]);
+
+#[derive(SerdeGeneric, Serialize, Clone)]
+enum WithTuples {
+    Tuple(u8, String),
+    #[serde(rename_all = "UPPERCASE")]
+    Struct {
+        #[serde(flatten)]
+        flat: Named<u8>,
+        other: u8,
+    },
+}
+
+#[derive(SerdeGeneric, Serialize, Clone)]
+#[serde(tag = "t", content = "c")]
+enum AdjacentTuples {
+    Tuple(u8, String),
+}
+
+#[derive(SerdeGeneric, Serialize, Clone)]
+enum Inner {
+    A,
+    B { x: u8 },
+    C(u8, String),
+    D(u8),
+}
+
+impl Inner {
+    fn all() -> Vec<Self> {
+        vec![
+            Inner::A,
+            Inner::B { x: 1 },
+            Inner::C(2, "c".to_owned()),
+            Inner::D(3),
+        ]
+    }
+}
+
+#[derive(SerdeGeneric, Serialize, Clone)]
+struct FlatEnum {
+    name: String,
+    #[serde(flatten)]
+    inner: Inner,
+}
+
+#[derive(SerdeGeneric, Serialize, Clone)]
+#[serde(tag = "t")]
+enum TaggedEnum {
+    Wrapped(Inner),
+}
+
+#[derive(SerdeGeneric, Clone)]
+enum Skipped {
+    #[serde(skip_serializing)]
+    Hidden,
+}
+
+#[test]
+fn enums() {
+    External::all().into_iter().for_each(check);
+    Internal::all().into_iter().for_each(check);
+    Adjacent::all().into_iter().for_each(check);
+    Untagged::all().into_iter().for_each(check);
+    let flat = Named {
+        first_field: 1,
+        second_field: 2,
+        skipped: false,
+        maybe: None,
+    };
+    check(WithTuples::Tuple(1, "a".to_owned()));
+    check(WithTuples::Struct { flat, other: 3 });
+    check(AdjacentTuples::Tuple(1, "a".to_owned()));
+    for inner in Inner::all() {
+        check(FlatEnum {
+            name: "n".to_owned(),
+            inner: inner.clone(),
+        });
+        check(TaggedEnum::Wrapped(inner));
+    }
+    let value = FlatEnum {
+        name: "n".to_owned(),
+        inner: Inner::B { x: 1 },
+    };
+    let actual = serde_generic::ser::serialize(value, serde_json::value::Serializer).unwrap();
+    assert_eq!(actual, serde_json::json!({"name": "n", "B": {"x": 1}}));
+
+    let err =
+        serde_generic::ser::serialize(Skipped::Hidden, serde_json::value::Serializer).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the enum variant Skipped::Hidden cannot be serialized"
+    );
+}
+
+fn pair_to_map<S: serde::Serializer>(
+    first: &u8,
+    second: &u8,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    BTreeMap::from([("pair", format!("{first}-{second}"))]).serialize(serializer)
+}
+
+fn empty_map<S: serde::Serializer>(serializer: S) -> Result<S::Ok, S::Error> {
+    BTreeMap::<String, u8>::new().serialize(serializer)
+}
+
+macro_rules! variant_with_tagging {
+    ($name:ident, #[$($attr:tt)*]) => {
+        #[derive(SerdeGeneric, Serialize, Clone)]
+        #[$($attr)*]
+        enum $name {
+            #[serde(serialize_with = "pair_to_map")]
+            Pair { first: u8, second: u8 },
+            #[serde(serialize_with = "empty_map")]
+            Unit,
+            Plain { a: u8 },
+        }
+
+        impl $name {
+            fn all() -> Vec<Self> {
+                vec![Self::Pair { first: 1, second: 2 }, Self::Unit, Self::Plain { a: 3 }]
+            }
+        }
+    };
+}
+
+variant_with_tagging!(ExternalWith, #[serde()]);
+variant_with_tagging!(InternalWith, #[serde(tag = "t")]);
+variant_with_tagging!(AdjacentWith, #[serde(tag = "t", content = "c")]);
+variant_with_tagging!(UntaggedWith, #[serde(untagged)]);
+
+#[test]
+fn variants_with() {
+    ExternalWith::all().into_iter().for_each(check);
+    InternalWith::all().into_iter().for_each(check);
+    AdjacentWith::all().into_iter().for_each(check);
+    UntaggedWith::all().into_iter().for_each(check);
+}
+
+#[derive(Serialize, Clone)]
+struct Wire(String);
+
+#[derive(SerdeGeneric, Serialize, Clone)]
+#[serde(into = "Wire")]
+struct Proxied {
+    a: u8,
+}
+
+impl From<Proxied> for Wire {
+    fn from(value: Proxied) -> Wire {
+        Wire(value.a.to_string())
+    }
+}
+
+#[test]
+fn proxies() {
+    let value = Proxied { a: 1 };
+    let expected = serde_json::to_value(&value).unwrap();
+    let actual =
+        serde_generic::ser::serialize_into(value.clone(), serde_json::value::Serializer).unwrap();
+    assert_eq!(actual, expected);
+    assert_eq!(Proxied::CONTAINER.type_into, Some("Wire"));
+
+    let err = serde_generic::ser::serialize(value, serde_json::value::Serializer).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Proxied is serialized as Wire, use `serialize_into`"
+    );
+}
+
+mod remote {
+    #[derive(Clone)]
+    pub struct Span {
+        pub start: u32,
+        length: u32,
+    }
+
+    impl Span {
+        pub fn new(start: u32, length: u32) -> Self {
+            Span { start, length }
+        }
+
+        pub fn length(&self) -> u32 {
+            self.length
+        }
+    }
+
+    // required by `SerdeRemote` of a mirror with getters
+    impl From<super::SpanDef> for Span {
+        fn from(def: super::SpanDef) -> Self {
+            Span::new(def.start, def.length)
+        }
+    }
+
+    #[derive(Clone)]
+    pub enum Shape {
+        Point,
+        Line(u32, u32),
+        Rect { w: u32, h: u32 },
+    }
+}
+
+#[derive(SerdeGeneric, Serialize)]
+#[serde(remote = "remote::Span")]
+struct SpanDef {
+    start: u32,
+    #[serde(getter = "remote::Span::length")]
+    length: u32,
+}
+
+#[derive(SerdeGeneric, Serialize)]
+#[serde(remote = "remote::Shape", rename_all = "lowercase")]
+#[allow(dead_code)]
+enum ShapeDef {
+    Point,
+    Line(u32, u32),
+    Rect { w: u32, h: u32 },
+}
+
+#[test]
+fn remotes() {
+    use serde_json::value::Serializer;
+
+    let span = remote::Span::new(1, 2);
+    let expected = SpanDef::serialize(&span, Serializer).unwrap();
+    let actual = serde_generic::ser::serialize_remote::<SpanDef, _>(span, Serializer).unwrap();
+    assert_eq!(actual, expected);
+
+    for shape in [
+        remote::Shape::Point,
+        remote::Shape::Line(1, 2),
+        remote::Shape::Rect { w: 3, h: 4 },
+    ] {
+        let expected = ShapeDef::serialize(&shape, Serializer).unwrap();
+        let actual =
+            serde_generic::ser::serialize_remote::<ShapeDef, _>(shape, Serializer).unwrap();
+        assert_eq!(actual, expected);
+    }
+    assert_eq!(SpanDef::CONTAINER.remote, Some("remote::Span"));
+    let (path, getter) = <SpanDef as SerdeRemoteGetter<u32, Succ<Zero>>>::GETTER.unwrap();
+    assert_eq!(path, "remote::Span::length");
+    assert_eq!(getter(&remote::Span::new(1, 2)), 2);
+    assert!(<SpanDef as SerdeRemoteGetter<u32, Succ<Succ<Zero>>>>::GETTER.is_none());
+}
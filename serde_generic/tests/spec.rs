@@ -1,6 +1,9 @@
 // this module both tests and demonstrates how you can use this crate
 // to define a spec generator based on SerdeGeneric trait.
 
+#![allow(clippy::disallowed_names)]
+
+use serde_generic::traverse::*;
 use serde_generic::*;
 use std::collections::HashSet;
 
@@ -23,43 +26,9 @@ struct Coo<X> {
     coo: X,
 }
 
-trait Traverser<H, I, Q> {
-    type Collector;
-    fn apply(state: &mut Self::Collector);
-}
-
-trait Traverse<TR, C, Idx, Q> {
-    fn traverse(state: &mut C);
-}
-
-impl<H, T, TR, C, Q, QT, I> Traverse<TR, C, Succ<I>, (Q, QT)> for (H, T)
-where
-    TR: Traverser<H, Succ<I>, Q, Collector = C>,
-    T: Traverse<TR, C, I, QT>,
-{
-    fn traverse(state: &mut C) {
-        TR::apply(state);
-        T::traverse(state);
-    }
-}
-
-impl<H, T, TR, C, Q, QT, I> Traverse<TR, C, Succ<I>, (Q, QT)> for HCons<H, T>
-where
-    TR: Traverser<H, Succ<I>, Q, Collector = C>,
-    T: Traverse<TR, C, I, QT>,
-{
-    fn traverse(state: &mut C) {
-        TR::apply(state);
-        T::traverse(state);
-    }
-}
-
-impl<TR, C> Traverse<TR, C, Zero, ()> for () {
-    fn traverse(_state: &mut C) {}
-}
-
-impl<TR, C> Traverse<TR, C, Zero, ()> for HNil {
-    fn traverse(_state: &mut C) {}
+#[derive(SerdeGeneric, Debug)]
+struct Buf<X, const N: usize> {
+    data: [X; N],
 }
 
 trait HasSchema<Type> {
@@ -81,17 +50,31 @@ impl HasSchema<Manual> for u8 {
 
 struct ViaGeneric<T>(core::marker::PhantomData<T>);
 struct Manual;
+struct ArrayOf<M>(core::marker::PhantomData<M>);
+
+// arrays are rendered without length, so that the definition of a type
+// with a const parameter does not depend on its value
+impl<T: HasSchema<M>, M, const N: usize> HasSchema<ArrayOf<M>> for [T; N] {
+    fn schema() -> String {
+        format!("[{}]", T::schema())
+    }
+
+    fn defs(defs: &mut HashSet<String>) {
+        T::defs(defs)
+    }
+}
 
 impl<C: SerdeGeneric, CTP, FS> HasSchema<ViaGeneric<(CTP, FS)>> for C
 where
     C::Params: HLen
-        + Traverse<CollectTypeParams, Vec<String>, <C::Params as HLen>::Len, CTP>
-        + Traverse<CollectTypeParamDefs, HashSet<String>, <C::Params as HLen>::Len, CTP>,
+        + Traverse<TypeParams<CollectTypeParams>, Vec<String>, <C::Params as HLen>::Len, CTP>
+        + Traverse<TypeParams<CollectTypeParamDefs>, HashSet<String>, <C::Params as HLen>::Len, CTP>,
     <C::Mocked as SerdeGeneric>::Repr: BodyDef<C::Mocked, FS>,
 {
     fn schema() -> String {
         let mut param_list = Vec::new();
-        <C::Params as Traverse<CollectTypeParams, _, _, _>>::traverse(&mut param_list);
+        <C::Params as Traverse<TypeParams<CollectTypeParams>, _, _, _>>::traverse(&mut param_list);
+        param_list.extend(C::CONST_PARAMS.iter().map(|param| param.value.to_string()));
         format!(
             "{}<{}>",
             Self::CONTAINER.name.serialize,
@@ -105,12 +88,13 @@ where
             Self::CONTAINER.name.serialize,
             (0..<C::Params as HLen>::Len::NUMBER)
                 .map(|i| format!("X{i}"))
+                .chain(C::CONST_PARAMS.iter().map(|param| param.name.to_owned()))
                 .collect::<Vec<_>>()
                 .join(","),
             <<C::Mocked as SerdeGeneric>::Repr as BodyDef<C::Mocked, FS>>::def(defs)
         );
         defs.insert(def);
-        <C::Params as Traverse<CollectTypeParamDefs, _, _, _>>::traverse(defs);
+        <C::Params as Traverse<TypeParams<CollectTypeParamDefs>, _, _, _>>::traverse(defs);
     }
 }
 
@@ -127,7 +111,7 @@ trait BodyDef<C, FS> {
 impl<C, X, FS> BodyDef<C, FS> for NamedStruct<X>
 where
     X: for<'s> Traverse<
-            NamedFieldsTraverser<'s, C>,
+            StructFields<C, NamedFieldsVisitor<'s>>,
             (Vec<(String, String)>, &'s mut HashSet<String>),
             X::Len,
             FS,
@@ -137,7 +121,7 @@ where
 {
     fn def(defs: &mut HashSet<String>) -> String {
         let mut collector = (Vec::new(), defs);
-        <X as Traverse<NamedFieldsTraverser<C>, _, _, _>>::traverse(&mut collector);
+        <X as Traverse<StructFields<C, NamedFieldsVisitor>, _, _, _>>::traverse(&mut collector);
         format!(
             "{{{}}}",
             collector
@@ -150,33 +134,31 @@ where
     }
 }
 
-#[allow(dead_code)]
-struct NamedFieldsTraverser<'s, C>(&'s core::marker::PhantomData<C>);
+struct NamedFieldsVisitor<'s>(core::marker::PhantomData<&'s ()>);
 
-impl<'s, H, I, C, M> Traverser<H, I, M> for NamedFieldsTraverser<'s, C>
+impl<'s, C, H, I, M> FieldVisitor<C, H, I, M> for NamedFieldsVisitor<'s>
 where
     H: HasSchema<M>,
-    C: SerdeFieldAttr<H, I>,
 {
     type Collector = (Vec<(String, String)>, &'s mut HashSet<String>);
-    fn apply((fields, defs): &mut Self::Collector) {
-        fields.push((C::FIELD.name.serialize.to_owned(), H::schema()));
+    fn visit((fields, defs): &mut Self::Collector, field: &serde::Field<C, H>) {
+        fields.push((field.name.serialize.to_owned(), H::schema()));
         H::defs(defs);
     }
 }
 
 struct CollectTypeParams;
-impl<H: HasSchema<M>, M, I> Traverser<H, I, M> for CollectTypeParams {
+impl<H: HasSchema<M>, M, I> ParamVisitor<H, I, M> for CollectTypeParams {
     type Collector = Vec<String>;
-    fn apply(param_list: &mut Self::Collector) {
+    fn visit(param_list: &mut Self::Collector) {
         param_list.push(H::schema());
     }
 }
 
 struct CollectTypeParamDefs;
-impl<H: HasSchema<M>, M, I> Traverser<H, I, M> for CollectTypeParamDefs {
+impl<H: HasSchema<M>, M, I> ParamVisitor<H, I, M> for CollectTypeParamDefs {
     type Collector = HashSet<String>;
-    fn apply(defs: &mut Self::Collector) {
+    fn visit(defs: &mut Self::Collector) {
         H::defs(defs);
     }
 }
@@ -201,3 +183,20 @@ fn test_schema_gen() {
         .collect::<HashSet<_>>()
     );
 }
+
+#[test]
+fn test_schema_gen_const_params() {
+    assert_eq!(
+        <Buf<Joo<u8>, 4> as HasSchema<_>>::schema(),
+        "Buf<Joo<byte>,4>"
+    );
+    let mut defs = HashSet::new();
+    <Buf<Joo<u8>, 4> as HasSchema<_>>::defs(&mut defs);
+    assert_eq!(
+        defs,
+        ["Buf<X0,N>={data:[X0]}", "Joo<X0>={joo:X0}"]
+            .into_iter()
+            .map(String::from)
+            .collect::<HashSet<_>>()
+    );
+}
@@ -0,0 +1,532 @@
+use serde_generic::SerdeGeneric;
+use serde_generic::reflect::Registry;
+use serde_generic::swift::{source, source_for};
+use std::collections::BTreeMap;
+
+/// A forecast of a station.
+///
+/// Written as "\(city)" in reports.
+#[derive(SerdeGeneric)]
+#[serde(rename_all = "camelCase")]
+struct Forecast<X> {
+    #[serde(rename = "default", alias = "station", alias = "stationId")]
+    station_id: u64,
+    #[serde(default = "default_low")]
+    low: f64,
+    #[serde(default = "default_unit")]
+    unit: char,
+    #[serde(default = "default_summary")]
+    summary: String,
+    /// Millimeters by hour.
+    #[serde(default = "default_rain")]
+    rain_by_hour: BTreeMap<u8, f32>,
+    #[serde(default)]
+    readings: BTreeMap<u64, Vec<X>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    flags: BTreeMap<bool, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warning: Option<String>,
+    #[serde(skip_deserializing)]
+    cached: Option<bool>,
+    pressure: i128,
+    span: (u8, String),
+    #[serde(flatten)]
+    place: Place,
+}
+
+fn default_low() -> f64 {
+    f64::NEG_INFINITY
+}
+
+fn default_unit() -> char {
+    '°'
+}
+
+fn default_summary() -> String {
+    "\"Clear\" \\ sunny".to_owned()
+}
+
+fn default_rain() -> BTreeMap<u8, f32> {
+    BTreeMap::from([(6, 0.5)])
+}
+
+#[derive(SerdeGeneric)]
+struct Place {
+    #[serde(rename = "Type")]
+    kind: String,
+    altitude: i32,
+}
+
+#[derive(SerdeGeneric)]
+struct Celsius(f32);
+
+#[test]
+fn structs() {
+    let mut registry = Registry::new();
+    registry.register::<Forecast<Celsius>, _>();
+    assert_eq!(
+        source(&registry),
+        r#"typealias Celsius = Float
+
+struct Place {
+    var type: String
+    var altitude: Int32
+}
+
+extension Place: Codable {
+    enum CodingKeys: String, CodingKey {
+        case type = "Type"
+        case altitude
+    }
+
+    init(from decoder: Decoder) throws {
+        let container = try decoder.container(keyedBy: CodingKeys.self)
+        self.type = try container.decode(String.self, forKey: .type)
+        self.altitude = try container.decode(Int32.self, forKey: .altitude)
+    }
+
+    func encode(to encoder: Encoder) throws {
+        var container = encoder.container(keyedBy: CodingKeys.self)
+        try container.encode(self.type, forKey: .type)
+        try container.encode(self.altitude, forKey: .altitude)
+    }
+}
+
+/// A forecast of a station.
+///
+/// Written as "\(city)" in reports.
+struct Forecast<X0: Codable> {
+    var `default`: UInt64
+    var low: Double
+    var unit: String
+    var summary: String
+    /// Millimeters by hour.
+    var rainByHour: [Int: Float]
+    var readings: [String: [X0]]
+    var flags: [String: String]
+    var warning: String?
+    var cached: Bool?
+    var pressure: JSONValue
+    var span: [JSONValue]
+    var place: Place
+}
+
+extension Forecast: Codable {
+    enum CodingKeys: String, CodingKey {
+        case `default`
+        case station
+        case stationId
+        case low
+        case unit
+        case summary
+        case rainByHour
+        case readings
+        case flags
+        case warning
+        case cached
+        case pressure
+        case span
+    }
+
+    init(from decoder: Decoder) throws {
+        let container = try decoder.container(keyedBy: CodingKeys.self)
+        self.`default` = try container.decode(UInt64.self, forKey: [CodingKeys.`default`, .station, .stationId].first(where: container.contains) ?? .`default`)
+        self.low = try container.decodeIfPresent(Double.self, forKey: .low) ?? -.infinity
+        self.unit = try container.decodeIfPresent(String.self, forKey: .unit) ?? "°"
+        self.summary = try container.decodeIfPresent(String.self, forKey: .summary) ?? "\"Clear\" \\ sunny"
+        self.rainByHour = try container.decodeIfPresent([Int: Float].self, forKey: .rainByHour) ?? [6: 0.5]
+        self.readings = try container.decodeIfPresent([String: [X0]].self, forKey: .readings) ?? [:]
+        self.flags = try container.decodeIfPresent([String: String].self, forKey: .flags) ?? [:]
+        self.warning = try container.decodeIfPresent(String.self, forKey: .warning)
+        self.cached = nil
+        self.pressure = try container.decode(JSONValue.self, forKey: .pressure)
+        self.span = try container.decode([JSONValue].self, forKey: .span)
+        self.place = try Place(from: decoder)
+    }
+
+    func encode(to encoder: Encoder) throws {
+        var container = encoder.container(keyedBy: CodingKeys.self)
+        try container.encode(self.`default`, forKey: .`default`)
+        try container.encode(self.low, forKey: .low)
+        try container.encode(self.unit, forKey: .unit)
+        try container.encode(self.summary, forKey: .summary)
+        try container.encode(self.rainByHour, forKey: .rainByHour)
+        try container.encode(self.readings, forKey: .readings)
+        if !self.flags.isEmpty {
+            try container.encode(self.flags, forKey: .flags)
+        }
+        try container.encodeIfPresent(self.warning, forKey: .warning)
+        try container.encode(self.cached, forKey: .cached)
+        try container.encode(self.pressure, forKey: .pressure)
+        try container.encode(self.span, forKey: .span)
+        try self.place.encode(to: encoder)
+    }
+}
+
+/// Any JSON value.
+enum JSONValue: Hashable {
+    case null
+    case bool(Bool)
+    case number(Double)
+    case string(String)
+    case array([JSONValue])
+    case object([String: JSONValue])
+}
+
+extension JSONValue: Codable {
+    init(from decoder: Decoder) throws {
+        let container = try decoder.singleValueContainer()
+        if container.decodeNil() {
+            self = .null
+        } else if let value = try? container.decode(Bool.self) {
+            self = .bool(value)
+        } else if let value = try? container.decode(Double.self) {
+            self = .number(value)
+        } else if let value = try? container.decode(String.self) {
+            self = .string(value)
+        } else if let value = try? container.decode([JSONValue].self) {
+            self = .array(value)
+        } else {
+            self = .object(try container.decode([String: JSONValue].self))
+        }
+    }
+
+    func encode(to encoder: Encoder) throws {
+        var container = encoder.singleValueContainer()
+        switch self {
+        case .null:
+            try container.encodeNil()
+        case let .bool(value):
+            try container.encode(value)
+        case let .number(value):
+            try container.encode(value)
+        case let .string(value):
+            try container.encode(value)
+        case let .array(value):
+            try container.encode(value)
+        case let .object(value):
+            try container.encode(value)
+        }
+    }
+}
+"#
+    );
+}
+
+#[derive(SerdeGeneric)]
+struct Studio {
+    city: String,
+}
+
+/// A playback state.
+#[derive(SerdeGeneric)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Playback<T> {
+    Empty,
+    /// A looping section.
+    Looping {
+        times: T,
+    },
+    Queued(Studio),
+}
+
+#[derive(SerdeGeneric)]
+#[serde(tag = "t", content = "c")]
+enum Cue {
+    Beep,
+    Text(String),
+    Retime { id: u64, text: String },
+}
+
+#[derive(SerdeGeneric)]
+enum Control {
+    Pause,
+    Seek(u8, u8),
+    Volume {
+        #[serde(alias = "lvl")]
+        level: i16,
+    },
+    #[serde(untagged)]
+    Custom(String),
+}
+
+#[derive(SerdeGeneric)]
+#[serde(untagged)]
+enum Source {
+    Silence,
+    Stream(f64),
+    File { name: String },
+}
+
+#[derive(SerdeGeneric)]
+struct Player {
+    playbacks: Vec<Playback<f64>>,
+    cue: Cue,
+    control: Control,
+    source: Source,
+}
+
+#[test]
+fn enums() {
+    assert_eq!(
+        source_for::<Player, _>(),
+        r#"struct Studio {
+    var city: String
+}
+
+extension Studio: Codable {
+    enum CodingKeys: String, CodingKey {
+        case city
+    }
+
+    init(from decoder: Decoder) throws {
+        let container = try decoder.container(keyedBy: CodingKeys.self)
+        self.city = try container.decode(String.self, forKey: .city)
+    }
+
+    func encode(to encoder: Encoder) throws {
+        var container = encoder.container(keyedBy: CodingKeys.self)
+        try container.encode(self.city, forKey: .city)
+    }
+}
+
+/// A playback state.
+enum Playback<X0: Codable> {
+    case empty
+    /// A looping section.
+    case looping(times: X0)
+    case queued(Studio)
+}
+
+extension Playback: Codable {
+    enum CodingKeys: String, CodingKey {
+        case type
+        case times
+    }
+
+    init(from decoder: Decoder) throws {
+        let container = try decoder.container(keyedBy: CodingKeys.self)
+        switch try container.decode(String.self, forKey: .type) {
+        case "empty":
+            self = .empty
+        case "looping":
+            self = .looping(times: try container.decode(X0.self, forKey: .times))
+        case "queued":
+            self = .queued(try Studio(from: decoder))
+        case let tag:
+            throw DecodingError.dataCorrupted(.init(codingPath: decoder.codingPath, debugDescription: "unknown variant \(tag)"))
+        }
+    }
+
+    func encode(to encoder: Encoder) throws {
+        switch self {
+        case .empty:
+            var container = encoder.container(keyedBy: CodingKeys.self)
+            try container.encode("empty", forKey: .type)
+        case let .looping(times):
+            var container = encoder.container(keyedBy: CodingKeys.self)
+            try container.encode("looping", forKey: .type)
+            try container.encode(times, forKey: .times)
+        case let .queued(value):
+            var container = encoder.container(keyedBy: CodingKeys.self)
+            try container.encode("queued", forKey: .type)
+            try value.encode(to: encoder)
+        }
+    }
+}
+
+enum Cue {
+    case beep
+    case text(String)
+    case retime(id: UInt64, text: String)
+}
+
+extension Cue: Codable {
+    enum CodingKeys: String, CodingKey {
+        case t
+        case c
+        case id
+        case text
+    }
+
+    init(from decoder: Decoder) throws {
+        let container = try decoder.container(keyedBy: CodingKeys.self)
+        switch try container.decode(String.self, forKey: .t) {
+        case "Beep":
+            self = .beep
+        case "Text":
+            self = .text(try container.decode(String.self, forKey: .c))
+        case "Retime":
+            let contentDecoder = try container.superDecoder(forKey: .c)
+            let fields = try contentDecoder.container(keyedBy: CodingKeys.self)
+            self = .retime(id: try fields.decode(UInt64.self, forKey: .id), text: try fields.decode(String.self, forKey: .text))
+        case let tag:
+            throw DecodingError.dataCorrupted(.init(codingPath: decoder.codingPath, debugDescription: "unknown variant \(tag)"))
+        }
+    }
+
+    func encode(to encoder: Encoder) throws {
+        switch self {
+        case .beep:
+            var container = encoder.container(keyedBy: CodingKeys.self)
+            try container.encode("Beep", forKey: .t)
+        case let .text(value):
+            var container = encoder.container(keyedBy: CodingKeys.self)
+            try container.encode("Text", forKey: .t)
+            try container.encode(value, forKey: .c)
+        case let .retime(id, text):
+            var container = encoder.container(keyedBy: CodingKeys.self)
+            try container.encode("Retime", forKey: .t)
+            let contentEncoder = container.superEncoder(forKey: .c)
+            var fields = contentEncoder.container(keyedBy: CodingKeys.self)
+            try fields.encode(id, forKey: .id)
+            try fields.encode(text, forKey: .text)
+        }
+    }
+}
+
+enum Control {
+    case pause
+    case seek(UInt8, UInt8)
+    case volume(level: Int16)
+    case custom(String)
+}
+
+extension Control: Codable {
+    enum CodingKeys: String, CodingKey {
+        case seek = "Seek"
+        case level
+        case volume = "Volume"
+        case lvl
+    }
+
+    init(from decoder: Decoder) throws {
+        do {
+            if let tag = try? decoder.singleValueContainer().decode(String.self) {
+                switch tag {
+                case "Pause":
+                    self = .pause
+                default:
+                    throw DecodingError.dataCorrupted(.init(codingPath: decoder.codingPath, debugDescription: "unknown variant \(tag)"))
+                }
+                return
+            }
+            let container = try decoder.container(keyedBy: CodingKeys.self)
+            if container.contains(.seek) {
+                var values = try container.nestedUnkeyedContainer(forKey: .seek)
+                self = .seek(try values.decode(UInt8.self), try values.decode(UInt8.self))
+            } else if container.contains(.volume) {
+                let contentDecoder = try container.superDecoder(forKey: .volume)
+                let fields = try contentDecoder.container(keyedBy: CodingKeys.self)
+                self = .volume(level: try fields.decode(Int16.self, forKey: [CodingKeys.level, .lvl].first(where: fields.contains) ?? .level))
+            } else {
+                throw DecodingError.dataCorrupted(.init(codingPath: decoder.codingPath, debugDescription: "unknown variant \(container.allKeys)"))
+            }
+            return
+        } catch {}
+        do {
+            self = .custom(try String(from: decoder))
+            return
+        } catch {}
+        throw DecodingError.dataCorrupted(.init(codingPath: decoder.codingPath, debugDescription: "data did not match any variant of untagged enum Control"))
+    }
+
+    func encode(to encoder: Encoder) throws {
+        switch self {
+        case .pause:
+            var container = encoder.singleValueContainer()
+            try container.encode("Pause")
+        case let .seek(field0, field1):
+            var container = encoder.container(keyedBy: CodingKeys.self)
+            var values = container.nestedUnkeyedContainer(forKey: .seek)
+            try values.encode(field0)
+            try values.encode(field1)
+        case let .volume(level):
+            var container = encoder.container(keyedBy: CodingKeys.self)
+            let contentEncoder = container.superEncoder(forKey: .volume)
+            var fields = contentEncoder.container(keyedBy: CodingKeys.self)
+            try fields.encode(level, forKey: .level)
+        case let .custom(value):
+            try value.encode(to: encoder)
+        }
+    }
+}
+
+enum Source {
+    case silence
+    case stream(Double)
+    case file(name: String)
+}
+
+extension Source: Codable {
+    enum CodingKeys: String, CodingKey {
+        case name
+    }
+
+    init(from decoder: Decoder) throws {
+        do {
+            if try decoder.singleValueContainer().decodeNil() {
+                self = .silence
+                return
+            }
+        } catch {}
+        do {
+            self = .stream(try Double(from: decoder))
+            return
+        } catch {}
+        do {
+            let fields = try decoder.container(keyedBy: CodingKeys.self)
+            self = .file(name: try fields.decode(String.self, forKey: .name))
+            return
+        } catch {}
+        throw DecodingError.dataCorrupted(.init(codingPath: decoder.codingPath, debugDescription: "data did not match any variant of untagged enum Source"))
+    }
+
+    func encode(to encoder: Encoder) throws {
+        switch self {
+        case .silence:
+            var container = encoder.singleValueContainer()
+            try container.encodeNil()
+        case let .stream(value):
+            try value.encode(to: encoder)
+        case let .file(name):
+            var fields = encoder.container(keyedBy: CodingKeys.self)
+            try fields.encode(name, forKey: .name)
+        }
+    }
+}
+
+struct Player {
+    var playbacks: [Playback<Double>]
+    var cue: Cue
+    var control: Control
+    var source: Source
+}
+
+extension Player: Codable {
+    enum CodingKeys: String, CodingKey {
+        case playbacks
+        case cue
+        case control
+        case source
+    }
+
+    init(from decoder: Decoder) throws {
+        let container = try decoder.container(keyedBy: CodingKeys.self)
+        self.playbacks = try container.decode([Playback<Double>].self, forKey: .playbacks)
+        self.cue = try container.decode(Cue.self, forKey: .cue)
+        self.control = try container.decode(Control.self, forKey: .control)
+        self.source = try container.decode(Source.self, forKey: .source)
+    }
+
+    func encode(to encoder: Encoder) throws {
+        var container = encoder.container(keyedBy: CodingKeys.self)
+        try container.encode(self.playbacks, forKey: .playbacks)
+        try container.encode(self.cue, forKey: .cue)
+        try container.encode(self.control, forKey: .control)
+        try container.encode(self.source, forKey: .source)
+    }
+}
+"#
+    );
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::disallowed_names)]
+
 use serde_generic::SerdeGeneric;
 
 #[derive(SerdeGeneric, Debug, Clone, PartialEq)]
@@ -23,6 +25,40 @@ enum Xoo<X, Y> {
 #[derive(SerdeGeneric, Debug, Clone, PartialEq)]
 struct Yoo;
 
+#[derive(SerdeGeneric, Debug, Clone, PartialEq)]
+struct Zoo<'a, 'b: 'a, X, Y> {
+    s: &'a str,
+    x: X,
+    bs: &'b [Y],
+}
+
+#[derive(SerdeGeneric, Debug, Clone, PartialEq)]
+struct Buf<X, const N: usize, const SIGNED: bool> {
+    data: [X; N],
+}
+
+#[derive(SerdeGeneric, Debug, Clone, PartialEq)]
+struct Bounded<X: std::fmt::Display + Ord, Y>
+where
+    Y: Default + std::hash::Hash,
+{
+    x: X,
+    y: Y,
+}
+
+#[derive(SerdeGeneric, Debug, Clone, PartialEq)]
+#[serde_generic(mock(I = "std::iter::Empty<serde_generic::TypeVar<serde_generic::Zero>>"))]
+enum Items<I: Iterator, Y> {
+    Next(Option<I::Item>, Y),
+    Done,
+}
+
+#[derive(SerdeGeneric, Debug, Clone, PartialEq)]
+#[serde_generic(no_mock)]
+struct Opaque<I: Iterator<Item = u8>> {
+    items: I,
+}
+
 // helper trait to assert type equality
 trait TypeEq<A, B> {
     const YES: bool = true;
@@ -81,7 +117,294 @@ fn types_derivations() {
     >>::YES;
     <() as TypeEq<<Boo<Boo<bool>> as SerdeGeneric>::Mocked, Boo<TypeVar<Zero>>>>::YES;
     <() as TypeEq<<Boo<Boo<bool>> as SerdeGeneric>::Params, (Boo<bool>, ())>>::YES;
+
+    // Zoo: lifetimes are kept out of Params, but preserved in Mocked and Repr
+    fn zoo<'a, 'b: 'a>() {
+        <() as TypeEq<
+            <Zoo<'a, 'b, bool, u32> as SerdeGeneric>::Repr,
+            NamedStruct<HCons<&'a str, HCons<bool, HCons<&'b [u32], HNil>>>>,
+        >>::YES;
+        <() as TypeEq<
+            <Zoo<'a, 'b, bool, u32> as SerdeGeneric>::Mocked,
+            Zoo<'a, 'b, TypeVar<Zero>, TypeVar<Succ<Zero>>>,
+        >>::YES;
+        <() as TypeEq<<Zoo<'a, 'b, bool, u32> as SerdeGeneric>::Params, (bool, (u32, ()))>>::YES;
+    }
+    zoo();
+
+    // Buf: const params are kept out of Params and left as is in Mocked
+    <() as TypeEq<<Buf<u8, 4, true> as SerdeGeneric>::Repr, NamedStruct<HCons<[u8; 4], HNil>>>>::YES;
+    <() as TypeEq<<Buf<u8, 4, true> as SerdeGeneric>::Mocked, Buf<TypeVar<Zero>, 4, true>>>::YES;
+    <() as TypeEq<<Buf<u8, 4, true> as SerdeGeneric>::Params, (u8, ())>>::YES;
+    assert_eq!(
+        <Buf<u8, 4, true> as SerdeGeneric>::CONST_PARAMS,
+        [
+            ConstParam {
+                name: "N",
+                ty: "usize",
+                value: ConstValue::Unsigned(4),
+            },
+            ConstParam {
+                name: "SIGNED",
+                ty: "bool",
+                value: ConstValue::Bool(true),
+            },
+        ]
+    );
+    assert!(<Foo<u8, u8> as SerdeGeneric>::CONST_PARAMS.is_empty());
+    assert_eq!(<Foo<u8, u8> as SerdeGeneric>::TYPE_PARAMS, ["X", "Y"]);
+    assert_eq!(<Zoo<u8, u8> as SerdeGeneric>::TYPE_PARAMS, ["X", "Y"]);
+
+    // bounded type params are mocked with TypeVar, an override or not at all
+    <() as TypeEq<
+        <Bounded<String, u8> as SerdeGeneric>::Mocked,
+        Bounded<TypeVar<Zero>, TypeVar<Succ<Zero>>>,
+    >>::YES;
+    <() as TypeEq<
+        <Items<std::vec::IntoIter<u8>, bool> as SerdeGeneric>::Mocked,
+        Items<std::iter::Empty<TypeVar<Zero>>, TypeVar<Succ<Zero>>>,
+    >>::YES;
+    <() as TypeEq<
+        <<Items<std::vec::IntoIter<u8>, bool> as SerdeGeneric>::Mocked as SerdeGeneric>::Repr,
+        Enum<
+            HSum<
+                UnnamedStruct<HCons<Option<TypeVar<Zero>>, HCons<TypeVar<Succ<Zero>>, HNil>>>,
+                HSum<UnitStruct, HSumNil>,
+            >,
+        >,
+    >>::YES;
+    <() as TypeEq<
+        <Opaque<std::vec::IntoIter<u8>> as SerdeGeneric>::Mocked,
+        Opaque<std::vec::IntoIter<u8>>,
+    >>::YES;
+    assert_eq!(TypeVar(Succ::<Zero>::default()).to_string(), "X1");
 }
+
+struct VariantNames;
+impl<S, H, I, M> serde_generic::traverse::VariantVisitor<S, H, I, M> for VariantNames
+where
+    H: serde_generic::HLen
+        + serde_generic::traverse::Traverse<
+            serde_generic::traverse::VariantFields<S, I, FieldNames>,
+            Vec<String>,
+            H::Len,
+            M,
+        >,
+{
+    type Collector = Vec<String>;
+    fn visit(names: &mut Self::Collector, variant: &serde_generic::serde::Variant) {
+        let mut fields = Vec::new();
+        H::traverse(&mut fields);
+        names.push(format!("{}({})", variant.name.serialize, fields.join(",")));
+    }
+}
+
+struct FieldNames;
+impl<S, F, I> serde_generic::traverse::FieldVisitor<S, F, I, ()> for FieldNames {
+    type Collector = Vec<String>;
+    fn visit(names: &mut Self::Collector, field: &serde_generic::serde::Field<S, F>) {
+        names.push(field.name.serialize.to_owned());
+    }
+}
+
+#[test]
+fn traverse_variants() {
+    use serde_generic::traverse::*;
+    use serde_generic::*;
+
+    type X = Xoo<bool, u32>;
+    let mut names = Vec::new();
+    <<X as SerdeGeneric>::Repr as Traverse<Variants<X, VariantNames>, _, _, _>>::traverse(
+        &mut names,
+    );
+    assert_eq!(names, ["lek(0)", "pek()", "kek(fes_o,les_i)"]);
+    let rules = <X as SerdeGeneric>::CONTAINER.rename_all_rules;
+    assert_eq!(rules.serialize, serde::RenameRule::CamelCase);
+    let rules = <X as SerdeVariantAttr<Succ<Zero>>>::VARIANT.rename_all_rules;
+    assert_eq!(rules.serialize, serde::RenameRule::None);
+
+    type F = Foo<bool, u32>;
+    let mut names = Vec::new();
+    <<F as SerdeGeneric>::Repr as Traverse<StructFields<F, FieldNames>, _, _, _>>::traverse(
+        &mut names,
+    );
+    assert_eq!(names, ["foo", "boo", "xoo", "yoo"]);
+}
+
+pub mod vis {
+    #[derive(serde_generic::SerdeGeneric)]
+    pub struct Vis {
+        pub a: u8,
+        pub(crate) b: Vec<u8>,
+        pub(super) c: Option<String>,
+        d: (),
+    }
+}
+
+#[test]
+fn rust_metadata() {
+    use serde_generic::rust::Visibility;
+    use serde_generic::*;
+
+    let field = <vis::Vis as SerdeFieldAttr<u8, Succ<Succ<Succ<Succ<Zero>>>>>>::FIELD.rust;
+    assert_eq!(field.ident, Some("a"));
+    assert_eq!(field.index, 0);
+    assert_eq!(field.ty, "u8");
+    assert_eq!(field.vis, Visibility::Public);
+    let field = <vis::Vis as SerdeFieldAttr<Vec<u8>, Succ<Succ<Succ<Zero>>>>>::FIELD.rust;
+    assert_eq!((field.ty, field.vis), ("Vec < u8 >", Visibility::Crate));
+    let field = <vis::Vis as SerdeFieldAttr<Option<String>, Succ<Succ<Zero>>>>::FIELD.rust;
+    assert_eq!(field.vis, Visibility::Restricted("super"));
+    let field = <vis::Vis as SerdeFieldAttr<(), Succ<Zero>>>::FIELD.rust;
+    assert_eq!((field.index, field.vis), (3, Visibility::Inherited));
+
+    let field = <Boo<bool> as SerdeFieldAttr<bool, Succ<Zero>>>::FIELD.rust;
+    assert_eq!((field.ident, field.index, field.ty), (None, 2, "X"));
+
+    let variant = <Xoo<bool, u32> as SerdeVariantAttr<Succ<Zero>>>::VARIANT.rust;
+    assert_eq!((variant.ident, variant.index), ("Kek", 2));
+    let field = <Xoo<bool, u32> as SerdeVariantFieldAttr<u32, Succ<Zero>, Succ<Zero>>>::FIELD.rust;
+    assert_eq!(field.ident, Some("les_i"));
+    assert_eq!((field.index, field.ty), (1, "Y"));
+}
+
+/// A documented
+/// enum.
+///
+/// With a paragraph.
+#[derive(SerdeGeneric)]
+enum Documented {
+    /// Has a field.
+    Field {
+        #[doc = "  indented"]
+        #[doc = "multi\nline"]
+        field: u8,
+    },
+    Plain,
+}
+
+#[test]
+fn doc_comments() {
+    use serde_generic::*;
+
+    assert_eq!(
+        Documented::CONTAINER.doc,
+        Some("A documented\nenum.\n\nWith a paragraph.")
+    );
+    assert_eq!(
+        <Documented as SerdeVariantAttr<Succ<Succ<Zero>>>>::VARIANT.doc,
+        Some("Has a field.")
+    );
+    assert_eq!(
+        <Documented as SerdeVariantAttr<Succ<Zero>>>::VARIANT.doc,
+        None
+    );
+    assert_eq!(
+        <Documented as SerdeVariantFieldAttr<u8, Succ<Succ<Zero>>, Succ<Zero>>>::FIELD.doc,
+        Some(" indented\nmulti\nline")
+    );
+    assert_eq!(Yoo::CONTAINER.doc, None);
+}
+
+#[derive(SerdeGeneric)]
+#[serde(bound = "X: Clone")]
+enum Borrowing<'a, X> {
+    #[serde(borrow, bound(deserialize = ""))]
+    Cow(std::borrow::Cow<'a, str>),
+    Field {
+        #[serde(bound(serialize = "X: std::fmt::Debug, X: Copy"))]
+        x: X,
+    },
+}
+
+#[test]
+fn bounds_and_borrows() {
+    use serde_generic::*;
+
+    let bound = Borrowing::<bool>::CONTAINER.bound;
+    assert_eq!(bound.serialize, Some(&["X : Clone"][..]));
+    assert_eq!(bound.serialize, bound.deserialize);
+    let bound = <Borrowing<bool> as SerdeVariantAttr<Succ<Succ<Zero>>>>::VARIANT.bound;
+    assert_eq!((bound.serialize, bound.deserialize), (None, Some(&[][..])));
+    let field = <Borrowing<bool> as SerdeVariantFieldAttr<
+        std::borrow::Cow<str>,
+        Succ<Succ<Zero>>,
+        Succ<Zero>,
+    >>::FIELD;
+    assert_eq!(field.borrowed_lifetimes, ["'a"]);
+    let field = <Borrowing<bool> as SerdeVariantFieldAttr<bool, Succ<Zero>, Succ<Zero>>>::FIELD;
+    assert_eq!(
+        field.bound.serialize,
+        Some(&["X : std :: fmt :: Debug", "X : Copy"][..])
+    );
+    assert_eq!(
+        (field.bound.deserialize, field.borrowed_lifetimes),
+        (None, &[][..])
+    );
+
+    let field = <Zoo<u8, u8> as SerdeFieldAttr<&str, Succ<Succ<Succ<Zero>>>>>::FIELD;
+    assert_eq!(field.borrowed_lifetimes, ["'a"]);
+    let field = <Zoo<u8, u8> as SerdeFieldAttr<&[u8], Succ<Zero>>>::FIELD;
+    assert!(field.borrowed_lifetimes.is_empty());
+}
+
+fn pair_to_string<S: serde::Serializer>(
+    a: &u8,
+    b: &String,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{a}{b}"))
+}
+
+fn pair_from_str<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<(u8, String), D::Error> {
+    let text = <String as serde::Deserialize>::deserialize(deserializer)?;
+    let (a, b) = text.split_at(1);
+    Ok((a.parse().map_err(serde::de::Error::custom)?, b.to_owned()))
+}
+
+fn number_to_string<S: serde::Serializer>(number: &u8, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(number)
+}
+
+#[derive(SerdeGeneric)]
+enum WithFns {
+    #[serde(serialize_with = "pair_to_string", deserialize_with = "pair_from_str")]
+    Pair(u8, String),
+    Plain {
+        #[serde(serialize_with = "number_to_string")]
+        number: u8,
+    },
+}
+
+#[test]
+fn with_fns() {
+    use serde_generic::*;
+    use serde_json::{json, value::Serializer};
+
+    type Pair = UnnamedStruct<HCons<u8, HCons<String, HNil>>>;
+    let repr = UnnamedStruct(HCons(1, HCons("x".to_owned(), HNil)));
+    let serialize =
+        <WithFns as SerdeVariantWith<Pair, Succ<Succ<Zero>>>>::serialize_with().unwrap();
+    assert_eq!(serialize(&repr, Serializer).unwrap(), json!("1x"));
+    let deserialize =
+        <WithFns as SerdeVariantWith<Pair, Succ<Succ<Zero>>>>::deserialize_with().unwrap();
+    assert_eq!(
+        deserialize(json!("2y")).unwrap(),
+        UnnamedStruct(HCons(2, HCons("y".to_owned(), HNil)))
+    );
+
+    let serialize =
+        <WithFns as SerdeVariantFieldWith<u8, Succ<Zero>, Succ<Zero>>>::serialize_with().unwrap();
+    assert_eq!(serialize(&3, Serializer).unwrap(), json!("3"));
+    let deserialize =
+        <WithFns as SerdeVariantFieldWith<u8, Succ<Zero>, Succ<Zero>>>::deserialize_with::<
+            serde_json::Value,
+        >();
+    assert!(deserialize.is_none());
+}
+
 /*
 enum Xoo<X, Y> {
     Lek(X),
@@ -0,0 +1,215 @@
+use serde_generic::SerdeGeneric;
+use serde_generic::reflect::Registry;
+use serde_generic::typescript::{declarations, declarations_for};
+use std::collections::HashMap;
+
+/// A page of items.
+#[derive(SerdeGeneric)]
+#[serde(rename_all = "camelCase")]
+struct Page<T, M> {
+    /// Items of the page,
+    /// in order.
+    items: Vec<Option<T>>,
+    #[serde(default)]
+    total_count: u64,
+    #[serde(flatten)]
+    meta: M,
+    #[serde(rename = "next-page")]
+    next_page: Option<String>,
+}
+
+#[derive(SerdeGeneric)]
+struct Meta {
+    tags: HashMap<String, (bool, char)>,
+}
+
+#[derive(SerdeGeneric)]
+#[serde(transparent)]
+struct Id(u32);
+
+#[derive(SerdeGeneric)]
+enum External<X> {
+    Unit,
+    Newtype(X),
+    Tuple(u8, X),
+    Struct { id: Id },
+}
+
+#[derive(SerdeGeneric)]
+#[serde(tag = "type")]
+enum Internal {
+    Unit,
+    Struct {
+        a: bool,
+    },
+    /// A newtype.
+    Newtype(Meta),
+}
+
+#[derive(SerdeGeneric)]
+#[serde(tag = "t", content = "c")]
+enum Adjacent<X> {
+    Unit,
+    Newtype(X),
+}
+
+#[derive(SerdeGeneric)]
+#[serde(untagged)]
+enum Untagged {
+    Unit,
+    Newtype(String),
+    #[serde(skip)]
+    #[allow(dead_code)]
+    Skipped,
+}
+
+#[test]
+fn structs() {
+    assert_eq!(
+        declarations_for::<Page<Id, Meta>, _>(),
+        "\
+export type Id = number;
+
+export type Meta = {
+  tags: Record<string, [boolean, string]>;
+};
+
+/** A page of items. */
+export type Page<T, M> = {
+  /**
+   * Items of the page,
+   * in order.
+   */
+  items: Array<T | null>;
+  totalCount?: number;
+  \"next-page\": string | null;
+} & M;
+"
+    );
+}
+
+#[test]
+fn enums() {
+    let mut registry = Registry::new();
+    registry.register::<External<u8>, _>();
+    registry.register::<External<String>, _>();
+    registry.register::<Internal, _>();
+    registry.register::<Adjacent<Id>, _>();
+    registry.register::<Untagged, _>();
+    assert_eq!(
+        declarations(&registry),
+        "\
+export type Id = number;
+
+export type External<X> =
+  | \"Unit\"
+  | { Newtype: X }
+  | { Tuple: [number, X] }
+  | { Struct: { id: Id } };
+
+export type Meta = {
+  tags: Record<string, [boolean, string]>;
+};
+
+export type Internal =
+  | { type: \"Unit\" }
+  | { type: \"Struct\"; a: boolean }
+  /** A newtype. */
+  | { type: \"Newtype\" } & Meta;
+
+export type Adjacent<X> =
+  | { t: \"Unit\" }
+  | { t: \"Newtype\"; c: X };
+
+export type Untagged =
+  | null
+  | string;
+"
+    );
+}
+
+/// Ends a comment: */
+#[derive(SerdeGeneric)]
+#[serde(rename = "my-type")]
+#[allow(dead_code)]
+struct Renamed {
+    id: Id,
+}
+
+#[derive(SerdeGeneric)]
+#[serde(rename = "string")]
+#[allow(dead_code)]
+struct Holder {
+    renamed: Renamed,
+}
+
+#[test]
+fn names() {
+    assert_eq!(
+        declarations_for::<Holder, _>(),
+        "\
+export type Id = number;
+
+/** Ends a comment: *\\/ */
+export type My_type = {
+  id: Id;
+};
+
+export type String = {
+  renamed: My_type;
+};
+"
+    );
+}
+
+mod other {
+    #[derive(serde_generic::SerdeGeneric)]
+    #[allow(dead_code)]
+    pub struct Meta {
+        pub page: u32,
+    }
+}
+
+#[derive(SerdeGeneric)]
+#[serde(rename = "both-named")]
+struct Dashed;
+
+#[derive(SerdeGeneric)]
+#[serde(rename = "both_named")]
+struct Underscored;
+
+#[derive(SerdeGeneric)]
+#[allow(dead_code)]
+struct Both {
+    meta: Meta,
+    other: other::Meta,
+    dashed: Dashed,
+    underscored: Underscored,
+}
+
+#[test]
+fn same_names() {
+    assert_eq!(
+        declarations_for::<Both, _>(),
+        "\
+export type Meta = {
+  tags: Record<string, [boolean, string]>;
+};
+
+export type Meta2 = {
+  page: number;
+};
+
+export type Both_named = null;
+
+export type Both_named2 = null;
+
+export type Both = {
+  meta: Meta;
+  other: Meta2;
+  dashed: Both_named;
+  underscored: Both_named2;
+};
+"
+    );
+}
@@ -0,0 +1,259 @@
+//! Type-level traversal of heterogeneous lists.
+//!
+//! [`Traverse`] walks a type list (`HCons`/`HNil`, `(H, T)`/`()` or
+//! `HSum`/`HSumNil`) and applies a [`Traverser`] to every element type.
+//! Indices are Peano numbers counted from the end of the list, which is
+//! how the derive numbers fields and variants in [`SerdeFieldAttr`],
+//! [`SerdeVariantAttr`] and [`SerdeVariantFieldAttr`].
+//!
+//! The `Q` parameter is a list of per-element "proofs": it lets a traverser
+//! pick between several implementations for an element type (for example a
+//! manual one for primitives and a generic one for `SerdeGeneric` types) and
+//! is normally left to type inference.
+//!
+//! Ready-made traversers look up `serde` metadata of each element and pass it
+//! to a visitor: [`StructFields`], [`VariantFields`], [`Variants`] and
+//! [`TypeParams`].
+
+#[cfg(feature = "serde")]
+use crate::{DeserializeWith, SerdeFieldWith, SerdeVariantFieldWith, SerializeWith};
+use crate::{
+    Enum, GenericFieldAttr, GenericVariantFieldAttr, HCons, HNil, HSum, HSumNil, NamedStruct,
+    SerdeFieldAttr, SerdeFieldDefault, SerdeVariantAttr, SerdeVariantFieldAttr,
+    SerdeVariantFieldDefault, Succ, UnitStruct, UnnamedStruct, Zero,
+};
+use crate::{attr, serde};
+use core::marker::PhantomData;
+
+/// Action applied to an element `H` at index `I` of a type list.
+pub trait Traverser<H, I, Q> {
+    type Collector;
+    fn apply(state: &mut Self::Collector);
+}
+
+/// Type list which can be walked with traverser `TR`.
+pub trait Traverse<TR, C, Idx, Q> {
+    fn traverse(state: &mut C);
+}
+
+impl<H, T, TR, C, Q, QT, I> Traverse<TR, C, Succ<I>, (Q, QT)> for (H, T)
+where
+    TR: Traverser<H, Succ<I>, Q, Collector = C>,
+    T: Traverse<TR, C, I, QT>,
+{
+    fn traverse(state: &mut C) {
+        TR::apply(state);
+        T::traverse(state);
+    }
+}
+
+impl<H, T, TR, C, Q, QT, I> Traverse<TR, C, Succ<I>, (Q, QT)> for HCons<H, T>
+where
+    TR: Traverser<H, Succ<I>, Q, Collector = C>,
+    T: Traverse<TR, C, I, QT>,
+{
+    fn traverse(state: &mut C) {
+        TR::apply(state);
+        T::traverse(state);
+    }
+}
+
+impl<H, T, TR, C, Q, QT, I> Traverse<TR, C, Succ<I>, (Q, QT)> for HSum<H, T>
+where
+    TR: Traverser<H, Succ<I>, Q, Collector = C>,
+    T: Traverse<TR, C, I, QT>,
+{
+    fn traverse(state: &mut C) {
+        TR::apply(state);
+        T::traverse(state);
+    }
+}
+
+impl<TR, C> Traverse<TR, C, Zero, ()> for () {
+    fn traverse(_state: &mut C) {}
+}
+
+impl<TR, C> Traverse<TR, C, Zero, ()> for HNil {
+    fn traverse(_state: &mut C) {}
+}
+
+impl<TR, C> Traverse<TR, C, Zero, ()> for HSumNil {
+    fn traverse(_state: &mut C) {}
+}
+
+// Representation wrappers are traversed as the list they wrap,
+// so `Repr` of a type can be walked directly.
+
+impl<X: Traverse<TR, C, I, Q>, TR, C, I, Q> Traverse<TR, C, I, Q> for NamedStruct<X> {
+    fn traverse(state: &mut C) {
+        X::traverse(state);
+    }
+}
+
+impl<X: Traverse<TR, C, I, Q>, TR, C, I, Q> Traverse<TR, C, I, Q> for UnnamedStruct<X> {
+    fn traverse(state: &mut C) {
+        X::traverse(state);
+    }
+}
+
+impl<X: Traverse<TR, C, I, Q>, TR, C, I, Q> Traverse<TR, C, I, Q> for Enum<X> {
+    fn traverse(state: &mut C) {
+        X::traverse(state);
+    }
+}
+
+impl<TR, C> Traverse<TR, C, Zero, ()> for UnitStruct {
+    fn traverse(_state: &mut C) {}
+}
+
+/// Source of field metadata.
+///
+/// Abstracts over fields of a struct ([`StructField`]) and fields of an enum
+/// variant ([`VariantField`]), so the same code can handle both.
+pub trait FieldAttrs<F, I> {
+    /// Type that owns the field.
+    type Container;
+    const FIELD: serde::Field<Self::Container, F>;
+}
+
+/// Fields of struct `S`, as described by [`SerdeFieldAttr`].
+pub struct StructField<S>(PhantomData<S>);
+
+impl<S: SerdeFieldAttr<F, I>, F, I> FieldAttrs<F, I> for StructField<S> {
+    type Container = S;
+    const FIELD: serde::Field<S, F> = S::FIELD;
+}
+
+/// Fields of variant `VI` of enum `S`, as described by [`SerdeVariantFieldAttr`].
+pub struct VariantField<S, VI>(PhantomData<(S, VI)>);
+
+impl<S: SerdeVariantFieldAttr<F, VI, I>, F, VI, I> FieldAttrs<F, I> for VariantField<S, VI> {
+    type Container = S;
+    const FIELD: serde::Field<S, F> = S::FIELD;
+}
+
+/// Source of field default constructors, see [`FieldAttrs`].
+pub trait FieldDefaults<F, I> {
+    const DEFAULT: Option<fn() -> F>;
+}
+
+impl<S: SerdeFieldDefault<F, I>, F, I> FieldDefaults<F, I> for StructField<S> {
+    const DEFAULT: Option<fn() -> F> = S::DEFAULT;
+}
+
+impl<S: SerdeVariantFieldDefault<F, VI, I>, F, VI, I> FieldDefaults<F, I> for VariantField<S, VI> {
+    const DEFAULT: Option<fn() -> F> = S::DEFAULT;
+}
+
+/// Source of field `#[serde_generic(...)]` attributes, see [`FieldAttrs`].
+pub trait FieldGenericAttrs<F, I> {
+    const GENERIC: attr::Field;
+}
+
+impl<S: GenericFieldAttr<F, I>, F, I> FieldGenericAttrs<F, I> for StructField<S> {
+    const GENERIC: attr::Field = S::FIELD;
+}
+
+impl<S: GenericVariantFieldAttr<F, VI, I>, F, VI, I> FieldGenericAttrs<F, I>
+    for VariantField<S, VI>
+{
+    const GENERIC: attr::Field = S::FIELD;
+}
+
+/// Source of field `serialize_with` and `deserialize_with` functions, see
+/// [`FieldAttrs`].
+#[cfg(feature = "serde")]
+pub trait FieldWith<F, I> {
+    fn serialize_with<S: ::serde::Serializer>() -> Option<SerializeWith<F, S>>;
+    fn deserialize_with<'de, D: ::serde::Deserializer<'de>>() -> Option<DeserializeWith<'de, F, D>>;
+}
+
+#[cfg(feature = "serde")]
+impl<S: SerdeFieldWith<F, I>, F, I> FieldWith<F, I> for StructField<S> {
+    fn serialize_with<Ser: ::serde::Serializer>() -> Option<SerializeWith<F, Ser>> {
+        S::serialize_with()
+    }
+    fn deserialize_with<'de, D: ::serde::Deserializer<'de>>() -> Option<DeserializeWith<'de, F, D>>
+    {
+        S::deserialize_with()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<S: SerdeVariantFieldWith<F, VI, I>, F, VI, I> FieldWith<F, I> for VariantField<S, VI> {
+    fn serialize_with<Ser: ::serde::Serializer>() -> Option<SerializeWith<F, Ser>> {
+        S::serialize_with()
+    }
+    fn deserialize_with<'de, D: ::serde::Deserializer<'de>>() -> Option<DeserializeWith<'de, F, D>>
+    {
+        S::deserialize_with()
+    }
+}
+
+/// Called by [`Fields`] for every field `F` of container `S`.
+pub trait FieldVisitor<S, F, I, M> {
+    type Collector;
+    fn visit(state: &mut Self::Collector, field: &serde::Field<S, F>);
+}
+
+/// Called by [`Variants`] for every variant of enum `S`. `H` is the
+/// representation of the variant: `NamedStruct`, `UnnamedStruct` or
+/// `UnitStruct`.
+pub trait VariantVisitor<S, H, I, M> {
+    type Collector;
+    fn visit(state: &mut Self::Collector, variant: &serde::Variant);
+}
+
+/// Called by [`TypeParams`] for every type parameter `H`.
+pub trait ParamVisitor<H, I, M> {
+    type Collector;
+    fn visit(state: &mut Self::Collector);
+}
+
+/// Traverser over fields described by `A`, see [`StructFields`] and
+/// [`VariantFields`].
+pub struct Fields<A, V>(PhantomData<(A, V)>);
+
+/// Traverser over fields of struct `S`, both named and tuple ones.
+pub type StructFields<S, V> = Fields<StructField<S>, V>;
+
+/// Traverser over fields of variant `VI` of enum `S`.
+pub type VariantFields<S, VI, V> = Fields<VariantField<S, VI>, V>;
+
+impl<A, V, H, I, M> Traverser<H, I, M> for Fields<A, V>
+where
+    A: FieldAttrs<H, I>,
+    V: FieldVisitor<A::Container, H, I, M>,
+{
+    type Collector = V::Collector;
+    fn apply(state: &mut Self::Collector) {
+        V::visit(state, &A::FIELD);
+    }
+}
+
+/// Traverser over variants of enum `S`.
+pub struct Variants<S, V>(PhantomData<(S, V)>);
+
+impl<S, V, H, I, M> Traverser<H, I, M> for Variants<S, V>
+where
+    S: SerdeVariantAttr<I>,
+    V: VariantVisitor<S, H, I, M>,
+{
+    type Collector = V::Collector;
+    fn apply(state: &mut Self::Collector) {
+        V::visit(state, &S::VARIANT);
+    }
+}
+
+/// Traverser over `SerdeGeneric::Params`.
+pub struct TypeParams<V>(PhantomData<V>);
+
+impl<V, H, I, M> Traverser<H, I, M> for TypeParams<V>
+where
+    V: ParamVisitor<H, I, M>,
+{
+    type Collector = V::Collector;
+    fn apply(state: &mut Self::Collector) {
+        V::visit(state);
+    }
+}
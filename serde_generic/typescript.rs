@@ -0,0 +1,342 @@
+//! TypeScript declarations of reflected types, generics included.
+//!
+//! ```
+//! # use serde_generic::SerdeGeneric;
+//! #[derive(SerdeGeneric)]
+//! struct Page<T> {
+//!     items: Vec<T>,
+//!     #[serde(skip_serializing_if = "Option::is_none")]
+//!     next: Option<String>,
+//! }
+//!
+//! assert_eq!(
+//!     serde_generic::typescript::declarations_for::<Page<u8>, _>(),
+//!     "export type Page<T> = {\n  items: Array<T>;\n  next?: string | null;\n};\n"
+//! );
+//! ```
+//!
+//! Every [`Definition`](crate::reflect::Definition) is declared once with its type parameters, which is
+//! what `Mocked` makes possible, so that `Page<u8>` and `Page<string>` are
+//! both `Page<T>`. Types are named after their serialized names, made
+//! identifiers starting with an uppercase letter, and followed by a number
+//! when two types have the same name, see [`Registry::definition_name`].
+//!
+//! Fields that can be missing either when serializing or deserializing are
+//! optional. Types with `from`, `try_from` or `into` proxies and fields or
+//! variants with `serialize_with` or `deserialize_with` are `unknown`.
+
+use crate::naming::{Comment, comment, string, type_name, unicode};
+use crate::reflect::{
+    Body, Container, Field, Primitive, Reflect, Registry, Shape, TagType, Variant,
+};
+
+/// Declarations of `T` and of the types it refers to.
+pub fn declarations_for<T: ?Sized + Reflect<M>, M>() -> String {
+    let mut registry = Registry::new();
+    registry.register::<T, _>();
+    declarations(&registry)
+}
+
+/// Declarations of all definitions in the registry, in topological order
+/// and separated by blank lines.
+pub fn declarations(registry: &Registry) -> String {
+    registry
+        .definitions()
+        .map(|(id, _)| declaration(registry, id))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `export type` declaration of a registered definition.
+///
+/// # Panics
+///
+/// If no definition with this id is registered.
+pub fn declaration(registry: &Registry, id: u64) -> String {
+    let def = registry.definition(id).expect("registered definition");
+    let container = &def.container;
+    let params = &def.params;
+    let body = match &def.body {
+        _ if container.proxy().is_some() => "unknown".to_owned(),
+        Body::Struct(fields) | Body::Tuple(fields) if container.transparent => {
+            let field = fields
+                .iter()
+                .find(|field| !(field.skip_serializing && field.skip_deserializing))
+                .expect("transparent struct has a field");
+            field_type(registry, field, params)
+        }
+        Body::Struct(fields) => {
+            let (members, flattened) = members(registry, fields, container.default, params);
+            let mut object = String::from("{\n");
+            for (doc, member) in members {
+                object += &jsdoc(doc.as_deref(), "  ");
+                object += &format!("  {member};\n");
+            }
+            object.push('}');
+            intersection(object, flattened)
+        }
+        Body::Tuple(fields) => tuple(registry, fields, params),
+        Body::Unit => "null".to_owned(),
+        Body::Enum(variants) => union(registry, container, variants, params),
+    };
+    let generics = if params.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", params.join(", "))
+    };
+    // Unions start on a new line
+    let body = if body.starts_with('\n') {
+        body
+    } else {
+        format!(" {body}")
+    };
+    format!(
+        "{}export type {}{generics} ={body};\n",
+        jsdoc(container.doc.as_deref(), ""),
+        name(registry, id),
+    )
+}
+
+/// Type expression of a shape from a definition with type parameters named
+/// `params`, referring to the types in the registry.
+pub fn type_expr(registry: &Registry, shape: &Shape, params: &[String]) -> String {
+    match shape {
+        Shape::Param(i) => params[*i].clone(),
+        Shape::Primitive(primitive) => primitive_type(*primitive).to_owned(),
+        Shape::Option(shape) => format!("{} | null", type_expr(registry, shape, params)),
+        // `Array<T>` needs no parentheses around unions
+        Shape::Seq(shape) | Shape::Array(shape, _) => {
+            format!("Array<{}>", type_expr(registry, shape, params))
+        }
+        Shape::Tuple(shapes) => {
+            let items = shapes
+                .iter()
+                .map(|shape| type_expr(registry, shape, params))
+                .collect::<Vec<_>>();
+            format!("[{}]", items.join(", "))
+        }
+        // Keys are strings in JSON, whatever they are in Rust
+        Shape::Map(key, value) => match **key {
+            Shape::Primitive(_) => {
+                format!("Record<string, {}>", type_expr(registry, value, params))
+            }
+            _ => format!(
+                "Partial<Record<{}, {}>>",
+                type_expr(registry, key, params),
+                type_expr(registry, value, params)
+            ),
+        },
+        Shape::Type(ty) => {
+            let name = name(registry, ty.id);
+            if ty.args.is_empty() {
+                name
+            } else {
+                let args = ty
+                    .args
+                    .iter()
+                    .map(|arg| type_expr(registry, arg, params))
+                    .collect::<Vec<_>>();
+                format!("{name}<{}>", args.join(", "))
+            }
+        }
+    }
+}
+
+fn primitive_type(primitive: Primitive) -> &'static str {
+    match primitive {
+        Primitive::Bool => "boolean",
+        Primitive::I8
+        | Primitive::I16
+        | Primitive::I32
+        | Primitive::I64
+        | Primitive::I128
+        | Primitive::U8
+        | Primitive::U16
+        | Primitive::U32
+        | Primitive::U64
+        | Primitive::U128
+        | Primitive::F32
+        | Primitive::F64 => "number",
+        Primitive::Char | Primitive::String => "string",
+        Primitive::Unit => "null",
+    }
+}
+
+fn field_type(registry: &Registry, field: &Field, params: &[String]) -> String {
+    if field.serialize_with.is_some() || field.deserialize_with.is_some() {
+        "unknown".to_owned()
+    } else {
+        type_expr(registry, &field.shape, params)
+    }
+}
+
+/// Members of an object type with their doc comments, and the types of
+/// flattened fields.
+fn members(
+    registry: &Registry,
+    fields: &[Field],
+    default: bool,
+    params: &[String],
+) -> (Vec<(Option<String>, String)>, Vec<String>) {
+    let mut members = Vec::new();
+    let mut flattened = Vec::new();
+    for field in fields {
+        if field.skip_serializing && field.skip_deserializing {
+            continue;
+        }
+        if field.flatten {
+            // `None` of a flattened option is no fields at all
+            flattened.push(match &field.shape {
+                Shape::Option(shape) => format!("Partial<{}>", type_expr(registry, shape, params)),
+                shape => type_expr(registry, shape, params),
+            });
+            continue;
+        }
+        let optional = default
+            || field.default
+            || field.skip_serializing
+            || field.skip_deserializing
+            || field.skip_serializing_if.is_some();
+        let member = format!(
+            "{}{}: {}",
+            property(&field.name.serialize),
+            if optional { "?" } else { "" },
+            field_type(registry, field, params),
+        );
+        members.push((field.doc.clone(), member));
+    }
+    (members, flattened)
+}
+
+/// Object type on a single line, with `first` member if any.
+fn inline_object(
+    registry: &Registry,
+    first: Option<String>,
+    fields: &[Field],
+    params: &[String],
+) -> String {
+    let (members, flattened) = members(registry, fields, false, params);
+    let members = first
+        .into_iter()
+        .chain(members.into_iter().map(|(_, member)| member))
+        .collect::<Vec<_>>();
+    let object = if members.is_empty() {
+        "{}".to_owned()
+    } else {
+        format!("{{ {} }}", members.join("; "))
+    };
+    intersection(object, flattened)
+}
+
+fn intersection(object: String, flattened: Vec<String>) -> String {
+    std::iter::once(object)
+        .chain(flattened)
+        .collect::<Vec<_>>()
+        .join(" & ")
+}
+
+/// Tuple fields, fields skipped either way are left out, as they have no
+/// position in the sequence.
+fn tuple(registry: &Registry, fields: &[Field], params: &[String]) -> String {
+    if let [field] = fields {
+        return field_type(registry, field, params);
+    }
+    let items = fields
+        .iter()
+        .filter(|field| !field.skip_serializing && !field.skip_deserializing)
+        .map(|field| field_type(registry, field, params))
+        .collect::<Vec<_>>();
+    format!("[{}]", items.join(", "))
+}
+
+fn union(
+    registry: &Registry,
+    container: &Container,
+    variants: &[Variant],
+    params: &[String],
+) -> String {
+    let variants = variants
+        .iter()
+        .filter(|variant| !(variant.skip_serializing && variant.skip_deserializing))
+        // Catches unknown tags when deserializing
+        .filter(|variant| !variant.other)
+        .map(|variant| {
+            let comment = jsdoc(variant.doc.as_deref(), "  ");
+            format!(
+                "{comment}  | {}",
+                variant_type(registry, container, variant, params)
+            )
+        })
+        .collect::<Vec<_>>();
+    if variants.is_empty() {
+        "never".to_owned()
+    } else {
+        format!("\n{}", variants.join("\n"))
+    }
+}
+
+fn variant_type(
+    registry: &Registry,
+    container: &Container,
+    variant: &Variant,
+    params: &[String],
+) -> String {
+    let content = match &variant.body {
+        _ if variant.serialize_with.is_some() || variant.deserialize_with.is_some() => {
+            Some("unknown".to_owned())
+        }
+        Body::Unit => None,
+        Body::Tuple(fields) => Some(tuple(registry, fields, params)),
+        Body::Struct(fields) => Some(inline_object(registry, None, fields, params)),
+        Body::Enum(_) => unreachable!("variants have no variants"),
+    };
+    let name = string(&variant.name.serialize, unicode);
+    match (&container.tag, content) {
+        (_, content) if variant.untagged => content.unwrap_or("null".to_owned()),
+        (TagType::None, content) => content.unwrap_or("null".to_owned()),
+        (TagType::External, None) => name,
+        (TagType::External, Some(content)) => {
+            format!("{{ {}: {content} }}", property(&variant.name.serialize))
+        }
+        (TagType::Internal { tag }, _) => {
+            let tag = format!("{}: {name}", property(tag));
+            match &variant.body {
+                Body::Struct(fields) => inline_object(registry, Some(tag), fields, params),
+                Body::Tuple(fields) => format!("{{ {tag} }} & {}", tuple(registry, fields, params)),
+                _ => format!("{{ {tag} }}"),
+            }
+        }
+        (TagType::Adjacent { tag, content: key }, content) => {
+            let tag = format!("{}: {name}", property(tag));
+            match content {
+                Some(content) => format!("{{ {tag}; {}: {content} }}", property(key)),
+                None => format!("{{ {tag} }}"),
+            }
+        }
+    }
+}
+
+/// Name of the type declared for a definition: its unique name as an
+/// identifier.
+fn name(registry: &Registry, id: u64) -> String {
+    type_name(registry.definition_name(id).expect("registered definition"))
+}
+
+/// Property name, quoted unless it is an identifier.
+fn property(name: &str) -> String {
+    let mut chars = name.chars();
+    let identifier = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if identifier {
+        name.to_owned()
+    } else {
+        string(name, unicode)
+    }
+}
+
+/// JSDoc comment ending with a newline, indented by `indent`.
+fn jsdoc(doc: Option<&str>, indent: &str) -> String {
+    comment(doc, indent, Comment::Block("*\\/"))
+}
@@ -0,0 +1,168 @@
+//! `#[serde_generic(...)]` attributes, which tune the derived impl rather
+//! than describe the serialized form.
+
+use crate::serde::Ctxt;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use std::collections::BTreeMap;
+
+const SERDE_GENERIC: &str = "serde_generic";
+
+/// Attributes of the struct or enum.
+#[derive(Default)]
+pub struct Container {
+    /// `#[serde_generic(no_mock)]`: `Mocked` is `Self`.
+    pub no_mock: bool,
+    /// `#[serde_generic(mock(X = "Type"))]`: substitutes of type parameters
+    /// in `Mocked`, instead of a `TypeVar`.
+    ///
+    /// Derive helper attributes are not allowed on generic parameters, so
+    /// they are listed on the container.
+    pub mocks: BTreeMap<syn::Ident, syn::Type>,
+}
+
+impl Container {
+    pub fn from_ast(cx: &Ctxt, input: &syn::DeriveInput) -> Self {
+        let mut container = Container::default();
+        for attr in &input.attrs {
+            if !attr.path().is_ident(SERDE_GENERIC) {
+                continue;
+            }
+            let result = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("no_mock") {
+                    // #[serde_generic(no_mock)]
+                    container.no_mock = true;
+                    Ok(())
+                } else if meta.path.is_ident("mock") {
+                    // #[serde_generic(mock(X = "Type", Y = "Type"))]
+                    meta.parse_nested_meta(|meta| {
+                        let Some(param) = meta.path.get_ident() else {
+                            return Err(meta.error("expected a type parameter"));
+                        };
+                        if !input.generics.type_params().any(|p| p.ident == *param) {
+                            return Err(meta.error(format!("`{param}` is not a type parameter")));
+                        }
+                        let lit: syn::LitStr = meta.value()?.parse()?;
+                        if container
+                            .mocks
+                            .insert(param.clone(), lit.parse()?)
+                            .is_some()
+                        {
+                            return Err(meta.error(format!("duplicate mock of `{param}`")));
+                        }
+                        Ok(())
+                    })
+                } else {
+                    Err(meta.error("unknown serde_generic attribute"))
+                }
+            });
+            if let Err(err) = result {
+                cx.syn_error(err);
+            }
+        }
+        if container.no_mock && !container.mocks.is_empty() {
+            cx.error_spanned_by(
+                &input.ident,
+                "`no_mock` and `mock(...)` cannot be used together",
+            );
+        }
+        container
+    }
+}
+
+/// Attributes of a field.
+pub struct Field {
+    /// `#[serde_generic(proto = N)]`: number of the field in Protocol Buffers
+    /// messages, instead of one given by its position, with the span of `N`.
+    pub proto: Option<(u32, Span)>,
+}
+
+impl Field {
+    pub fn from_ast(cx: &Ctxt, field: &syn::Field) -> Self {
+        Field {
+            proto: proto_number(cx, &field.attrs),
+        }
+    }
+
+    pub fn to_serde_generic_term_repr(&self) -> TokenStream {
+        let proto = option_repr(self.proto);
+        quote! {
+            ::serde_generic::attr::Field {
+                proto: #proto,
+            }
+        }
+    }
+}
+
+/// Attributes of an enum variant.
+pub struct Variant {
+    /// `#[serde_generic(proto = N)]`: number of the variant in Protocol
+    /// Buffers `oneof`s or enums, instead of one given by its position, with
+    /// the span of `N`.
+    pub proto: Option<(u32, Span)>,
+}
+
+impl Variant {
+    pub fn from_ast(cx: &Ctxt, variant: &syn::Variant) -> Self {
+        Variant {
+            proto: proto_number(cx, &variant.attrs),
+        }
+    }
+
+    pub fn to_serde_generic_term_repr(&self) -> TokenStream {
+        let proto = option_repr(self.proto);
+        quote! {
+            ::serde_generic::attr::Variant {
+                proto: #proto,
+            }
+        }
+    }
+}
+
+/// Reports the numbers of `#[serde_generic(proto = N)]` given twice among
+/// the fields of a struct or variant, or among the variants of an enum.
+pub fn check_proto_numbers(cx: &Ctxt, numbers: impl IntoIterator<Item = Option<(u32, Span)>>) {
+    let mut seen = BTreeMap::new();
+    for (number, span) in numbers.into_iter().flatten() {
+        if seen.insert(number, span).is_some() {
+            cx.syn_error(syn::Error::new(
+                span,
+                format!("`proto = {number}` is given twice"),
+            ));
+        }
+    }
+}
+
+fn option_repr(number: Option<(u32, Span)>) -> TokenStream {
+    match number {
+        Some((number, _)) => quote! {Some(#number)},
+        None => quote! {None},
+    }
+}
+
+/// `#[serde_generic(proto = N)]` of a field or variant, the only attribute
+/// they can have.
+fn proto_number(cx: &Ctxt, attrs: &[syn::Attribute]) -> Option<(u32, Span)> {
+    let mut number = None;
+    for attr in attrs {
+        if !attr.path().is_ident(SERDE_GENERIC) {
+            continue;
+        }
+        let result = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("proto") {
+                // #[serde_generic(proto = 3)]
+                let lit: syn::LitInt = meta.value()?.parse()?;
+                if number.replace((lit.base10_parse()?, lit.span())).is_some() {
+                    return Err(meta.error("duplicate serde_generic attribute `proto`"));
+                }
+                Ok(())
+            } else {
+                Err(meta.error("unknown serde_generic attribute"))
+            }
+        });
+        if let Err(err) = result {
+            cx.syn_error(err);
+        }
+    }
+    number
+}
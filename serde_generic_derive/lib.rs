@@ -1,16 +1,20 @@
 extern crate proc_macro;
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
+use quote::ToTokens;
 use quote::format_ident;
 use quote::quote;
 use serde::Ctxt;
+use std::collections::BTreeSet;
 use syn::Data;
 use syn::DeriveInput;
 use syn::Fields;
+use syn::GenericParam;
 use syn::parse_macro_input;
 use syn::punctuated::Punctuated;
 use syn::token::Comma;
 
+mod attr;
 mod serde;
 
 fn mk_idx(i: usize) -> TokenStream2 {
@@ -25,183 +29,560 @@ fn mk_type_var(i: usize) -> TokenStream2 {
     quote! {::serde_generic::TypeVar<#idx>}
 }
 
-#[proc_macro_derive(SerdeGeneric, attributes(serde))]
+fn mk_where_clause(
+    where_clause: Option<&syn::WhereClause>,
+    bound: Option<syn::WherePredicate>,
+) -> Option<syn::WhereClause> {
+    let mut where_clause = where_clause.cloned();
+    if let Some(bound) = bound {
+        where_clause
+            .get_or_insert_with(|| syn::parse_quote! {where})
+            .predicates
+            .push(bound);
+    }
+    where_clause
+}
+
+/// Impl of `SerdeFieldWith`, `SerdeVariantFieldWith` or `SerdeVariantWith`
+/// (given as `with_trait`) for values of type `ty`. These traits only exist
+/// with the `serde` feature.
+fn mk_with_impl(
+    with_trait: TokenStream2,
+    ty: TokenStream2,
+    (serialize_with, deserialize_with): (TokenStream2, TokenStream2),
+    type_ident: &syn::Ident,
+    ty_generics: &syn::TypeGenerics,
+    impl_generics: &syn::ImplGenerics,
+    where_clause: Option<&syn::WhereClause>,
+) -> TokenStream2 {
+    if !cfg!(feature = "serde") {
+        return quote! {};
+    }
+    quote! {
+        #[automatically_derived]
+        impl #impl_generics #with_trait for #type_ident #ty_generics #where_clause {
+            fn serialize_with<__S: ::serde_generic::__private::serde::Serializer>(
+            ) -> Option<::serde_generic::SerializeWith<#ty, __S>> {
+                #serialize_with
+            }
+            fn deserialize_with<'__de, __D: ::serde_generic::__private::serde::Deserializer<'__de>>(
+            ) -> Option<::serde_generic::DeserializeWith<'__de, #ty, __D>> {
+                #deserialize_with
+            }
+        }
+    }
+}
+
+fn mk_const_param(cx: &Ctxt, param: &syn::ConstParam) -> TokenStream2 {
+    let ident = &param.ident;
+    let ty = &param.ty;
+    let kind = match ty {
+        syn::Type::Path(path) if path.qself.is_none() => path
+            .path
+            .get_ident()
+            .map(|ident| ident.to_string())
+            .unwrap_or_default(),
+        _ => String::new(),
+    };
+    let value = match kind.as_str() {
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => {
+            quote! {::serde_generic::ConstValue::Unsigned(#ident as u128)}
+        }
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => {
+            quote! {::serde_generic::ConstValue::Signed(#ident as i128)}
+        }
+        "bool" => quote! {::serde_generic::ConstValue::Bool(#ident)},
+        "char" => quote! {::serde_generic::ConstValue::Char(#ident)},
+        _ => {
+            cx.error_spanned_by(ty, "unsupported type of a const generic parameter");
+            quote! {::serde_generic::ConstValue::Bool(false)}
+        }
+    };
+    quote! {
+        ::serde_generic::ConstParam {
+            name: stringify!(#ident),
+            ty: stringify!(#ty),
+            value: #value,
+        }
+    }
+}
+
+/// Pushes, for every array in `ty` in the order they are written, the
+/// position in `const_params` of its length, if it is one of them.
+fn array_lens(ty: &syn::Type, const_params: &[&syn::Ident], lens: &mut Vec<Option<usize>>) {
+    match ty {
+        syn::Type::Array(array) => {
+            let len = match &array.len {
+                syn::Expr::Path(path) if path.qself.is_none() => path
+                    .path
+                    .get_ident()
+                    .and_then(|ident| const_params.iter().position(|param| *param == ident)),
+                _ => None,
+            };
+            lens.push(len);
+            array_lens(&array.elem, const_params, lens);
+        }
+        syn::Type::Group(group) => array_lens(&group.elem, const_params, lens),
+        syn::Type::Paren(paren) => array_lens(&paren.elem, const_params, lens),
+        syn::Type::Ptr(ptr) => array_lens(&ptr.elem, const_params, lens),
+        syn::Type::Reference(reference) => array_lens(&reference.elem, const_params, lens),
+        syn::Type::Slice(slice) => array_lens(&slice.elem, const_params, lens),
+        syn::Type::Tuple(tuple) => {
+            for elem in &tuple.elems {
+                array_lens(elem, const_params, lens);
+            }
+        }
+        syn::Type::Path(path) => {
+            for segment in &path.path.segments {
+                if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+                    for arg in &args.args {
+                        if let syn::GenericArgument::Type(ty) = arg {
+                            array_lens(ty, const_params, lens);
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[proc_macro_derive(SerdeGeneric, attributes(serde, serde_generic))]
 pub fn derive_serde_generic(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    let cx = Ctxt::new();
+    let expanded = expand(&cx, &input);
+    // Like `serde_derive`, nothing but the errors is emitted if there are any
+    match cx.check() {
+        Ok(()) => expanded,
+        Err(err) => err.to_compile_error(),
+    }
+    .into()
+}
+
+fn expand(cx: &Ctxt, input: &DeriveInput) -> TokenStream2 {
+    if let Data::Union(data) = &input.data {
+        cx.error_spanned_by(data.union_token, "union types are not supported");
+        return quote! {};
+    }
     let type_ident = &input.ident;
 
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    // Lifetimes are not a part of `Params`, `Mocked` keeps them as they are
     let type_params = input
+        .generics
+        .type_params()
+        .map(|param| &param.ident)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rfold(quote! {()}, |tail, param| quote! {(#param, #tail)});
+    let generic_attrs = attr::Container::from_ast(cx, input);
+    // Type variables are numbered by position in `Params`, overridden ones included
+    let mock_params = input
         .generics
         .params
         .iter()
-        .rfold(quote! {()}, |tail, field| quote! {(#field, #tail)});
-    let mock_params = input
+        .scan(0, |type_param_idx, param| {
+            Some(match param {
+                GenericParam::Lifetime(param) => {
+                    let lifetime = &param.lifetime;
+                    quote! {#lifetime}
+                }
+                GenericParam::Type(param) => {
+                    *type_param_idx += 1;
+                    match generic_attrs.mocks.get(&param.ident) {
+                        Some(mock) => quote! {#mock},
+                        None => mk_type_var(*type_param_idx - 1),
+                    }
+                }
+                GenericParam::Const(param) => {
+                    let ident = &param.ident;
+                    quote! {#ident}
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    let mocked = if generic_attrs.no_mock {
+        quote! {Self}
+    } else {
+        quote! {#type_ident <#(#mock_params,)*>}
+    };
+    let type_param_names = input
         .generics
         .type_params()
-        .enumerate()
-        .map(|(i, _)| mk_type_var(i));
-    let cx = serde::Ctxt::new();
-    let container_attrs = serde::attr::Container::from_ast(&cx, &input);
-
-    assert!(
-        container_attrs.type_from().is_none()
-            && container_attrs.type_try_from().is_none()
-            && container_attrs.type_into().is_none()
-            && container_attrs.remote().is_none(),
-        "serde attributes `type_from`, `type_try_from`, `type_into` and `remote` are not supported",
-    );
+        .map(|param| param.ident.to_string());
+    let const_params = input
+        .generics
+        .const_params()
+        .map(|param| mk_const_param(cx, param));
+    let const_idents = input
+        .generics
+        .const_params()
+        .map(|param| &param.ident)
+        .collect::<Vec<_>>();
+    let container_attrs = serde::attr::Container::from_ast(cx, input);
 
     let container_default = container_attrs.default();
     let rename_rules = container_attrs.rename_all_rules();
-    let container_def = container_attrs.to_serde_generic_term_repr();
+    let rename_fields_rules = container_attrs.rename_all_fields_rules();
+    let (container_default_fn, container_default_bound) =
+        container_default.to_serde_generic_default_fn(&quote! {#type_ident #ty_generics});
+    let container_default_where = mk_where_clause(where_clause, container_default_bound);
+    let from_impl = match (container_attrs.type_from(), container_attrs.type_try_from()) {
+        (Some(proxy), _) => quote! {
+          #[automatically_derived]
+          impl #impl_generics ::serde_generic::SerdeContainerFrom for #type_ident #ty_generics #where_clause {
+            type Proxy = #proxy;
+            fn from_proxy(proxy: #proxy) -> Result<Self, String> {
+              Ok(<Self as ::core::convert::From<#proxy>>::from(proxy))
+            }
+          }
+        },
+        (None, Some(proxy)) => quote! {
+          #[automatically_derived]
+          impl #impl_generics ::serde_generic::SerdeContainerFrom for #type_ident #ty_generics #where_clause {
+            type Proxy = #proxy;
+            fn from_proxy(proxy: #proxy) -> Result<Self, String> {
+              <Self as ::core::convert::TryFrom<#proxy>>::try_from(proxy)
+                .map_err(|err| ::std::string::ToString::to_string(&err))
+            }
+          }
+        },
+        (None, None) => quote! {},
+    };
+    let into_impl = container_attrs.type_into().map(|proxy| {
+        quote! {
+          #[automatically_derived]
+          impl #impl_generics ::serde_generic::SerdeContainerInto for #type_ident #ty_generics #where_clause {
+            type Proxy = #proxy;
+            fn into_proxy(self) -> #proxy {
+              <Self as ::core::convert::Into<#proxy>>::into(self)
+            }
+          }
+        }
+    });
 
-    let ((repr, other_impls), to_repr, from_repr) = match &input.data {
-        Data::Struct(str) => match &str.fields {
-            Fields::Named(fields) => {
-                let struct_common = for_a_struct(
-                    &cx,
-                    &container_default,
-                    rename_rules,
-                    &type_ident,
+    // Field attributes of a struct are parsed once, the remote impl needs them too
+    let field_attrs = match &input.data {
+        Data::Struct(data) => data
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                serde::attr::Field::from_ast(cx, i, field, None, container_default)
+                    .mutate(|f| f.rename_by_rules(rename_rules))
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let field_names = deserialized_names(
+        field_attrs
+            .iter()
+            .map(|attrs| (attrs.skip_deserializing(), attrs.aliases())),
+    );
+    let variant_attrs = match &input.data {
+        Data::Enum(data) => data
+            .variants
+            .iter()
+            .enumerate()
+            .map(|(i, variant)| {
+                serde::attr::Variant::from_ast(cx, i, variant).mutate(|v| {
+                    v.rename_by_rules(rename_rules);
+                    v.inherit_rename_all_fields_rules(rename_fields_rules);
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let variant_names = deserialized_names(
+        variant_attrs
+            .iter()
+            .map(|attrs| (attrs.skip_deserializing(), attrs.aliases())),
+    );
+    let container_def = container_attrs.to_serde_generic_term_repr(&field_names, &variant_names);
+    let getters = field_attrs
+        .iter()
+        .map(|attrs| attrs.getter().cloned())
+        .collect::<Vec<_>>();
+
+    let ((repr, other_impls), (to_repr, from_repr)) = match &input.data {
+        Data::Struct(data) => (
+            match &data.fields {
+                Fields::Named(fields) => for_a_struct(
+                    cx,
+                    type_ident,
                     &ty_generics,
                     &impl_generics,
+                    where_clause,
+                    &const_idents,
                     quote! {::serde_generic::NamedStruct},
                     &fields.named,
-                );
-                let idents = fields
-                    .named
-                    .iter()
-                    .map(|field| field.ident.as_ref().unwrap());
-
-                let to_repr = idents.clone().rfold(
-                    quote! {::serde_generic::HNil},
-                    |tail, field_name| quote! {::serde_generic::HCons(self.#field_name, #tail)},
-                );
-                let from_repr_pat = idents.clone().rfold(
-                    quote! {::serde_generic::HNil},
-                    |tail, field_name| quote! {::serde_generic::HCons(#field_name, #tail)},
-                );
-                let from_repr = quote! {let ::serde_generic::NamedStruct(#from_repr_pat) = repr; Self { #(#idents,)* } };
-                (
-                    struct_common,
-                    quote! {::serde_generic::NamedStruct(#to_repr)},
-                    from_repr,
-                )
-            }
-            Fields::Unnamed(fields) => {
-                let struct_common = for_a_struct(
-                    &cx,
-                    &container_default,
-                    rename_rules,
-                    &type_ident,
+                    field_attrs,
+                ),
+                Fields::Unnamed(fields) => for_a_struct(
+                    cx,
+                    type_ident,
                     &ty_generics,
                     &impl_generics,
+                    where_clause,
+                    &const_idents,
                     quote! {::serde_generic::UnnamedStruct},
                     &fields.unnamed,
-                );
-                let indexes = fields.unnamed.iter().enumerate();
-
-                let to_repr = indexes.clone().map(|(i, _)| syn::Index::from(i)).rfold(
-                    quote! {::serde_generic::HNil},
-                    |tail, i| quote! {::serde_generic::HCons(self.#i, #tail)},
-                );
-
-                let idents = indexes.map(|(i, _)| format_ident!("x{i}"));
-
-                let from_repr_pat = idents.clone().rfold(
-                    quote! {::serde_generic::HNil},
-                    |tail, i| quote! {::serde_generic::HCons(#i, #tail)},
-                );
-
-                let from_repr = quote! {let ::serde_generic::UnnamedStruct(#from_repr_pat) = repr; Self ( #(#idents,)* ) };
-                (
-                    struct_common,
-                    quote! {::serde_generic::UnnamedStruct(#to_repr)},
-                    from_repr,
-                )
-            }
-            Fields::Unit => (
-                (
+                    field_attrs,
+                ),
+                Fields::Unit => (
                     quote! {::serde_generic::UnitStruct},
                     Box::new(std::iter::empty()) as Box<dyn Iterator<Item = TokenStream2>>,
                 ),
-                quote! {::serde_generic::UnitStruct},
-                quote! {Self},
+            },
+            struct_to_from_repr(&data.fields, quote! {self}, quote! {Self}, &[]),
+        ),
+        Data::Enum(data) => (
+            for_an_enum(
+                cx,
+                container_default,
+                type_ident,
+                &ty_generics,
+                &impl_generics,
+                where_clause,
+                &const_idents,
+                data,
+                variant_attrs,
             ),
-        },
-        Data::Enum(variants) => for_an_enum(
-            &cx,
-            &container_default,
-            rename_rules,
-            &type_ident,
-            &ty_generics,
-            &impl_generics,
-            variants,
+            enum_to_from_repr(data, quote! {self}, quote! {Self}),
         ),
-        Data::Union(_) => panic!("Union types are not supported"),
+        Data::Union(_) => unreachable!("unions are rejected above"),
     };
 
-    let res = quote! {
+    // The remote type shares the representation of the mirror, fields
+    // without a getter are moved out of it directly.
+    let remote_impl = container_attrs.remote().map(|remote| {
+        let (to_repr, from_repr) = match &input.data {
+            Data::Struct(data) => {
+                let (to_repr, from_repr) =
+                    struct_to_from_repr(&data.fields, quote! {remote}, quote! {#remote}, &getters);
+                // Like `serde_derive`, private fields require `From<Mirror>`
+                let from_repr = if getters.iter().any(Option::is_some) {
+                    quote! {
+                      ::core::convert::Into::<#remote #ty_generics>::into(
+                        <Self as ::serde_generic::SerdeGeneric>::from_repr(repr)
+                      )
+                    }
+                } else {
+                    from_repr
+                };
+                (to_repr, from_repr)
+            }
+            Data::Enum(data) => enum_to_from_repr(data, quote! {remote}, quote! {#remote}),
+            Data::Union(_) => unreachable!("unions are rejected above"),
+        };
+        let fields = match &input.data {
+            Data::Struct(data) => data.fields.iter().collect(),
+            _ => Vec::new(),
+        };
+        let getter_impls = fields.iter().zip(&getters).enumerate().map(|(i, (field, getter))| {
+            let idx = mk_idx(fields.len() - i);
+            let ty = &field.ty;
+            let getter = remote_getter(getter.as_ref(), ty);
+            quote! {
+              #[automatically_derived]
+              impl #impl_generics ::serde_generic::SerdeRemoteGetter<#ty, #idx>
+                 for #type_ident #ty_generics #where_clause {
+                 const GETTER: Option<(&'static str, ::serde_generic::Getter<#remote #ty_generics, #ty>)> =
+                   #getter;
+              }
+            }
+        });
+        quote! {
+          #[automatically_derived]
+          impl #impl_generics ::serde_generic::SerdeRemote for #type_ident #ty_generics #where_clause {
+            type Remote = #remote #ty_generics;
+            fn remote_to_repr(remote: Self::Remote) -> Self::Repr { #to_repr }
+            fn remote_from_repr(repr: Self::Repr) -> Self::Remote { #from_repr }
+          }
+          #(#getter_impls)*
+        }
+    });
+
+    let reflect_impl = mk_reflect_impl(input, generic_attrs.no_mock);
+
+    quote! {
       #[automatically_derived]
       impl #impl_generics  ::serde_generic::SerdeGeneric for #type_ident #ty_generics #where_clause {
         type Params = #type_params;
-        type Mocked = #type_ident <#(#mock_params,)*>;
+        type Mocked = #mocked;
         type Repr = #repr;
         fn to_repr(self) -> Self::Repr { #to_repr }
         fn from_repr(repr: Self::Repr) -> Self { #from_repr }
         const CONTAINER: ::serde_generic::serde::Container<Self> = #container_def;
+        const TYPE_PARAMS: &'static [&'static str] = &[#(#type_param_names,)*];
+        const CONST_PARAMS: &'static [::serde_generic::ConstParam] = &[#(#const_params,)*];
+      }
+      #[automatically_derived]
+      impl #impl_generics ::serde_generic::SerdeContainerDefault for #type_ident #ty_generics #container_default_where {
+        const DEFAULT: Option<fn() -> Self> = #container_default_fn;
       }
+      #from_impl
+      #into_impl
+      #remote_impl
+      #reflect_impl
       #(#other_impls)*
     }
-    .into();
+}
 
-    cx.check().unwrap();
-    res
+/// Impl of `Reflect`, whose proof is made of the proofs of the type
+/// parameters, so that the type can refer to itself.
+///
+/// `Mocked` has to be `'static`, which the compiler can't tell from a bound
+/// on `Mocked` alone, so lifetimes, as well as type parameters that are not
+/// mocked, are bounded by `'static` themselves.
+fn mk_reflect_impl(input: &DeriveInput, no_mock: bool) -> TokenStream2 {
+    let type_ident = &input.ident;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let params = input
+        .generics
+        .type_params()
+        .map(|param| &param.ident)
+        .collect::<Vec<_>>();
+    let proofs = (0..params.len())
+        .map(|i| format_ident!("__M{}", i))
+        .collect::<Vec<_>>();
+    let mut generics = input.generics.clone();
+    generics.params.extend(
+        proofs
+            .iter()
+            .map(|proof| -> GenericParam { syn::parse_quote!(#proof) }),
+    );
+    let where_clause = generics.make_where_clause();
+    where_clause
+        .predicates
+        .extend(
+            params
+                .iter()
+                .zip(&proofs)
+                .map(|(param, proof)| -> syn::WherePredicate {
+                    syn::parse_quote!(#param: ::serde_generic::reflect::Reflect<#proof>)
+                }),
+        );
+    let lifetimes = input.generics.lifetimes().map(|param| &param.lifetime);
+    where_clause.predicates.extend(
+        lifetimes.map(|lifetime| -> syn::WherePredicate { syn::parse_quote!(#lifetime: 'static) }),
+    );
+    if no_mock {
+        where_clause.predicates.extend(
+            params
+                .iter()
+                .map(|param| -> syn::WherePredicate { syn::parse_quote!(#param: 'static) }),
+        );
+    }
+    where_clause.predicates.push(syn::parse_quote! {
+        <Self as ::serde_generic::SerdeGeneric>::Mocked: 'static
+    });
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let proof = proofs
+        .iter()
+        .rfold(quote! {()}, |tail, proof| quote! {(#proof, #tail)});
+    quote! {
+      #[automatically_derived]
+      impl #impl_generics ::serde_generic::reflect::Reflect<::serde_generic::reflect::ViaGeneric<#proof>>
+         for #type_ident #ty_generics #where_clause {
+        fn shape(registry: &mut ::serde_generic::reflect::Registry) -> ::serde_generic::reflect::Shape {
+          let args = ::std::vec![
+            #(<#params as ::serde_generic::reflect::Reflect<#proofs>>::shape(registry),)*
+          ];
+          ::serde_generic::reflect::generic_shape::<Self, _>(registry, args)
+        }
+        fn value(value: Self) -> ::core::option::Option<::serde_generic::reflect::Value> {
+          ::serde_generic::reflect::generic_value::<Self, _>(value)
+        }
+      }
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn for_a_struct<'a>(
     cx: &'a Ctxt,
-    container_default: &'a serde::attr::Default,
-    rename_rules: serde::attr::RenameAllRules,
     type_ident: &'a syn::Ident,
     ty_generics: &'a syn::TypeGenerics<'a>,
     impl_generics: &'a syn::ImplGenerics<'a>,
+    where_clause: Option<&'a syn::WhereClause>,
+    const_params: &'a [&'a syn::Ident],
     wrapper: TokenStream2,
     fields: &'a Punctuated<syn::Field, Comma>,
+    field_attrs: Vec<serde::attr::Field>,
 ) -> (TokenStream2, Box<dyn Iterator<Item = TokenStream2> + 'a>) {
     let types_hlist = fields.iter().map(|field| &field.ty).rfold(
         quote! {::serde_generic::HNil},
         |tail, field| quote! {::serde_generic::HCons<#field, #tail>},
     );
 
+    let generic_attrs = fields
+        .iter()
+        .map(|field| attr::Field::from_ast(cx, field))
+        .collect::<Vec<_>>();
+    attr::check_proto_numbers(cx, generic_attrs.iter().map(|attrs| attrs.proto));
     let number_of_fields = fields.len();
     let other_impls = fields
         .iter()
+        .zip(field_attrs.into_iter().zip(generic_attrs))
         .scan(number_of_fields + 1, |i, x| {
             *i -= 1;
             Some((*i, x))
         })
-        .enumerate()
-        .map(move |(i, (i_rev, field))| {
+        .map(move |(i_rev, (field, (serde_field_attr, generic_field_attr)))| {
             let idx = mk_idx(i_rev);
             let field_type = &field.ty;
-            let serde_field_attr =
-                serde::attr::Field::from_ast(&cx, i, &field, None, &container_default)
-                    .mutate(|f| f.rename_by_rules(rename_rules))
-                    .to_serde_generic_term_repr();
+            let (default_fn, default_bound) = serde_field_attr
+                .default()
+                .to_serde_generic_default_fn(&quote! {#field_type});
+            let default_where = mk_where_clause(where_clause, default_bound);
+            let with_impl = mk_with_impl(
+                quote! {::serde_generic::SerdeFieldWith<#field_type, #idx>},
+                quote! {#field_type},
+                serde_field_attr.to_serde_generic_with_fns(),
+                type_ident,
+                ty_generics,
+                impl_generics,
+                where_clause,
+            );
+            let mut lens = Vec::new();
+            array_lens(field_type, const_params, &mut lens);
+            let serde_field_attr = serde_field_attr.to_serde_generic_term_repr(&lens);
+            let generic_field_attr = generic_field_attr.to_serde_generic_term_repr();
             quote! {
                 #[automatically_derived]
                 impl #impl_generics ::serde_generic::SerdeFieldAttr<#field_type, #idx>
-                   for #type_ident #ty_generics {
+                   for #type_ident #ty_generics #where_clause {
                    const FIELD: ::serde_generic::serde::Field<Self, #field_type> = #serde_field_attr;
                 }
+                #[automatically_derived]
+                impl #impl_generics ::serde_generic::GenericFieldAttr<#field_type, #idx>
+                   for #type_ident #ty_generics #where_clause {
+                   const FIELD: ::serde_generic::attr::Field = #generic_field_attr;
+                }
+                #[automatically_derived]
+                impl #impl_generics ::serde_generic::SerdeFieldDefault<#field_type, #idx>
+                   for #type_ident #ty_generics #default_where {
+                   const DEFAULT: Option<fn() -> #field_type> = #default_fn;
+                }
+                #with_impl
             }
         });
     let repr = quote! {#wrapper <#types_hlist>};
     (repr, Box::new(other_impls))
 }
 
+/// Names and aliases of the fields or variants which are deserialized, in
+/// the order `serde_derive` lists them in `FIELDS` and `VARIANTS`.
+fn deserialized_names<'a>(
+    attrs: impl Iterator<Item = (bool, &'a BTreeSet<serde::name::Name>)>,
+) -> Vec<String> {
+    attrs
+        .filter(|(skip_deserializing, _)| !skip_deserializing)
+        .flat_map(|(_, aliases)| aliases.iter().map(|alias| alias.value.clone()))
+        .collect()
+}
+
 trait GenericCombinator: Sized {
     fn mutate(mut self, f: impl FnOnce(&mut Self)) -> Self {
         f(&mut self);
@@ -210,93 +591,167 @@ trait GenericCombinator: Sized {
 }
 impl<X: Sized> GenericCombinator for X {}
 
+fn mk_variant_repr(fields: &Fields) -> TokenStream2 {
+    let var_struct = fields.iter().map(|field| &field.ty).rfold(
+        quote! {::serde_generic::HNil},
+        |tail, field| quote! {::serde_generic::HCons<#field, #tail>},
+    );
+    match fields {
+        Fields::Named(_) => quote! {::serde_generic::NamedStruct<#var_struct>},
+        Fields::Unnamed(_) => quote! {::serde_generic::UnnamedStruct<#var_struct>},
+        Fields::Unit => quote! {::serde_generic::UnitStruct},
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn for_an_enum<'a>(
     cx: &'a Ctxt,
     container_default: &'a serde::attr::Default,
-    rename_rules: serde::attr::RenameAllRules,
     type_ident: &'a syn::Ident,
     ty_generics: &'a syn::TypeGenerics<'a>,
     impl_generics: &'a syn::ImplGenerics<'a>,
+    where_clause: Option<&'a syn::WhereClause>,
+    const_params: &'a [&'a syn::Ident],
     data_enum: &'a syn::DataEnum,
-) -> (
-    (TokenStream2, Box<dyn Iterator<Item = TokenStream2> + 'a>),
-    TokenStream2,
-    TokenStream2,
-) {
+    variant_attrs: Vec<serde::attr::Variant>,
+) -> (TokenStream2, Box<dyn Iterator<Item = TokenStream2> + 'a>) {
     let repr = data_enum
         .variants
         .iter()
-        .map(|variant| &variant.fields)
-        .rfold(quote! {::serde_generic::HSumNil}, |tail, var_fields| {
-            let var_struct = var_fields.iter().map(|field| &field.ty).rfold(
-                quote! {::serde_generic::HNil},
-                |tail, field| quote! {::serde_generic::HCons<#field, #tail>},
-            );
-
-            match &var_fields {
-                Fields::Named(_) => {
-                    quote! {::serde_generic::HSum<::serde_generic::NamedStruct<#var_struct>, #tail>}
-                }
-                Fields::Unnamed(_) => {
-                    quote! {::serde_generic::HSum<::serde_generic::UnnamedStruct<#var_struct>, #tail>}
-                }
-                Fields::Unit => quote! {::serde_generic::HSum<::serde_generic::UnitStruct, #tail>},
-            }
+        .map(|variant| mk_variant_repr(&variant.fields))
+        .rfold(quote! {::serde_generic::HSumNil}, |tail, variant_repr| {
+            quote! {::serde_generic::HSum<#variant_repr, #tail>}
         });
 
+    let generic_attrs = data_enum
+        .variants
+        .iter()
+        .map(|variant| attr::Variant::from_ast(cx, variant))
+        .collect::<Vec<_>>();
+    attr::check_proto_numbers(cx, generic_attrs.iter().map(|attrs| attrs.proto));
     let number_of_variants = data_enum.variants.len();
     let other_impls = data_enum
         .variants
         .iter()
+        .zip(variant_attrs.into_iter().zip(generic_attrs))
         .scan(number_of_variants + 1, |i, x| {
             *i -= 1;
             Some((*i, x))
         })
-        .map(move |(i_rev, variant)| {
+        .map(move |(i_rev, (variant, (serde_var_attr, generic_var_attr)))| {
             let idx = mk_idx(i_rev);
-            let serde_var_attr = serde::attr::Variant::from_ast(&cx, &variant)
-                    .mutate(|f| f.rename_by_rules(rename_rules));
+            let variant_repr = mk_variant_repr(&variant.fields);
 
+            let field_attrs = variant
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(j, field)| {
+                    serde::attr::Field::from_ast(
+                        cx,
+                        j,
+                        field,
+                        Some(&serde_var_attr),
+                        container_default,
+                    )
+                    .mutate(|f| f.rename_by_rules(serde_var_attr.rename_all_rules()))
+                })
+                .collect::<Vec<_>>();
+            let field_names = deserialized_names(
+                field_attrs
+                    .iter()
+                    .map(|attrs| (attrs.skip_deserializing(), attrs.aliases())),
+            );
+            let generic_field_attrs = variant
+                .fields
+                .iter()
+                .map(|field| attr::Field::from_ast(cx, field))
+                .collect::<Vec<_>>();
+            attr::check_proto_numbers(cx, generic_field_attrs.iter().map(|attrs| attrs.proto));
             let number_of_fields = variant.fields.len();
             let field_impls = variant
                 .fields
                 .iter()
+                .zip(field_attrs.into_iter().zip(generic_field_attrs))
                 .scan(number_of_fields + 1, |j, x| {
                     *j -= 1;
                     Some((*j, x))
                 })
-                .enumerate()
-                .map(|(j, (j_rev, field))| {
+                .map(|(j_rev, (field, (serde_field_attr, generic_field_attr)))| {
                     let jdx = mk_idx(j_rev);
                     let field_type = &field.ty;
-                    let serde_field_attr = serde::attr::Field::from_ast(
-                        &cx,
-                        j,
-                        &field,
-                        Some(&serde_var_attr),
-                        &container_default,
-                    )
-                    .mutate(|f| f.rename_by_rules(rename_rules))
-                    .to_serde_generic_term_repr();
+                    let (default_fn, default_bound) = serde_field_attr
+                        .default()
+                        .to_serde_generic_default_fn(&quote! {#field_type});
+                    let default_where = mk_where_clause(where_clause, default_bound);
+                    let with_impl = mk_with_impl(
+                        quote! {::serde_generic::SerdeVariantFieldWith<#field_type, #idx, #jdx>},
+                        quote! {#field_type},
+                        serde_field_attr.to_serde_generic_with_fns(),
+                        type_ident,
+                        ty_generics,
+                        impl_generics,
+                        where_clause,
+                    );
+                    let mut lens = Vec::new();
+                    array_lens(field_type, const_params, &mut lens);
+                    let serde_field_attr = serde_field_attr.to_serde_generic_term_repr(&lens);
+                    let generic_field_attr = generic_field_attr.to_serde_generic_term_repr();
                     quote! {
                         #[automatically_derived]
                         impl #impl_generics ::serde_generic::SerdeVariantFieldAttr<#field_type, #idx, #jdx>
-                            for #type_ident #ty_generics {
+                            for #type_ident #ty_generics #where_clause {
                             const FIELD: ::serde_generic::serde::Field<Self, #field_type> = #serde_field_attr;
                         }
+                        #[automatically_derived]
+                        impl #impl_generics ::serde_generic::GenericVariantFieldAttr<#field_type, #idx, #jdx>
+                            for #type_ident #ty_generics #where_clause {
+                            const FIELD: ::serde_generic::attr::Field = #generic_field_attr;
+                        }
+                        #[automatically_derived]
+                        impl #impl_generics ::serde_generic::SerdeVariantFieldDefault<#field_type, #idx, #jdx>
+                            for #type_ident #ty_generics #default_where {
+                            const DEFAULT: Option<fn() -> #field_type> = #default_fn;
+                        }
+                        #with_impl
                     }
                 });
 
-            let serde_var_attr = serde_var_attr.to_serde_generic_term_repr();
+            let with_impl = mk_with_impl(
+                quote! {::serde_generic::SerdeVariantWith<#variant_repr, #idx>},
+                variant_repr,
+                serde_var_attr.to_serde_generic_with_fns(&variant.fields),
+                type_ident,
+                ty_generics,
+                impl_generics,
+                where_clause,
+            );
+            let serde_var_attr = serde_var_attr.to_serde_generic_term_repr(&field_names);
+            let generic_var_attr = generic_var_attr.to_serde_generic_term_repr();
             quote! {
                 #[automatically_derived]
-                impl #impl_generics ::serde_generic::SerdeVariantAttr<#idx> for #type_ident #ty_generics {
+                impl #impl_generics ::serde_generic::SerdeVariantAttr<#idx> for #type_ident #ty_generics #where_clause {
                    const VARIANT: ::serde_generic::serde::Variant = #serde_var_attr;
                 }
+                #[automatically_derived]
+                impl #impl_generics ::serde_generic::GenericVariantAttr<#idx> for #type_ident #ty_generics #where_clause {
+                   const VARIANT: ::serde_generic::attr::Variant = #generic_var_attr;
+                }
+                #with_impl
                 #(#field_impls)*
             }
         });
 
+    (quote! {::serde_generic::Enum<#repr>}, Box::new(other_impls))
+}
+
+/// Bodies of `to_repr` and `from_repr` of an enum, matching on `value` and
+/// constructing variants of `this`.
+fn enum_to_from_repr(
+    data_enum: &syn::DataEnum,
+    value: TokenStream2,
+    this: TokenStream2,
+) -> (TokenStream2, TokenStream2) {
     let to_from_repr = data_enum.variants.iter().enumerate().map(|(i, variant)| {
         let var_ident = &variant.ident;
         let (orig, generic) = match &variant.fields {
@@ -340,16 +795,80 @@ fn for_an_enum<'a>(
 
     let to_repr_matches = to_from_repr
         .clone()
-        .map(|(var_ident, orig, generic)| quote! {Self::#var_ident #orig => #generic});
-    let to_repr = quote! { ::serde_generic::Enum(match self { #(#to_repr_matches,)* }) };
+        .map(|(var_ident, orig, generic)| quote! {#this::#var_ident #orig => #generic});
+    let to_repr = quote! { ::serde_generic::Enum(match #value { #(#to_repr_matches,)* }) };
 
-    let from_repr_matches =
-        to_from_repr.map(|(var_ident, orig, generic)| quote! {#generic => Self::#var_ident #orig });
+    let from_repr_matches = to_from_repr
+        .map(|(var_ident, orig, generic)| quote! {#generic => #this::#var_ident #orig });
     let from_repr = quote! { match repr.0 { #(#from_repr_matches,)* } };
 
-    (
-        (quote! {::serde_generic::Enum<#repr>}, Box::new(other_impls)),
-        to_repr,
-        from_repr,
-    )
+    (to_repr, from_repr)
+}
+
+/// Value of `SerdeRemoteGetter::GETTER` of a field of type `ty`.
+fn remote_getter(getter: Option<&syn::ExprPath>, ty: &syn::Type) -> TokenStream2 {
+    let Some(getter) = getter else {
+        return quote! {None};
+    };
+    let path = getter.to_token_stream().to_string().replace(' ', "");
+    quote! {
+      Some((#path, |remote| ::core::clone::Clone::clone(
+        ::core::borrow::Borrow::<#ty>::borrow(&#getter(remote))
+      )))
+    }
+}
+
+/// Bodies of `to_repr` and `from_repr` of a struct, moving fields out of
+/// `value` and constructing `this`. Fields with a getter are cloned from
+/// what it returns instead.
+fn struct_to_from_repr(
+    fields: &Fields,
+    value: TokenStream2,
+    this: TokenStream2,
+    getters: &[Option<syn::ExprPath>],
+) -> (TokenStream2, TokenStream2) {
+    let wrapper = match fields {
+        Fields::Named(_) => quote! {::serde_generic::NamedStruct},
+        Fields::Unnamed(_) => quote! {::serde_generic::UnnamedStruct},
+        Fields::Unit => return (quote! {::serde_generic::UnitStruct}, this),
+    };
+    let members = fields.members().collect::<Vec<_>>();
+    let vars = (0..members.len())
+        .map(|i| format_ident!("x{i}"))
+        .collect::<Vec<_>>();
+    // Getters borrow `value`, so they are called before moving out of it
+    let getter_lets = fields
+        .iter()
+        .zip(&vars)
+        .zip(getters)
+        .filter_map(|((field, var), getter)| {
+            let ty = &field.ty;
+            getter.as_ref().map(|getter| {
+                quote! {
+                  let #var: #ty = ::core::clone::Clone::clone(
+                    ::core::borrow::Borrow::<#ty>::borrow(&#getter(&#value))
+                  );
+                }
+            })
+        });
+    let to_repr_cons = members.iter().zip(&vars).enumerate().rfold(
+        quote! {::serde_generic::HNil},
+        |tail, (i, (member, var))| match getters.get(i) {
+            Some(Some(_)) => quote! {::serde_generic::HCons(#var, #tail)},
+            _ => quote! {::serde_generic::HCons(#value.#member, #tail)},
+        },
+    );
+    let to_repr = quote! {
+      #(#getter_lets)*
+      #wrapper(#to_repr_cons)
+    };
+    let from_repr_pat = vars.iter().rfold(
+        quote! {::serde_generic::HNil},
+        |tail, var| quote! {::serde_generic::HCons(#var, #tail)},
+    );
+    let from_repr = quote! {
+      let #wrapper(#from_repr_pat) = repr;
+      #this { #(#members: #vars,)* }
+    };
+    (to_repr, from_repr)
 }
@@ -1,7 +1,7 @@
 // copy-pasted from serde_derive with minor additions
 // TODO: figure out copyright notices
 
-#![allow(dead_code)]
+#![allow(dead_code, clippy::collapsible_if, clippy::enum_variant_names)]
 
 #[rustfmt::skip]
 pub(crate) mod attr;
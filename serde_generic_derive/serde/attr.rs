@@ -138,6 +138,57 @@ fn unraw(ident: &Ident) -> Ident {
     Ident::new(ident.to_string().trim_start_matches("r#"), ident.span())
 }
 
+/// Collects `///` comments and `#[doc = "..."]` attributes, one line per
+/// attribute, with the space following `///` stripped.
+fn get_doc(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(meta) if meta.path == DOC => match &meta.value {
+                syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit), .. }) => Some(lit.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .flat_map(|doc| {
+            // Not `lines`, which has none for the empty lines of paragraphs
+            doc.split('\n')
+                .map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end().to_owned())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
+fn doc_to_serde_generic_term_repr(doc: &Option<String>) -> TokenStream {
+    use quote::quote;
+    doc.as_ref()
+        .map(|doc| quote!{Some(#doc)})
+        .unwrap_or(quote!{None})
+}
+
+fn bounds_to_serde_generic_term_repr(
+    ser_bound: Option<&[syn::WherePredicate]>,
+    de_bound: Option<&[syn::WherePredicate]>,
+) -> TokenStream {
+    use quote::quote;
+    let bound = |bound: Option<&[syn::WherePredicate]>| bound
+        .map(|predicates| quote!{Some(&[#(stringify!(#predicates)),*])})
+        .unwrap_or(quote!{None});
+    let serialize = bound(ser_bound);
+    let deserialize = bound(de_bound);
+    quote!{
+        ::serde_generic::serde::Bounds {
+            serialize: #serialize,
+            deserialize: #deserialize,
+        }
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct RenameAllRules {
     pub serialize: RenameRule,
@@ -153,6 +204,29 @@ impl RenameAllRules {
             deserialize: self.deserialize.or(other_rules.deserialize),
         }
     }
+
+    pub fn to_serde_generic_term_repr(self) -> TokenStream {
+        use quote::quote;
+        let rule = |rule: RenameRule| match rule {
+            RenameRule::None => quote!{::serde_generic::serde::RenameRule::None},
+            RenameRule::LowerCase => quote!{::serde_generic::serde::RenameRule::LowerCase},
+            RenameRule::UpperCase => quote!{::serde_generic::serde::RenameRule::UpperCase},
+            RenameRule::PascalCase => quote!{::serde_generic::serde::RenameRule::PascalCase},
+            RenameRule::CamelCase => quote!{::serde_generic::serde::RenameRule::CamelCase},
+            RenameRule::SnakeCase => quote!{::serde_generic::serde::RenameRule::SnakeCase},
+            RenameRule::ScreamingSnakeCase => quote!{::serde_generic::serde::RenameRule::ScreamingSnakeCase},
+            RenameRule::KebabCase => quote!{::serde_generic::serde::RenameRule::KebabCase},
+            RenameRule::ScreamingKebabCase => quote!{::serde_generic::serde::RenameRule::ScreamingKebabCase},
+        };
+        let serialize = rule(self.serialize);
+        let deserialize = rule(self.deserialize);
+        quote!{
+            ::serde_generic::serde::RenameAllRules {
+                serialize: #serialize,
+                deserialize: #deserialize,
+            }
+        }
+    }
 }
 
 /// Represents struct or enum attribute information.
@@ -176,19 +250,25 @@ pub struct Container {
     /// Error message generated when type can't be deserialized
     expecting: Option<String>,
     non_exhaustive: bool,
+    doc: Option<String>,
 }
 
 impl Container {
-    pub fn to_serde_generic_term_repr(&self) -> TokenStream {
+    /// `fields` and `variants` are the names and aliases of the fields and
+    /// variants which are deserialized, once renamed.
+    pub fn to_serde_generic_term_repr(&self, fields: &[String], variants: &[String]) -> TokenStream {
         use quote::quote;
         let name = self.name.to_serde_generic_term_repr();
         let transparent = self.transparent;
         let deny_unknown_fields = self.deny_unknown_fields;
         let default = self.default.to_serde_generic_term_repr();
+        let rename_all_rules = self.rename_all_rules.to_serde_generic_term_repr();
+        let rename_all_fields_rules = self.rename_all_fields_rules.to_serde_generic_term_repr();
+        let bound = bounds_to_serde_generic_term_repr(self.ser_bound(), self.de_bound());
         let tag = match &self.tag {
             TagType::External => quote!{::serde_generic::serde::TagType::External},
-            TagType::Internal{tag} => quote!{::serde_generic::serde::TagType::Internal{#tag}},
-            TagType::Adjacent{tag, content} => quote!{::serde_generic::serde::TagType::Adjacent{#tag, #content}},
+            TagType::Internal{tag} => quote!{::serde_generic::serde::TagType::Internal{tag: #tag}},
+            TagType::Adjacent{tag, content} => quote!{::serde_generic::serde::TagType::Adjacent{tag: #tag, content: #content}},
             TagType::None => quote!{::serde_generic::serde::TagType::None},
         };
         let identifier = match self.identifier {
@@ -196,22 +276,45 @@ impl Container {
             Identifier::Field => quote!{::serde_generic::serde::Identifier::Field},
             Identifier::Variant => quote!{::serde_generic::serde::Identifier::Variant},
         };
+        let proxy = |ty: &Option<syn::Type>| ty.as_ref()
+            .map(|ty| quote!{Some(stringify!(#ty))})
+            .unwrap_or(quote!{None});
+        let type_from = proxy(&self.type_from);
+        let type_try_from = proxy(&self.type_try_from);
+        let type_into = proxy(&self.type_into);
+        let remote = self.remote.as_ref()
+            .map(|path| {
+                let path = path.to_token_stream().to_string().replace(' ', "");
+                quote!{Some(#path)}
+            })
+            .unwrap_or(quote!{None});
         let is_packed = self.is_packed;
         let expecting = self.expecting.as_ref()
             .map(|msg| quote!{Some(#msg)})
             .unwrap_or(quote!{None});
         let non_exhaustive = self.non_exhaustive;
+        let doc = doc_to_serde_generic_term_repr(&self.doc);
         quote! {
             ::serde_generic::serde::Container {
                 name: #name,
                 transparent: #transparent,
                 deny_unknown_fields: #deny_unknown_fields,
                 default: #default,
+                rename_all_rules: #rename_all_rules,
+                rename_all_fields_rules: #rename_all_fields_rules,
+                bound: #bound,
                 tag: #tag,
+                fields: &[#(#fields,)*],
+                variants: &[#(#variants,)*],
+                type_from: #type_from,
+                type_try_from: #type_try_from,
+                type_into: #type_into,
+                remote: #remote,
                 identifier: #identifier,
                 is_packed: #is_packed,
                 expecting: #expecting,
                 non_exhaustive: #non_exhaustive,
+                doc: #doc,
             }
         }
     }
@@ -586,6 +689,7 @@ impl Container {
             is_packed,
             expecting: expecting.get(),
             non_exhaustive,
+            doc: get_doc(&item.attrs),
         }
     }
 
@@ -782,12 +886,19 @@ pub struct Variant {
     deserialize_with: Option<syn::ExprPath>,
     borrow: Option<BorrowAttribute>,
     untagged: bool,
+    ident: syn::Ident,
+    index: usize,
+    doc: Option<String>,
 }
 
 impl Variant {
-    pub fn to_serde_generic_term_repr(&self) -> TokenStream {
+    /// `fields` are the names and aliases of the fields which are
+    /// deserialized, once renamed.
+    pub fn to_serde_generic_term_repr(&self, fields: &[String]) -> TokenStream {
         use quote::quote;
         let name = self.name.to_serde_generic_term_repr();
+        let rename_all_rules = self.rename_all_rules.to_serde_generic_term_repr();
+        let bound = bounds_to_serde_generic_term_repr(self.ser_bound(), self.de_bound());
         let skip_serializing = self.skip_serializing;
         let skip_deserializing = self.skip_deserializing;
         let other = self.other;
@@ -798,15 +909,26 @@ impl Variant {
           .map(|path| quote!{Some(stringify!(#path))})
           .unwrap_or(quote!{None});
         let untagged = self.untagged;
+        let ident = self.ident.to_string();
+        let index = self.index;
+        let doc = doc_to_serde_generic_term_repr(&self.doc);
         quote!{
             ::serde_generic::serde::Variant {
                 name: #name,
+                rename_all_rules: #rename_all_rules,
+                bound: #bound,
                 skip_deserializing: #skip_deserializing,
                 skip_serializing: #skip_serializing,
                 other: #other,
                 serialize_with: #serialize_with,
                 deserialize_with: #deserialize_with,
                 untagged: #untagged,
+                fields: &[#(#fields,)*],
+                doc: #doc,
+                rust: ::serde_generic::rust::Variant {
+                    ident: #ident,
+                    index: #index,
+                },
             }
         }
     }
@@ -818,7 +940,7 @@ struct BorrowAttribute {
 }
 
 impl Variant {
-    pub fn from_ast(cx: &Ctxt, variant: &syn::Variant) -> Self {
+    pub fn from_ast(cx: &Ctxt, index: usize, variant: &syn::Variant) -> Self {
         let mut ser_name = Attr::none(cx, RENAME);
         let mut de_name = Attr::none(cx, RENAME);
         let mut de_aliases = VecAttr::none(cx, RENAME);
@@ -984,6 +1106,9 @@ impl Variant {
             deserialize_with: deserialize_with.get(),
             borrow: borrow.get(),
             untagged: untagged.get(),
+            ident: variant.ident.clone(),
+            index,
+            doc: get_doc(&variant.attrs),
         }
     }
 
@@ -1014,6 +1139,12 @@ impl Variant {
         self.rename_all_rules
     }
 
+    /// Falls back to the container's `rename_all_fields` rules where the
+    /// variant has no `rename_all` of its own.
+    pub fn inherit_rename_all_fields_rules(&mut self, rules: RenameAllRules) {
+        self.rename_all_rules = self.rename_all_rules.or(rules);
+    }
+
     pub fn ser_bound(&self) -> Option<&[syn::WherePredicate]> {
         self.ser_bound.as_ref().map(|vec| &vec[..])
     }
@@ -1045,6 +1176,36 @@ impl Variant {
     pub fn untagged(&self) -> bool {
         self.untagged
     }
+
+    /// Bodies of `SerdeVariantWith::serialize_with` and `deserialize_with`,
+    /// adapting the functions to the representation of the variant.
+    pub fn to_serde_generic_with_fns(&self, fields: &syn::Fields) -> (TokenStream, TokenStream) {
+        use quote::{format_ident, quote};
+        let vars: Vec<_> = (0..fields.len()).map(|i| format_ident!("__field{}", i)).collect();
+        let cons = vars.iter().rfold(
+            quote!{::serde_generic::HNil},
+            |tail, var| quote!{::serde_generic::HCons(#var, #tail)},
+        );
+        let repr = match fields {
+            syn::Fields::Named(_) => quote!{::serde_generic::NamedStruct(#cons)},
+            syn::Fields::Unnamed(_) => quote!{::serde_generic::UnnamedStruct(#cons)},
+            syn::Fields::Unit => quote!{::serde_generic::UnitStruct},
+        };
+        let tuple = match vars.as_slice() {
+            [var] => quote!{#var},
+            vars => quote!{(#(#vars),*)},
+        };
+        let serialize_with = self.serialize_with.as_ref()
+            .map(|path| quote!{Some(|repr, serializer| {
+                let #repr = repr;
+                #path(#(#vars,)* serializer)
+            })})
+            .unwrap_or(quote!{None});
+        let deserialize_with = self.deserialize_with.as_ref()
+            .map(|path| quote!{Some(|deserializer| #path(deserializer).map(|#tuple| #repr))})
+            .unwrap_or(quote!{None});
+        (serialize_with, deserialize_with)
+    }
 }
 
 /// Represents field attribute information
@@ -1062,10 +1223,17 @@ pub struct Field {
     getter: Option<syn::ExprPath>,
     flatten: bool,
     transparent: bool,
+    ident: Option<syn::Ident>,
+    index: usize,
+    ty: syn::Type,
+    vis: syn::Visibility,
+    doc: Option<String>,
 }
 
 impl Field {
-    pub fn to_serde_generic_term_repr(&self) -> TokenStream {
+    /// `array_lens` are the positions of the const parameters that are the
+    /// lengths of the arrays in the type of the field, if they are.
+    pub fn to_serde_generic_term_repr(&self, array_lens: &[Option<usize>]) -> TokenStream {
         use quote::quote;
         let name = self.name.to_serde_generic_term_repr();
         let skip_serializing = self.skip_serializing;
@@ -1080,11 +1248,37 @@ let default = self.default.to_serde_generic_term_repr();
         let deserialize_with = self.deserialize_with.as_ref()
           .map(|path| quote!{Some(stringify!(#path))})
           .unwrap_or(quote!{None});
-        let getter = self.getter.as_ref()
-          .map(|path| quote!{Some((stringify!(#path), #path))})
-          .unwrap_or(quote!{None});
+        let bound = bounds_to_serde_generic_term_repr(self.ser_bound(), self.de_bound());
+        let borrowed_lifetimes = self.borrowed_lifetimes.iter().map(|lifetime| lifetime.to_string());
         let flatten = self.flatten;
         let transparent = self.transparent;
+        let ident = self.ident.as_ref()
+          .map(|ident| {
+              let ident = ident.to_string();
+              quote!{Some(#ident)}
+          })
+          .unwrap_or(quote!{None});
+        let index = self.index;
+        let ty = &self.ty;
+        let array_lens = array_lens.iter().map(|len| match len {
+            Some(len) => quote!{Some(#len)},
+            None => quote!{None},
+        });
+        let doc = doc_to_serde_generic_term_repr(&self.doc);
+        let vis = match &self.vis {
+            syn::Visibility::Public(_) => quote!{::serde_generic::rust::Visibility::Public},
+            syn::Visibility::Restricted(vis) if vis.in_token.is_none() && vis.path.is_ident("crate") =>
+                quote!{::serde_generic::rust::Visibility::Crate},
+            syn::Visibility::Restricted(vis) => {
+                let path = &vis.path;
+                let path = match vis.in_token {
+                    Some(_) => quote!{in #path},
+                    None => quote!{#path},
+                };
+                quote!{::serde_generic::rust::Visibility::Restricted(stringify!(#path))}
+            }
+            syn::Visibility::Inherited => quote!{::serde_generic::rust::Visibility::Inherited},
+        };
         quote! {
           ::serde_generic::serde::Field {
             name: #name,
@@ -1094,9 +1288,19 @@ let default = self.default.to_serde_generic_term_repr();
             default: #default,
             serialize_with: #serialize_with,
             deserialize_with: #deserialize_with,
-            getter: #getter,
+            bound: #bound,
+            borrowed_lifetimes: &[#(#borrowed_lifetimes),*],
             flatten: #flatten,
             transparent: #transparent,
+            doc: #doc,
+            rust: ::serde_generic::rust::Field {
+                ident: #ident,
+                index: #index,
+                ty: stringify!(#ty),
+                array_lens: &[#(#array_lens),*],
+                vis: #vis,
+            },
+            container: ::core::marker::PhantomData,
           }
         }
     }
@@ -1122,6 +1326,20 @@ impl Default {
         }
     }
 
+    /// Constructor of the default value of type `ty`, along with the bound
+    /// it requires.
+    pub fn to_serde_generic_default_fn(&self, ty: &TokenStream) -> (TokenStream, Option<syn::WherePredicate>) {
+        use quote::quote;
+        match self {
+            Default::None => (quote! {None}, None),
+            Default::Default => (
+                quote! {Some(<#ty as ::core::default::Default>::default)},
+                Some(syn::parse_quote! {#ty: ::core::default::Default}),
+            ),
+            Default::Path(path) => (quote! {Some(#path)}, None),
+        }
+    }
+
     pub fn is_none(&self) -> bool {
         match self {
             Default::None => true,
@@ -1380,6 +1598,11 @@ impl Field {
             getter: getter.get(),
             flatten: flatten.get(),
             transparent: false,
+            ident: field.ident.as_ref().map(unraw),
+            index,
+            ty: field.ty.clone(),
+            vis: field.vis.clone(),
+            doc: get_doc(&field.attrs),
         }
     }
 
@@ -1429,6 +1652,18 @@ impl Field {
         self.deserialize_with.as_ref()
     }
 
+    /// Bodies of `SerdeFieldWith::serialize_with` and `deserialize_with`.
+    pub fn to_serde_generic_with_fns(&self) -> (TokenStream, TokenStream) {
+        use quote::quote;
+        let serialize_with = self.serialize_with.as_ref()
+            .map(|path| quote!{Some(|value, serializer| #path(value, serializer))})
+            .unwrap_or(quote!{None});
+        let deserialize_with = self.deserialize_with.as_ref()
+            .map(|path| quote!{Some(|deserializer| #path(deserializer))})
+            .unwrap_or(quote!{None});
+        (serialize_with, deserialize_with)
+    }
+
     pub fn ser_bound(&self) -> Option<&[syn::WherePredicate]> {
         self.ser_bound.as_ref().map(|vec| &vec[..])
     }